@@ -0,0 +1,5 @@
+fn main() {
+    // the grpc service is generated from its proto only when it is built
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/clmm.proto").unwrap();
+}
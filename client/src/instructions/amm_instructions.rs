@@ -9,12 +9,21 @@ use mpl_token_metadata::accounts::Metadata;
 use raydium_amm_v3::accounts as raydium_accounts;
 use raydium_amm_v3::instruction as raydium_instruction;
 use raydium_amm_v3::states::{
-    AMM_CONFIG_SEED, OBSERVATION_SEED, OPERATION_SEED, POOL_SEED, POOL_VAULT_SEED, POSITION_SEED,
-    TICK_ARRAY_SEED,
+    FeeDiscountTier, PoolState, AMM_CONFIG_SEED, EMERGENCY_PAUSE_SEED, FEE_DISCOUNT_SEED,
+    FEE_DISCOUNT_TIER_NUM, OBSERVATION_SEED, OPERATION_SEED, POOL_CREATOR_PERMISSION_SEED,
+    POOL_SEED, POOL_VAULT_SEED, POSITION_SEED, SUPPORT_MINT_SEED, TICK_ARRAY_SEED,
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
-use super::super::{read_keypair_file, ClientConfig};
+use super::super::ClientConfig;
+
+// swaps and deposits pass the emergency pause account after the other remaining accounts
+fn emergency_pause_account(program_id: &Pubkey) -> AccountMeta {
+    AccountMeta::new_readonly(
+        Pubkey::find_program_address(&[EMERGENCY_PAUSE_SEED.as_bytes()], program_id).0,
+        false,
+    )
+}
 
 pub fn create_amm_config_instr(
     config: &ClientConfig,
@@ -24,10 +33,10 @@ pub fn create_amm_config_instr(
     protocol_fee_rate: u32,
     fund_fee_rate: u32,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.admin_path)?;
+    let payer = config.admin()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let (amm_config_key, __bump) = Pubkey::find_program_address(
         &[AMM_CONFIG_SEED.as_bytes(), &config_index.to_be_bytes()],
@@ -58,11 +67,11 @@ pub fn update_amm_config_instr(
     param: u8,
     value: u32,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
-    let admin = read_keypair_file(&config.admin_path)?;
+    let payer = config.payer()?;
+    let admin = config.admin()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let instructions = program
         .request()
@@ -76,11 +85,216 @@ pub fn update_amm_config_instr(
     Ok(instructions)
 }
 
+pub fn update_pool_status_instr(
+    config: &ClientConfig,
+    pool_account_key: Pubkey,
+    status: u8,
+) -> Result<Vec<Instruction>> {
+    let payer = config.payer()?;
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(payer));
+    let program = client.program(config.raydium_v3_program)?;
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::UpdatePoolStatus {
+            authority: admin.pubkey(),
+            pool_state: pool_account_key,
+        })
+        .args(raydium_instruction::UpdatePoolStatus { status })
+        .instructions()?;
+    Ok(instructions)
+}
+
+pub fn create_support_mint_associated_instr(
+    config: &ClientConfig,
+    token_mint: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(admin));
+    let program = client.program(config.raydium_v3_program)?;
+    let (support_mint_associated, __bump) = Pubkey::find_program_address(
+        &[SUPPORT_MINT_SEED.as_bytes(), token_mint.to_bytes().as_ref()],
+        &program.id(),
+    );
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::CreateSupportMintAssociated {
+            owner: program.payer(),
+            token_mint,
+            support_mint_associated,
+            system_program: system_program::id(),
+        })
+        .args(raydium_instruction::CreateSupportMintAssociated)
+        .instructions()?;
+    Ok(instructions)
+}
+
+pub fn close_support_mint_associated_instr(
+    config: &ClientConfig,
+    token_mint: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(admin));
+    let program = client.program(config.raydium_v3_program)?;
+    let (support_mint_associated, __bump) = Pubkey::find_program_address(
+        &[SUPPORT_MINT_SEED.as_bytes(), token_mint.to_bytes().as_ref()],
+        &program.id(),
+    );
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::CloseSupportMintAssociated {
+            owner: program.payer(),
+            support_mint_associated,
+        })
+        .args(raydium_instruction::CloseSupportMintAssociated)
+        .instructions()?;
+    Ok(instructions)
+}
+
+pub fn set_fee_discount_instr(
+    config: &ClientConfig,
+    amm_config: Pubkey,
+    discount_mint: Pubkey,
+    tiers: [FeeDiscountTier; FEE_DISCOUNT_TIER_NUM],
+) -> Result<Vec<Instruction>> {
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(admin));
+    let program = client.program(config.raydium_v3_program)?;
+    let (fee_discount_config, __bump) = Pubkey::find_program_address(
+        &[FEE_DISCOUNT_SEED.as_bytes(), amm_config.to_bytes().as_ref()],
+        &program.id(),
+    );
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::SetFeeDiscount {
+            owner: program.payer(),
+            amm_config,
+            discount_mint,
+            fee_discount_config,
+            system_program: system_program::id(),
+        })
+        .args(raydium_instruction::SetFeeDiscount { tiers })
+        .instructions()?;
+    Ok(instructions)
+}
+
+pub fn set_emergency_pause_instr(
+    config: &ClientConfig,
+    pause_secs: u64,
+) -> Result<Vec<Instruction>> {
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(admin));
+    let program = client.program(config.raydium_v3_program)?;
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::SetEmergencyPause {
+            authority: program.payer(),
+            emergency_pause: emergency_pause_account(&program.id()).pubkey,
+            system_program: system_program::id(),
+        })
+        .args(raydium_instruction::SetEmergencyPause { pause_secs })
+        .instructions()?;
+    Ok(instructions)
+}
+
+pub fn update_create_pool_fee_instr(
+    config: &ClientConfig,
+    amm_config: Pubkey,
+    create_pool_fee: u64,
+) -> Result<Vec<Instruction>> {
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(admin));
+    let program = client.program(config.raydium_v3_program)?;
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::UpdateAmmConfig {
+            owner: program.payer(),
+            amm_config,
+        })
+        .args(raydium_instruction::UpdateCreatePoolFee { create_pool_fee })
+        .instructions()?;
+    Ok(instructions)
+}
+
+pub fn create_pool_creator_permission_instr(
+    config: &ClientConfig,
+    amm_config: Pubkey,
+    creator: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(admin));
+    let program = client.program(config.raydium_v3_program)?;
+    let (pool_creator_permission, __bump) = Pubkey::find_program_address(
+        &[
+            POOL_CREATOR_PERMISSION_SEED.as_bytes(),
+            amm_config.to_bytes().as_ref(),
+            creator.to_bytes().as_ref(),
+        ],
+        &program.id(),
+    );
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::CreatePoolCreatorPermission {
+            owner: program.payer(),
+            amm_config,
+            creator,
+            pool_creator_permission,
+            system_program: system_program::id(),
+        })
+        .args(raydium_instruction::CreatePoolCreatorPermission)
+        .instructions()?;
+    Ok(instructions)
+}
+
+pub fn close_pool_creator_permission_instr(
+    config: &ClientConfig,
+    amm_config: Pubkey,
+    creator: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(admin));
+    let program = client.program(config.raydium_v3_program)?;
+    let (pool_creator_permission, __bump) = Pubkey::find_program_address(
+        &[
+            POOL_CREATOR_PERMISSION_SEED.as_bytes(),
+            amm_config.to_bytes().as_ref(),
+            creator.to_bytes().as_ref(),
+        ],
+        &program.id(),
+    );
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::ClosePoolCreatorPermission {
+            owner: program.payer(),
+            amm_config,
+            pool_creator_permission,
+        })
+        .args(raydium_instruction::ClosePoolCreatorPermission)
+        .instructions()?;
+    Ok(instructions)
+}
+
 pub fn create_operation_account_instr(config: &ClientConfig) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.admin_path)?;
+    let payer = config.admin()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let (operation_account_key, __bump) =
         Pubkey::find_program_address(&[OPERATION_SEED.as_bytes()], &program.id());
@@ -101,10 +315,10 @@ pub fn update_operation_account_instr(
     param: u8,
     keys: Vec<Pubkey>,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.admin_path)?;
+    let payer = config.admin()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let (operation_account_key, __bump) =
         Pubkey::find_program_address(&[OPERATION_SEED.as_bytes()], &program.id());
@@ -128,13 +342,14 @@ pub fn create_pool_instr(
     token_program_0: Pubkey,
     token_program_1: Pubkey,
     tick_array_bitmap: Pubkey,
+    remaining_accounts: Vec<AccountMeta>,
     sqrt_price_x64: u128,
     open_time: u64,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let (pool_account_key, __bump) = Pubkey::find_program_address(
         &[
@@ -185,6 +400,7 @@ pub fn create_pool_instr(
             system_program: system_program::id(),
             rent: sysvar::rent::id(),
         })
+        .accounts(remaining_accounts)
         .args(raydium_instruction::CreatePool {
             sqrt_price_x64,
             open_time,
@@ -193,6 +409,45 @@ pub fn create_pool_instr(
     Ok(instructions)
 }
 
+pub fn initialize_tick_arrays_instr(
+    config: &ClientConfig,
+    pool_account_key: Pubkey,
+    tick_array_start_indexs: Vec<i32>,
+) -> Result<Vec<Instruction>> {
+    let payer = config.payer()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(payer));
+    let program = client.program(config.raydium_v3_program)?;
+    let remaining_accounts = tick_array_start_indexs
+        .iter()
+        .map(|start_index| {
+            let (tick_array, __bump) = Pubkey::find_program_address(
+                &[
+                    TICK_ARRAY_SEED.as_bytes(),
+                    pool_account_key.to_bytes().as_ref(),
+                    &start_index.to_be_bytes(),
+                ],
+                &program.id(),
+            );
+            AccountMeta::new(tick_array, false)
+        })
+        .collect::<Vec<_>>();
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::InitializeTickArrays {
+            payer: program.payer(),
+            pool_state: pool_account_key,
+            system_program: system_program::id(),
+        })
+        .accounts(remaining_accounts)
+        .args(raydium_instruction::InitializeTickArrays {
+            tick_array_start_indexs,
+        })
+        .instructions()?;
+    Ok(instructions)
+}
+
 pub fn open_position_instr(
     config: &ClientConfig,
     pool_account_key: Pubkey,
@@ -214,13 +469,14 @@ pub fn open_position_instr(
     tick_array_upper_start_index: i32,
     with_metadata: bool,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
+    // the payer pays the rent and the deposit, the nft goes to the owner's ata
     let nft_ata_token_account =
-        spl_associated_token_account::get_associated_token_address(&program.payer(), &nft_mint_key);
+        spl_associated_token_account::get_associated_token_address(&nft_to_owner, &nft_mint_key);
     let (metadata_account_key, _bump) = Pubkey::find_program_address(
         &[
             Metadata::PREFIX,
@@ -285,6 +541,7 @@ pub fn open_position_instr(
             vault_1_mint: token_mint_1,
         })
         .accounts(remaining_accounts)
+        .accounts(vec![emergency_pause_account(&program.id())])
         .args(raydium_instruction::OpenPositionV2 {
             liquidity,
             amount_0_max,
@@ -321,14 +578,14 @@ pub fn open_position_with_token22_nft_instr(
     tick_array_upper_start_index: i32,
     with_metadata: bool,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let nft_ata_token_account =
         spl_associated_token_account::get_associated_token_address_with_program_id(
-            &program.payer(),
+            &nft_to_owner,
             &nft_mint_key,
             &spl_token_2022::id(),
         );
@@ -386,6 +643,7 @@ pub fn open_position_with_token22_nft_instr(
             vault_1_mint: token_mint_1,
         })
         .accounts(remaining_accounts)
+        .accounts(vec![emergency_pause_account(&program.id())])
         .args(raydium_instruction::OpenPositionWithToken22Nft {
             liquidity,
             amount_0_max,
@@ -421,10 +679,10 @@ pub fn increase_liquidity_instr(
     tick_array_lower_start_index: i32,
     tick_array_upper_start_index: i32,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let (tick_array_lower, __bump) = Pubkey::find_program_address(
         &[
@@ -476,6 +734,7 @@ pub fn increase_liquidity_instr(
             vault_1_mint: token_mint_1,
         })
         .accounts(remaining_accounts)
+        .accounts(vec![emergency_pause_account(&program.id())])
         .args(raydium_instruction::IncreaseLiquidityV2 {
             liquidity,
             amount_0_max,
@@ -486,6 +745,91 @@ pub fn increase_liquidity_instr(
     Ok(instructions)
 }
 
+/// Add liquidity paid by the payer to the position of the nft, whoever owns it
+pub fn donate_liquidity_instr(
+    config: &ClientConfig,
+    pool_account_key: Pubkey,
+    token_vault_0: Pubkey,
+    token_vault_1: Pubkey,
+    token_mint_0: Pubkey,
+    token_mint_1: Pubkey,
+    nft_mint_key: Pubkey,
+    user_token_account_0: Pubkey,
+    user_token_account_1: Pubkey,
+    remaining_accounts: Vec<AccountMeta>,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+) -> Result<Vec<Instruction>> {
+    let payer = config.payer()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(payer));
+    let program = client.program(config.raydium_v3_program)?;
+    let (tick_array_lower, __bump) = Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_account_key.to_bytes().as_ref(),
+            &tick_array_lower_start_index.to_be_bytes(),
+        ],
+        &program.id(),
+    );
+    let (tick_array_upper, __bump) = Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_account_key.to_bytes().as_ref(),
+            &tick_array_upper_start_index.to_be_bytes(),
+        ],
+        &program.id(),
+    );
+    let (protocol_position_key, __bump) = Pubkey::find_program_address(
+        &[
+            POSITION_SEED.as_bytes(),
+            pool_account_key.to_bytes().as_ref(),
+            &tick_lower_index.to_be_bytes(),
+            &tick_upper_index.to_be_bytes(),
+        ],
+        &program.id(),
+    );
+    let (personal_position_key, __bump) = Pubkey::find_program_address(
+        &[POSITION_SEED.as_bytes(), nft_mint_key.to_bytes().as_ref()],
+        &program.id(),
+    );
+
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::DonateLiquidity {
+            donor: program.payer(),
+            pool_state: pool_account_key,
+            protocol_position: protocol_position_key,
+            personal_position: personal_position_key,
+            tick_array_lower,
+            tick_array_upper,
+            token_account_0: user_token_account_0,
+            token_account_1: user_token_account_1,
+            token_vault_0,
+            token_vault_1,
+            token_program: spl_token::id(),
+            token_program_2022: spl_token_2022::id(),
+            vault_0_mint: token_mint_0,
+            vault_1_mint: token_mint_1,
+        })
+        .accounts(remaining_accounts)
+        .accounts(vec![emergency_pause_account(&program.id())])
+        .args(raydium_instruction::DonateLiquidity {
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            base_flag: None,
+        })
+        .instructions()?;
+    Ok(instructions)
+}
+
 pub fn decrease_liquidity_instr(
     config: &ClientConfig,
     pool_account_key: Pubkey,
@@ -506,10 +850,10 @@ pub fn decrease_liquidity_instr(
     tick_array_lower_start_index: i32,
     tick_array_upper_start_index: i32,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let (personal_position_key, __bump) = Pubkey::find_program_address(
         &[POSITION_SEED.as_bytes(), nft_mint_key.to_bytes().as_ref()],
@@ -576,10 +920,10 @@ pub fn close_personal_position_instr(
     nft_token_key: Pubkey,
     nft_token_program: Pubkey,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let (personal_position_key, __bump) = Pubkey::find_program_address(
         &[POSITION_SEED.as_bytes(), nft_mint_key.to_bytes().as_ref()],
@@ -600,6 +944,55 @@ pub fn close_personal_position_instr(
     Ok(instructions)
 }
 
+/// Settle the fee growth checkpoints of a position, signed by the admin to repair an inconsistent
+/// checkpoint
+pub fn settle_position_fee_growth_instr(
+    config: &ClientConfig,
+    pool_account_key: Pubkey,
+    personal_position_key: Pubkey,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32,
+    repair: bool,
+) -> Result<Vec<Instruction>> {
+    let authority = if repair {
+        config.admin()?
+    } else {
+        config.payer()?
+    };
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(authority));
+    let program = client.program(config.raydium_v3_program)?;
+    let (tick_array_lower, __bump) = Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_account_key.to_bytes().as_ref(),
+            &tick_array_lower_start_index.to_be_bytes(),
+        ],
+        &program.id(),
+    );
+    let (tick_array_upper, __bump) = Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_account_key.to_bytes().as_ref(),
+            &tick_array_upper_start_index.to_be_bytes(),
+        ],
+        &program.id(),
+    );
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::SettlePositionFeeGrowth {
+            authority: program.payer(),
+            pool_state: pool_account_key,
+            personal_position: personal_position_key,
+            tick_array_lower,
+            tick_array_upper,
+        })
+        .args(raydium_instruction::SettlePositionFeeGrowth)
+        .instructions()?;
+    Ok(instructions)
+}
+
 pub fn swap_instr(
     config: &ClientConfig,
     amm_config: Pubkey,
@@ -616,10 +1009,10 @@ pub fn swap_instr(
     sqrt_price_limit_x64: Option<u128>,
     is_base_input: bool,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let instructions = program
         .request()
@@ -636,6 +1029,7 @@ pub fn swap_instr(
             token_program: spl_token::id(),
         })
         .accounts(remaining_accounts)
+        .accounts(vec![emergency_pause_account(&program.id())])
         .args(raydium_instruction::Swap {
             amount,
             other_amount_threshold,
@@ -663,10 +1057,10 @@ pub fn swap_v2_instr(
     sqrt_price_limit_x64: Option<u128>,
     is_base_input: bool,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(config.raydium_v3_program)?;
     let instructions = program
         .request()
@@ -686,6 +1080,7 @@ pub fn swap_v2_instr(
             output_vault_mint,
         })
         .accounts(remaining_accounts)
+        .accounts(vec![emergency_pause_account(&program.id())])
         .args(raydium_instruction::SwapV2 {
             amount,
             other_amount_threshold,
@@ -709,10 +1104,10 @@ pub fn initialize_reward_instr(
     end_time: u64,
     emissions_per_second_x64: u128,
 ) -> Result<Vec<Instruction>> {
-    let admin = read_keypair_file(&config.admin_path)?;
+    let admin = config.admin()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(admin));
+    let client = Client::new(url, Arc::new(admin));
     let program = client.program(config.raydium_v3_program)?;
 
     let instructions = program
@@ -740,6 +1135,26 @@ pub fn initialize_reward_instr(
     Ok(instructions)
 }
 
+/// Accrue the rewards of the pool up to now, anyone can sign it
+pub fn update_reward_infos_instr(
+    config: &ClientConfig,
+    pool_account_key: Pubkey,
+) -> Result<Vec<Instruction>> {
+    let payer = config.payer()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(payer));
+    let program = client.program(config.raydium_v3_program)?;
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::UpdateRewardInfos {
+            pool_state: pool_account_key,
+        })
+        .args(raydium_instruction::UpdateRewardInfos)
+        .instructions()?;
+    Ok(instructions)
+}
+
 pub fn set_reward_params_instr(
     config: &ClientConfig,
     amm_config: Pubkey,
@@ -752,10 +1167,10 @@ pub fn set_reward_params_instr(
     end_time: u64,
     emissions_per_second_x64: u128,
 ) -> Result<Vec<Instruction>> {
-    let admin = read_keypair_file(&config.admin_path)?;
+    let admin = config.admin()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(admin));
+    let client = Client::new(url, Arc::new(admin));
     let program = client.program(config.raydium_v3_program)?;
 
     let remaining_accounts = vec![
@@ -785,6 +1200,82 @@ pub fn set_reward_params_instr(
     Ok(instructions)
 }
 
+pub fn collect_protocol_fee_instr(
+    config: &ClientConfig,
+    pool_account_key: Pubkey,
+    pool: &PoolState,
+    recipient_token_account_0: Pubkey,
+    recipient_token_account_1: Pubkey,
+    amount_0_requested: u64,
+    amount_1_requested: u64,
+) -> Result<Vec<Instruction>> {
+    let payer = config.payer()?;
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(payer));
+    let program = client.program(config.raydium_v3_program)?;
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::CollectProtocolFee {
+            owner: admin.pubkey(),
+            pool_state: pool_account_key,
+            amm_config: pool.amm_config,
+            token_vault_0: pool.token_vault_0,
+            token_vault_1: pool.token_vault_1,
+            vault_0_mint: pool.token_mint_0,
+            vault_1_mint: pool.token_mint_1,
+            recipient_token_account_0,
+            recipient_token_account_1,
+            token_program: spl_token::id(),
+            token_program_2022: spl_token_2022::id(),
+        })
+        .args(raydium_instruction::CollectProtocolFee {
+            amount_0_requested,
+            amount_1_requested,
+        })
+        .instructions()?;
+    Ok(instructions)
+}
+
+pub fn collect_fund_fee_instr(
+    config: &ClientConfig,
+    pool_account_key: Pubkey,
+    pool: &PoolState,
+    recipient_token_account_0: Pubkey,
+    recipient_token_account_1: Pubkey,
+    amount_0_requested: u64,
+    amount_1_requested: u64,
+) -> Result<Vec<Instruction>> {
+    let payer = config.payer()?;
+    let admin = config.admin()?;
+    let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
+    // Client.
+    let client = Client::new(url, Arc::new(payer));
+    let program = client.program(config.raydium_v3_program)?;
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::CollectFundFee {
+            owner: admin.pubkey(),
+            pool_state: pool_account_key,
+            amm_config: pool.amm_config,
+            token_vault_0: pool.token_vault_0,
+            token_vault_1: pool.token_vault_1,
+            vault_0_mint: pool.token_mint_0,
+            vault_1_mint: pool.token_mint_1,
+            recipient_token_account_0,
+            recipient_token_account_1,
+            token_program: spl_token::id(),
+            token_program_2022: spl_token_2022::id(),
+        })
+        .args(raydium_instruction::CollectFundFee {
+            amount_0_requested,
+            amount_1_requested,
+        })
+        .instructions()?;
+    Ok(instructions)
+}
+
 pub fn transfer_reward_owner(
     config: &ClientConfig,
     pool_account_key: Pubkey,
@@ -792,10 +1283,10 @@ pub fn transfer_reward_owner(
     encode: bool,
     authority: Option<Pubkey>,
 ) -> Result<Vec<Instruction>> {
-    let admin = read_keypair_file(&config.admin_path)?;
+    let admin = config.admin()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(admin));
+    let client = Client::new(url, Arc::new(admin));
     let program = client.program(config.raydium_v3_program)?;
 
     let instructions = program
@@ -0,0 +1,152 @@
+use anchor_client::solana_client::rpc_client::{
+    GetConfirmedSignaturesForAddress2Config, RpcClient,
+};
+use anchor_client::solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use anyhow::Result;
+use raydium_amm_v3::libraries::liquidity_math;
+use raydium_amm_v3::states::{AmmConfig, PoolState, FEE_RATE_DENOMINATOR_VALUE};
+
+use super::indexer::{
+    get_transactions_throttled, record_transaction_events, IndexedEventData, PoolIndex,
+};
+use super::portfolio::ui_amount;
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+// liquidity of the hypothetical position valuing a range
+const UNIT_LIQUIDITY: u128 = 1_000_000_000_000;
+
+/// The swaps of a pool in its latest transactions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapSample {
+    pub swaps: u64,
+    /// Amounts swapped in of each token, raw amounts
+    pub volume_0: u128,
+    pub volume_1: u128,
+    /// Seconds between the oldest and the newest sampled swap
+    pub seconds: i64,
+}
+
+/// Sample the swaps of the latest `limit` transactions of the pool
+pub fn sample_pool_swaps(
+    rpc_client: &RpcClient,
+    program_str: &str,
+    pool_id: &Pubkey,
+    limit: usize,
+    concurrency: usize,
+    requests_per_second: u32,
+) -> Result<SwapSample> {
+    let signatures: Vec<String> = rpc_client
+        .get_signatures_for_address_with_config(
+            pool_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before: None,
+                until: None,
+                limit: Some(limit),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?
+        .into_iter()
+        .filter(|signature_info| signature_info.err.is_none())
+        .map(|signature_info| signature_info.signature)
+        .collect();
+    let txs =
+        get_transactions_throttled(rpc_client, &signatures, concurrency, requests_per_second)?;
+    let mut pool_index = PoolIndex::default();
+    for (signature, tx) in signatures.iter().zip(txs) {
+        record_transaction_events(program_str, &mut pool_index, signature, tx)?;
+    }
+
+    let pool_str = pool_id.to_string();
+    let mut sample = SwapSample::default();
+    let mut block_times = (i64::MAX, i64::MIN);
+    for indexed in pool_index.events.iter() {
+        if let IndexedEventData::Swap {
+            pool_state,
+            amount_0,
+            amount_1,
+            zero_for_one,
+            ..
+        } = &indexed.event
+        {
+            // a route swaps through other pools in the same transaction
+            if *pool_state != pool_str {
+                continue;
+            }
+            sample.swaps += 1;
+            if *zero_for_one {
+                sample.volume_0 += u128::from(*amount_0);
+            } else {
+                sample.volume_1 += u128::from(*amount_1);
+            }
+            if let Some(block_time) = indexed.block_time {
+                block_times = (block_times.0.min(block_time), block_times.1.max(block_time));
+            }
+        }
+    }
+    sample.seconds = (block_times.1 - block_times.0).max(0);
+    Ok(sample)
+}
+
+/// The fees the liquidity providers earn in a year at the volume of the sample, in token_1 ui
+/// amount at the price. The protocol and fund shares of the trade fee are left out.
+pub fn yearly_lp_fees(
+    sample: &SwapSample,
+    amm_config: &AmmConfig,
+    price: f64,
+    decimals_0: u8,
+    decimals_1: u8,
+) -> f64 {
+    if sample.seconds == 0 {
+        return 0.0;
+    }
+    let denominator = f64::from(FEE_RATE_DENOMINATOR_VALUE);
+    let lp_share =
+        1.0 - f64::from(amm_config.protocol_fee_rate + amm_config.fund_fee_rate) / denominator;
+    let fee_rate = f64::from(amm_config.trade_fee_rate) / denominator * lp_share;
+    let volume = sample.volume_0 as f64 / 10f64.powi(i32::from(decimals_0)) * price
+        + sample.volume_1 as f64 / 10f64.powi(i32::from(decimals_1));
+    volume * fee_rate * SECONDS_PER_YEAR / sample.seconds as f64
+}
+
+/// The APR in percent of liquidity in range worth `value`, it earns the share of the fees of its
+/// liquidity in the active liquidity as long as the price stays in range
+pub fn liquidity_apr(
+    yearly_lp_fees: f64,
+    liquidity: u128,
+    active_liquidity: u128,
+    value: f64,
+) -> f64 {
+    if active_liquidity == 0 || value <= 0.0 {
+        return 0.0;
+    }
+    yearly_lp_fees * (liquidity as f64 / active_liquidity as f64) / value * 100.0
+}
+
+/// The APR in percent of a new position of the range, 0 when the range is not around the price.
+/// It is the marginal APR, the position is assumed too small to dilute the fees of the pool.
+pub fn range_apr(
+    yearly_lp_fees: f64,
+    pool_state: &PoolState,
+    price: f64,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Result<f64> {
+    if pool_state.tick_current < tick_lower || pool_state.tick_current >= tick_upper {
+        return Ok(0.0);
+    }
+    let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
+        pool_state.tick_current,
+        pool_state.sqrt_price_x64,
+        tick_lower,
+        tick_upper,
+        UNIT_LIQUIDITY as i128,
+    )?;
+    let value = ui_amount(amount_0, pool_state.mint_decimals_0) * price
+        + ui_amount(amount_1, pool_state.mint_decimals_1);
+    Ok(liquidity_apr(
+        yearly_lp_fees,
+        UNIT_LIQUIDITY,
+        pool_state.liquidity,
+        value,
+    ))
+}
@@ -0,0 +1,78 @@
+use anchor_client::solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::RpcClientConfig, rpc_sender::RpcSender,
+};
+use anchor_client::solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey,
+};
+use anyhow::Result;
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::Runtime;
+use tokio::task::JoinSet;
+
+use super::utils::MAX_MULTIPLE_ACCOUNTS;
+
+// set once the async rpc is enabled, for the rest of the process
+static ASYNC_RPC: OnceLock<AsyncRpc> = OnceLock::new();
+
+/// A nonblocking rpc client with the runtime driving it. The requests of a future are sent
+/// concurrently over the connections of one http client, instead of a thread per request.
+pub struct AsyncRpc {
+    runtime: Runtime,
+    client: RpcClient,
+}
+
+impl AsyncRpc {
+    pub fn client(&self) -> &RpcClient {
+        &self.client
+    }
+
+    /// Run the future to completion from the blocking code of the commands
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        // the grpc service calls the commands code from its own runtime
+        tokio::task::block_in_place(|| self.runtime.block_on(future))
+    }
+}
+
+/// Send the concurrent requests through a nonblocking client over the sender. The client keeps its
+/// own connections, the ones of the blocking client only make progress while it is called.
+pub fn enable_async_rpc(sender: impl RpcSender + Send + Sync + 'static) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .thread_name("async-rpc")
+        .enable_all()
+        .build()?;
+    let client = RpcClient::new_sender(
+        sender,
+        RpcClientConfig::with_commitment(CommitmentConfig::default()),
+    );
+    let _ = ASYNC_RPC.set(AsyncRpc { runtime, client });
+    Ok(())
+}
+
+/// The async rpc, None unless it is enabled
+pub fn async_rpc() -> Option<&'static AsyncRpc> {
+    ASYNC_RPC.get()
+}
+
+/// Fetch any number of accounts, the chunks of MAX_MULTIPLE_ACCOUNTS are requested concurrently and
+/// the accounts returned in the order of the keys
+pub async fn get_multiple_accounts_async(
+    client: &'static RpcClient,
+    pubkeys: &[Pubkey],
+) -> Result<Vec<Option<Account>>> {
+    let mut requests = JoinSet::new();
+    for (index, chunk) in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS).enumerate() {
+        let chunk = chunk.to_vec();
+        requests.spawn(async move { (index, client.get_multiple_accounts(&chunk).await) });
+    }
+    let mut chunks = Vec::with_capacity(requests.len());
+    while let Some(result) = requests.join_next().await {
+        let (index, accounts) = result?;
+        chunks.push((index, accounts?));
+    }
+    chunks.sort_by_key(|(index, _)| *index);
+    Ok(chunks
+        .into_iter()
+        .flat_map(|(_, accounts)| accounts)
+        .collect())
+}
@@ -0,0 +1,120 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use raydium_amm_v3::libraries::{check_current_tick_array_is_initialized, tick_math, U1024};
+use raydium_amm_v3::states::{
+    PoolState, TickArrayBitmapExtension, TickArrayState, TICK_ARRAY_SEED,
+};
+
+use super::rpc::get_multiple_accounts_concurrently;
+
+/// One tick array of the bitmap map of a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickArraySlot {
+    pub start_index: i32,
+    /// Flagged initialized in the default bitmap or its extension
+    pub flagged: bool,
+    /// The tick array account exists
+    pub exists: bool,
+}
+
+impl TickArraySlot {
+    /// A swap expects the account of a flagged array, it fails when it is missing
+    pub fn is_missing(&self) -> bool {
+        self.flagged && !self.exists
+    }
+}
+
+/// Whether the tick array starting at the index is flagged initialized, in the default bitmap of
+/// the pool or in its extension beyond the range of the default one
+pub fn is_tick_array_flagged(
+    pool_state: &PoolState,
+    tickarray_bitmap_extension: &TickArrayBitmapExtension,
+    start_index: i32,
+) -> Result<bool> {
+    if pool_state.is_overflow_default_tickarray_bitmap(&[start_index]) {
+        Ok(tickarray_bitmap_extension
+            .check_tick_array_is_initialized(start_index, pool_state.tick_spacing)?
+            .0)
+    } else {
+        // the first array starts below the min tick, any tick of the array locates it
+        Ok(check_current_tick_array_is_initialized(
+            U1024(pool_state.tick_array_bitmap),
+            start_index.max(tick_math::MIN_TICK),
+            pool_state.tick_spacing,
+        )?
+        .0)
+    }
+}
+
+/// The tick arrays within `arrays` arrays below and above the one of the current tick, with their
+/// flag in the bitmaps and whether their account exists
+pub fn tick_array_map(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_id: &Pubkey,
+    pool_state: &PoolState,
+    tickarray_bitmap_extension: &TickArrayBitmapExtension,
+    arrays: u32,
+) -> Result<Vec<TickArraySlot>> {
+    let tick_spacing = pool_state.tick_spacing;
+    let tick_count = TickArrayState::tick_count(tick_spacing);
+    let current_start =
+        TickArrayState::get_array_start_index(pool_state.tick_current, tick_spacing);
+    let min_start = TickArrayState::get_array_start_index(tick_math::MIN_TICK, tick_spacing);
+    let max_start = TickArrayState::get_array_start_index(tick_math::MAX_TICK, tick_spacing);
+    let arrays = i64::from(arrays);
+    let start_indexes: Vec<i32> = (-arrays..=arrays)
+        .map(|offset| i64::from(current_start) + offset * i64::from(tick_count))
+        .filter(|start_index| {
+            i64::from(min_start) <= *start_index && *start_index <= i64::from(max_start)
+        })
+        .map(|start_index| start_index as i32)
+        .collect();
+    let tick_array_keys: Vec<Pubkey> = start_indexes
+        .iter()
+        .map(|start_index| {
+            Pubkey::find_program_address(
+                &[
+                    TICK_ARRAY_SEED.as_bytes(),
+                    pool_id.as_ref(),
+                    &start_index.to_be_bytes(),
+                ],
+                program_id,
+            )
+            .0
+        })
+        .collect();
+    let accounts = get_multiple_accounts_concurrently(rpc_client, &tick_array_keys)?;
+    let mut slots = Vec::with_capacity(start_indexes.len());
+    for (start_index, account) in start_indexes.into_iter().zip(accounts) {
+        slots.push(TickArraySlot {
+            start_index,
+            flagged: is_tick_array_flagged(pool_state, tickarray_bitmap_extension, start_index)?,
+            exists: account.is_some(),
+        });
+    }
+    Ok(slots)
+}
+
+/// The runs of consecutive unflagged arrays between two flagged ones, as (first, last) start
+/// indexes. The price jumps over them without liquidity.
+pub fn tick_array_gaps(slots: &[TickArraySlot]) -> Vec<(i32, i32)> {
+    let mut gaps = Vec::new();
+    let mut run: Option<(i32, i32)> = None;
+    let mut seen_flagged = false;
+    for slot in slots {
+        if slot.flagged {
+            if let (Some(gap), true) = (run.take(), seen_flagged) {
+                gaps.push(gap);
+            }
+            seen_flagged = true;
+        } else {
+            run = Some(match run {
+                Some((first, _)) => (first, slot.start_index),
+                None => (slot.start_index, slot.start_index),
+            });
+        }
+    }
+    gaps
+}
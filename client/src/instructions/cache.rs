@@ -0,0 +1,259 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::{account::Account, pubkey::Pubkey};
+use anchor_lang::Discriminator;
+use anyhow::Result;
+use raydium_amm_v3::states::AmmConfig;
+use spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::rpc::get_multiple_accounts;
+
+/// Disk cache for accounts which essentially never change, such as mints. Entries are keyed by
+/// cluster and pubkey and expire after `ttl` seconds. The token-2022 mints with a transfer fee are
+/// never cached, their fee changes at an epoch boundary. The amm configs, whose fees and pool
+/// creation settings the admin updates, are only kept in memory for the life of the process.
+pub struct AccountCache {
+    dir: PathBuf,
+    ttl: u64,
+    amm_configs: Mutex<HashMap<Pubkey, Account>>,
+}
+
+impl AccountCache {
+    pub fn new(cache_dir: &str, http_url: &str, ttl: u64) -> Self {
+        // one sub directory for each cluster, so devnet and mainnet accounts never mix
+        let cluster: String = http_url
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        Self {
+            dir: PathBuf::from(cache_dir).join(cluster),
+            ttl,
+            amm_configs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load the account from the cache, fetch and cache it if it is missing or expired
+    pub fn get_account(&self, rpc_client: &RpcClient, key: &Pubkey) -> Result<Account> {
+        if let Some(account) = self.read(key) {
+            return Ok(account);
+        }
+        let account = rpc_client.get_account(key)?;
+        self.write(key, &account);
+        Ok(account)
+    }
+
+    /// Same as `get_account` for multiple keys, only missing or expired accounts are fetched
+    pub fn get_multiple_accounts(
+        &self,
+        rpc_client: &RpcClient,
+        keys: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>> {
+        let mut accounts: Vec<Option<Account>> = keys.iter().map(|key| self.read(key)).collect();
+        let missing_keys: Vec<Pubkey> = keys
+            .iter()
+            .zip(accounts.iter())
+            .filter(|(_, account)| account.is_none())
+            .map(|(key, _)| *key)
+            .collect();
+        if missing_keys.is_empty() {
+            return Ok(accounts);
+        }
+        let mut rsps = get_multiple_accounts(rpc_client, &missing_keys)?.into_iter();
+        for (key, account) in keys.iter().zip(accounts.iter_mut()) {
+            if account.is_some() {
+                continue;
+            }
+            *account = rsps.next().unwrap();
+            if let Some(account) = account {
+                self.write(key, account);
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// Remove the cached account, the next load will fetch it again
+    pub fn invalidate(&self, key: &Pubkey) {
+        self.amm_configs.lock().unwrap().remove(key);
+        let _ = fs::remove_file(self.dir.join(key.to_string()));
+    }
+
+    fn read(&self, key: &Pubkey) -> Option<Account> {
+        if let Some(account) = self.amm_configs.lock().unwrap().get(key) {
+            return Some(account.clone());
+        }
+        let data = fs::read(self.dir.join(key.to_string())).ok()?;
+        let (fetched_at, account): (u64, Account) = bincode::deserialize(&data).ok()?;
+        if now().saturating_sub(fetched_at) > self.ttl {
+            return None;
+        }
+        Some(account)
+    }
+
+    // a failed write only means the account is fetched again next time
+    fn write(&self, key: &Pubkey, account: &Account) {
+        if is_amm_config(account) {
+            self.amm_configs
+                .lock()
+                .unwrap()
+                .insert(*key, account.clone());
+            return;
+        }
+        if has_transfer_fee_config(account) || fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        if let Ok(data) = bincode::serialize(&(now(), account)) {
+            let _ = fs::write(self.dir.join(key.to_string()), data);
+        }
+    }
+}
+
+struct SlotCacheEntry {
+    slot: u64,
+    account: Option<Account>,
+}
+
+/// Memory cache of the daemons loading the same accounts again and again, e.g. the quote loops.
+/// An account is served again to the loads at most `max_age_slots` after the slot it was fetched
+/// at, the immutable accounts for the life of the process but the token-2022 mints with a transfer
+/// fee. Disabled, every load is fetched.
+pub struct SlotCache {
+    entries: Mutex<HashMap<Pubkey, SlotCacheEntry>>,
+    max_age_slots: u64,
+    enabled: bool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SlotCache {
+    pub fn new(max_age_slots: u64, enabled: bool) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_age_slots,
+            enabled,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The accounts as of `slot`, the ones missing or fetched too long before are fetched
+    pub fn get_multiple_accounts(
+        &self,
+        rpc_client: &RpcClient,
+        keys: &[Pubkey],
+        slot: u64,
+    ) -> Result<Vec<Option<Account>>> {
+        self.load(keys, slot, |missing_keys| {
+            get_multiple_accounts(rpc_client, missing_keys)
+        })
+    }
+
+    /// The accounts which never change, such as mints and amm configs, fetched through the disk
+    /// cache only once per process
+    pub fn get_immutable_accounts(
+        &self,
+        rpc_client: &RpcClient,
+        account_cache: &AccountCache,
+        keys: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>> {
+        self.load(keys, u64::MAX, |missing_keys| {
+            account_cache.get_multiple_accounts(rpc_client, missing_keys)
+        })
+    }
+
+    /// Accounts served from the cache and accounts fetched since the start
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+
+    // the immutable accounts are stored at the last slot, no load is too late for them
+    fn load(
+        &self,
+        keys: &[Pubkey],
+        slot: u64,
+        fetch: impl FnOnce(&[Pubkey]) -> Result<Vec<Option<Account>>>,
+    ) -> Result<Vec<Option<Account>>> {
+        if !self.enabled {
+            self.misses.fetch_add(keys.len() as u64, Ordering::Relaxed);
+            return fetch(keys);
+        }
+        let mut accounts: Vec<Option<Option<Account>>> = {
+            let entries = self.entries.lock().unwrap();
+            keys.iter()
+                .map(|key| {
+                    entries
+                        .get(key)
+                        .filter(|entry| entry.slot.saturating_add(self.max_age_slots) >= slot)
+                        .map(|entry| entry.account.clone())
+                })
+                .collect()
+        };
+        let missing_keys: Vec<Pubkey> = keys
+            .iter()
+            .zip(accounts.iter())
+            .filter(|(_, account)| account.is_none())
+            .map(|(key, _)| *key)
+            .collect();
+        self.hits
+            .fetch_add((keys.len() - missing_keys.len()) as u64, Ordering::Relaxed);
+        self.misses
+            .fetch_add(missing_keys.len() as u64, Ordering::Relaxed);
+        if !missing_keys.is_empty() {
+            let mut rsps = fetch(&missing_keys)?.into_iter();
+            let mut entries = self.entries.lock().unwrap();
+            for (key, account) in keys.iter().zip(accounts.iter_mut()) {
+                if account.is_some() {
+                    continue;
+                }
+                let rsp = rsps.next().unwrap();
+                // an immutable account missing now may be created later, the transfer fee of a
+                // mint changes at an epoch boundary
+                let cacheable = match &rsp {
+                    Some(account) => slot != u64::MAX || !has_transfer_fee_config(account),
+                    None => slot != u64::MAX,
+                };
+                if cacheable {
+                    entries.insert(
+                        *key,
+                        SlotCacheEntry {
+                            slot,
+                            account: rsp.clone(),
+                        },
+                    );
+                }
+                *account = Some(rsp);
+            }
+        }
+        Ok(accounts.into_iter().map(Option::unwrap).collect())
+    }
+}
+
+/// Whether the account is a token-2022 mint with a transfer fee, whose fee may change at the next
+/// epoch
+fn has_transfer_fee_config(account: &Account) -> bool {
+    account.owner == spl_token_2022::id()
+        && StateWithExtensions::<Mint>::unpack(&account.data).map_or(false, |mint| {
+            mint.get_extension::<TransferFeeConfig>().is_ok()
+        })
+}
+
+fn is_amm_config(account: &Account) -> bool {
+    account.data.starts_with(&AmmConfig::DISCRIMINATOR)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,140 @@
+use anchor_client::solana_client::{
+    rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig,
+    rpc_response::RpcSimulateTransactionResult,
+};
+use anchor_client::solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
+    compute_budget::{self, ComputeBudgetInstruction},
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use anyhow::Result;
+use std::sync::OnceLock;
+
+// tag of the SetComputeUnitLimit variant of the compute budget instruction
+const SET_COMPUTE_UNIT_LIMIT_TAG: u8 = 2;
+// the most compute units a transaction can request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+// the margin added to the units consumed, set once the simulated limit is enabled
+static COMPUTE_UNIT_MARGIN_PERCENT: OnceLock<u32> = OnceLock::new();
+
+/// Set the compute unit limit of every transaction to the units its simulation consumes plus the
+/// margin, for the rest of the process
+pub fn enable_simulated_compute_unit_limit(margin_percent: u32) {
+    let _ = COMPUTE_UNIT_MARGIN_PERCENT.set(margin_percent);
+}
+
+pub fn compute_unit_margin_percent() -> Option<u32> {
+    COMPUTE_UNIT_MARGIN_PERCENT.get().copied()
+}
+
+pub fn is_set_compute_unit_limit(instruction: &Instruction) -> bool {
+    instruction.program_id == compute_budget::id()
+        && instruction.data.first() == Some(&SET_COMPUTE_UNIT_LIMIT_TAG)
+}
+
+/// The limit for the units consumed, with the margin and within the transaction maximum
+pub fn compute_unit_limit(units_consumed: u64, margin_percent: u32) -> u32 {
+    let limit = units_consumed.saturating_mul(100 + u64::from(margin_percent)) / 100;
+    u32::try_from(limit)
+        .unwrap_or(MAX_COMPUTE_UNIT_LIMIT)
+        .min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// Simulate the instructions with the maximum limit. The transaction is not signed, the
+/// simulation replaces its blockhash.
+pub fn simulate_with_max_compute_unit_limit(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<RpcSimulateTransactionResult> {
+    let mut simulated = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        MAX_COMPUTE_UNIT_LIMIT,
+    )];
+    simulated.extend(
+        instructions
+            .iter()
+            .filter(|instruction| !is_set_compute_unit_limit(instruction))
+            .cloned(),
+    );
+    let message = if lookup_tables.is_empty() {
+        VersionedMessage::Legacy(Message::new(&simulated, Some(payer)))
+    } else {
+        VersionedMessage::V0(v0::Message::try_compile(
+            payer,
+            &simulated,
+            lookup_tables,
+            Hash::default(),
+        )?)
+    };
+    let txn = VersionedTransaction {
+        signatures: vec![
+            Signature::default();
+            usize::from(message.header().num_required_signatures)
+        ],
+        message,
+    };
+    Ok(client
+        .simulate_transaction_with_config(
+            &txn,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(CommitmentConfig::processed()),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value)
+}
+
+/// Simulate the instructions with the maximum limit and return the units they consume, None when
+/// the simulation fails
+pub fn simulate_compute_units(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Option<u64>> {
+    let result = simulate_with_max_compute_unit_limit(client, instructions, payer, lookup_tables)?;
+    if let Some(err) = result.err {
+        println!("compute units not simulated, the simulation fails: {}", err);
+        return Ok(None);
+    }
+    Ok(result.units_consumed)
+}
+
+/// The instructions with the compute unit limit set from their simulation, replacing the limit
+/// they set, when the simulated limit is enabled. They are left as they are when the simulation
+/// fails, sending them reports the failure.
+pub fn with_simulated_compute_unit_limit(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Vec<Instruction>> {
+    let margin_percent = match compute_unit_margin_percent() {
+        Some(margin_percent) => margin_percent,
+        None => return Ok(instructions.to_vec()),
+    };
+    let units_consumed = match simulate_compute_units(client, instructions, payer, lookup_tables)? {
+        Some(units_consumed) => units_consumed,
+        None => return Ok(instructions.to_vec()),
+    };
+    let mut limited_instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_unit_limit(units_consumed, margin_percent),
+    )];
+    limited_instructions.extend(
+        instructions
+            .iter()
+            .filter(|instruction| !is_set_compute_unit_limit(instruction))
+            .cloned(),
+    );
+    Ok(limited_instructions)
+}
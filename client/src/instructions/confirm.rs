@@ -0,0 +1,206 @@
+use anchor_client::solana_client::{
+    client_error::ClientError, rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig,
+};
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::Signature,
+    system_program,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super::rpc::get_durable_nonce;
+
+// interval of the signature status polling while waiting for the confirmation
+const POLL_INTERVAL: Duration = Duration::from_millis(400);
+// the transaction is sent again at this interval until it lands, a leader may have dropped it
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+// consecutive failed polls after which the rpc is deemed unavailable
+const MAX_POLL_ERRORS: u32 = 10;
+// tag of the AdvanceNonceAccount variant of the system instruction
+const ADVANCE_NONCE_ACCOUNT_TAG: [u8; 4] = [4, 0, 0, 0];
+
+/// Why a transaction was not confirmed
+#[derive(Debug)]
+pub enum ConfirmError {
+    /// The transaction landed and failed, sending it again fails the same way
+    Failed {
+        signature: Signature,
+        err: TransactionError,
+    },
+    /// The blockhash, or the durable nonce, expired without the transaction landing. It can't
+    /// land anymore, it is safe to sign the instructions again.
+    Expired { signature: Signature },
+    /// The rpc kept failing, the transaction may still land
+    Rpc {
+        signature: Signature,
+        err: anyhow::Error,
+    },
+}
+
+impl ConfirmError {
+    pub fn signature(&self) -> Signature {
+        match self {
+            ConfirmError::Failed { signature, .. }
+            | ConfirmError::Expired { signature }
+            | ConfirmError::Rpc { signature, .. } => *signature,
+        }
+    }
+}
+
+impl fmt::Display for ConfirmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfirmError::Failed { signature, err } => {
+                write!(f, "transaction {} failed: {}", signature, err)
+            }
+            ConfirmError::Expired { signature } => write!(
+                f,
+                "transaction {} expired without landing, it is safe to send it again",
+                signature
+            ),
+            ConfirmError::Rpc { signature, err } => write!(
+                f,
+                "transaction {} not confirmed, the rpc failed and it may still land: {}",
+                signature, err
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfirmError {}
+
+/// The nonce account whose durable nonce the transaction uses, advanced by its first instruction
+pub fn durable_nonce_account(txn: &VersionedTransaction) -> Option<Pubkey> {
+    let message = &txn.message;
+    let keys = message.static_account_keys();
+    let instruction = message.instructions().first()?;
+    if keys.get(usize::from(instruction.program_id_index)) != Some(&system_program::id())
+        || instruction.data.get(..4) != Some(&ADVANCE_NONCE_ACCOUNT_TAG)
+    {
+        return None;
+    }
+    keys.get(usize::from(*instruction.accounts.first()?))
+        .copied()
+}
+
+/// The error of a failed send which sending again can't fix, the transaction itself fails. The
+/// transaction may have reached the leader despite the other errors, e.g. of the connection or
+/// of a node behind the cluster, they are only reported.
+pub fn fatal_send_error(err: &ClientError) -> Option<TransactionError> {
+    match err.get_transaction_error() {
+        // landed already, the status tells how
+        Some(TransactionError::AlreadyProcessed) => None,
+        // the node may be behind, the expiry is checked against the status
+        Some(TransactionError::BlockhashNotFound) | None => None,
+        Some(err) => Some(err),
+    }
+}
+
+/// Whether the transaction can't land anymore: its blockhash expired, or the nonce it uses was
+/// advanced, by this transaction or another one
+fn is_expired(
+    client: &RpcClient,
+    txn: &VersionedTransaction,
+    commitment: CommitmentConfig,
+) -> anyhow::Result<bool> {
+    let blockhash = txn.message.recent_blockhash();
+    match durable_nonce_account(txn) {
+        Some(nonce_account) => Ok(get_durable_nonce(client, &nonce_account)?.0 != *blockhash),
+        None => Ok(!client.is_blockhash_valid(blockhash, commitment)?),
+    }
+}
+
+/// Send the transaction and wait until it is confirmed at the commitment. The signature status is
+/// polled and the transaction rebroadcast until it lands or expires, the status is checked once
+/// more after the expiry since it may land in between. A transaction processed by then is polled
+/// until it reaches the commitment, or is dropped with its fork. Failing sends are only reported,
+/// the transaction may have reached the leader anyway.
+pub fn send_and_confirm(
+    client: &RpcClient,
+    txn: &VersionedTransaction,
+    commitment: CommitmentConfig,
+) -> Result<Signature, ConfirmError> {
+    let signature = txn.signatures[0];
+    let rpc_err = |err: ClientError| ConfirmError::Rpc {
+        signature,
+        err: err.into(),
+    };
+    let mut last_broadcast: Option<Instant> = None;
+    let mut poll_errors = 0;
+    // processed past its expiry, it is no more rebroadcast
+    let mut expired = false;
+    loop {
+        let rebroadcast = !expired
+            && last_broadcast.map_or(true, |broadcast| {
+                broadcast.elapsed() >= REBROADCAST_INTERVAL
+            });
+        if rebroadcast {
+            if let Err(err) = client.send_transaction_with_config(
+                txn,
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    max_retries: Some(0),
+                    ..RpcSendTransactionConfig::default()
+                },
+            ) {
+                if let Some(err) = fatal_send_error(&err) {
+                    return Err(ConfirmError::Failed { signature, err });
+                }
+                println!("send {} failed: {}", signature, err);
+            }
+            last_broadcast = Some(Instant::now());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+
+        let status = match client.get_signature_statuses(&[signature]) {
+            Ok(response) => {
+                poll_errors = 0;
+                response.value.into_iter().next().flatten()
+            }
+            Err(err) => {
+                poll_errors += 1;
+                if poll_errors >= MAX_POLL_ERRORS {
+                    return Err(rpc_err(err));
+                }
+                continue;
+            }
+        };
+        match status {
+            Some(status) => {
+                if let Some(err) = status.err {
+                    return Err(ConfirmError::Failed { signature, err });
+                }
+                if status.satisfies_commitment(commitment) {
+                    return Ok(signature);
+                }
+                // processed, it is not rebroadcast while it reaches the commitment
+                last_broadcast = Some(Instant::now());
+            }
+            // the fork which processed it was dropped
+            None if expired => return Err(ConfirmError::Expired { signature }),
+            None if rebroadcast => {
+                expired = is_expired(client, txn, commitment)
+                    .map_err(|err| ConfirmError::Rpc { signature, err })?;
+                if expired {
+                    // it may have been processed in between, it is then polled until it reaches
+                    // the commitment
+                    match client
+                        .get_signature_status_with_commitment(
+                            &signature,
+                            CommitmentConfig::processed(),
+                        )
+                        .map_err(rpc_err)?
+                    {
+                        Some(Ok(())) => {}
+                        Some(Err(err)) => return Err(ConfirmError::Failed { signature, err }),
+                        None => return Err(ConfirmError::Expired { signature }),
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::HashMap;
+
+use super::indexer::{IndexCheckpoint, IndexedEvent, PoolIndex};
+use super::position_history::event_position_nft_mint;
+
+// the typed columns are the ones the reports filter on, the event itself is kept as json. The
+// views expose the amounts of the swaps, liquidity changes and fee collections to plain sql.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    signature TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    finalized INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    pool_id TEXT,
+    position_nft_mint TEXT,
+    data TEXT NOT NULL,
+    PRIMARY KEY (signature, seq)
+);
+CREATE INDEX IF NOT EXISTS events_pool_id ON events (pool_id, slot);
+CREATE INDEX IF NOT EXISTS events_position_nft_mint ON events (position_nft_mint, slot);
+CREATE INDEX IF NOT EXISTS events_finalized ON events (finalized);
+CREATE TABLE IF NOT EXISTS checkpoints (
+    address TEXT PRIMARY KEY,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL
+);
+CREATE VIEW IF NOT EXISTS swaps AS
+SELECT slot, block_time, signature, finalized, pool_id,
+    json_extract(data, '$.sender') AS sender,
+    json_extract(data, '$.zero_for_one') AS zero_for_one,
+    json_extract(data, '$.amount_0') AS amount_0,
+    json_extract(data, '$.amount_1') AS amount_1,
+    json_extract(data, '$.transfer_fee_0') AS transfer_fee_0,
+    json_extract(data, '$.transfer_fee_1') AS transfer_fee_1,
+    json_extract(data, '$.tick') AS tick
+FROM events WHERE kind = 'Swap';
+CREATE VIEW IF NOT EXISTS liquidity_changes AS
+SELECT slot, block_time, signature, finalized, position_nft_mint, kind,
+    coalesce(json_extract(data, '$.deposit_amount_0'), json_extract(data, '$.amount_0'),
+        -json_extract(data, '$.decrease_amount_0')) AS amount_0,
+    coalesce(json_extract(data, '$.deposit_amount_1'), json_extract(data, '$.amount_1'),
+        -json_extract(data, '$.decrease_amount_1')) AS amount_1
+FROM events
+WHERE kind IN ('CreatePersonalPosition', 'IncreaseLiquidity', 'DecreaseLiquidity');
+CREATE VIEW IF NOT EXISTS fee_collections AS
+SELECT slot, block_time, signature, finalized, position_nft_mint,
+    coalesce(json_extract(data, '$.fee_amount_0'), json_extract(data, '$.amount_0')) AS fees_0,
+    coalesce(json_extract(data, '$.fee_amount_1'), json_extract(data, '$.amount_1')) AS fees_1
+FROM events WHERE kind IN ('DecreaseLiquidity', 'CollectPersonalFee');
+";
+
+const EVENT_COLUMNS: &str = "slot, block_time, signature, finalized, data";
+
+/// SQLite storage of the program events written by Index. The events of all the pools are kept
+/// in one database, the reports read them from there instead of the rpc.
+pub struct EventDb {
+    connection: Connection,
+}
+
+impl EventDb {
+    pub fn open(path: &str) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        // the reports read while Index writes
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// The events not finalized yet and the ones of the slots scanned again before the checkpoint,
+    /// with the checkpoint of the tailed address. index_pool_events and finalize_pool_events
+    /// resume from it without fetching the transactions already stored.
+    pub fn load_pending(&self, address: &Pubkey, overlap_slots: u64) -> Result<PoolIndex> {
+        let checkpoint = self
+            .connection
+            .query_row(
+                "SELECT signature, slot FROM checkpoints WHERE address = ?1",
+                params![address.to_string()],
+                |row| {
+                    Ok(IndexCheckpoint {
+                        signature: row.get(0)?,
+                        slot: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        // null without checkpoint, no finalized event is scanned again then
+        let overlap_start = checkpoint
+            .as_ref()
+            .map(|checkpoint| checkpoint.slot.saturating_sub(overlap_slots));
+        let events = self.query_events(
+            &format!(
+                "SELECT {} FROM events WHERE finalized = 0 OR slot >= ?1
+                 ORDER BY slot, signature, seq",
+                EVENT_COLUMNS
+            ),
+            params![overlap_start],
+        )?;
+        Ok(PoolIndex {
+            events,
+            checkpoint,
+            backfill_cursor: None,
+        })
+    }
+
+    /// Replace the provisional events by the ones of `pool_index` and save the checkpoint of the
+    /// tailed address, in one transaction. An event already stored finalized is never replaced.
+    pub fn save_pending(&mut self, address: &Pubkey, pool_index: &PoolIndex) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        // the rolled back events are left out of pool_index, they are dropped with the others
+        transaction.execute("DELETE FROM events WHERE finalized = 0", params![])?;
+        {
+            let mut insert = transaction.prepare(
+                "INSERT OR IGNORE INTO events
+                 (signature, seq, slot, block_time, finalized, kind, pool_id, position_nft_mint, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            // the events of a transaction are recorded in the order of its logs
+            let mut seqs: HashMap<&str, u32> = HashMap::new();
+            for indexed in pool_index.events.iter() {
+                let seq = seqs.entry(indexed.signature.as_str()).or_default();
+                let data = serde_json::to_value(&indexed.event)?;
+                insert.execute(params![
+                    indexed.signature,
+                    *seq,
+                    indexed.slot,
+                    indexed.block_time,
+                    indexed.finalized,
+                    data["type"].as_str(),
+                    data["pool_state"].as_str(),
+                    event_position_nft_mint(&indexed.event),
+                    data.to_string(),
+                ])?;
+                *seq += 1;
+            }
+        }
+        if let Some(checkpoint) = &pool_index.checkpoint {
+            transaction.execute(
+                "INSERT OR REPLACE INTO checkpoints (address, signature, slot) VALUES (?1, ?2, ?3)",
+                params![address.to_string(), checkpoint.signature, checkpoint.slot],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The events of the transactions of the pool, as the event index of the pool would keep them
+    pub fn pool_index(&self, pool_id: &Pubkey) -> Result<PoolIndex> {
+        let events = self.query_events(
+            &format!(
+                "SELECT {} FROM events WHERE signature IN
+                 (SELECT signature FROM events WHERE pool_id = ?1)
+                 ORDER BY slot, signature, seq",
+                EVENT_COLUMNS
+            ),
+            params![pool_id.to_string()],
+        )?;
+        Ok(PoolIndex {
+            events,
+            checkpoint: None,
+            backfill_cursor: None,
+        })
+    }
+
+    /// The swaps of the pool since the slot, oldest first
+    pub fn pool_swaps(&self, pool_id: &Pubkey, since_slot: u64) -> Result<Vec<IndexedEvent>> {
+        self.query_events(
+            &format!(
+                "SELECT {} FROM events WHERE kind = 'Swap' AND pool_id = ?1 AND slot >= ?2
+                 ORDER BY slot, signature, seq",
+                EVENT_COLUMNS
+            ),
+            params![pool_id.to_string(), since_slot],
+        )
+    }
+
+    /// The events of the position, oldest first
+    pub fn position_events(&self, position_nft_mint: &Pubkey) -> Result<Vec<IndexedEvent>> {
+        self.query_events(
+            &format!(
+                "SELECT {} FROM events WHERE position_nft_mint = ?1 ORDER BY slot, signature, seq",
+                EVENT_COLUMNS
+            ),
+            params![position_nft_mint.to_string()],
+        )
+    }
+
+    fn query_events(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<IndexedEvent>> {
+        let mut statement = self.connection.prepare(sql)?;
+        let rows = statement.query_map(params, |row: &Row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+        let mut events = Vec::new();
+        for row in rows {
+            let (slot, block_time, signature, finalized, data) = row?;
+            events.push(IndexedEvent {
+                slot,
+                block_time,
+                signature,
+                finalized,
+                event: serde_json::from_str(&data)?,
+            });
+        }
+        Ok(events)
+    }
+}
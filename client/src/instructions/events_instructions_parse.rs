@@ -11,6 +11,7 @@ use regex::Regex;
 use solana_transaction_status::{
     option_serializer::OptionSerializer, EncodedTransaction, UiTransactionStatusMeta,
 };
+use std::collections::{BTreeMap, BTreeSet};
 
 const PROGRAM_LOG: &str = "Program log: ";
 const PROGRAM_DATA: &str = "Program data: ";
@@ -65,6 +66,143 @@ pub fn parse_program_event(
     Ok(())
 }
 
+/// Activity of one pool aggregated over the transactions of a block
+#[derive(Debug, Default)]
+pub struct PoolActivity {
+    pub transactions: u64,
+    pub swaps: u64,
+    pub swap_amount_0: u128,
+    pub swap_amount_1: u128,
+    pub swappers: BTreeSet<Pubkey>,
+    pub first_swap_tick: Option<i32>,
+    pub last_swap_tick: Option<i32>,
+    pub liquidity_changes: u64,
+    pub positions_opened: u64,
+    pub protocol_fee_collects: u64,
+    pub created: bool,
+}
+
+/// Collect the base64 event data logged by the program itself, cpi calls into other programs are skipped
+pub fn program_event_data(
+    self_program_str: &str,
+    meta: &UiTransactionStatusMeta,
+) -> Result<Vec<String>, ClientError> {
+    match &meta.log_messages {
+        OptionSerializer::Some(log_messages) => {
+            program_log_event_data(self_program_str, log_messages)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Collect the base64 event data logged by the program itself in the logs of a transaction or a
+/// simulation
+pub fn program_log_event_data(
+    self_program_str: &str,
+    logs: &[String],
+) -> Result<Vec<String>, ClientError> {
+    let mut logs = logs;
+    if logs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut event_data = Vec::new();
+    let mut execution = Execution::new(&mut logs)?;
+    for l in logs {
+        if !execution.is_empty() && self_program_str == execution.program() {
+            if l.starts_with(PROGRAM_LOG) {
+                continue;
+            }
+            if let Some(log) = l.strip_prefix(PROGRAM_DATA) {
+                event_data.push(log.to_string());
+                continue;
+            }
+        }
+        let (new_program, did_pop) = handle_system_log(self_program_str, l);
+        if let Some(new_program) = new_program {
+            execution.push(new_program);
+        }
+        if did_pop {
+            execution.pop();
+        }
+    }
+    Ok(event_data)
+}
+
+/// Decode the events emitted by the program in one transaction and add them to the per pool summary
+pub fn aggregate_program_event(
+    self_program_str: &str,
+    meta: &UiTransactionStatusMeta,
+    summary: &mut BTreeMap<Pubkey, PoolActivity>,
+) -> Result<(), ClientError> {
+    let mut touched_pools = BTreeSet::new();
+    for log in program_event_data(self_program_str, meta)? {
+        if let Some(pool) = aggregate_event(&log, summary)? {
+            touched_pools.insert(pool);
+        }
+    }
+    for pool in touched_pools {
+        summary.entry(pool).or_default().transactions += 1;
+    }
+    Ok(())
+}
+
+fn aggregate_event(
+    log: &str,
+    summary: &mut BTreeMap<Pubkey, PoolActivity>,
+) -> Result<Option<Pubkey>, ClientError> {
+    let borsh_bytes = match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, log)
+    {
+        Ok(borsh_bytes) if borsh_bytes.len() >= 8 => borsh_bytes,
+        _ => return Ok(None),
+    };
+    let mut slice: &[u8] = &borsh_bytes[8..];
+    let pool = match &borsh_bytes[..8] {
+        SwapEvent::DISCRIMINATOR => {
+            let event = decode_event::<SwapEvent>(&mut slice)?;
+            let activity = summary.entry(event.pool_state).or_default();
+            activity.swaps += 1;
+            activity.swap_amount_0 += u128::from(event.amount_0);
+            activity.swap_amount_1 += u128::from(event.amount_1);
+            activity.swappers.insert(event.sender);
+            activity.first_swap_tick.get_or_insert(event.tick);
+            activity.last_swap_tick = Some(event.tick);
+            event.pool_state
+        }
+        LiquidityChangeEvent::DISCRIMINATOR => {
+            let event = decode_event::<LiquidityChangeEvent>(&mut slice)?;
+            summary
+                .entry(event.pool_state)
+                .or_default()
+                .liquidity_changes += 1;
+            event.pool_state
+        }
+        CreatePersonalPositionEvent::DISCRIMINATOR => {
+            let event = decode_event::<CreatePersonalPositionEvent>(&mut slice)?;
+            summary
+                .entry(event.pool_state)
+                .or_default()
+                .positions_opened += 1;
+            event.pool_state
+        }
+        CollectProtocolFeeEvent::DISCRIMINATOR => {
+            let event = decode_event::<CollectProtocolFeeEvent>(&mut slice)?;
+            summary
+                .entry(event.pool_state)
+                .or_default()
+                .protocol_fee_collects += 1;
+            event.pool_state
+        }
+        PoolCreatedEvent::DISCRIMINATOR => {
+            let event = decode_event::<PoolCreatedEvent>(&mut slice)?;
+            summary.entry(event.pool_state).or_default().created = true;
+            event.pool_state
+        }
+        // the other events don't carry the pool key
+        _ => return Ok(None),
+    };
+    Ok(Some(pool))
+}
+
 struct Execution {
     stack: Vec<String>,
 }
@@ -211,7 +349,7 @@ fn handle_system_log(this_program_str: &str, log: &str) -> (Option<String>, bool
     }
 }
 
-fn decode_event<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+pub fn decode_event<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
     slice: &mut &[u8],
 ) -> Result<T, ClientError> {
     let event: T = anchor_lang::AnchorDeserialize::deserialize(slice)
@@ -308,6 +446,70 @@ pub fn parse_program_instruction(
     Ok(())
 }
 
+/// Data and accounts of the program instructions of a transaction in execution order,
+/// each top level instruction is followed by its inner instructions
+pub fn program_instructions(
+    self_program_str: &str,
+    encoded_transaction: &EncodedTransaction,
+    meta: &UiTransactionStatusMeta,
+) -> Vec<(Vec<u8>, Vec<String>)> {
+    let ui_raw_msg = match encoded_transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            solana_transaction_status::UiMessage::Raw(ui_raw_msg) => ui_raw_msg,
+            _ => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    let mut account_keys = ui_raw_msg.account_keys.clone();
+    if let OptionSerializer::Some(addresses) = &meta.loaded_addresses {
+        account_keys.extend(addresses.writable.iter().cloned());
+        account_keys.extend(addresses.readonly.iter().cloned());
+    }
+    let decode = |program_id_index: u8, accounts: &[u8], data: &str| {
+        if account_keys
+            .get(program_id_index as usize)
+            .map(|key| key.as_str())
+            != Some(self_program_str)
+        {
+            return None;
+        }
+        let data = bs58::decode(data).into_vec().ok()?;
+        let accounts = accounts
+            .iter()
+            .filter_map(|index| account_keys.get(*index as usize).cloned())
+            .collect();
+        Some((data, accounts))
+    };
+    let mut instructions = Vec::new();
+    for (i, ui_compiled_instruction) in ui_raw_msg.instructions.iter().enumerate() {
+        instructions.extend(decode(
+            ui_compiled_instruction.program_id_index,
+            &ui_compiled_instruction.accounts,
+            &ui_compiled_instruction.data,
+        ));
+        if let OptionSerializer::Some(inner_instructions) = &meta.inner_instructions {
+            for inner in inner_instructions
+                .iter()
+                .filter(|inner| inner.index as usize == i)
+            {
+                for instruction in inner.instructions.iter() {
+                    if let solana_transaction_status::UiInstruction::Compiled(
+                        ui_compiled_instruction,
+                    ) = instruction
+                    {
+                        instructions.extend(decode(
+                            ui_compiled_instruction.program_id_index,
+                            &ui_compiled_instruction.accounts,
+                            &ui_compiled_instruction.data,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    instructions
+}
+
 pub fn handle_program_instruction(
     instr_data: &str,
     decode_type: InstructionDecodeType,
@@ -409,6 +611,21 @@ pub fn handle_program_instruction(
             }
             println!("{:#?}", CreatePool::from(ix));
         }
+        instruction::InitializeTickArrays::DISCRIMINATOR => {
+            let ix = decode_instruction::<instruction::InitializeTickArrays>(&mut ix_data).unwrap();
+            #[derive(Debug)]
+            pub struct InitializeTickArrays {
+                pub tick_array_start_indexs: Vec<i32>,
+            }
+            impl From<instruction::InitializeTickArrays> for InitializeTickArrays {
+                fn from(instr: instruction::InitializeTickArrays) -> InitializeTickArrays {
+                    InitializeTickArrays {
+                        tick_array_start_indexs: instr.tick_array_start_indexs,
+                    }
+                }
+            }
+            println!("{:#?}", InitializeTickArrays::from(ix));
+        }
         instruction::UpdatePoolStatus::DISCRIMINATOR => {
             let ix = decode_instruction::<instruction::UpdatePoolStatus>(&mut ix_data).unwrap();
             #[derive(Debug)]
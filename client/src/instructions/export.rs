@@ -0,0 +1,56 @@
+use anchor_client::solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::OnceLock;
+
+// where the transactions are exported, "-" for the standard output
+static EXPORT_PATH: OnceLock<String> = OnceLock::new();
+
+/// Export every transaction instead of sending it, for the rest of the process
+pub fn enable_export_mode(path: String) {
+    let _ = EXPORT_PATH.set(path);
+}
+
+pub fn export_path() -> Option<&'static str> {
+    EXPORT_PATH.get().map(String::as_str)
+}
+
+/// The base64 of the transaction in its wire format, as the wallets and multisigs import it
+pub fn encode_transaction(txn: &VersionedTransaction) -> Result<String> {
+    Ok(STANDARD.encode(bincode::serialize(txn)?))
+}
+
+/// The signers whose signature is missing, they sign the exported transaction later
+pub fn missing_signers(txn: &VersionedTransaction) -> Vec<String> {
+    let keys = txn.message.static_account_keys();
+    txn.signatures
+        .iter()
+        .zip(keys)
+        .filter(|(signature, _)| **signature == Signature::default())
+        .map(|(_, key)| key.to_string())
+        .collect()
+}
+
+/// In export mode, write the transaction as base64 and return its signature without sending it.
+/// A file gets one transaction per line, in the order they would be sent.
+pub fn export_instead_of_send(txn: &VersionedTransaction) -> Result<Option<Signature>> {
+    let path = match export_path() {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let encoded = encode_transaction(txn)?;
+    let missing = missing_signers(txn);
+    if path == "-" {
+        println!("{}", encoded);
+    } else {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", encoded)?;
+        println!("transaction exported to {}", path);
+    }
+    if !missing.is_empty() {
+        println!("missing signatures of {}", missing.join(", "));
+    }
+    Ok(Some(txn.signatures[0]))
+}
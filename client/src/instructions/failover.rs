@@ -0,0 +1,225 @@
+use anchor_client::solana_client::{
+    client_error::{ClientError, ClientErrorKind, Result as ClientResult},
+    http_sender::HttpSender,
+    rpc_client::RpcClient,
+    rpc_request::{RpcError, RpcRequest},
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
+
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+// the rpc answers with this code when the node is behind the cluster
+const NODE_UNHEALTHY_CODE: i64 = -32005;
+const TOO_MANY_REQUESTS: u16 = 429;
+
+struct Endpoint {
+    url: String,
+    sender: HttpSender,
+    healthy: AtomicBool,
+}
+
+// the endpoints of the live senders by their urls, the senders of the same urls share them and
+// their health check
+static ENDPOINTS: OnceLock<Mutex<HashMap<Vec<String>, Weak<Vec<Endpoint>>>>> = OnceLock::new();
+
+// the endpoints of the urls, with a thread checking their health while a sender uses them
+fn shared_endpoints(urls: &[String]) -> Arc<Vec<Endpoint>> {
+    let mut registry = ENDPOINTS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap();
+    if let Some(endpoints) = registry.get(urls).and_then(Weak::upgrade) {
+        return endpoints;
+    }
+    let endpoints: Arc<Vec<Endpoint>> = Arc::new(
+        urls.iter()
+            .map(|url| Endpoint {
+                url: url.clone(),
+                sender: HttpSender::new(url.clone()),
+                healthy: AtomicBool::new(true),
+            })
+            .collect(),
+    );
+    if endpoints.len() > 1 {
+        let endpoints = Arc::downgrade(&endpoints);
+        // stops once the senders using the endpoints are dropped
+        std::thread::spawn(move || loop {
+            std::thread::sleep(HEALTH_CHECK_INTERVAL);
+            let endpoints = match endpoints.upgrade() {
+                Some(endpoints) => endpoints,
+                None => break,
+            };
+            for endpoint in endpoints.iter() {
+                let healthy =
+                    RpcClient::new_with_timeout(endpoint.url.clone(), HEALTH_CHECK_TIMEOUT)
+                        .get_health()
+                        .is_ok();
+                if endpoint.healthy.swap(healthy, Ordering::Relaxed) != healthy {
+                    eprintln!(
+                        "rpc endpoint {} is {}",
+                        endpoint.url,
+                        if healthy {
+                            "healthy again"
+                        } else {
+                            "unhealthy"
+                        }
+                    );
+                }
+            }
+        });
+    }
+    registry.insert(urls.to_vec(), Arc::downgrade(&endpoints));
+    endpoints
+}
+
+/// How a request failing with a transient error on every endpoint is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts of a request over all the endpoints, 1 never retries
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl RetryPolicy {
+    /// The delay before the retry following the attempt, drawn up to the base delay doubled at
+    /// each attempt and capped, so that the clients failing together don't retry together
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay_ms
+            .saturating_mul(1 << attempt.min(20))
+            .min(self.max_delay_ms);
+        Duration::from_millis(rand::random_range(0..=ceiling))
+    }
+}
+
+/// Sends the rpc requests to the first healthy endpoint from the one which served the last
+/// request. An endpoint is marked unhealthy when it is rate limited, times out, fails with a
+/// server error or reports itself behind, and the request is sent to the next one. With several
+/// endpoints a thread checks their health, an endpoint is used again once healthy. The senders of
+/// the same endpoints share their health and its thread. A request failing on every endpoint is
+/// retried with the retry policy.
+pub struct FailoverSender {
+    endpoints: Arc<Vec<Endpoint>>,
+    current: AtomicUsize,
+    retry_policy: RetryPolicy,
+    /// Print the endpoint and the duration of each request
+    trace: bool,
+}
+
+impl FailoverSender {
+    pub fn new(urls: &[String], retry_policy: RetryPolicy, trace: bool) -> Self {
+        let endpoints = shared_endpoints(urls);
+        Self {
+            endpoints,
+            current: AtomicUsize::new(0),
+            retry_policy,
+            trace,
+        }
+    }
+
+    // the healthy endpoints from the current one, then the unhealthy ones as a last resort
+    fn endpoint_order(&self) -> Vec<usize> {
+        let len = self.endpoints.len();
+        let start = self.current.load(Ordering::Relaxed);
+        let (healthy, unhealthy): (Vec<usize>, Vec<usize>) = (0..len)
+            .map(|offset| (start + offset) % len)
+            .partition(|index| self.endpoints[*index].healthy.load(Ordering::Relaxed));
+        healthy.into_iter().chain(unhealthy).collect()
+    }
+
+    // one attempt of the request, over the endpoints until one doesn't fail with a transient error
+    async fn send_to_endpoints(&self, request: RpcRequest, params: &Value) -> ClientResult<Value> {
+        let mut last_err = None;
+        for index in self.endpoint_order() {
+            let endpoint = &self.endpoints[index];
+            let started = Instant::now();
+            match endpoint.sender.send(request, params.clone()).await {
+                Err(err) if is_transient(&err) => {
+                    endpoint.healthy.store(false, Ordering::Relaxed);
+                    eprintln!("rpc {} failed on {}: {}", request, endpoint.url, err);
+                    last_err = Some(err);
+                }
+                result => {
+                    if self.current.swap(index, Ordering::Relaxed) != index {
+                        eprintln!("rpc requests now served by {}", endpoint.url);
+                    }
+                    if self.trace {
+                        eprintln!(
+                            "rpc {} served by {} in {}ms",
+                            request,
+                            endpoint.url,
+                            started.elapsed().as_millis()
+                        );
+                    }
+                    return result;
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| ClientErrorKind::Custom("no rpc endpoint".into()).into()))
+    }
+}
+
+/// Whether another endpoint, or the same one later, may succeed where this one failed. The errors
+/// of the request itself, e.g. a failed simulation, are the same on any endpoint.
+fn is_transient(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(err) => {
+            err.is_timeout()
+                || err.is_connect()
+                || err.status().map_or(false, |status| {
+                    status.as_u16() == TOO_MANY_REQUESTS || status.is_server_error()
+                })
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == NODE_UNHEALTHY_CODE
+        }
+        _ => false,
+    }
+}
+
+#[async_trait::async_trait]
+impl RpcSender for FailoverSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let mut attempt = 1;
+        loop {
+            match self.send_to_endpoints(request, &params).await {
+                Err(err) if is_transient(&err) && attempt < self.retry_policy.max_attempts => {
+                    let delay = self.retry_policy.delay(attempt);
+                    eprintln!(
+                        "rpc {} failed on every endpoint, retry {} in {}ms",
+                        request,
+                        attempt,
+                        delay.as_millis()
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        let mut stats = RpcTransportStats::default();
+        for endpoint in self.endpoints.iter() {
+            let endpoint_stats = endpoint.sender.get_transport_stats();
+            stats.request_count += endpoint_stats.request_count;
+            stats.elapsed_time += endpoint_stats.elapsed_time;
+            stats.rate_limited_time += endpoint_stats.rate_limited_time;
+        }
+        stats
+    }
+
+    fn url(&self) -> String {
+        self.endpoints[self.current.load(Ordering::Relaxed)]
+            .url
+            .clone()
+    }
+}
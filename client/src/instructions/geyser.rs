@@ -0,0 +1,253 @@
+use anchor_client::solana_account_decoder::{UiAccount, UiAccountEncoding, UiDataSliceConfig};
+use anchor_client::solana_client::{
+    client_error::Result as ClientResult,
+    rpc_request::RpcRequest,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use anchor_client::solana_sdk::{account::Account, pubkey::Pubkey};
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use raydium_amm_v3::states::POOL_TICK_ARRAY_BITMAP_SEED;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use yellowstone_grpc_client::GeyserGrpcClient;
+use yellowstone_grpc_proto::prelude::{
+    subscribe_request_filter_accounts_filter::Filter as AccountsFilter,
+    subscribe_request_filter_accounts_filter_memcmp::Data as MemcmpData,
+    subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+    SubscribeRequestFilterAccountsFilterMemcmp, SubscribeRequestPing,
+};
+
+// the pool key of a tick array follows the discriminator
+const TICK_ARRAY_POOL_ID_OFFSET: u64 = 8;
+
+/// The latest state of the mirrored accounts with the slot of their update
+#[derive(Clone, Default)]
+pub struct AccountMirror {
+    accounts: Arc<RwLock<HashMap<Pubkey, (u64, Account)>>>,
+}
+
+impl AccountMirror {
+    pub fn get(&self, key: &Pubkey) -> Option<(u64, Account)> {
+        self.accounts.read().unwrap().get(key).cloned()
+    }
+
+    // an update of an older slot, e.g. from a dropped fork, never replaces a newer one
+    fn update(&self, key: Pubkey, slot: u64, account: Account) {
+        let mut accounts = self.accounts.write().unwrap();
+        if accounts
+            .get(&key)
+            .map_or(true, |(mirrored_slot, _)| *mirrored_slot <= slot)
+        {
+            accounts.insert(key, (slot, account));
+        }
+    }
+}
+
+/// Mirror the pools, their bitmap extensions and all their tick arrays from the yellowstone
+/// endpoint, at processed commitment. The subscription runs on its own thread and reconnects
+/// until the process exits; the accounts not received yet are not in the mirror.
+pub fn spawn_geyser_mirror(
+    endpoint: &str,
+    x_token: Option<String>,
+    program_id: &Pubkey,
+    pool_ids: &[Pubkey],
+) -> Result<AccountMirror> {
+    let mirror = AccountMirror::default();
+    let mut accounts_filters = HashMap::new();
+    let mut keys = Vec::new();
+    for pool_id in pool_ids {
+        let bitmap_extension = Pubkey::find_program_address(
+            &[POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_id.as_ref()],
+            program_id,
+        )
+        .0;
+        keys.push(pool_id.to_string());
+        keys.push(bitmap_extension.to_string());
+        // the filters of one entry must all match, one entry for the tick arrays of each pool
+        accounts_filters.insert(
+            format!("tick_arrays_{}", pool_id),
+            SubscribeRequestFilterAccounts {
+                owner: vec![program_id.to_string()],
+                filters: vec![SubscribeRequestFilterAccountsFilter {
+                    filter: Some(AccountsFilter::Memcmp(
+                        SubscribeRequestFilterAccountsFilterMemcmp {
+                            offset: TICK_ARRAY_POOL_ID_OFFSET,
+                            data: Some(MemcmpData::Bytes(pool_id.to_bytes().to_vec())),
+                        },
+                    )),
+                }],
+                ..Default::default()
+            },
+        );
+    }
+    accounts_filters.insert(
+        "pools".to_string(),
+        SubscribeRequestFilterAccounts {
+            account: keys,
+            ..Default::default()
+        },
+    );
+    let request = SubscribeRequest {
+        accounts: accounts_filters,
+        commitment: Some(CommitmentLevel::Processed as i32),
+        ..Default::default()
+    };
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    let endpoint = endpoint.to_string();
+    let thread_mirror = mirror.clone();
+    std::thread::spawn(move || loop {
+        let result = runtime.block_on(subscribe_accounts(
+            &endpoint,
+            x_token.clone(),
+            request.clone(),
+            &thread_mirror,
+        ));
+        match result {
+            Ok(()) => println!("geyser subscription closed, reconnecting"),
+            Err(err) => println!("geyser subscription failed: {}, reconnecting", err),
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    });
+    Ok(mirror)
+}
+
+async fn subscribe_accounts(
+    endpoint: &str,
+    x_token: Option<String>,
+    request: SubscribeRequest,
+    mirror: &AccountMirror,
+) -> Result<()> {
+    let mut client = GeyserGrpcClient::build_from_shared(endpoint.to_string())?
+        .x_token(x_token)?
+        .connect()
+        .await?;
+    let (mut subscribe_tx, mut stream) = client.subscribe_with_request(Some(request)).await?;
+    while let Some(message) = stream.next().await {
+        match message?.update_oneof {
+            Some(UpdateOneof::Account(update)) => {
+                let info = match update.account {
+                    Some(info) => info,
+                    None => continue,
+                };
+                let (key, owner) = match (
+                    Pubkey::try_from(info.pubkey.as_slice()),
+                    Pubkey::try_from(info.owner.as_slice()),
+                ) {
+                    (Ok(key), Ok(owner)) => (key, owner),
+                    _ => continue,
+                };
+                mirror.update(
+                    key,
+                    update.slot,
+                    Account {
+                        lamports: info.lamports,
+                        data: info.data,
+                        owner,
+                        executable: info.executable,
+                        rent_epoch: info.rent_epoch,
+                    },
+                );
+            }
+            // load balancers close idle streams, the server pings are answered to keep it open
+            Some(UpdateOneof::Ping(_)) => {
+                subscribe_tx
+                    .send(SubscribeRequest {
+                        ping: Some(SubscribeRequestPing { id: 1 }),
+                        ..Default::default()
+                    })
+                    .await?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Sends the rpc requests with the http sender, except the account requests which the mirror can
+/// serve
+pub struct MirrorSender {
+    http_sender: Box<dyn RpcSender + Send + Sync>,
+    mirror: AccountMirror,
+}
+
+impl MirrorSender {
+    pub fn new(http_sender: impl RpcSender + Send + Sync + 'static, mirror: AccountMirror) -> Self {
+        Self {
+            http_sender: Box::new(http_sender),
+            mirror,
+        }
+    }
+
+    // the response to getAccountInfo or getMultipleAccounts, None unless every account is mirrored
+    fn mirrored_response(&self, keys: &[Value], config: Option<&Value>) -> Option<Value> {
+        let encoding = config
+            .and_then(|config| config.get("encoding"))
+            .and_then(|encoding| serde_json::from_value(encoding.clone()).ok())
+            .unwrap_or(UiAccountEncoding::Base64);
+        let data_slice: Option<UiDataSliceConfig> = config
+            .and_then(|config| config.get("dataSlice"))
+            .and_then(|data_slice| serde_json::from_value(data_slice.clone()).ok());
+        let mut slot = 0;
+        let mut accounts = Vec::with_capacity(keys.len());
+        for key in keys {
+            let key = Pubkey::from_str(key.as_str()?).ok()?;
+            let (account_slot, account) = self.mirror.get(&key)?;
+            slot = slot.max(account_slot);
+            // a closed account is mirrored with no lamports, the rpc returns none for it
+            if account.lamports == 0 {
+                accounts.push(Value::Null);
+                continue;
+            }
+            accounts.push(json!(UiAccount::encode(
+                &key, &account, encoding, None, data_slice,
+            )));
+        }
+        Some(json!({ "slot": slot, "accounts": accounts }))
+    }
+}
+
+#[async_trait::async_trait]
+impl RpcSender for MirrorSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let mirrored = match request {
+            RpcRequest::GetAccountInfo => params
+                .get(0)
+                .and_then(|key| self.mirrored_response(std::slice::from_ref(key), params.get(1))),
+            RpcRequest::GetMultipleAccounts => params
+                .get(0)
+                .and_then(Value::as_array)
+                .and_then(|keys| self.mirrored_response(keys, params.get(1))),
+            _ => None,
+        };
+        match mirrored {
+            Some(mut response) => {
+                let accounts = response["accounts"].take();
+                let value = match request {
+                    RpcRequest::GetAccountInfo => accounts[0].clone(),
+                    _ => accounts,
+                };
+                Ok(json!({
+                    "context": { "slot": response["slot"] },
+                    "value": value,
+                }))
+            }
+            None => self.http_sender.send(request, params).await,
+        }
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.http_sender.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.http_sender.url()
+    }
+}
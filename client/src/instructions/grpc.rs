@@ -0,0 +1,156 @@
+use anchor_client::solana_sdk::{pubkey::Pubkey, signature::Signature};
+use anyhow::Result;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use super::quote::SwapQuote;
+use super::quote_server::QuoteRequest;
+use super::ticker::subscribe_pool_ticks_while;
+use super::utils::sqrt_price_x64_to_price;
+
+pub mod proto {
+    tonic::include_proto!("clmm");
+}
+
+use proto::clmm_server::{Clmm, ClmmServer};
+
+// price updates buffered for a slow stream consumer before the subscription waits
+const PRICE_UPDATE_BUFFER: usize = 64;
+
+/// What the grpc service quotes and swaps with, the calls may block
+pub trait ClmmBackend: Send + Sync + 'static {
+    /// The best quote over the served pools, with its pool and the slot of the pool accounts
+    fn quote(&self, request: &QuoteRequest) -> Result<(Pubkey, u64, SwapQuote)>;
+    /// Quote the swap from the latest state of the pool then send it signed by the hot wallet
+    fn execute_swap(&self, request: &QuoteRequest) -> Result<(Signature, Pubkey, u64, SwapQuote)>;
+    /// The decimals of the mints of a served pool
+    fn pool_decimals(&self, pool_id: &Pubkey) -> Option<(u8, u8)>;
+    fn ws_url(&self) -> String;
+}
+
+struct ClmmService {
+    backend: Arc<dyn ClmmBackend>,
+}
+
+impl ClmmService {
+    // the backend blocks on the rpc, it runs off the async workers
+    async fn blocking<T: Send + 'static>(
+        &self,
+        call: impl FnOnce(&dyn ClmmBackend) -> Result<T> + Send + 'static,
+    ) -> Result<T, Status> {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || call(backend.as_ref()))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?
+            .map_err(|err| Status::failed_precondition(err.to_string()))
+    }
+}
+
+fn quote_request(request: &proto::QuoteRequest) -> Result<QuoteRequest, Status> {
+    let mint = |mint: &str| {
+        Pubkey::from_str(mint)
+            .map_err(|_| Status::invalid_argument(format!("{} is not a pubkey", mint)))
+    };
+    Ok(QuoteRequest {
+        input_mint: mint(&request.input_mint)?,
+        output_mint: mint(&request.output_mint)?,
+        amount: request.amount,
+        base_in: !request.exact_out,
+    })
+}
+
+fn quote_response(pool_id: &Pubkey, slot: u64, quote: &SwapQuote) -> proto::QuoteResponse {
+    proto::QuoteResponse {
+        pool_id: pool_id.to_string(),
+        slot,
+        amount_in: quote.amount_in,
+        amount_out: quote.amount_out,
+        trade_fee: quote.trade_fee,
+        transfer_fee_in: quote.transfer_fee_in,
+        transfer_fee_out: quote.transfer_fee_out,
+        price_before: quote.price_before,
+        price_after: quote.price_after,
+        price_impact: quote.price_impact(),
+        ticks_crossed: quote.ticks_crossed() as u32,
+    }
+}
+
+#[tonic::async_trait]
+impl Clmm for ClmmService {
+    async fn quote(
+        &self,
+        request: Request<proto::QuoteRequest>,
+    ) -> Result<Response<proto::QuoteResponse>, Status> {
+        let request = quote_request(request.get_ref())?;
+        let (pool_id, slot, quote) = self
+            .blocking(move |backend| backend.quote(&request))
+            .await?;
+        Ok(Response::new(quote_response(&pool_id, slot, &quote)))
+    }
+
+    type StreamPricesStream = ReceiverStream<Result<proto::PriceUpdate, Status>>;
+
+    async fn stream_prices(
+        &self,
+        request: Request<proto::StreamPricesRequest>,
+    ) -> Result<Response<Self::StreamPricesStream>, Status> {
+        let pool_id = Pubkey::from_str(&request.get_ref().pool_id)
+            .map_err(|_| Status::invalid_argument("pool_id is not a pubkey"))?;
+        let (decimals_0, decimals_1) = self
+            .backend
+            .pool_decimals(&pool_id)
+            .ok_or(Status::not_found(format!("pool {} is not served", pool_id)))?;
+        let ws_url = self.backend.ws_url();
+        let (sender, receiver) = tokio::sync::mpsc::channel(PRICE_UPDATE_BUFFER);
+        // the subscription ends with the stream, at the next update once the client is gone
+        std::thread::spawn(move || {
+            let result = subscribe_pool_ticks_while(&ws_url, &pool_id, |tick| {
+                let update = proto::PriceUpdate {
+                    pool_id: pool_id.to_string(),
+                    slot: tick.slot,
+                    tick: tick.tick_current,
+                    sqrt_price_x64: tick.sqrt_price_x64.to_string(),
+                    price: sqrt_price_x64_to_price(tick.sqrt_price_x64, decimals_0, decimals_1),
+                    liquidity: tick.liquidity.to_string(),
+                };
+                sender.blocking_send(Ok(update)).is_ok()
+            });
+            if let Err(err) = result {
+                let _ = sender.blocking_send(Err(Status::unavailable(err.to_string())));
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn execute_swap(
+        &self,
+        request: Request<proto::QuoteRequest>,
+    ) -> Result<Response<proto::ExecuteSwapResponse>, Status> {
+        let request = quote_request(request.get_ref())?;
+        let (signature, pool_id, slot, quote) = self
+            .blocking(move |backend| backend.execute_swap(&request))
+            .await?;
+        Ok(Response::new(proto::ExecuteSwapResponse {
+            signature: signature.to_string(),
+            quote: Some(quote_response(&pool_id, slot, &quote)),
+        }))
+    }
+}
+
+/// Serve the grpc service on `addr` until the process exits
+pub fn serve_grpc(addr: &str, backend: Arc<dyn ClmmBackend>) -> Result<()> {
+    let addr: SocketAddr = addr.parse()?;
+    println!("grpc service listening on {}", addr);
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(
+            tonic::transport::Server::builder()
+                .add_service(ClmmServer::new(ClmmService { backend }))
+                .serve(addr),
+        )?;
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use anyhow::Result;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// the subscription status is only reported by the modes which subscribe
+const SUBSCRIPTION_UNKNOWN: u64 = 0;
+const SUBSCRIPTION_UP: u64 = 1;
+const SUBSCRIPTION_DOWN: u64 = 2;
+
+/// Liveness of a daemon mode, served over http and watched for wedges.
+/// `GET /health` answers 200 while progress is made and 503 once it stalls, with a json body of
+/// the last processed slot, the subscription status, the number of pending transactions and the
+/// hits and misses of the slot cache.
+/// The process exits with status 1 when no progress is made for `max_stall_secs`.
+pub struct HealthMonitor {
+    max_stall_secs: u64,
+    last_progress: AtomicU64,
+    last_slot: AtomicU64,
+    subscription: AtomicU64,
+    pending_txs: AtomicUsize,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl HealthMonitor {
+    /// Start serving on `addr`, e.g. "0.0.0.0:8080", and watching the progress
+    pub fn serve(addr: &str, max_stall_secs: u64) -> Result<Arc<Self>> {
+        let listener = TcpListener::bind(addr)?;
+        let monitor = Arc::new(Self {
+            max_stall_secs,
+            last_progress: AtomicU64::new(now()),
+            last_slot: AtomicU64::new(0),
+            subscription: AtomicU64::new(SUBSCRIPTION_UNKNOWN),
+            pending_txs: AtomicUsize::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+        });
+        println!("health endpoint listening on http://{}/health", addr);
+
+        let server = monitor.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // a broken connection only fails the probe
+                let _ = server.respond(stream);
+            }
+        });
+        let watchdog = monitor.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(Duration::from_secs(1));
+            let stalled = watchdog.stalled_secs();
+            if stalled > watchdog.max_stall_secs {
+                eprintln!(
+                    "no progress for {}s, last slot {}, exiting",
+                    stalled,
+                    watchdog.last_slot.load(Ordering::Relaxed)
+                );
+                std::process::exit(1);
+            }
+        });
+        Ok(monitor)
+    }
+
+    /// Record a completed iteration of the daemon, with the slot processed if it has one
+    pub fn heartbeat(&self, slot: Option<u64>) {
+        if let Some(slot) = slot {
+            self.last_slot.fetch_max(slot, Ordering::Relaxed);
+        }
+        self.last_progress.store(now(), Ordering::Relaxed);
+    }
+
+    pub fn set_subscribed(&self, subscribed: bool) {
+        self.subscription.store(
+            if subscribed {
+                SUBSCRIPTION_UP
+            } else {
+                SUBSCRIPTION_DOWN
+            },
+            Ordering::Relaxed,
+        );
+    }
+
+    pub fn set_pending_txs(&self, pending_txs: usize) {
+        self.pending_txs.store(pending_txs, Ordering::Relaxed);
+    }
+
+    /// The accounts the slot cache served and the ones it fetched
+    pub fn set_cache_stats(&self, (hits, misses): (u64, u64)) {
+        self.cache_hits.store(hits, Ordering::Relaxed);
+        self.cache_misses.store(misses, Ordering::Relaxed);
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.stalled_secs() <= self.max_stall_secs
+            && self.subscription.load(Ordering::Relaxed) != SUBSCRIPTION_DOWN
+    }
+
+    fn stalled_secs(&self) -> u64 {
+        now().saturating_sub(self.last_progress.load(Ordering::Relaxed))
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let mut request = [0u8; 1024];
+        let len = stream.read(&mut request)?;
+        let request = String::from_utf8_lossy(&request[..len]);
+        let (status, body) = if request.starts_with("GET /health ") {
+            let subscribed = match self.subscription.load(Ordering::Relaxed) {
+                SUBSCRIPTION_UP => Some(true),
+                SUBSCRIPTION_DOWN => Some(false),
+                _ => None,
+            };
+            let body = serde_json::json!({
+                "healthy": self.is_healthy(),
+                "last_slot": self.last_slot.load(Ordering::Relaxed),
+                "secs_since_progress": self.stalled_secs(),
+                "subscribed": subscribed,
+                "pending_txs": self.pending_txs.load(Ordering::Relaxed),
+                "cache_hits": self.cache_hits.load(Ordering::Relaxed),
+                "cache_misses": self.cache_misses.load(Ordering::Relaxed),
+            })
+            .to_string();
+            if self.is_healthy() {
+                ("200 OK", body)
+            } else {
+                ("503 Service Unavailable", body)
+            }
+        } else {
+            ("404 Not Found", String::new())
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
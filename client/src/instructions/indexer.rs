@@ -0,0 +1,685 @@
+use anchor_client::solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::{RpcBlockConfig, RpcTransactionConfig},
+};
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+};
+use anchor_lang::Discriminator;
+use anyhow::Result;
+use raydium_amm_v3::instruction;
+use raydium_amm_v3::states::*;
+use serde::{Deserialize, Serialize};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    EncodedTransaction, TransactionDetails, UiMessage, UiTransactionEncoding,
+    UiTransactionStatusMeta,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use super::events_instructions_parse::{decode_event, program_event_data, program_instructions};
+
+// getSignatureStatuses accepts at most 256 signatures per request
+const MAX_SIGNATURE_STATUSES: usize = 256;
+// position_nft_mint is the third account of all the open position instructions
+const OPEN_POSITION_NFT_MINT_INDEX: usize = 2;
+
+/// Program event stored by the indexer, pubkeys are kept as base58 strings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum IndexedEventData {
+    Swap {
+        pool_state: String,
+        sender: String,
+        amount_0: u64,
+        transfer_fee_0: u64,
+        amount_1: u64,
+        transfer_fee_1: u64,
+        zero_for_one: bool,
+        sqrt_price_x64: u128,
+        liquidity: u128,
+        tick: i32,
+    },
+    LiquidityChange {
+        pool_state: String,
+        tick: i32,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_before: u128,
+        liquidity_after: u128,
+    },
+    CreatePersonalPosition {
+        pool_state: String,
+        /// Not part of the event, taken from the accounts of the open position instruction
+        #[serde(default)]
+        position_nft_mint: Option<String>,
+        minter: String,
+        nft_owner: String,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        liquidity: u128,
+        deposit_amount_0: u64,
+        deposit_amount_1: u64,
+        deposit_amount_0_transfer_fee: u64,
+        deposit_amount_1_transfer_fee: u64,
+    },
+    IncreaseLiquidity {
+        position_nft_mint: String,
+        liquidity: u128,
+        amount_0: u64,
+        amount_1: u64,
+        amount_0_transfer_fee: u64,
+        amount_1_transfer_fee: u64,
+    },
+    DecreaseLiquidity {
+        position_nft_mint: String,
+        liquidity: u128,
+        decrease_amount_0: u64,
+        decrease_amount_1: u64,
+        fee_amount_0: u64,
+        fee_amount_1: u64,
+        reward_amounts: [u64; REWARD_NUM],
+        transfer_fee_0: u64,
+        transfer_fee_1: u64,
+    },
+    CollectPersonalFee {
+        position_nft_mint: String,
+        recipient_token_account_0: String,
+        recipient_token_account_1: String,
+        amount_0: u64,
+        amount_1: u64,
+    },
+    CollectProtocolFee {
+        pool_state: String,
+        recipient_token_account_0: String,
+        recipient_token_account_1: String,
+        amount_0: u64,
+        amount_1: u64,
+    },
+    PoolCreated {
+        pool_state: String,
+        token_mint_0: String,
+        token_mint_1: String,
+        tick_spacing: u16,
+        sqrt_price_x64: u128,
+        tick: i32,
+    },
+}
+
+/// Event of a pool transaction, it is provisional until its slot is finalized
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedEvent {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub signature: String,
+    pub finalized: bool,
+    pub event: IndexedEventData,
+}
+
+/// The last processed transaction of a pool, indexing resumes from there after a restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexCheckpoint {
+    pub signature: String,
+    pub slot: u64,
+}
+
+/// The indexed events of one pool, ordered by slot
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PoolIndex {
+    pub events: Vec<IndexedEvent>,
+    #[serde(default)]
+    pub checkpoint: Option<IndexCheckpoint>,
+    /// The oldest backfilled signature while a backfill is in progress
+    #[serde(default)]
+    pub backfill_cursor: Option<String>,
+}
+
+impl PoolIndex {
+    /// Events which can't be rolled back by a fork anymore
+    pub fn finalized_events(&self) -> impl Iterator<Item = &IndexedEvent> {
+        self.events.iter().filter(|event| event.finalized)
+    }
+
+    pub fn provisional_count(&self) -> usize {
+        self.events.iter().filter(|event| !event.finalized).count()
+    }
+}
+
+/// Disk storage of the indexed events, one json file for each pool
+pub struct EventIndex {
+    dir: PathBuf,
+}
+
+impl EventIndex {
+    pub fn new(index_dir: &str, http_url: &str) -> Self {
+        // one sub directory for each cluster, so devnet and mainnet events never mix
+        let cluster: String = http_url
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        Self {
+            dir: PathBuf::from(index_dir).join(cluster),
+        }
+    }
+
+    pub fn load(&self, pool_id: &Pubkey) -> Result<PoolIndex> {
+        let path = self.path(pool_id);
+        if !path.exists() {
+            return Ok(PoolIndex::default());
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, pool_id: &Pubkey, pool_index: &PoolIndex) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        // write to a temporary file first, so a crash never leaves a truncated index
+        let path = self.path(pool_id);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(pool_index)?)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    fn path(&self, pool_id: &Pubkey) -> PathBuf {
+        self.dir.join(format!("{}.json", pool_id))
+    }
+}
+
+/// Record the events of the pool transactions at `confirmed` commitment, they are provisional
+/// until `finalize_pool_events` sees their slot finalized. The scan resumes from the checkpoint,
+/// the transactions of the last `overlap_slots` slots before it are scanned again and deduplicated
+/// so a restart never misses a transaction that landed late. Without checkpoint only the latest
+/// `limit` transactions are scanned. Returns the number of new events.
+pub fn index_pool_events(
+    rpc_client: &RpcClient,
+    program_str: &str,
+    pool_id: &Pubkey,
+    pool_index: &mut PoolIndex,
+    limit: usize,
+    overlap_slots: u64,
+) -> Result<usize> {
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let page = rpc_client.get_signatures_for_address_with_config(
+            pool_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(limit),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+        let page_len = page.len();
+        let mut reached_checkpoint = pool_index.checkpoint.is_none();
+        for signature_info in page {
+            if let Some(checkpoint) = &pool_index.checkpoint {
+                if signature_info.slot + overlap_slots < checkpoint.slot {
+                    reached_checkpoint = true;
+                    break;
+                }
+            }
+            signatures.push(signature_info);
+        }
+        if reached_checkpoint || page_len < limit {
+            break;
+        }
+        before = Some(Signature::from_str(&signatures.last().unwrap().signature)?);
+    }
+    let known: HashSet<String> = pool_index
+        .events
+        .iter()
+        .map(|event| event.signature.clone())
+        .collect();
+    let mut new_events = 0;
+    let mut checkpoint = pool_index.checkpoint.clone();
+    // signatures are returned newest first
+    for signature_info in signatures.into_iter().rev() {
+        if checkpoint
+            .as_ref()
+            .map_or(true, |checkpoint| signature_info.slot >= checkpoint.slot)
+        {
+            checkpoint = Some(IndexCheckpoint {
+                signature: signature_info.signature.clone(),
+                slot: signature_info.slot,
+            });
+        }
+        if signature_info.err.is_some() || known.contains(&signature_info.signature) {
+            continue;
+        }
+        let tx = get_transaction(rpc_client, &signature_info.signature)?;
+        new_events +=
+            record_transaction_events(program_str, pool_index, &signature_info.signature, tx)?;
+    }
+    pool_index.events.sort_by_key(|event| event.slot);
+    // saved in the same file as the events, so the checkpoint never runs ahead of them
+    pool_index.checkpoint = checkpoint;
+    Ok(new_events)
+}
+
+/// Limits of a backfill
+pub struct BackfillConfig {
+    /// Unix timestamp of the oldest transaction to backfill
+    pub from_time: i64,
+    /// Number of signatures fetched in each page
+    pub limit: usize,
+    /// Max number of transactions fetched at the same time
+    pub concurrency: usize,
+    /// Max number of transaction requests per second
+    pub requests_per_second: u32,
+}
+
+/// Backfill one page of the pool history, walking the signatures backwards from `before` and
+/// recording the events of the transactions which are not older than `from_time`.
+/// Returns the number of new events and the cursor of the next page, which is None once
+/// `from_time` or the first transaction of the pool is reached.
+pub fn backfill_pool_events(
+    rpc_client: &RpcClient,
+    program_str: &str,
+    pool_id: &Pubkey,
+    pool_index: &mut PoolIndex,
+    before: Option<Signature>,
+    config: &BackfillConfig,
+) -> Result<(usize, Option<Signature>)> {
+    let page = rpc_client.get_signatures_for_address_with_config(
+        pool_id,
+        GetConfirmedSignaturesForAddress2Config {
+            before,
+            until: None,
+            limit: Some(config.limit),
+            commitment: Some(CommitmentConfig::confirmed()),
+        },
+    )?;
+    if before.is_none() && pool_index.checkpoint.is_none() {
+        // live indexing takes over after the newest backfilled transaction
+        pool_index.checkpoint = page.first().map(|signature_info| IndexCheckpoint {
+            signature: signature_info.signature.clone(),
+            slot: signature_info.slot,
+        });
+    }
+    let known: HashSet<String> = pool_index
+        .events
+        .iter()
+        .map(|event| event.signature.clone())
+        .collect();
+    let mut next_before = if page.len() < config.limit {
+        None
+    } else {
+        page.last()
+            .map(|signature_info| Signature::from_str(&signature_info.signature))
+            .transpose()?
+    };
+    let mut signatures = Vec::new();
+    for signature_info in page {
+        if signature_info
+            .block_time
+            .map_or(false, |block_time| block_time < config.from_time)
+        {
+            next_before = None;
+            break;
+        }
+        if signature_info.err.is_none() && !known.contains(&signature_info.signature) {
+            signatures.push(signature_info.signature);
+        }
+    }
+
+    let txs = get_transactions_throttled(
+        rpc_client,
+        &signatures,
+        config.concurrency,
+        config.requests_per_second,
+    )?;
+    let mut new_events = 0;
+    for (signature, tx) in signatures.iter().zip(txs) {
+        new_events += record_transaction_events(program_str, pool_index, signature, tx)?;
+    }
+    pool_index.events.sort_by_key(|event| event.slot);
+    Ok((new_events, next_before))
+}
+
+fn get_transaction(
+    rpc_client: &RpcClient,
+    signature: &str,
+) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    Ok(rpc_client.get_transaction_with_config(
+        &Signature::from_str(signature)?,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?)
+}
+
+/// Fetch the transactions in batches of `concurrency` parallel requests, a batch is never sent
+/// earlier than the rate limit allows
+pub fn get_transactions_throttled(
+    rpc_client: &RpcClient,
+    signatures: &[String],
+    concurrency: usize,
+    requests_per_second: u32,
+) -> Result<Vec<EncodedConfirmedTransactionWithStatusMeta>> {
+    let concurrency = concurrency.max(1);
+    let batch_interval =
+        Duration::from_secs_f64(concurrency as f64 / requests_per_second.max(1) as f64);
+    let mut txs = Vec::with_capacity(signatures.len());
+    for batch in signatures.chunks(concurrency) {
+        let started = Instant::now();
+        let results: Vec<Result<EncodedConfirmedTransactionWithStatusMeta>> =
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|signature| scope.spawn(move || get_transaction(rpc_client, signature)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect()
+            });
+        for tx in results {
+            txs.push(tx?);
+        }
+        if let Some(remaining) = batch_interval.checked_sub(started.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+    }
+    Ok(txs)
+}
+
+/// Record the decoded events of the transaction as provisional, returns the number of events
+pub fn record_transaction_events(
+    program_str: &str,
+    pool_index: &mut PoolIndex,
+    signature: &str,
+    tx: EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<usize> {
+    let meta = match tx.transaction.meta {
+        Some(meta) => meta,
+        None => return Ok(0),
+    };
+    // the create position events are emitted in the same order as the open position instructions
+    let mut opened_nft_mints =
+        program_instructions(program_str, &tx.transaction.transaction, &meta)
+            .into_iter()
+            .filter_map(|(data, accounts)| {
+                if data.len() < 8 {
+                    return None;
+                }
+                match &data[..8] {
+                    instruction::OpenPosition::DISCRIMINATOR
+                    | instruction::OpenPositionV2::DISCRIMINATOR
+                    | instruction::OpenPositionWithToken22Nft::DISCRIMINATOR => {
+                        accounts.get(OPEN_POSITION_NFT_MINT_INDEX).cloned()
+                    }
+                    _ => None,
+                }
+            });
+    let mut new_events = 0;
+    for log in program_event_data(program_str, &meta)? {
+        if let Some(mut event) = decode_indexed_event(&log)? {
+            if let IndexedEventData::CreatePersonalPosition {
+                position_nft_mint, ..
+            } = &mut event
+            {
+                *position_nft_mint = opened_nft_mints.next();
+            }
+            pool_index.events.push(IndexedEvent {
+                slot: tx.slot,
+                block_time: tx.block_time,
+                signature: signature.to_string(),
+                finalized: false,
+                event,
+            });
+            new_events += 1;
+        }
+    }
+    Ok(new_events)
+}
+
+/// The events of the successful program transactions of the confirmed block, in the order of the
+/// block. With a pool, only the transactions loading the pool account are decoded.
+pub fn block_events(
+    rpc_client: &RpcClient,
+    program_str: &str,
+    slot: u64,
+    pool_id: Option<&Pubkey>,
+) -> Result<Vec<IndexedEvent>> {
+    let block = rpc_client.get_block_with_config(
+        slot,
+        RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            transaction_details: Some(TransactionDetails::Full),
+            rewards: Some(false),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    let invoke_log = format!("Program {} invoke", program_str);
+    let pool_str = pool_id.map(|pool_id| pool_id.to_string());
+    let mut pool_index = PoolIndex::default();
+    for tx in block.transactions.unwrap_or_default() {
+        let meta = match &tx.meta {
+            Some(meta) if meta.err.is_none() => meta,
+            _ => continue,
+        };
+        // the program is invoked directly or by cpi, possibly via a lookup table
+        let touches_program = match &meta.log_messages {
+            OptionSerializer::Some(logs) => logs.iter().any(|l| l.starts_with(&invoke_log)),
+            _ => false,
+        };
+        let touches_pool = pool_str.as_ref().map_or(true, |pool_str| {
+            transaction_account_keys(&tx.transaction, meta).contains(pool_str)
+        });
+        let signature = match &tx.transaction {
+            EncodedTransaction::Json(ui_tx) => ui_tx.signatures[0].clone(),
+            _ => continue,
+        };
+        if !touches_program || !touches_pool {
+            continue;
+        }
+        record_transaction_events(
+            program_str,
+            &mut pool_index,
+            &signature,
+            EncodedConfirmedTransactionWithStatusMeta {
+                slot,
+                transaction: tx,
+                block_time: block.block_time,
+            },
+        )?;
+    }
+    Ok(pool_index.events)
+}
+
+// the static account keys and the ones loaded from lookup tables
+fn transaction_account_keys(
+    encoded_transaction: &EncodedTransaction,
+    meta: &UiTransactionStatusMeta,
+) -> Vec<String> {
+    let mut account_keys = match encoded_transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Raw(ui_raw_msg) => ui_raw_msg.account_keys.clone(),
+            UiMessage::Parsed(ui_parsed_msg) => ui_parsed_msg
+                .account_keys
+                .iter()
+                .map(|account| account.pubkey.clone())
+                .collect(),
+        },
+        _ => Vec::new(),
+    };
+    if let OptionSerializer::Some(addresses) = &meta.loaded_addresses {
+        account_keys.extend(addresses.writable.iter().cloned());
+        account_keys.extend(addresses.readonly.iter().cloned());
+    }
+    account_keys
+}
+
+/// Finalize the provisional events whose slot is finalized. The events of a transaction which
+/// is not finalized in the same slot by then were recorded from a dropped fork and are rolled back.
+/// Returns the number of finalized and rolled back events.
+pub fn finalize_pool_events(
+    rpc_client: &RpcClient,
+    pool_index: &mut PoolIndex,
+) -> Result<(usize, usize)> {
+    let finalized_slot = rpc_client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+    let mut signatures: Vec<String> = pool_index
+        .events
+        .iter()
+        .filter(|event| !event.finalized && event.slot <= finalized_slot)
+        .map(|event| event.signature.clone())
+        .collect();
+    signatures.dedup();
+    if signatures.is_empty() {
+        return Ok((0, 0));
+    }
+    let mut finalized_in_slot = HashMap::new();
+    for chunk in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+        let keys = chunk
+            .iter()
+            .map(|signature| Signature::from_str(signature))
+            .collect::<Result<Vec<_>, _>>()?;
+        let statuses = rpc_client.get_signature_statuses_with_history(&keys)?.value;
+        for (signature, status) in chunk.iter().zip(statuses) {
+            if let Some(status) = status {
+                if status.err.is_none()
+                    && status.satisfies_commitment(CommitmentConfig::finalized())
+                {
+                    finalized_in_slot.insert(signature.clone(), status.slot);
+                }
+            }
+        }
+    }
+
+    let (mut finalized, mut rolled_back) = (0, 0);
+    pool_index.events.retain_mut(|event| {
+        if event.finalized || event.slot > finalized_slot {
+            return true;
+        }
+        if finalized_in_slot.get(&event.signature) == Some(&event.slot) {
+            event.finalized = true;
+            finalized += 1;
+            true
+        } else {
+            rolled_back += 1;
+            false
+        }
+    });
+    Ok((finalized, rolled_back))
+}
+
+/// Decode the base64 data of a program event, None for the events which are not indexed
+pub fn decode_indexed_event(log: &str) -> Result<Option<IndexedEventData>> {
+    let borsh_bytes = match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, log)
+    {
+        Ok(borsh_bytes) if borsh_bytes.len() >= 8 => borsh_bytes,
+        _ => return Ok(None),
+    };
+    let mut slice: &[u8] = &borsh_bytes[8..];
+    let event = match &borsh_bytes[..8] {
+        SwapEvent::DISCRIMINATOR => {
+            let event = decode_event::<SwapEvent>(&mut slice)?;
+            IndexedEventData::Swap {
+                pool_state: event.pool_state.to_string(),
+                sender: event.sender.to_string(),
+                amount_0: event.amount_0,
+                transfer_fee_0: event.transfer_fee_0,
+                amount_1: event.amount_1,
+                transfer_fee_1: event.transfer_fee_1,
+                zero_for_one: event.zero_for_one,
+                sqrt_price_x64: event.sqrt_price_x64,
+                liquidity: event.liquidity,
+                tick: event.tick,
+            }
+        }
+        LiquidityChangeEvent::DISCRIMINATOR => {
+            let event = decode_event::<LiquidityChangeEvent>(&mut slice)?;
+            IndexedEventData::LiquidityChange {
+                pool_state: event.pool_state.to_string(),
+                tick: event.tick,
+                tick_lower: event.tick_lower,
+                tick_upper: event.tick_upper,
+                liquidity_before: event.liquidity_before,
+                liquidity_after: event.liquidity_after,
+            }
+        }
+        CreatePersonalPositionEvent::DISCRIMINATOR => {
+            let event = decode_event::<CreatePersonalPositionEvent>(&mut slice)?;
+            IndexedEventData::CreatePersonalPosition {
+                pool_state: event.pool_state.to_string(),
+                position_nft_mint: None,
+                minter: event.minter.to_string(),
+                nft_owner: event.nft_owner.to_string(),
+                tick_lower_index: event.tick_lower_index,
+                tick_upper_index: event.tick_upper_index,
+                liquidity: event.liquidity,
+                deposit_amount_0: event.deposit_amount_0,
+                deposit_amount_1: event.deposit_amount_1,
+                deposit_amount_0_transfer_fee: event.deposit_amount_0_transfer_fee,
+                deposit_amount_1_transfer_fee: event.deposit_amount_1_transfer_fee,
+            }
+        }
+        IncreaseLiquidityEvent::DISCRIMINATOR => {
+            let event = decode_event::<IncreaseLiquidityEvent>(&mut slice)?;
+            IndexedEventData::IncreaseLiquidity {
+                position_nft_mint: event.position_nft_mint.to_string(),
+                liquidity: event.liquidity,
+                amount_0: event.amount_0,
+                amount_1: event.amount_1,
+                amount_0_transfer_fee: event.amount_0_transfer_fee,
+                amount_1_transfer_fee: event.amount_1_transfer_fee,
+            }
+        }
+        DecreaseLiquidityEvent::DISCRIMINATOR => {
+            let event = decode_event::<DecreaseLiquidityEvent>(&mut slice)?;
+            IndexedEventData::DecreaseLiquidity {
+                position_nft_mint: event.position_nft_mint.to_string(),
+                liquidity: event.liquidity,
+                decrease_amount_0: event.decrease_amount_0,
+                decrease_amount_1: event.decrease_amount_1,
+                fee_amount_0: event.fee_amount_0,
+                fee_amount_1: event.fee_amount_1,
+                reward_amounts: event.reward_amounts,
+                transfer_fee_0: event.transfer_fee_0,
+                transfer_fee_1: event.transfer_fee_1,
+            }
+        }
+        CollectPersonalFeeEvent::DISCRIMINATOR => {
+            let event = decode_event::<CollectPersonalFeeEvent>(&mut slice)?;
+            IndexedEventData::CollectPersonalFee {
+                position_nft_mint: event.position_nft_mint.to_string(),
+                recipient_token_account_0: event.recipient_token_account_0.to_string(),
+                recipient_token_account_1: event.recipient_token_account_1.to_string(),
+                amount_0: event.amount_0,
+                amount_1: event.amount_1,
+            }
+        }
+        CollectProtocolFeeEvent::DISCRIMINATOR => {
+            let event = decode_event::<CollectProtocolFeeEvent>(&mut slice)?;
+            IndexedEventData::CollectProtocolFee {
+                pool_state: event.pool_state.to_string(),
+                recipient_token_account_0: event.recipient_token_account_0.to_string(),
+                recipient_token_account_1: event.recipient_token_account_1.to_string(),
+                amount_0: event.amount_0,
+                amount_1: event.amount_1,
+            }
+        }
+        PoolCreatedEvent::DISCRIMINATOR => {
+            let event = decode_event::<PoolCreatedEvent>(&mut slice)?;
+            IndexedEventData::PoolCreated {
+                pool_state: event.pool_state.to_string(),
+                token_mint_0: event.token_mint_0.to_string(),
+                token_mint_1: event.token_mint_1.to_string(),
+                tick_spacing: event.tick_spacing,
+                sqrt_price_x64: event.sqrt_price_x64,
+                tick: event.tick,
+            }
+        }
+        // config changes and calculation events are not indexed
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
@@ -0,0 +1,138 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, instruction::Instruction, pubkey::Pubkey,
+    signature::Signature, system_instruction, transaction::VersionedTransaction,
+};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use super::export::export_instead_of_send;
+use super::plan::plan_instead_of_send;
+
+const JITO_TIMEOUT_SECS: u64 = 10;
+// the blockhash of a bundle not landed by then is about to expire
+const BUNDLE_LANDING_TIMEOUT: Duration = Duration::from_secs(60);
+const BUNDLE_STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Submits bundles to a jito block engine. The transactions of a bundle are executed in order and
+/// all or none of them land, they never go through the public mempool where a swap is sandwiched.
+pub struct JitoClient {
+    url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl JitoClient {
+    pub fn new(url: &str) -> Result<Self> {
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            client: reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(JITO_TIMEOUT_SECS))
+                .build()?,
+        })
+    }
+
+    fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self
+            .client
+            .post(format!("{}/api/v1/bundles", self.url))
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()?
+            .error_for_status()?
+            .json()?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("jito {} failed: {}", method, error));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or(anyhow!("jito {} returned no result", method))
+    }
+
+    /// The accounts the block engine collects the tips on
+    pub fn tip_accounts(&self) -> Result<Vec<Pubkey>> {
+        let tip_accounts: Vec<String> =
+            serde_json::from_value(self.request("getTipAccounts", json!([]))?)?;
+        tip_accounts
+            .iter()
+            .map(|tip_account| Ok(Pubkey::from_str(tip_account)?))
+            .collect()
+    }
+
+    /// The transfer of the tip from the payer to one of the tip accounts, drawn at random so that
+    /// the bundles don't all lock the same account
+    pub fn tip_instruction(&self, payer: &Pubkey, lamports: u64) -> Result<Instruction> {
+        let tip_accounts = self.tip_accounts()?;
+        if tip_accounts.is_empty() {
+            return Err(anyhow!("jito returned no tip account"));
+        }
+        let tip_account = tip_accounts[rand::random_range(0..tip_accounts.len())];
+        Ok(system_instruction::transfer(payer, &tip_account, lamports))
+    }
+
+    /// Submit the signed transactions as a bundle, returns its id
+    pub fn send_bundle(&self, txns: &[VersionedTransaction]) -> Result<String> {
+        let encoded = txns
+            .iter()
+            .map(|txn| Ok(STANDARD.encode(bincode::serialize(txn)?)))
+            .collect::<Result<Vec<String>>>()?;
+        Ok(serde_json::from_value(self.request(
+            "sendBundle",
+            json!([encoded, { "encoding": "base64" }]),
+        )?)?)
+    }
+
+    /// The status the block engine reports for a bundle submitted in the last minutes: Pending,
+    /// Failed, Landed, or Invalid when it doesn't know the bundle
+    pub fn inflight_bundle_status(&self, bundle_id: &str) -> Result<Option<String>> {
+        let result = self.request("getInflightBundleStatuses", json!([[bundle_id]]))?;
+        Ok(result["value"]
+            .get(0)
+            .and_then(|status| status["status"].as_str())
+            .map(str::to_string))
+    }
+}
+
+/// Submit the transactions as a bundle and wait until the first one is confirmed, the bundle
+/// landing whole. The wait ends when the block engine reports the bundle failed or at the timeout.
+pub fn send_bundle_and_confirm(
+    rpc_client: &RpcClient,
+    jito_client: &JitoClient,
+    txns: &[VersionedTransaction],
+) -> Result<Signature> {
+    let txn = txns.first().ok_or(anyhow!("empty bundle"))?;
+    if let Some(signature) = plan_instead_of_send(rpc_client, txn)? {
+        return Ok(signature);
+    }
+    if let Some(signature) = export_instead_of_send(txn)? {
+        return Ok(signature);
+    }
+    let signature = txn.signatures[0];
+    let bundle_id = jito_client.send_bundle(txns)?;
+    println!("bundle:{}", bundle_id);
+    let started = Instant::now();
+    while started.elapsed() < BUNDLE_LANDING_TIMEOUT {
+        std::thread::sleep(BUNDLE_STATUS_INTERVAL);
+        if let Some(status) = rpc_client
+            .get_signature_status_with_commitment(&signature, CommitmentConfig::confirmed())?
+        {
+            status?;
+            return Ok(signature);
+        }
+        if jito_client.inflight_bundle_status(&bundle_id)?.as_deref() == Some("Failed") {
+            return Err(anyhow!("bundle {} failed", bundle_id));
+        }
+    }
+    Err(anyhow!(
+        "bundle {} not landed after {}s",
+        bundle_id,
+        BUNDLE_LANDING_TIMEOUT.as_secs()
+    ))
+}
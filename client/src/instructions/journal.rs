@@ -0,0 +1,190 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, hash::Hash, signature::Signature,
+    transaction::VersionedTransaction,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// the blockhash of an in-flight transaction expires after about 150 slots
+const IN_FLIGHT_POLL_INTERVAL_SECS: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "error")]
+pub enum JournalStatus {
+    /// Recorded before sending, the outcome is unknown
+    Pending,
+    Confirmed,
+    Failed(String),
+    /// The blockhash expired without any version landing, it is safe to send it again
+    Expired,
+}
+
+/// A transaction a bot intends to send, signatures and blockhash are kept as base58 strings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// The step of the job, e.g. the exit or the zap out of a keeper
+    pub step: String,
+    pub blockhash: String,
+    /// All the versions signed with the blockhash, e.g. resubmitted with a higher priority fee
+    pub signatures: Vec<String>,
+    pub status: JournalStatus,
+    /// Signature of the landed version
+    pub landed: Option<String>,
+    pub created_at: u64,
+}
+
+/// Crash-safe record of the transactions of a bot job, in one json file per job. Transactions are
+/// recorded before they are sent and marked once their outcome is known, so a restarted bot can
+/// tell which ones landed instead of sending them twice.
+pub struct Journal {
+    path: PathBuf,
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn open(journal_dir: &str, http_url: &str, job: &str) -> Result<Self> {
+        // one sub directory for each cluster, as the event index
+        let sanitize = |name: &str| -> String {
+            name.chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect()
+        };
+        let path = PathBuf::from(journal_dir)
+            .join(sanitize(http_url))
+            .join(format!("{}.json", sanitize(job)));
+        let entries = if path.exists() {
+            serde_json::from_slice(&fs::read(&path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// The latest entry of the step
+    pub fn last(&self, step: &str) -> Option<&JournalEntry> {
+        self.entries.iter().rev().find(|entry| entry.step == step)
+    }
+
+    /// Record a signed transaction before sending it. A version of the pending transaction of the
+    /// step, signed with the same blockhash, is added to its entry.
+    pub fn record(&mut self, step: &str, txn: &VersionedTransaction) -> Result<()> {
+        let blockhash = txn.message.recent_blockhash().to_string();
+        let signature = txn.signatures[0].to_string();
+        match self.entries.iter_mut().rev().find(|entry| {
+            entry.step == step
+                && entry.status == JournalStatus::Pending
+                && entry.blockhash == blockhash
+        }) {
+            Some(entry) => {
+                if !entry.signatures.contains(&signature) {
+                    entry.signatures.push(signature);
+                }
+            }
+            None => self.entries.push(JournalEntry {
+                step: step.to_string(),
+                blockhash,
+                signatures: vec![signature],
+                status: JournalStatus::Pending,
+                landed: None,
+                created_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            }),
+        }
+        self.save()
+    }
+
+    /// Mark the outcome of the pending transaction of the step
+    pub fn finish(
+        &mut self,
+        step: &str,
+        status: JournalStatus,
+        landed: Option<Signature>,
+    ) -> Result<()> {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.step == step && entry.status == JournalStatus::Pending)
+        {
+            entry.status = status;
+            entry.landed = landed.map(|signature| signature.to_string());
+        }
+        self.save()
+    }
+
+    /// Resolve the pending entries left by a previous run against the chain. An entry none of
+    /// whose versions landed stays pending until its blockhash expires, so this waits for the
+    /// in-flight transactions and never reports one as safe to resend while it still may land.
+    pub fn reconcile(&mut self, client: &RpcClient) -> Result<()> {
+        loop {
+            let mut in_flight = 0;
+            for entry in self
+                .entries
+                .iter_mut()
+                .filter(|entry| entry.status == JournalStatus::Pending)
+            {
+                let signatures = entry
+                    .signatures
+                    .iter()
+                    .map(|signature| Signature::from_str(signature))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // read the blockhash validity first, a version landing in between is then seen
+                let expired = !client.is_blockhash_valid(
+                    &Hash::from_str(&entry.blockhash)?,
+                    CommitmentConfig::confirmed(),
+                )?;
+                // the previous run may have stopped long ago, older than the recent statuses
+                let statuses = client
+                    .get_signature_statuses_with_history(&signatures)?
+                    .value;
+                let landed = signatures
+                    .iter()
+                    .zip(statuses)
+                    .find_map(|(signature, status)| status.map(|status| (*signature, status)));
+                match landed {
+                    Some((signature, status)) => {
+                        entry.status = match status.err {
+                            Some(err) => JournalStatus::Failed(err.to_string()),
+                            None => JournalStatus::Confirmed,
+                        };
+                        entry.landed = Some(signature.to_string());
+                    }
+                    None if expired => entry.status = JournalStatus::Expired,
+                    None => in_flight += 1,
+                }
+            }
+            self.save()?;
+            if in_flight == 0 {
+                return Ok(());
+            }
+            println!(
+                "{} transactions of a previous run are in flight, waiting for them to land or expire",
+                in_flight
+            );
+            std::thread::sleep(Duration::from_secs(IN_FLIGHT_POLL_INTERVAL_SECS));
+        }
+    }
+
+    /// Remove the journal of a completed job, a later run of the same job starts afresh
+    pub fn clear(self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // write to a temporary file first, so a crash never leaves a truncated journal
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&self.entries)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
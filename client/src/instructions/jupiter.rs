@@ -0,0 +1,124 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{format_err, Result};
+use serde::Deserialize;
+use std::time::Duration;
+
+// a slow aggregator must not hold the swap for long
+const JUPITER_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuoteResponse {
+    in_amount: String,
+    out_amount: String,
+    #[serde(default)]
+    route_plan: Vec<RoutePlanStep>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RoutePlanStep {
+    swap_info: SwapInfo,
+}
+
+#[derive(Deserialize)]
+struct SwapInfo {
+    #[serde(default)]
+    label: Option<String>,
+}
+
+/// The route jupiter quotes for a swap, raw amounts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JupiterQuote {
+    pub in_amount: u64,
+    pub out_amount: u64,
+    /// The labels of the amms of the route
+    pub route: Vec<String>,
+}
+
+/// Quote the swap of `amount` in, or out without `base_in`, from the jupiter quote api
+pub fn jupiter_quote(
+    api_url: &str,
+    input_mint: &Pubkey,
+    output_mint: &Pubkey,
+    amount: u64,
+    base_in: bool,
+) -> Result<JupiterQuote> {
+    let response: QuoteResponse = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(JUPITER_TIMEOUT_SECS))
+        .build()?
+        .get(format!("{}/quote", api_url.trim_end_matches('/')))
+        .query(&[
+            ("inputMint", input_mint.to_string()),
+            ("outputMint", output_mint.to_string()),
+            ("amount", amount.to_string()),
+            (
+                "swapMode",
+                if base_in { "ExactIn" } else { "ExactOut" }.to_string(),
+            ),
+        ])
+        .send()?
+        .error_for_status()?
+        .json()?;
+    Ok(JupiterQuote {
+        in_amount: response.in_amount.parse()?,
+        out_amount: response.out_amount.parse()?,
+        route: response
+            .route_plan
+            .into_iter()
+            .filter_map(|step| step.swap_info.label)
+            .collect(),
+    })
+}
+
+/// How much worse the pool quote is than the jupiter one in basis points, negative when it is
+/// better. With `base_in` the amounts are the ones out, otherwise the ones in.
+pub fn shortfall_bps(pool_amount: u64, jupiter_amount: u64, base_in: bool) -> f64 {
+    if jupiter_amount == 0 {
+        return 0.0;
+    }
+    let (pool_amount, jupiter_amount) = (pool_amount as f64, jupiter_amount as f64);
+    let shortfall = if base_in {
+        jupiter_amount - pool_amount
+    } else {
+        pool_amount - jupiter_amount
+    };
+    shortfall / jupiter_amount * 10000.0
+}
+
+/// Compare the quote of the pool with the jupiter one for the same swap and fail when the pool is
+/// worse by more than `max_shortfall_bps`, so that a thin pool is not swapped through
+pub fn check_against_jupiter(
+    api_url: &str,
+    input_mint: &Pubkey,
+    output_mint: &Pubkey,
+    amount: u64,
+    base_in: bool,
+    pool_amount: u64,
+    max_shortfall_bps: u64,
+) -> Result<()> {
+    let quote = jupiter_quote(api_url, input_mint, output_mint, amount, base_in)?;
+    let jupiter_amount = if base_in {
+        quote.out_amount
+    } else {
+        quote.in_amount
+    };
+    let shortfall = shortfall_bps(pool_amount, jupiter_amount, base_in);
+    println!(
+        "jupiter {}:{} via [{}], pool {}:{}, pool worse by {:.1}bps",
+        if base_in { "amount out" } else { "amount in" },
+        jupiter_amount,
+        quote.route.join(", "),
+        if base_in { "amount out" } else { "amount in" },
+        pool_amount,
+        shortfall
+    );
+    if shortfall > max_shortfall_bps as f64 {
+        return Err(format_err!(
+            "the pool quote is {:.1}bps worse than jupiter, above the {}bps threshold",
+            shortfall,
+            max_shortfall_bps
+        ));
+    }
+    Ok(())
+}
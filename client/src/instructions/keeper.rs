@@ -0,0 +1,487 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use anchor_lang::prelude::AccountMeta;
+use anyhow::{anyhow, Result};
+use raydium_amm_v3::libraries::{fixed_point_64, liquidity_math, tick_math};
+use raydium_amm_v3::states::{
+    AmmConfig, PersonalPositionState, PoolState, TickArrayBitmapExtension, TickArrayState,
+    TICK_ARRAY_SEED,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
+use std::collections::HashMap;
+
+use super::super::{load_cur_and_next_tick_arrays, ClientConfig};
+use super::amm_instructions::{
+    close_personal_position_instr, decrease_liquidity_instr, swap_v2_instr,
+};
+use super::portfolio::PositionReport;
+use super::utils::{
+    amount_with_slippage, deserialize_anchor_account, get_out_put_amount_and_remaining_accounts,
+    get_pool_mints_transfer_fee, get_transfer_fee_with_next_epoch,
+};
+
+// tick arrays loaded for the quote of a zap out swap
+const ZAP_OUT_TICK_ARRAY_NUM: usize = 8;
+
+/// Conditions on which a keeper fully exits a position, any of them triggers the exit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StopLossPolicy {
+    /// Exit when the price of token_0 in token_1 falls below this level
+    pub price_below: Option<f64>,
+    /// Exit when the price of token_0 in token_1 rises above this level
+    pub price_above: Option<f64>,
+    /// Exit when the position has been out of range for longer, in seconds
+    pub max_out_of_range_secs: Option<u64>,
+}
+
+/// Conditions on which a keeper takes the profit of a position, any of them triggers the exit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TakeProfitPolicy {
+    /// Exit when the value of the liquidity and fees exceeds this one, in token_1 ui amount
+    pub value_above: Option<f64>,
+    /// Exit when the value of the fees exceeds this one, in token_1 ui amount
+    pub fees_above: Option<f64>,
+    /// Exit when the single-sided range order is fully converted
+    pub range_order: Option<RangeOrder>,
+}
+
+/// Single-sided position converting one token into the other as the price crosses its range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeOrder {
+    /// Placed above the price holding token_0, filled once the price is above the range
+    SellToken0,
+    /// Placed below the price holding token_1, filled once the price is below the range
+    SellToken1,
+}
+
+impl RangeOrder {
+    /// The side of a position out of range, None when it is in range and holds both tokens
+    pub fn of(report: &PositionReport) -> Option<Self> {
+        if report.tick_current < report.tick_lower {
+            Some(RangeOrder::SellToken0)
+        } else if report.tick_current >= report.tick_upper {
+            Some(RangeOrder::SellToken1)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_filled(&self, report: &PositionReport) -> bool {
+        match self {
+            RangeOrder::SellToken0 => report.tick_current >= report.tick_upper,
+            RangeOrder::SellToken1 => report.tick_current < report.tick_lower,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitTrigger {
+    PriceBelow(f64),
+    PriceAbove(f64),
+    /// Seconds the position has been out of range
+    OutOfRange(u64),
+    ValueAbove(f64),
+    FeesAbove(f64),
+    RangeOrderFilled(RangeOrder),
+}
+
+impl StopLossPolicy {
+    /// `out_of_range_since` is the unix timestamp the position was first seen out of range,
+    /// it is tracked by the keeper between the checks
+    pub fn check(
+        &self,
+        report: &PositionReport,
+        out_of_range_since: Option<u64>,
+        now: u64,
+    ) -> Option<ExitTrigger> {
+        if let Some(level) = self.price_below {
+            if report.price < level {
+                return Some(ExitTrigger::PriceBelow(report.price));
+            }
+        }
+        if let Some(level) = self.price_above {
+            if report.price > level {
+                return Some(ExitTrigger::PriceAbove(report.price));
+            }
+        }
+        match (self.max_out_of_range_secs, out_of_range_since) {
+            (Some(max_secs), Some(since)) if now.saturating_sub(since) > max_secs => {
+                Some(ExitTrigger::OutOfRange(now - since))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TakeProfitPolicy {
+    pub fn check(&self, report: &PositionReport) -> Option<ExitTrigger> {
+        if let Some(target) = self.value_above {
+            let value = report.value_in_token_1();
+            if value > target {
+                return Some(ExitTrigger::ValueAbove(value));
+            }
+        }
+        if let Some(target) = self.fees_above {
+            let fees = report.fees_in_token_1();
+            if fees > target {
+                return Some(ExitTrigger::FeesAbove(fees));
+            }
+        }
+        match self.range_order {
+            Some(range_order) if range_order.is_filled(report) => {
+                Some(ExitTrigger::RangeOrderFilled(range_order))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Instructions removing liquidity from a position, with the minimum amounts received
+pub struct PositionExit {
+    pub instructions: Vec<Instruction>,
+    pub amount_0_min: u64,
+    pub amount_1_min: u64,
+    /// The token accounts of the owner receiving the tokens
+    pub user_token_account_0: Pubkey,
+    pub user_token_account_1: Pubkey,
+}
+
+/// Build the decrease of `liquidity` from the position, the pending fees and rewards are
+/// collected with it and the position is closed when all its liquidity is removed.
+pub fn exit_position_instrs(
+    config: &ClientConfig,
+    rpc_client: &RpcClient,
+    owner: &Pubkey,
+    pool_state: &PoolState,
+    position: &PersonalPositionState,
+    nft_token_account: (Pubkey, Pubkey),
+    liquidity: u128,
+) -> Result<PositionExit> {
+    let (nft_token_key, nft_token_program) = nft_token_account;
+    let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
+        pool_state.tick_current,
+        pool_state.sqrt_price_x64,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        -i128::try_from(liquidity)?,
+    )?;
+    let amount_0_with_slippage = amount_with_slippage(amount_0, config.slippage, false);
+    let amount_1_with_slippage = amount_with_slippage(amount_1, config.slippage, false);
+    let transfer_fee = get_pool_mints_transfer_fee(
+        rpc_client,
+        pool_state.token_mint_0,
+        pool_state.token_mint_1,
+        amount_0_with_slippage,
+        amount_1_with_slippage,
+    );
+    let amount_0_min = amount_0_with_slippage.saturating_sub(transfer_fee.0.transfer_fee);
+    let amount_1_min = amount_1_with_slippage.saturating_sub(transfer_fee.1.transfer_fee);
+    let user_token_account_0 = get_associated_token_address_with_program_id(
+        owner,
+        &pool_state.token_mint_0,
+        &transfer_fee.0.owner,
+    );
+    let user_token_account_1 = get_associated_token_address_with_program_id(
+        owner,
+        &pool_state.token_mint_1,
+        &transfer_fee.1.owner,
+    );
+
+    let mut remaining_accounts = vec![AccountMeta::new(
+        config.tickarray_bitmap_extension().unwrap(),
+        false,
+    )];
+    for reward_info in pool_state.reward_infos.iter() {
+        if reward_info.token_mint != Pubkey::default() {
+            remaining_accounts.push(AccountMeta::new(reward_info.token_vault, false));
+            remaining_accounts.push(AccountMeta::new(
+                get_associated_token_address(owner, &reward_info.token_mint),
+                false,
+            ));
+            remaining_accounts.push(AccountMeta::new(reward_info.token_mint, false));
+        }
+    }
+    let mut instructions = decrease_liquidity_instr(
+        config,
+        position.pool_id,
+        pool_state.token_vault_0,
+        pool_state.token_vault_1,
+        pool_state.token_mint_0,
+        pool_state.token_mint_1,
+        position.nft_mint,
+        nft_token_key,
+        user_token_account_0,
+        user_token_account_1,
+        remaining_accounts,
+        liquidity,
+        amount_0_min,
+        amount_1_min,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        TickArrayState::get_array_start_index(position.tick_lower_index, pool_state.tick_spacing),
+        TickArrayState::get_array_start_index(position.tick_upper_index, pool_state.tick_spacing),
+    )?;
+    if liquidity == position.liquidity {
+        instructions.extend(close_personal_position_instr(
+            config,
+            position.nft_mint,
+            nft_token_key,
+            nft_token_program,
+        )?);
+    }
+    Ok(PositionExit {
+        instructions,
+        amount_0_min,
+        amount_1_min,
+        user_token_account_0,
+        user_token_account_1,
+    })
+}
+
+/// Build the collection of the pending fees and rewards of the position, a decrease of no
+/// liquidity which leaves the position open. The token accounts of the owner are created if
+/// missing, `token_programs` are the programs of the pool and reward mints.
+pub fn collect_position_fees_instrs(
+    config: &ClientConfig,
+    owner: &Pubkey,
+    pool_state: &PoolState,
+    position: &PersonalPositionState,
+    nft_token_key: Pubkey,
+    token_programs: &HashMap<Pubkey, Pubkey>,
+) -> Result<Vec<Instruction>> {
+    let mut instructions = Vec::new();
+    let mut token_account = |mint: Pubkey| {
+        let token_program = token_programs[&mint];
+        instructions.push(create_associated_token_account_idempotent(
+            owner,
+            owner,
+            &mint,
+            &token_program,
+        ));
+        get_associated_token_address_with_program_id(owner, &mint, &token_program)
+    };
+    let user_token_account_0 = token_account(pool_state.token_mint_0);
+    let user_token_account_1 = token_account(pool_state.token_mint_1);
+    let mut remaining_accounts = vec![AccountMeta::new(
+        config.tickarray_bitmap_extension().unwrap(),
+        false,
+    )];
+    for reward_info in pool_state.reward_infos.iter() {
+        if reward_info.token_mint != Pubkey::default() {
+            remaining_accounts.push(AccountMeta::new(reward_info.token_vault, false));
+            remaining_accounts.push(AccountMeta::new(
+                token_account(reward_info.token_mint),
+                false,
+            ));
+            remaining_accounts.push(AccountMeta::new(reward_info.token_mint, false));
+        }
+    }
+    instructions.extend(decrease_liquidity_instr(
+        config,
+        position.pool_id,
+        pool_state.token_vault_0,
+        pool_state.token_vault_1,
+        pool_state.token_mint_0,
+        pool_state.token_mint_1,
+        position.nft_mint,
+        nft_token_key,
+        user_token_account_0,
+        user_token_account_1,
+        remaining_accounts,
+        0,
+        0,
+        0,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        TickArrayState::get_array_start_index(position.tick_lower_index, pool_state.tick_spacing),
+        TickArrayState::get_array_start_index(position.tick_upper_index, pool_state.tick_spacing),
+    )?);
+    Ok(instructions)
+}
+
+/// Build the swap of `amount` of the other token of the pool into `target_mint`, used to leave
+/// a position entirely in one asset
+pub fn zap_out_instrs(
+    config: &ClientConfig,
+    rpc_client: &RpcClient,
+    owner: &Pubkey,
+    pool_state: &PoolState,
+    target_mint: &Pubkey,
+    amount: u64,
+) -> Result<Vec<Instruction>> {
+    let zero_for_one = if *target_mint == pool_state.token_mint_1 {
+        true
+    } else if *target_mint == pool_state.token_mint_0 {
+        false
+    } else {
+        return Err(anyhow!(
+            "zap out mint {} is not a token of the pool",
+            target_mint
+        ));
+    };
+    let load_accounts = vec![
+        pool_state.amm_config,
+        pool_state.token_mint_0,
+        pool_state.token_mint_1,
+        config.tickarray_bitmap_extension().unwrap(),
+    ];
+    let rsps = rpc_client.get_multiple_accounts(&load_accounts)?;
+    let mut accounts = Vec::with_capacity(rsps.len());
+    for (key, rsp) in load_accounts.iter().zip(rsps) {
+        accounts.push(rsp.ok_or(anyhow!("account {} not found", key))?);
+    }
+    let amm_config_state = deserialize_anchor_account::<AmmConfig>(&accounts[0])?;
+    let tickarray_bitmap_extension =
+        deserialize_anchor_account::<TickArrayBitmapExtension>(&accounts[3])?;
+    let (input_mint_account, output_mint_account) = if zero_for_one {
+        (&accounts[1], &accounts[2])
+    } else {
+        (&accounts[2], &accounts[1])
+    };
+    let epoch = rpc_client.get_epoch_info()?.epoch;
+    let input_mint_state = StateWithExtensions::<Mint>::unpack(&input_mint_account.data)?;
+    let transfer_fee = get_transfer_fee_with_next_epoch(&input_mint_state, epoch, amount);
+
+    let mut tick_arrays = load_cur_and_next_tick_arrays(
+        rpc_client,
+        config,
+        pool_state,
+        &tickarray_bitmap_extension,
+        zero_for_one,
+        ZAP_OUT_TICK_ARRAY_NUM,
+    );
+    let (amount_out, tick_array_indexs) = get_out_put_amount_and_remaining_accounts(
+        amount - transfer_fee,
+        None,
+        zero_for_one,
+        true,
+        &amm_config_state,
+        pool_state,
+        &tickarray_bitmap_extension,
+        &mut tick_arrays,
+    )
+    .map_err(|err| anyhow!(err))?;
+    let other_amount_threshold = amount_with_slippage(amount_out, config.slippage, false);
+
+    let pool_id = config.pool_id_account().unwrap();
+    let mut remaining_accounts = vec![AccountMeta::new_readonly(
+        config.tickarray_bitmap_extension().unwrap(),
+        false,
+    )];
+    remaining_accounts.extend(tick_array_indexs.into_iter().map(|index| {
+        AccountMeta::new(
+            Pubkey::find_program_address(
+                &[
+                    TICK_ARRAY_SEED.as_bytes(),
+                    pool_id.as_ref(),
+                    &index.to_be_bytes(),
+                ],
+                &config.raydium_v3_program,
+            )
+            .0,
+            false,
+        )
+    }));
+    let (input_vault, output_vault) = if zero_for_one {
+        (pool_state.token_vault_0, pool_state.token_vault_1)
+    } else {
+        (pool_state.token_vault_1, pool_state.token_vault_0)
+    };
+    let (input_mint, output_mint) = if zero_for_one {
+        (pool_state.token_mint_0, pool_state.token_mint_1)
+    } else {
+        (pool_state.token_mint_1, pool_state.token_mint_0)
+    };
+    swap_v2_instr(
+        config,
+        pool_state.amm_config,
+        pool_id,
+        input_vault,
+        output_vault,
+        pool_state.observation_key,
+        get_associated_token_address_with_program_id(owner, &input_mint, &input_mint_account.owner),
+        get_associated_token_address_with_program_id(
+            owner,
+            &output_mint,
+            &output_mint_account.owner,
+        ),
+        input_mint,
+        output_mint,
+        remaining_accounts,
+        amount,
+        other_amount_threshold,
+        None,
+        true,
+    )
+}
+
+/// The liquidity the amounts deposit in the range at the current price, after their transfer fees
+/// and the slippage
+pub fn liquidity_for_amounts(
+    config: &ClientConfig,
+    rpc_client: &RpcClient,
+    pool_state: &PoolState,
+    tick_lower: i32,
+    tick_upper: i32,
+    amount_0: u64,
+    amount_1: u64,
+) -> Result<u128> {
+    let transfer_fee = get_pool_mints_transfer_fee(
+        rpc_client,
+        pool_state.token_mint_0,
+        pool_state.token_mint_1,
+        amount_0,
+        amount_1,
+    );
+    Ok(liquidity_math::get_liquidity_from_amounts(
+        pool_state.sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(tick_lower)?,
+        tick_math::get_sqrt_price_at_tick(tick_upper)?,
+        amount_with_slippage(
+            amount_0.saturating_sub(transfer_fee.0.transfer_fee),
+            config.slippage,
+            false,
+        ),
+        amount_with_slippage(
+            amount_1.saturating_sub(transfer_fee.1.transfer_fee),
+            config.slippage,
+            false,
+        ),
+    ))
+}
+
+/// The swap bringing the amounts to the proportions of a position of the range at the current
+/// price, as `(zero_for_one, amount_in)`. None when the amounts already match. The fees and the
+/// price impact of the swap are not accounted for, the deposit absorbs them.
+pub fn rebalance_swap_amount(
+    amount_0: u64,
+    amount_1: u64,
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Result<Option<(bool, u64)>> {
+    let q64 = fixed_point_64::Q64 as f64;
+    let sqrt_lower = tick_math::get_sqrt_price_at_tick(tick_lower)? as f64 / q64;
+    let sqrt_upper = tick_math::get_sqrt_price_at_tick(tick_upper)? as f64 / q64;
+    let sqrt_price = (sqrt_price_x64 as f64 / q64).clamp(sqrt_lower, sqrt_upper);
+    // raw token_1 per raw token_0
+    let price = (sqrt_price_x64 as f64 / q64).powi(2);
+    // the amounts of one unit of liquidity, valued in token_1
+    let value_0 = (1.0 / sqrt_price - 1.0 / sqrt_upper) * price;
+    let value_1 = sqrt_price - sqrt_lower;
+    let value = amount_0 as f64 * price + amount_1 as f64;
+    let target_1 = value * value_1 / (value_0 + value_1);
+    let swap = if amount_1 as f64 > target_1 {
+        (false, (amount_1 as f64 - target_1) as u64)
+    } else {
+        (true, ((target_1 - amount_1 as f64) / price) as u64)
+    };
+    if swap.1 == 0 {
+        return Ok(None);
+    }
+    Ok(Some(swap))
+}
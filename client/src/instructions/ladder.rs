@@ -0,0 +1,123 @@
+use anyhow::{format_err, Result};
+use clap::ValueEnum;
+use raydium_amm_v3::libraries::liquidity_math;
+
+/// How the liquidity of a ladder is spread over its buckets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LadderDistribution {
+    /// The same liquidity in every bucket
+    #[default]
+    Uniform,
+    /// A bell curve centered on the current price, a standard deviation of a quarter of the range
+    Normal,
+}
+
+/// One position of a ladder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LadderBucket {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    /// The amounts deposited at the current price, without slippage and transfer fees
+    pub amount_0: u64,
+    pub amount_1: u64,
+}
+
+/// Split the range into consecutive buckets aligned to the tick spacing and spread the
+/// liquidity over them with the distribution, as much as the budgets of both tokens allow.
+/// The buckets below the price only hold token_1 and the buckets above it only token_0.
+pub fn ladder_buckets(
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_spacing: u16,
+    buckets: u16,
+    distribution: LadderDistribution,
+    tick_current: i32,
+    sqrt_price_x64: u128,
+    budget_0: u64,
+    budget_1: u64,
+) -> Result<Vec<LadderBucket>> {
+    let tick_spacing = i32::from(tick_spacing);
+    let spacings = (tick_upper - tick_lower) / tick_spacing;
+    let buckets = i32::from(buckets);
+    if buckets == 0 || spacings < buckets {
+        return Err(format_err!(
+            "the range of {} tick spacings can't hold {} buckets",
+            spacings,
+            buckets
+        ));
+    }
+    // the first buckets take one more spacing when they don't divide evenly
+    let mut ranges = Vec::new();
+    let mut lower = tick_lower;
+    for i in 0..buckets {
+        let width = spacings / buckets + i32::from(i < spacings % buckets);
+        ranges.push((lower, lower + width * tick_spacing));
+        lower += width * tick_spacing;
+    }
+
+    let center = f64::from(tick_current.clamp(tick_lower, tick_upper));
+    let sigma = f64::from(tick_upper - tick_lower) / 4.0;
+    let weights: Vec<f64> = ranges
+        .iter()
+        .map(|(lower, upper)| match distribution {
+            LadderDistribution::Uniform => 1.0,
+            LadderDistribution::Normal => {
+                let middle = f64::from(lower + upper) / 2.0;
+                (-0.5 * ((middle - center) / sigma).powi(2)).exp()
+            }
+        })
+        .collect();
+
+    // the amounts of the weights as liquidity, then scaled down to the budgets
+    const UNIT_LIQUIDITY: f64 = 1e12;
+    let mut unit_amounts = Vec::new();
+    for ((lower, upper), weight) in ranges.iter().zip(weights.iter()) {
+        unit_amounts.push(liquidity_math::get_delta_amounts_signed(
+            tick_current,
+            sqrt_price_x64,
+            *lower,
+            *upper,
+            (weight * UNIT_LIQUIDITY) as i128,
+        )?);
+    }
+    let total_0: f64 = unit_amounts
+        .iter()
+        .map(|(amount_0, _)| *amount_0 as f64)
+        .sum();
+    let total_1: f64 = unit_amounts
+        .iter()
+        .map(|(_, amount_1)| *amount_1 as f64)
+        .sum();
+    let scale = [(total_0, budget_0), (total_1, budget_1)]
+        .iter()
+        .filter(|(total, _)| *total > 0.0)
+        .map(|(total, budget)| *budget as f64 / total)
+        .fold(f64::INFINITY, f64::min);
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(format_err!("no budget for the tokens of the range"));
+    }
+
+    let mut ladder = Vec::new();
+    for ((lower, upper), weight) in ranges.into_iter().zip(weights) {
+        let liquidity = (weight * UNIT_LIQUIDITY * scale) as u128;
+        if liquidity == 0 {
+            continue;
+        }
+        let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
+            tick_current,
+            sqrt_price_x64,
+            lower,
+            upper,
+            i128::try_from(liquidity)?,
+        )?;
+        ladder.push(LadderBucket {
+            tick_lower: lower,
+            tick_upper: upper,
+            liquidity,
+            amount_0,
+            amount_1,
+        });
+    }
+    Ok(ladder)
+}
@@ -0,0 +1,259 @@
+use anchor_client::solana_account_decoder::UiAccountEncoding;
+use anchor_client::solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use raydium_amm_v3::states::{
+    PoolState, TickArrayBitmapExtension, TickArrayState, TICK_ARRAY_SEED,
+};
+
+use super::rpc::get_multiple_accounts_concurrently;
+use super::utils::zero_copy_account;
+
+/// Liquidity of an initialized tick
+#[derive(Debug, Clone, Copy)]
+pub struct InitializedTick {
+    pub tick: i32,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+/// Load all the initialized ticks of a pool, sorted by tick
+pub fn load_initialized_ticks(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_id: &Pubkey,
+) -> Result<Vec<InitializedTick>> {
+    let tick_arrays_by_pool = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(8, &pool_id.to_bytes())),
+                RpcFilterType::DataSize(TickArrayState::LEN as u64),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64Zstd),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: Some(false),
+            sort_results: None,
+        },
+    )?;
+    let mut ticks = Vec::new();
+    for (_, account) in tick_arrays_by_pool.iter() {
+        let tick_array_state = zero_copy_account::<TickArrayState>(&account.data)?;
+        if tick_array_state.pool_id != *pool_id {
+            continue;
+        }
+        for tick_state in tick_array_state.ticks.iter() {
+            if tick_state.liquidity_gross != 0 {
+                ticks.push(InitializedTick {
+                    tick: tick_state.tick,
+                    liquidity_net: tick_state.liquidity_net,
+                    liquidity_gross: tick_state.liquidity_gross,
+                });
+            }
+        }
+    }
+    ticks.sort_by_key(|tick| tick.tick);
+    Ok(ticks)
+}
+
+/// Load the initialized ticks of a pool within [tick_lower, tick_upper], sorted by tick. The tick
+/// arrays are found from the bitmap of the pool and its extension instead of scanning the program
+/// accounts, which many rpc nodes don't serve.
+pub fn load_initialized_ticks_in_range(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_id: &Pubkey,
+    pool_state: &PoolState,
+    tickarray_bitmap_extension: &TickArrayBitmapExtension,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Result<Vec<InitializedTick>> {
+    let tickarray_bitmap_extension = Some(*tickarray_bitmap_extension);
+    // the array of the lower bound is fetched in any case, it is skipped when not initialized
+    let mut start_index =
+        TickArrayState::get_array_start_index(tick_lower, pool_state.tick_spacing);
+    let mut start_indexes = vec![start_index];
+    while let Some(next_start_index) = pool_state.next_initialized_tick_array_start_index(
+        &tickarray_bitmap_extension,
+        start_index,
+        false,
+    )? {
+        if next_start_index > tick_upper {
+            break;
+        }
+        start_indexes.push(next_start_index);
+        start_index = next_start_index;
+    }
+    let tick_array_keys: Vec<Pubkey> = start_indexes
+        .iter()
+        .map(|start_index| {
+            Pubkey::find_program_address(
+                &[
+                    TICK_ARRAY_SEED.as_bytes(),
+                    pool_id.as_ref(),
+                    &start_index.to_be_bytes(),
+                ],
+                program_id,
+            )
+            .0
+        })
+        .collect();
+    let mut ticks = Vec::new();
+    for account in get_multiple_accounts_concurrently(rpc_client, &tick_array_keys)?
+        .iter()
+        .flatten()
+    {
+        let tick_array_state = zero_copy_account::<TickArrayState>(&account.data)?;
+        for tick_state in tick_array_state.ticks.iter() {
+            if tick_state.liquidity_gross != 0
+                && tick_lower <= tick_state.tick
+                && tick_state.tick <= tick_upper
+            {
+                ticks.push(InitializedTick {
+                    tick: tick_state.tick,
+                    liquidity_net: tick_state.liquidity_net,
+                    liquidity_gross: tick_state.liquidity_gross,
+                });
+            }
+        }
+    }
+    ticks.sort_by_key(|tick| tick.tick);
+    Ok(ticks)
+}
+
+/// The nearest initialized ticks strictly below and above `tick`
+pub fn neighbor_ticks(
+    ticks: &[InitializedTick],
+    tick: i32,
+) -> (Option<InitializedTick>, Option<InitializedTick>) {
+    let below = ticks.iter().rev().find(|t| t.tick < tick).copied();
+    let above = ticks.iter().find(|t| t.tick > tick).copied();
+    (below, above)
+}
+
+/// Active liquidity in the range starting at `tick`, the sum of the net liquidity of all the
+/// initialized ticks at or below it
+pub fn active_liquidity_at(ticks: &[InitializedTick], tick: i32) -> i128 {
+    ticks
+        .iter()
+        .take_while(|t| t.tick <= tick)
+        .map(|t| t.liquidity_net)
+        .sum()
+}
+
+/// Consistency of the liquidity accounting of a pool
+#[derive(Debug, Clone)]
+pub struct LiquidityCheck {
+    /// Sum of the net liquidity of all the ticks, zero when every position adds and removes it
+    pub liquidity_net_sum: i128,
+    /// Active liquidity at the current tick rebuilt from the ticks
+    pub reconstructed_liquidity: i128,
+    pub pool_liquidity: u128,
+    /// Ticks whose net liquidity exceeds their gross liquidity
+    pub gross_violations: Vec<i32>,
+}
+
+impl LiquidityCheck {
+    pub fn is_consistent(&self) -> bool {
+        self.liquidity_net_sum == 0
+            && self.reconstructed_liquidity == self.pool_liquidity as i128
+            && self.gross_violations.is_empty()
+    }
+}
+
+pub fn check_liquidity(
+    ticks: &[InitializedTick],
+    tick_current: i32,
+    pool_liquidity: u128,
+) -> LiquidityCheck {
+    LiquidityCheck {
+        liquidity_net_sum: ticks.iter().map(|t| t.liquidity_net).sum(),
+        reconstructed_liquidity: active_liquidity_at(ticks, tick_current),
+        pool_liquidity,
+        gross_violations: ticks
+            .iter()
+            .filter(|t| t.liquidity_net.unsigned_abs() > t.liquidity_gross)
+            .map(|t| t.tick)
+            .collect(),
+    }
+}
+
+/// One row of the liquidity profile, the active liquidity holds from `tick` up to the next row
+#[derive(Debug, Clone, Copy)]
+pub struct LiquidityLevel {
+    pub tick: i32,
+    pub liquidity_net: i128,
+    pub active_liquidity: i128,
+}
+
+/// Rebuild the active liquidity at every initialized tick within `range_ticks` of the current
+/// tick. The first row starts at the lower bound of the range with the liquidity crossed below it.
+pub fn liquidity_profile(
+    ticks: &[InitializedTick],
+    tick_current: i32,
+    range_ticks: u32,
+) -> Vec<LiquidityLevel> {
+    let lower = tick_current.saturating_sub_unsigned(range_ticks);
+    let upper = tick_current.saturating_add_unsigned(range_ticks);
+    let liquidity_below = ticks
+        .iter()
+        .take_while(|t| t.tick < lower)
+        .map(|t| t.liquidity_net)
+        .sum();
+    profile_levels(ticks, lower, upper, liquidity_below)
+}
+
+/// The liquidity profile from the initialized ticks of the range only, the liquidity crossed
+/// below the range is rebuilt from the active liquidity of the pool
+pub fn liquidity_profile_in_range(
+    ticks: &[InitializedTick],
+    tick_current: i32,
+    pool_liquidity: u128,
+    range_ticks: u32,
+) -> Vec<LiquidityLevel> {
+    let lower = tick_current.saturating_sub_unsigned(range_ticks);
+    let upper = tick_current.saturating_add_unsigned(range_ticks);
+    // the ticks from the lower bound up to the current one are crossed to reach the pool liquidity
+    let crossed: i128 = ticks
+        .iter()
+        .filter(|t| lower <= t.tick && t.tick <= tick_current)
+        .map(|t| t.liquidity_net)
+        .sum();
+    profile_levels(ticks, lower, upper, pool_liquidity as i128 - crossed)
+}
+
+// the levels of the ticks within [lower, upper], starting with the liquidity below the range
+fn profile_levels(
+    ticks: &[InitializedTick],
+    lower: i32,
+    upper: i32,
+    liquidity_below: i128,
+) -> Vec<LiquidityLevel> {
+    let mut active_liquidity = liquidity_below;
+    let mut levels = vec![LiquidityLevel {
+        tick: lower,
+        liquidity_net: 0,
+        active_liquidity,
+    }];
+    for t in ticks.iter().filter(|t| lower <= t.tick && t.tick <= upper) {
+        active_liquidity += t.liquidity_net;
+        let level = LiquidityLevel {
+            tick: t.tick,
+            liquidity_net: t.liquidity_net,
+            active_liquidity,
+        };
+        // an initialized tick at the lower bound replaces the starting row
+        if t.tick == lower {
+            levels[0] = level;
+        } else {
+            levels.push(level);
+        }
+    }
+    levels
+}
@@ -0,0 +1,98 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{format_err, Result};
+use raydium_amm_v3::states::{
+    PoolState, TickArrayState, POOL_TICK_ARRAY_BITMAP_SEED, TICK_ARRAY_SEED,
+};
+use std::process::Command;
+
+use super::rpc::get_multiple_accounts_concurrently;
+use super::utils::deserialize_anchor_account;
+
+/// The accounts a swap through the pool reads: the pool, its config, vaults, mints, observation,
+/// bitmap extension and the tick arrays up to `tick_array_count` arrays on each side of the
+/// current tick. Only the accounts which exist are returned, a missing account can't be cloned.
+pub fn pool_clone_accounts(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_id: &Pubkey,
+    tick_array_count: u16,
+) -> Result<Vec<Pubkey>> {
+    let pool_account = rpc_client.get_account(pool_id)?;
+    let pool = deserialize_anchor_account::<PoolState>(&pool_account)?;
+    let tick_spacing = pool.tick_spacing;
+    let tick_current = pool.tick_current;
+
+    let mut accounts = vec![
+        *pool_id,
+        pool.amm_config,
+        pool.token_vault_0,
+        pool.token_vault_1,
+        pool.token_mint_0,
+        pool.token_mint_1,
+        pool.observation_key,
+        Pubkey::find_program_address(
+            &[POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_id.as_ref()],
+            program_id,
+        )
+        .0,
+    ];
+    let current_start_index = TickArrayState::get_array_start_index(tick_current, tick_spacing);
+    let tick_count = TickArrayState::tick_count(tick_spacing);
+    for offset in -i32::from(tick_array_count)..=i32::from(tick_array_count) {
+        let start_index = current_start_index + offset * tick_count;
+        if !TickArrayState::check_is_valid_start_index(start_index, tick_spacing) {
+            continue;
+        }
+        accounts.push(
+            Pubkey::find_program_address(
+                &[
+                    TICK_ARRAY_SEED.as_bytes(),
+                    pool_id.as_ref(),
+                    &start_index.to_be_bytes(),
+                ],
+                program_id,
+            )
+            .0,
+        );
+    }
+
+    let existing = get_multiple_accounts_concurrently(rpc_client, &accounts)?;
+    Ok(accounts
+        .into_iter()
+        .zip(existing)
+        .filter_map(|(key, account)| account.map(|_| key))
+        .collect())
+}
+
+/// Run `solana-test-validator` with the program and the accounts cloned from `source_url`,
+/// until the validator exits
+pub fn run_cloning_validator(
+    source_url: &str,
+    program_id: &Pubkey,
+    accounts: &[Pubkey],
+    ledger: &str,
+    reset: bool,
+) -> Result<()> {
+    let mut command = Command::new("solana-test-validator");
+    command
+        .arg("--url")
+        .arg(source_url)
+        .arg("--ledger")
+        .arg(ledger)
+        .arg("--clone-upgradeable-program")
+        .arg(program_id.to_string());
+    for account in accounts {
+        command.arg("--clone").arg(account.to_string());
+    }
+    if reset {
+        command.arg("--reset");
+    }
+    let status = command
+        .status()
+        .map_err(|err| format_err!("failed to run solana-test-validator: {}", err))?;
+    if !status.success() {
+        return Err(format_err!("solana-test-validator exited with {}", status));
+    }
+    Ok(())
+}
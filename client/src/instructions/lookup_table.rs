@@ -0,0 +1,94 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::{
+    address_lookup_table::{
+        instruction as lookup_table_instruction, state::AddressLookupTable,
+        AddressLookupTableAccount,
+    },
+    instruction::Instruction,
+    pubkey::Pubkey,
+};
+use anyhow::{format_err, Result};
+
+use super::rpc::get_multiple_accounts;
+
+// addresses added by one extend instruction, so the transaction stays below the packet size
+pub const MAX_EXTEND_ADDRESSES: usize = 20;
+
+/// The instruction creating the lookup table of the authority, and the address of the table.
+/// The recent slot seeds the address, it must be a slot of the recent slot hashes.
+pub fn create_lookup_table_instr(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+) -> (Instruction, Pubkey) {
+    lookup_table_instruction::create_lookup_table(*authority, *payer, recent_slot)
+}
+
+/// The instructions adding the addresses to the table, one transaction each
+pub fn extend_lookup_table_instrs(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> Vec<Instruction> {
+    addresses
+        .chunks(MAX_EXTEND_ADDRESSES)
+        .map(|chunk| {
+            lookup_table_instruction::extend_lookup_table(
+                *table,
+                *authority,
+                Some(*payer),
+                chunk.to_vec(),
+            )
+        })
+        .collect()
+}
+
+/// Deactivate the table, it can be closed once the deactivation slot leaves the slot hashes
+pub fn deactivate_lookup_table_instr(table: &Pubkey, authority: &Pubkey) -> Instruction {
+    lookup_table_instruction::deactivate_lookup_table(*table, *authority)
+}
+
+/// Close the deactivated table, the rent goes to the recipient
+pub fn close_lookup_table_instr(
+    table: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+) -> Instruction {
+    lookup_table_instruction::close_lookup_table(*table, *authority, *recipient)
+}
+
+/// Load the lookup tables, fetched for each transaction as they are extended over time
+pub fn load_lookup_tables(
+    client: &RpcClient,
+    keys: &[Pubkey],
+) -> Result<Vec<AddressLookupTableAccount>> {
+    if keys.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut tables = Vec::new();
+    for (key, account) in keys.iter().zip(get_multiple_accounts(client, keys)?) {
+        let account = account.ok_or_else(|| format_err!("lookup table {} not found", key))?;
+        let table = AddressLookupTable::deserialize(&account.data)?;
+        tables.push(AddressLookupTableAccount {
+            key: *key,
+            addresses: table.addresses.to_vec(),
+        });
+    }
+    Ok(tables)
+}
+
+/// The addresses which are not in the table yet, in order and without duplicates
+pub fn missing_addresses(
+    table: Option<&AddressLookupTableAccount>,
+    addresses: &[Pubkey],
+) -> Vec<Pubkey> {
+    let mut missing: Vec<Pubkey> = Vec::new();
+    for address in addresses {
+        let in_table = table.map_or(false, |table| table.addresses.contains(address));
+        if !in_table && !missing.contains(address) {
+            missing.push(*address);
+        }
+    }
+    missing
+}
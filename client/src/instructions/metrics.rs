@@ -0,0 +1,153 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use raydium_amm_v3::states::PoolState;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::portfolio::{ui_amount, PositionReport};
+use super::utils::sqrt_price_x64_to_price;
+
+/// Serves the metrics of the latest refresh at `GET /metrics`, in the prometheus text format
+pub struct MetricsExporter {
+    body: RwLock<String>,
+}
+
+impl MetricsExporter {
+    /// Start serving on `addr`, e.g. "0.0.0.0:9100"
+    pub fn serve(addr: &str) -> Result<Arc<Self>> {
+        let listener = TcpListener::bind(addr)?;
+        let exporter = Arc::new(Self {
+            body: RwLock::new(String::new()),
+        });
+        println!("metrics endpoint listening on http://{}/metrics", addr);
+        let server = exporter.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // a broken connection only fails the scrape
+                let _ = server.respond(stream);
+            }
+        });
+        Ok(exporter)
+    }
+
+    pub fn update(&self, body: String) {
+        *self.body.write().unwrap() = body;
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let mut request = [0u8; 1024];
+        let len = stream.read(&mut request)?;
+        let request = String::from_utf8_lossy(&request[..len]);
+        let (status, body) = if request.starts_with("GET /metrics ") {
+            ("200 OK", self.body.read().unwrap().clone())
+        } else {
+            ("404 Not Found", String::new())
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        Ok(())
+    }
+}
+
+/// Render the gauges of the pools and of the positions, the positions are labeled with their
+/// pool so they can be joined with the pool gauges
+pub fn render_metrics(pools: &[(Pubkey, PoolState)], positions: &[PositionReport]) -> String {
+    let mut body = String::new();
+    let mut gauge = |name: &str, help: &str, samples: Vec<(String, f64)>| {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} gauge", name);
+        for (labels, value) in samples {
+            let _ = writeln!(body, "{}{{{}}} {}", name, labels, value);
+        }
+    };
+    let pool_label = |pool_id: &Pubkey| format!("pool=\"{}\"", pool_id);
+    gauge(
+        "clmm_pool_price",
+        "Price of token_0 in token_1",
+        pools
+            .iter()
+            .map(|(pool_id, pool)| {
+                (
+                    pool_label(pool_id),
+                    sqrt_price_x64_to_price(
+                        pool.sqrt_price_x64,
+                        pool.mint_decimals_0,
+                        pool.mint_decimals_1,
+                    ),
+                )
+            })
+            .collect(),
+    );
+    gauge(
+        "clmm_pool_tick",
+        "Current tick of the pool",
+        pools
+            .iter()
+            .map(|(pool_id, pool)| (pool_label(pool_id), f64::from(pool.tick_current)))
+            .collect(),
+    );
+    gauge(
+        "clmm_pool_liquidity",
+        "Active liquidity of the pool",
+        pools
+            .iter()
+            .map(|(pool_id, pool)| (pool_label(pool_id), pool.liquidity as f64))
+            .collect(),
+    );
+    let position_label = |report: &PositionReport| {
+        format!(
+            "position=\"{}\",nft_mint=\"{}\",pool=\"{}\"",
+            report.position, report.nft_mint, report.pool_id
+        )
+    };
+    gauge(
+        "clmm_position_in_range",
+        "1 when the current tick of the pool is in the range of the position",
+        positions
+            .iter()
+            .map(|report| {
+                (
+                    position_label(report),
+                    if report.in_range() { 1.0 } else { 0.0 },
+                )
+            })
+            .collect(),
+    );
+    gauge(
+        "clmm_position_liquidity",
+        "Liquidity of the position",
+        positions
+            .iter()
+            .map(|report| (position_label(report), report.liquidity as f64))
+            .collect(),
+    );
+    gauge(
+        "clmm_position_pending_fees",
+        "Fees earned and not collected yet, in ui amount of the token",
+        positions
+            .iter()
+            .flat_map(|report| {
+                [
+                    (
+                        format!("{},token=\"0\"", position_label(report)),
+                        ui_amount(report.fees_0, report.decimals_0),
+                    ),
+                    (
+                        format!("{},token=\"1\"", position_label(report)),
+                        ui_amount(report.fees_1, report.decimals_1),
+                    ),
+                ]
+            })
+            .collect(),
+    );
+    body
+}
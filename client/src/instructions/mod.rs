@@ -1,5 +1,50 @@
 pub mod amm_instructions;
+pub mod apr;
+pub mod async_rpc;
+pub mod bitmap;
+pub mod cache;
+pub mod compute_units;
+pub mod confirm;
+#[cfg(feature = "sqlite")]
+pub mod event_db;
 pub mod events_instructions_parse;
+pub mod export;
+pub mod failover;
+#[cfg(feature = "yellowstone")]
+pub mod geyser;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod health;
+pub mod indexer;
+pub mod jito;
+pub mod journal;
+pub mod jupiter;
+pub mod keeper;
+pub mod ladder;
+pub mod liquidity;
+pub mod localnet;
+pub mod lookup_table;
+pub mod metrics;
+pub mod monitor;
+pub mod plan;
+pub mod pools;
+pub mod portfolio;
+pub mod position_history;
+pub mod priority_fee;
+pub mod profile;
+pub mod quote;
+pub mod quote_server;
+pub mod replay;
+pub mod revenue;
 pub mod rpc;
+pub mod scan;
+pub mod settings;
+pub mod signer;
+pub mod squads;
+pub mod tax_export;
+pub mod ticker;
 pub mod token_instructions;
+pub mod twap;
+pub mod tx_builder;
 pub mod utils;
+pub mod volume;
@@ -0,0 +1,75 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use serde::Serialize;
+
+/// Where the current tick of a pool is relative to the range of a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeStatus {
+    InRange,
+    /// In range, within the buffer of a bound
+    NearBound,
+    OutOfRange,
+}
+
+/// The status of the range of a position at the tick, `buffer_ticks` from the bounds counts as
+/// near a bound
+pub fn range_status(
+    tick_current: i32,
+    tick_lower: i32,
+    tick_upper: i32,
+    buffer_ticks: i32,
+) -> RangeStatus {
+    if tick_current < tick_lower || tick_current >= tick_upper {
+        RangeStatus::OutOfRange
+    } else if tick_current < tick_lower + buffer_ticks || tick_current >= tick_upper - buffer_ticks
+    {
+        RangeStatus::NearBound
+    } else {
+        RangeStatus::InRange
+    }
+}
+
+/// A change of the range status of a position
+#[derive(Debug, Clone, Serialize)]
+pub struct RangeAlert {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub position: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub pool_id: Pubkey,
+    pub status: RangeStatus,
+    pub slot: u64,
+    pub tick_current: i32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub price: f64,
+}
+
+fn serialize_pubkey<S: serde::Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&key.to_string())
+}
+
+/// Print the alert, and post it as json to the webhook when one is set. A webhook failure is
+/// reported without stopping the monitor.
+pub fn send_range_alert(webhook: Option<&str>, alert: &RangeAlert) {
+    println!(
+        "slot:{}, position:{}, pool:{}, status:{:?}, tick:{} in [{}, {}), price:{}",
+        alert.slot,
+        alert.position,
+        alert.pool_id,
+        alert.status,
+        alert.tick_current,
+        alert.tick_lower,
+        alert.tick_upper,
+        alert.price
+    );
+    if let Some(webhook) = webhook {
+        let result = reqwest::blocking::Client::new()
+            .post(webhook)
+            .json(alert)
+            .send()
+            .and_then(|response| response.error_for_status());
+        if let Err(err) = result {
+            println!("webhook {} failed: {}", webhook, err);
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use anchor_client::solana_account_decoder::UiAccountEncoding;
+use anchor_client::solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+};
+use anchor_client::solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    transaction::VersionedTransaction,
+};
+use anchor_lang::Discriminator;
+use anyhow::Result;
+use mpl_token_metadata::accounts::Metadata;
+use raydium_amm_v3::states::*;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::rpc::get_multiple_accounts;
+use super::utils::deserialize_anchor_account;
+
+// the program of the pdas to explain, set once the plan mode is enabled
+static PLAN_PROGRAM: OnceLock<Pubkey> = OnceLock::new();
+
+/// Print the plan of every transaction instead of sending it, for the rest of the process
+pub fn enable_plan_mode(program_id: Pubkey) {
+    let _ = PLAN_PROGRAM.set(program_id);
+}
+
+pub fn plan_mode_program() -> Option<Pubkey> {
+    PLAN_PROGRAM.get().copied()
+}
+
+/// In plan mode, print the plan of the transaction and return its signature without sending it
+pub fn plan_instead_of_send(
+    client: &RpcClient,
+    txn: &VersionedTransaction,
+) -> Result<Option<Signature>> {
+    let program_id = match plan_mode_program() {
+        Some(program_id) => program_id,
+        None => return Ok(None),
+    };
+    print_plan(&plan_transaction(client, txn, &program_id)?);
+    Ok(Some(txn.signatures[0]))
+}
+
+/// An account of a transaction and what the transaction does to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountAccess {
+    pub key: Pubkey,
+    pub writable: bool,
+    pub signer: bool,
+    /// The seeds and program of a pda, when derivable from the keys and data of the transaction
+    pub seeds: Option<String>,
+    /// None when the account doesn't exist before the transaction
+    pub pre_lamports: Option<u64>,
+    /// The simulated state after the transaction, only for the writable accounts
+    pub post_lamports: Option<u64>,
+    pub post_size: Option<usize>,
+}
+
+impl AccountAccess {
+    /// The rent paid to create the account, negative when the transaction closes it
+    pub fn rent(&self) -> i128 {
+        match (self.pre_lamports.unwrap_or(0), self.post_lamports) {
+            (0, Some(post)) => i128::from(post),
+            (pre, Some(0)) => -i128::from(pre),
+            _ => 0,
+        }
+    }
+}
+
+/// Simulate the transaction and list the accounts it touches, in the order of the message
+pub fn plan_transaction(
+    client: &RpcClient,
+    txn: &VersionedTransaction,
+    program_id: &Pubkey,
+) -> Result<Vec<AccountAccess>> {
+    let message = &txn.message;
+    let keys = message.static_account_keys().to_vec();
+    let writable_keys: Vec<Pubkey> = keys
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| message.is_maybe_writable(*i))
+        .map(|(_, key)| *key)
+        .collect();
+    let pre_accounts = get_multiple_accounts(client, &keys)?;
+    let result = client
+        .simulate_transaction_with_config(
+            txn,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                commitment: Some(CommitmentConfig::confirmed()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: writable_keys.iter().map(|key| key.to_string()).collect(),
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+    if let Some(err) = &result.err {
+        println!("simulation failed: {:?}, logs: {:#?}", err, result.logs);
+    }
+    let post_accounts: HashMap<Pubkey, Option<Account>> = writable_keys
+        .iter()
+        .cloned()
+        .zip(
+            result
+                .accounts
+                .unwrap_or_default()
+                .into_iter()
+                .map(|ui_account| ui_account.and_then(|ui_account| ui_account.decode())),
+        )
+        .collect();
+
+    // the data of the program accounts holds the integer seeds, e.g. the start index of a tick array
+    let mut data_accounts: Vec<(Pubkey, &Account)> = Vec::new();
+    for (key, account) in keys.iter().zip(pre_accounts.iter()) {
+        if let Some(account) = post_accounts.get(key).and_then(Option::as_ref) {
+            data_accounts.push((*key, account));
+        } else if let Some(account) = account {
+            data_accounts.push((*key, account));
+        }
+    }
+    let seeds = known_pdas(program_id, &keys, &data_accounts);
+
+    Ok(keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| {
+            let post_account = post_accounts.get(key).map(Option::as_ref);
+            AccountAccess {
+                key: *key,
+                writable: message.is_maybe_writable(i),
+                signer: message.is_signer(i),
+                seeds: seeds.get(key).cloned(),
+                pre_lamports: pre_accounts[i].as_ref().map(|account| account.lamports),
+                post_lamports: post_account
+                    .map(|account| account.map_or(0, |account| account.lamports)),
+                post_size: post_account.flatten().map(|account| account.data.len()),
+            }
+        })
+        .collect())
+}
+
+pub fn print_plan(accesses: &[AccountAccess]) {
+    println!("plan, not sent:");
+    for access in accesses {
+        println!(
+            "  {:<44} {}{} {}{}",
+            access.key,
+            if access.writable { "w" } else { "-" },
+            if access.signer { "s" } else { "-" },
+            match (access.pre_lamports, access.rent()) {
+                (None, rent) if rent > 0 => format!(
+                    "created, size:{}, rent:{}",
+                    access.post_size.unwrap_or_default(),
+                    rent
+                ),
+                (_, rent) if rent < 0 => format!("closed, refund:{}", -rent),
+                (None, _) => "missing".to_string(),
+                _ => "existing".to_string(),
+            },
+            access
+                .seeds
+                .as_ref()
+                .map(|seeds| format!(", pda {}", seeds))
+                .unwrap_or_default()
+        );
+    }
+    let rent: i128 = accesses.iter().map(AccountAccess::rent).sum();
+    println!("  rent paid:{} lamports", rent);
+}
+
+struct PdaCandidate {
+    program: Pubkey,
+    label: String,
+    seeds: Vec<Vec<u8>>,
+}
+
+impl PdaCandidate {
+    fn new(program: Pubkey, label: String, seeds: Vec<&[u8]>) -> Self {
+        Self {
+            program,
+            label,
+            seeds: seeds.into_iter().map(<[u8]>::to_vec).collect(),
+        }
+    }
+
+    fn address(&self) -> Pubkey {
+        let seeds: Vec<&[u8]> = self.seeds.iter().map(Vec::as_slice).collect();
+        Pubkey::find_program_address(&seeds, &self.program).0
+    }
+}
+
+// the seeds of the keys which are pdas of the program, the token accounts or the metadata,
+// derived from the other keys of the transaction and the data of its program accounts
+fn known_pdas(
+    program_id: &Pubkey,
+    keys: &[Pubkey],
+    data_accounts: &[(Pubkey, &Account)],
+) -> HashMap<Pubkey, String> {
+    let mut candidates = vec![
+        PdaCandidate::new(
+            *program_id,
+            format!("[\"{}\"]", EMERGENCY_PAUSE_SEED),
+            vec![EMERGENCY_PAUSE_SEED.as_bytes()],
+        ),
+        PdaCandidate::new(
+            *program_id,
+            format!("[\"{}\"]", OPERATION_SEED),
+            vec![OPERATION_SEED.as_bytes()],
+        ),
+    ];
+    for a in keys {
+        for seed in [
+            POSITION_SEED,
+            POOL_TICK_ARRAY_BITMAP_SEED,
+            OBSERVATION_SEED,
+            SUPPORT_MINT_SEED,
+            FEE_DISCOUNT_SEED,
+        ] {
+            candidates.push(PdaCandidate::new(
+                *program_id,
+                format!("[\"{}\", {}]", seed, a),
+                vec![seed.as_bytes(), a.as_ref()],
+            ));
+        }
+        candidates.push(PdaCandidate::new(
+            mpl_token_metadata::ID,
+            format!("[\"metadata\", metadata program, {}]", a),
+            vec![
+                Metadata::PREFIX,
+                mpl_token_metadata::ID.as_ref(),
+                a.as_ref(),
+            ],
+        ));
+        for b in keys {
+            if a == b {
+                continue;
+            }
+            for seed in [
+                POOL_VAULT_SEED,
+                POOL_REWARD_VAULT_SEED,
+                POOL_CREATOR_PERMISSION_SEED,
+            ] {
+                candidates.push(PdaCandidate::new(
+                    *program_id,
+                    format!("[\"{}\", {}, {}]", seed, a, b),
+                    vec![seed.as_bytes(), a.as_ref(), b.as_ref()],
+                ));
+            }
+            for token_program in [spl_token::id(), spl_token_2022::id()] {
+                candidates.push(PdaCandidate::new(
+                    spl_associated_token_account::id(),
+                    format!("ata of owner {}, mint {}", a, b),
+                    vec![a.as_ref(), token_program.as_ref(), b.as_ref()],
+                ));
+            }
+        }
+    }
+    for (key, account) in data_accounts {
+        if account.owner != *program_id || account.data.len() < 8 {
+            continue;
+        }
+        let candidate = match &account.data[..8] {
+            AmmConfig::DISCRIMINATOR => {
+                deserialize_anchor_account::<AmmConfig>(account)
+                    .ok()
+                    .map(|config| {
+                        let index = config.index;
+                        PdaCandidate::new(
+                            *program_id,
+                            format!("[\"{}\", {}]", AMM_CONFIG_SEED, index),
+                            vec![AMM_CONFIG_SEED.as_bytes(), &index.to_be_bytes()],
+                        )
+                    })
+            }
+            PoolState::DISCRIMINATOR => {
+                deserialize_anchor_account::<PoolState>(account)
+                    .ok()
+                    .map(|pool| {
+                        let (amm_config, mint_0, mint_1) =
+                            (pool.amm_config, pool.token_mint_0, pool.token_mint_1);
+                        PdaCandidate::new(
+                            *program_id,
+                            format!(
+                                "[\"{}\", {}, {}, {}]",
+                                POOL_SEED, amm_config, mint_0, mint_1
+                            ),
+                            vec![
+                                POOL_SEED.as_bytes(),
+                                amm_config.as_ref(),
+                                mint_0.as_ref(),
+                                mint_1.as_ref(),
+                            ],
+                        )
+                    })
+            }
+            TickArrayState::DISCRIMINATOR => deserialize_anchor_account::<TickArrayState>(account)
+                .ok()
+                .map(|tick_array| {
+                    let (pool_id, start_index) = (tick_array.pool_id, tick_array.start_tick_index);
+                    PdaCandidate::new(
+                        *program_id,
+                        format!("[\"{}\", {}, {}]", TICK_ARRAY_SEED, pool_id, start_index),
+                        vec![
+                            TICK_ARRAY_SEED.as_bytes(),
+                            pool_id.as_ref(),
+                            &start_index.to_be_bytes(),
+                        ],
+                    )
+                }),
+            ProtocolPositionState::DISCRIMINATOR => {
+                deserialize_anchor_account::<ProtocolPositionState>(account)
+                    .ok()
+                    .map(|position| {
+                        let (pool_id, lower, upper) = (
+                            position.pool_id,
+                            position.tick_lower_index,
+                            position.tick_upper_index,
+                        );
+                        PdaCandidate::new(
+                            *program_id,
+                            format!("[\"{}\", {}, {}, {}]", POSITION_SEED, pool_id, lower, upper),
+                            vec![
+                                POSITION_SEED.as_bytes(),
+                                pool_id.as_ref(),
+                                &lower.to_be_bytes(),
+                                &upper.to_be_bytes(),
+                            ],
+                        )
+                    })
+            }
+            _ => None,
+        };
+        // a pda is only explained when the data derives the account's own address
+        if let Some(candidate) = candidate.filter(|candidate| candidate.address() == *key) {
+            candidates.push(candidate);
+        }
+    }
+
+    let mut seeds = HashMap::new();
+    for candidate in candidates {
+        let address = candidate.address();
+        if keys.contains(&address) {
+            seeds.entry(address).or_insert_with(|| {
+                if candidate.program == *program_id {
+                    candidate.label
+                } else {
+                    format!("{} of {}", candidate.label, candidate.program)
+                }
+            });
+        }
+    }
+    seeds
+}
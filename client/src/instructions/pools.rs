@@ -0,0 +1,167 @@
+use anchor_client::solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use anchor_client::solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_lang::Discriminator;
+use anyhow::{format_err, Result};
+use raydium_amm_v3::states::{AmmConfig, PoolState};
+
+use super::utils::deserialize_anchor_account;
+
+// offset of the amm config in the pool state data, after the discriminator and bump
+const POOL_AMM_CONFIG_OFFSET: usize = 8 + 1;
+// offsets of the token mints in the pool state data, after the discriminator, bump, amm_config
+// and owner
+const POOL_TOKEN_MINT_0_OFFSET: usize = 8 + 1 + 32 + 32;
+const POOL_TOKEN_MINT_1_OFFSET: usize = POOL_TOKEN_MINT_0_OFFSET + 32;
+// offset of the mint decimals, after the vaults and the observation key
+const POOL_MINT_DECIMALS_0_OFFSET: usize = POOL_TOKEN_MINT_1_OFFSET + 32 + 32 + 32 + 32;
+// the pool state data up to tick_current, the rest is left out of the listings
+const POOL_SUMMARY_LEN: usize = POOL_MINT_DECIMALS_0_OFFSET + 1 + 1 + 2 + 16 + 16 + 4;
+
+/// The fields of a pool state a listing needs, read from the head of its data only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolSummary {
+    pub amm_config: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+}
+
+impl PoolSummary {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        if data.len() < POOL_SUMMARY_LEN || !data.starts_with(&PoolState::DISCRIMINATOR) {
+            return Err(format_err!("not the head of a pool state"));
+        }
+        let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).unwrap();
+        let o = POOL_MINT_DECIMALS_0_OFFSET;
+        Ok(Self {
+            amm_config: pubkey(POOL_AMM_CONFIG_OFFSET),
+            token_mint_0: pubkey(POOL_TOKEN_MINT_0_OFFSET),
+            token_mint_1: pubkey(POOL_TOKEN_MINT_1_OFFSET),
+            mint_decimals_0: data[o],
+            mint_decimals_1: data[o + 1],
+            tick_spacing: u16::from_le_bytes(data[o + 2..o + 4].try_into()?),
+            liquidity: u128::from_le_bytes(data[o + 4..o + 20].try_into()?),
+            sqrt_price_x64: u128::from_le_bytes(data[o + 20..o + 36].try_into()?),
+            tick_current: i32::from_le_bytes(data[o + 36..o + 40].try_into()?),
+        })
+    }
+}
+
+/// Find the pools of a token pair in all the amm configs, the most liquid first.
+/// `mint0` must be less than `mint1` as in the pool state.
+pub fn find_pools_by_mints(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    mint0: &Pubkey,
+    mint1: &Pubkey,
+) -> Result<Vec<(Pubkey, PoolSummary)>> {
+    let mut pools = get_pools(
+        rpc_client,
+        program_id,
+        &[
+            (POOL_TOKEN_MINT_0_OFFSET, *mint0),
+            (POOL_TOKEN_MINT_1_OFFSET, *mint1),
+        ],
+    )?;
+    pools.sort_by_key(|(_, pool)| std::cmp::Reverse(pool.liquidity));
+    Ok(pools)
+}
+
+/// All the pools of the program, the most liquid first. Only the pools holding `mint` on either
+/// side and created with `amm_config` are listed when they are set.
+pub fn list_pools(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    mint: Option<&Pubkey>,
+    amm_config: Option<&Pubkey>,
+) -> Result<Vec<(Pubkey, PoolSummary)>> {
+    let config_filter = amm_config.map(|amm_config| (POOL_AMM_CONFIG_OFFSET, *amm_config));
+    let mut pools = match mint {
+        // the filters can't match either side, the mint is searched as token_0 then as token_1
+        Some(mint) => {
+            let mut pools = Vec::new();
+            for offset in [POOL_TOKEN_MINT_0_OFFSET, POOL_TOKEN_MINT_1_OFFSET] {
+                let filters: Vec<(usize, Pubkey)> = std::iter::once((offset, *mint))
+                    .chain(config_filter)
+                    .collect();
+                pools.extend(get_pools(rpc_client, program_id, &filters)?);
+            }
+            pools
+        }
+        None => get_pools(
+            rpc_client,
+            program_id,
+            &config_filter.into_iter().collect::<Vec<_>>(),
+        )?,
+    };
+    pools.sort_by_key(|(_, pool)| std::cmp::Reverse(pool.liquidity));
+    Ok(pools)
+}
+
+// the pools whose data matches the keys at the offsets, only the head of their data is fetched
+fn get_pools(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    keys_at_offsets: &[(usize, Pubkey)],
+) -> Result<Vec<(Pubkey, PoolSummary)>> {
+    let mut filters = vec![RpcFilterType::DataSize(PoolState::LEN as u64)];
+    filters.extend(keys_at_offsets.iter().map(|(offset, key)| {
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(*offset, &key.to_bytes()))
+    }));
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64Zstd),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 0,
+                    length: POOL_SUMMARY_LEN,
+                }),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: Some(false),
+            sort_results: None,
+        },
+    )?;
+    let mut pools = Vec::new();
+    for (pool_id, account) in accounts {
+        pools.push((pool_id, PoolSummary::unpack(&account.data)?));
+    }
+    Ok(pools)
+}
+
+/// All the amm configs of the program, the fee tiers pools are created with, by index
+pub fn list_amm_configs(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<Vec<(Pubkey, AmmConfig)>> {
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::DataSize(AmmConfig::LEN as u64)]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64Zstd),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: Some(false),
+            sort_results: None,
+        },
+    )?;
+    let mut amm_configs = Vec::new();
+    for (key, account) in accounts {
+        amm_configs.push((key, deserialize_anchor_account::<AmmConfig>(&account)?));
+    }
+    amm_configs.sort_by_key(|(_, amm_config)| amm_config.index);
+    Ok(amm_configs)
+}
@@ -0,0 +1,288 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use raydium_amm_v3::instructions::calculate_latest_token_fees;
+use raydium_amm_v3::libraries::liquidity_math;
+use raydium_amm_v3::states::{
+    get_fee_growth_inside, get_reward_growths_inside, PersonalPositionState, PoolState,
+    TickArrayState, TICK_ARRAY_SEED,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::rpc::get_multiple_accounts_concurrently;
+use super::utils::{deserialize_anchor_account, sqrt_price_x64_to_price};
+
+/// Valuation of one position at the current pool price
+#[derive(Debug, Clone)]
+pub struct PositionReport {
+    pub owner: Pubkey,
+    pub position: Pubkey,
+    pub nft_mint: Pubkey,
+    pub pool_id: Pubkey,
+    pub mint_0: Pubkey,
+    pub mint_1: Pubkey,
+    pub decimals_0: u8,
+    pub decimals_1: u8,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub tick_current: i32,
+    pub liquidity: u128,
+    /// Token amounts received if all the liquidity is removed now
+    pub amount_0: u64,
+    pub amount_1: u64,
+    /// Fees earned up to now, including the fees not yet settled into the position
+    pub fees_0: u64,
+    pub fees_1: u64,
+    /// Rewards earned up to now by reward mint, including the rewards not yet settled into the
+    /// position
+    pub rewards: Vec<(Pubkey, u64)>,
+    /// Price of token_0 in token_1
+    pub price: f64,
+}
+
+impl PositionReport {
+    pub fn in_range(&self) -> bool {
+        self.tick_lower <= self.tick_current && self.tick_current < self.tick_upper
+    }
+
+    /// Value of the liquidity and fees in token_1 ui amount
+    pub fn value_in_token_1(&self) -> f64 {
+        ui_amount(self.amount_0 + self.fees_0, self.decimals_0) * self.price
+            + ui_amount(self.amount_1 + self.fees_1, self.decimals_1)
+    }
+
+    /// Value of the fees only in token_1 ui amount
+    pub fn fees_in_token_1(&self) -> f64 {
+        ui_amount(self.fees_0, self.decimals_0) * self.price
+            + ui_amount(self.fees_1, self.decimals_1)
+    }
+}
+
+pub fn ui_amount(amount: u64, decimals: u8) -> f64 {
+    amount as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Load and value the positions, each one is given as (owner, personal position key).
+/// All the positions, pools and tick arrays are fetched concurrently.
+pub fn load_position_reports(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    positions: &[(Pubkey, Pubkey)],
+) -> Result<Vec<PositionReport>> {
+    let position_keys: Vec<Pubkey> = positions.iter().map(|(_, position)| *position).collect();
+    let mut personal_positions = Vec::new();
+    for ((owner, position), account) in positions.iter().zip(get_multiple_accounts_concurrently(
+        rpc_client,
+        &position_keys,
+    )?) {
+        // the nft may still be held after the position is closed
+        if let Some(account) = account {
+            personal_positions.push((
+                *owner,
+                *position,
+                deserialize_anchor_account::<PersonalPositionState>(&account)?,
+            ));
+        }
+    }
+
+    let mut pool_keys: Vec<Pubkey> = personal_positions
+        .iter()
+        .map(|(_, _, position)| position.pool_id)
+        .collect();
+    pool_keys.sort();
+    pool_keys.dedup();
+    let mut pools = HashMap::new();
+    for (pool_id, account) in pool_keys
+        .iter()
+        .zip(get_multiple_accounts_concurrently(rpc_client, &pool_keys)?)
+    {
+        if let Some(account) = account {
+            pools.insert(*pool_id, deserialize_anchor_account::<PoolState>(&account)?);
+        }
+    }
+
+    let tick_array_key = |pool_id: &Pubkey, tick: i32, tick_spacing: u16| {
+        let start_index = TickArrayState::get_array_start_index(tick, tick_spacing);
+        Pubkey::find_program_address(
+            &[
+                TICK_ARRAY_SEED.as_bytes(),
+                pool_id.as_ref(),
+                &start_index.to_be_bytes(),
+            ],
+            program_id,
+        )
+        .0
+    };
+    let mut tick_array_keys = Vec::new();
+    for (_, _, position) in personal_positions.iter() {
+        if let Some(pool) = pools.get(&position.pool_id) {
+            for tick in [position.tick_lower_index, position.tick_upper_index] {
+                tick_array_keys.push(tick_array_key(&position.pool_id, tick, pool.tick_spacing));
+            }
+        }
+    }
+    tick_array_keys.sort();
+    tick_array_keys.dedup();
+    let mut tick_arrays = HashMap::new();
+    for (key, account) in tick_array_keys
+        .iter()
+        .zip(get_multiple_accounts_concurrently(
+            rpc_client,
+            &tick_array_keys,
+        )?)
+    {
+        if let Some(account) = account {
+            tick_arrays.insert(
+                *key,
+                deserialize_anchor_account::<TickArrayState>(&account)?,
+            );
+        }
+    }
+
+    // the rewards are emitted up to now
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut reports = Vec::new();
+    for (owner, position_key, position) in personal_positions {
+        let pool = match pools.get(&position.pool_id) {
+            Some(pool) => pool,
+            None => continue,
+        };
+        let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
+            pool.tick_current,
+            pool.sqrt_price_x64,
+            position.tick_lower_index,
+            position.tick_upper_index,
+            -i128::try_from(position.liquidity)?,
+        )?;
+        let mut tick_state = |tick: i32| {
+            let key = tick_array_key(&position.pool_id, tick, pool.tick_spacing);
+            tick_arrays
+                .get_mut(&key)
+                .and_then(|tick_array| tick_array.get_tick_state_mut(tick, pool.tick_spacing).ok())
+                .map(|tick_state| *tick_state)
+        };
+        let tick_states = (
+            tick_state(position.tick_lower_index),
+            tick_state(position.tick_upper_index),
+        );
+        let (fees_0, fees_1) = match tick_states {
+            (Some(tick_lower_state), Some(tick_upper_state)) => {
+                let (fee_growth_inside_0_x64, fee_growth_inside_1_x64) = get_fee_growth_inside(
+                    &tick_lower_state,
+                    &tick_upper_state,
+                    pool.tick_current,
+                    pool.fee_growth_global_0_x64,
+                    pool.fee_growth_global_1_x64,
+                );
+                (
+                    calculate_latest_token_fees(
+                        position.token_fees_owed_0,
+                        position.fee_growth_inside_0_last_x64,
+                        fee_growth_inside_0_x64,
+                        position.liquidity,
+                    ),
+                    calculate_latest_token_fees(
+                        position.token_fees_owed_1,
+                        position.fee_growth_inside_1_last_x64,
+                        fee_growth_inside_1_x64,
+                        position.liquidity,
+                    ),
+                )
+            }
+            // the settled fees are still owed without the tick states
+            _ => (position.token_fees_owed_0, position.token_fees_owed_1),
+        };
+        let mut pool_now = *pool;
+        let reward_infos = pool_now
+            .update_reward_infos(now)
+            .unwrap_or(pool.reward_infos);
+        let reward_growths_inside = match tick_states {
+            (Some(tick_lower_state), Some(tick_upper_state)) => Some(get_reward_growths_inside(
+                &tick_lower_state,
+                &tick_upper_state,
+                pool.tick_current,
+                &reward_infos,
+            )),
+            _ => None,
+        };
+        let mut rewards = Vec::new();
+        for (i, reward_info) in reward_infos.iter().enumerate() {
+            if !reward_info.initialized() {
+                continue;
+            }
+            let position_reward = position.reward_infos[i];
+            // the rewards accrue with the same math as the fees
+            let amount = match reward_growths_inside {
+                Some(reward_growths_inside) => calculate_latest_token_fees(
+                    position_reward.reward_amount_owed,
+                    position_reward.growth_inside_last_x64,
+                    reward_growths_inside[i],
+                    position.liquidity,
+                ),
+                None => position_reward.reward_amount_owed,
+            };
+            rewards.push((reward_info.token_mint, amount));
+        }
+        reports.push(PositionReport {
+            owner,
+            position: position_key,
+            nft_mint: position.nft_mint,
+            pool_id: position.pool_id,
+            mint_0: pool.token_mint_0,
+            mint_1: pool.token_mint_1,
+            decimals_0: pool.mint_decimals_0,
+            decimals_1: pool.mint_decimals_1,
+            tick_lower: position.tick_lower_index,
+            tick_upper: position.tick_upper_index,
+            tick_current: pool.tick_current,
+            liquidity: position.liquidity,
+            amount_0,
+            amount_1,
+            fees_0,
+            fees_1,
+            rewards,
+            price: sqrt_price_x64_to_price(
+                pool.sqrt_price_x64,
+                pool.mint_decimals_0,
+                pool.mint_decimals_1,
+            ),
+        });
+    }
+    Ok(reports)
+}
+
+/// Totals of one token over many positions, in raw amounts
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokenTotal {
+    pub decimals: u8,
+    pub amount: u64,
+    pub fees: u64,
+}
+
+/// Sum the liquidity amounts and unclaimed fees of the positions for each token mint
+pub fn token_totals(reports: &[PositionReport]) -> BTreeMap<Pubkey, TokenTotal> {
+    let mut totals: BTreeMap<Pubkey, TokenTotal> = BTreeMap::new();
+    for report in reports {
+        for (mint, decimals, amount, fees) in [
+            (
+                report.mint_0,
+                report.decimals_0,
+                report.amount_0,
+                report.fees_0,
+            ),
+            (
+                report.mint_1,
+                report.decimals_1,
+                report.amount_1,
+                report.fees_1,
+            ),
+        ] {
+            let total = totals.entry(mint).or_default();
+            total.decimals = decimals;
+            total.amount += amount;
+            total.fees += fees;
+        }
+    }
+    totals
+}
@@ -0,0 +1,273 @@
+use anchor_client::solana_client::rpc_client::{
+    GetConfirmedSignaturesForAddress2Config, RpcClient,
+};
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+};
+use anyhow::Result;
+use raydium_amm_v3::states::REWARD_NUM;
+use std::str::FromStr;
+
+use super::indexer::{
+    get_transactions_throttled, record_transaction_events, BackfillConfig, IndexedEvent,
+    IndexedEventData, PoolIndex,
+};
+use super::portfolio::ui_amount;
+
+/// The events of a position, oldest first. They are parsed from the transactions of its personal
+/// position account, which every open, increase, decrease and close of the position writes, so
+/// the pool doesn't have to be indexed. Transactions older than `from_time` are skipped.
+pub fn load_position_history(
+    rpc_client: &RpcClient,
+    program_str: &str,
+    position_key: &Pubkey,
+    position_nft_mint: &Pubkey,
+    config: &BackfillConfig,
+) -> Result<Vec<IndexedEvent>> {
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let page = rpc_client.get_signatures_for_address_with_config(
+            position_key,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(config.limit),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+        let page_len = page.len();
+        let last = page
+            .last()
+            .map(|signature_info| signature_info.signature.clone());
+        let mut reached_from_time = false;
+        for signature_info in page {
+            if signature_info
+                .block_time
+                .map_or(false, |block_time| block_time < config.from_time)
+            {
+                reached_from_time = true;
+                break;
+            }
+            if signature_info.err.is_none() {
+                signatures.push(signature_info.signature);
+            }
+        }
+        match last {
+            Some(last) if !reached_from_time && page_len >= config.limit => {
+                before = Some(Signature::from_str(&last)?)
+            }
+            _ => break,
+        }
+    }
+
+    let txs = get_transactions_throttled(
+        rpc_client,
+        &signatures,
+        config.concurrency,
+        config.requests_per_second,
+    )?;
+    let mut pool_index = PoolIndex::default();
+    for (signature, tx) in signatures.iter().zip(txs) {
+        record_transaction_events(program_str, &mut pool_index, signature, tx)?;
+    }
+    pool_index.events.sort_by_key(|event| event.slot);
+    // a transaction may also touch other positions of the pool
+    let position_nft_mint = position_nft_mint.to_string();
+    Ok(pool_index
+        .events
+        .into_iter()
+        .filter(|event| event_position_nft_mint(&event.event) == Some(&position_nft_mint))
+        .collect())
+}
+
+/// The pool of the position, from the event of its opening
+pub fn position_pool_id(events: &[IndexedEvent]) -> Option<Pubkey> {
+    events.iter().find_map(|indexed| match &indexed.event {
+        IndexedEventData::CreatePersonalPosition { pool_state, .. } => {
+            Pubkey::from_str(pool_state).ok()
+        }
+        _ => None,
+    })
+}
+
+/// The position of a position event, the create events recorded without instruction have none
+pub fn event_position_nft_mint(event: &IndexedEventData) -> Option<&String> {
+    match event {
+        IndexedEventData::CreatePersonalPosition {
+            position_nft_mint, ..
+        } => position_nft_mint.as_ref(),
+        IndexedEventData::IncreaseLiquidity {
+            position_nft_mint, ..
+        }
+        | IndexedEventData::DecreaseLiquidity {
+            position_nft_mint, ..
+        }
+        | IndexedEventData::CollectPersonalFee {
+            position_nft_mint, ..
+        } => Some(position_nft_mint),
+        _ => None,
+    }
+}
+
+/// The token flows of a position over its history, in raw amounts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PositionLedger {
+    /// Paid into the position, the transfer fees included
+    pub deposited_0: u64,
+    pub deposited_1: u64,
+    /// Received from the liquidity removed, after the transfer fees
+    pub withdrawn_0: u64,
+    pub withdrawn_1: u64,
+    /// Fees collected
+    pub fees_0: u64,
+    pub fees_1: u64,
+    /// Rewards collected, by reward index of the pool
+    pub rewards: [u64; REWARD_NUM],
+}
+
+impl PositionLedger {
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a IndexedEvent>) -> Self {
+        let mut ledger = Self::default();
+        for indexed in events {
+            match &indexed.event {
+                IndexedEventData::CreatePersonalPosition {
+                    deposit_amount_0,
+                    deposit_amount_1,
+                    deposit_amount_0_transfer_fee,
+                    deposit_amount_1_transfer_fee,
+                    ..
+                } => {
+                    ledger.deposited_0 += deposit_amount_0 + deposit_amount_0_transfer_fee;
+                    ledger.deposited_1 += deposit_amount_1 + deposit_amount_1_transfer_fee;
+                }
+                IndexedEventData::IncreaseLiquidity {
+                    amount_0,
+                    amount_1,
+                    amount_0_transfer_fee,
+                    amount_1_transfer_fee,
+                    ..
+                } => {
+                    ledger.deposited_0 += amount_0 + amount_0_transfer_fee;
+                    ledger.deposited_1 += amount_1 + amount_1_transfer_fee;
+                }
+                IndexedEventData::DecreaseLiquidity {
+                    decrease_amount_0,
+                    decrease_amount_1,
+                    fee_amount_0,
+                    fee_amount_1,
+                    reward_amounts,
+                    transfer_fee_0,
+                    transfer_fee_1,
+                    ..
+                } => {
+                    ledger.withdrawn_0 += decrease_amount_0.saturating_sub(*transfer_fee_0);
+                    ledger.withdrawn_1 += decrease_amount_1.saturating_sub(*transfer_fee_1);
+                    ledger.fees_0 += fee_amount_0;
+                    ledger.fees_1 += fee_amount_1;
+                    for (total, amount) in ledger.rewards.iter_mut().zip(reward_amounts) {
+                        *total += amount;
+                    }
+                }
+                IndexedEventData::CollectPersonalFee {
+                    amount_0, amount_1, ..
+                } => {
+                    ledger.fees_0 += amount_0;
+                    ledger.fees_1 += amount_1;
+                }
+                _ => {}
+            }
+        }
+        ledger
+    }
+}
+
+/// The P&L of a position against holding the deposited tokens, every amount is valued at the
+/// current price in token_1 ui amount
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PositionPnl {
+    pub deposited: f64,
+    pub withdrawn: f64,
+    /// The liquidity still in the position
+    pub position: f64,
+    /// Fees collected
+    pub realized_fees: f64,
+    /// Fees pending in the position
+    pub unrealized_fees: f64,
+    /// The position and withdrawals less the deposits, negative for a loss
+    pub impermanent_loss: f64,
+    /// The impermanent loss plus all the fees
+    pub net: f64,
+}
+
+impl PositionPnl {
+    /// `amounts` and `pending_fees` are the raw amounts of the liquidity and the uncollected fees
+    /// of the position, zero when it is closed
+    pub fn new(
+        ledger: &PositionLedger,
+        price: f64,
+        decimals: (u8, u8),
+        amounts: (u64, u64),
+        pending_fees: (u64, u64),
+    ) -> Self {
+        let value = |amount_0: u64, amount_1: u64| {
+            ui_amount(amount_0, decimals.0) * price + ui_amount(amount_1, decimals.1)
+        };
+        let deposited = value(ledger.deposited_0, ledger.deposited_1);
+        let withdrawn = value(ledger.withdrawn_0, ledger.withdrawn_1);
+        let position = value(amounts.0, amounts.1);
+        let realized_fees = value(ledger.fees_0, ledger.fees_1);
+        let unrealized_fees = value(pending_fees.0, pending_fees.1);
+        let impermanent_loss = position + withdrawn - deposited;
+        Self {
+            deposited,
+            withdrawn,
+            position,
+            realized_fees,
+            unrealized_fees,
+            impermanent_loss,
+            net: impermanent_loss + realized_fees + unrealized_fees,
+        }
+    }
+}
+
+/// The fees collected from a position in one transaction, raw amounts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeCollection {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub signature: String,
+    pub fees_0: u64,
+    pub fees_1: u64,
+}
+
+/// The fee collections of the position history, oldest first. A decrease of liquidity collects
+/// the fees of the position with it, the decreases which collected nothing are left out.
+pub fn fee_collections(events: &[IndexedEvent]) -> Vec<FeeCollection> {
+    events
+        .iter()
+        .filter_map(|indexed| {
+            let (fees_0, fees_1) = match &indexed.event {
+                IndexedEventData::DecreaseLiquidity {
+                    fee_amount_0,
+                    fee_amount_1,
+                    ..
+                } => (*fee_amount_0, *fee_amount_1),
+                IndexedEventData::CollectPersonalFee {
+                    amount_0, amount_1, ..
+                } => (*amount_0, *amount_1),
+                _ => return None,
+            };
+            if fees_0 == 0 && fees_1 == 0 {
+                return None;
+            }
+            Some(FeeCollection {
+                slot: indexed.slot,
+                block_time: indexed.block_time,
+                signature: indexed.signature.clone(),
+                fees_0,
+                fees_1,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,121 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::Instruction,
+    pubkey::Pubkey,
+};
+use anyhow::Result;
+use std::sync::OnceLock;
+
+use super::tx_builder::TransactionBuilder;
+
+// getRecentPrioritizationFees accepts at most 128 accounts
+const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+// tag of the SetComputeUnitPrice variant of the compute budget instruction
+const SET_COMPUTE_UNIT_PRICE_TAG: u8 = 3;
+
+/// How the compute unit price is estimated from the recent prioritization fees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFeeEstimate {
+    /// Percentile of the recent fees paid to lock the writable accounts, from 0 to 100
+    pub percentile: u8,
+    /// The estimate is capped to this price, in micro lamports. 0 for no cap.
+    pub max_compute_unit_price: u64,
+}
+
+// the estimate of the compute unit price, set once the estimation is enabled
+static PRIORITY_FEE_ESTIMATE: OnceLock<PriorityFeeEstimate> = OnceLock::new();
+
+/// Price every transaction with the estimate for the rest of the process
+pub fn enable_priority_fee_estimate(estimate: PriorityFeeEstimate) {
+    let _ = PRIORITY_FEE_ESTIMATE.set(estimate);
+}
+
+pub fn priority_fee_estimate() -> Option<PriorityFeeEstimate> {
+    PRIORITY_FEE_ESTIMATE.get().copied()
+}
+
+/// The fee at the percentile of the fees, by the nearest rank
+pub fn fee_percentile(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    let rank = (usize::from(percentile.min(100)) * fees.len() + 99) / 100;
+    fees[rank.saturating_sub(1)]
+}
+
+/// Estimate the compute unit price from the fees paid in the recent slots by the transactions
+/// locking any of the writable accounts
+pub fn estimate_compute_unit_price(
+    client: &RpcClient,
+    writable_accounts: &[Pubkey],
+    estimate: &PriorityFeeEstimate,
+) -> Result<u64> {
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for account in writable_accounts {
+        if !accounts.contains(account) {
+            accounts.push(*account);
+        }
+    }
+    accounts.truncate(MAX_PRIORITIZATION_FEE_ACCOUNTS);
+    let fees = client
+        .get_recent_prioritization_fees(&accounts)?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    let compute_unit_price = fee_percentile(fees, estimate.percentile);
+    if estimate.max_compute_unit_price > 0 {
+        return Ok(compute_unit_price.min(estimate.max_compute_unit_price));
+    }
+    Ok(compute_unit_price)
+}
+
+pub fn is_set_compute_unit_price(instruction: &Instruction) -> bool {
+    instruction.program_id == compute_budget::id()
+        && instruction.data.first() == Some(&SET_COMPUTE_UNIT_PRICE_TAG)
+}
+
+/// The accounts written by the instructions, in the order of the instructions
+pub fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    instructions
+        .iter()
+        .flat_map(|instruction| instruction.accounts.iter())
+        .filter(|account| account.is_writable)
+        .map(|account| account.pubkey)
+        .collect()
+}
+
+/// The instructions with the estimated compute unit price prepended, when the estimation is
+/// enabled and the instructions don't set a price themselves
+pub fn with_estimated_priority_fee(
+    client: &RpcClient,
+    instructions: &[Instruction],
+) -> Result<Vec<Instruction>> {
+    let estimate = match priority_fee_estimate() {
+        Some(estimate) if !instructions.iter().any(is_set_compute_unit_price) => estimate,
+        _ => return Ok(instructions.to_vec()),
+    };
+    let compute_unit_price =
+        estimate_compute_unit_price(client, &writable_accounts(instructions), &estimate)?;
+    let mut priced_instructions = vec![ComputeBudgetInstruction::set_compute_unit_price(
+        compute_unit_price,
+    )];
+    priced_instructions.extend(instructions.iter().cloned());
+    Ok(priced_instructions)
+}
+
+/// The builder priced with the estimate when it is enabled and no price is set
+pub fn price_transaction_builder(
+    client: &RpcClient,
+    builder: TransactionBuilder,
+) -> Result<TransactionBuilder> {
+    match priority_fee_estimate() {
+        Some(estimate) if !builder.has_compute_unit_price() => {
+            let compute_unit_price =
+                estimate_compute_unit_price(client, &builder.writable_accounts(), &estimate)?;
+            Ok(builder.compute_unit_price(compute_unit_price))
+        }
+        _ => Ok(builder),
+    }
+}
@@ -0,0 +1,147 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, instruction::Instruction, pubkey::Pubkey,
+};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use super::compute_units::simulate_with_max_compute_unit_limit;
+
+/// The compute units of one instruction of the program in one setting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CuSample {
+    pub instruction: String,
+    pub pool_id: String,
+    /// The token programs of the two mints of the pool, e.g. "spl/token-2022"
+    pub token_programs: String,
+    /// The parameters varied, e.g. the amount and the direction of a swap
+    pub params: String,
+    /// The ticks a swap crosses by its quote
+    pub ticks_crossed: Option<usize>,
+    /// Consumed by the transaction, the compute budget instruction included. None when it fails.
+    pub units_consumed: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl CuSample {
+    /// The same measure in another report
+    pub fn same_measure(&self, other: &CuSample) -> bool {
+        self.instruction == other.instruction
+            && self.pool_id == other.pool_id
+            && self.params == other.params
+    }
+}
+
+/// The name of the token program in the reports
+pub fn token_program_label(token_program: &Pubkey) -> &'static str {
+    if *token_program == spl_token_2022::id() {
+        "token-2022"
+    } else {
+        "spl"
+    }
+}
+
+/// Simulates the instructions of a pool and records the units each consumes
+pub struct CuProfiler<'a> {
+    client: &'a RpcClient,
+    payer: Pubkey,
+    lookup_tables: &'a [AddressLookupTableAccount],
+    pool_id: Pubkey,
+    token_programs: String,
+    pub samples: Vec<CuSample>,
+}
+
+impl<'a> CuProfiler<'a> {
+    pub fn new(
+        client: &'a RpcClient,
+        payer: Pubkey,
+        lookup_tables: &'a [AddressLookupTableAccount],
+        pool_id: Pubkey,
+        token_programs: (&Pubkey, &Pubkey),
+    ) -> Self {
+        Self {
+            client,
+            payer,
+            lookup_tables,
+            pool_id,
+            token_programs: format!(
+                "{}/{}",
+                token_program_label(token_programs.0),
+                token_program_label(token_programs.1)
+            ),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Simulate the instructions and record the units they consume, or why they can't be
+    /// measured when they fail to build or to execute
+    pub fn measure(
+        &mut self,
+        instruction: &str,
+        params: String,
+        ticks_crossed: Option<usize>,
+        instructions: Result<Vec<Instruction>>,
+    ) -> Result<()> {
+        let (units_consumed, error) = match instructions {
+            Ok(instructions) => {
+                let result = simulate_with_max_compute_unit_limit(
+                    self.client,
+                    &instructions,
+                    &self.payer,
+                    self.lookup_tables,
+                )?;
+                match result.err {
+                    Some(err) => (None, Some(err.to_string())),
+                    None => (result.units_consumed, None),
+                }
+            }
+            Err(err) => (None, Some(err.to_string())),
+        };
+        self.samples.push(CuSample {
+            instruction: instruction.to_string(),
+            pool_id: self.pool_id.to_string(),
+            token_programs: self.token_programs.clone(),
+            params,
+            ticks_crossed,
+            units_consumed,
+            error,
+        });
+        Ok(())
+    }
+}
+
+pub fn write_cu_report(path: &str, samples: &[CuSample]) -> Result<()> {
+    fs::write(path, serde_json::to_vec_pretty(samples)?)?;
+    Ok(())
+}
+
+pub fn read_cu_report(path: &str) -> Result<Vec<CuSample>> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+/// The units of the same measure in the baseline, None when it wasn't measured
+pub fn baseline_units(baseline: &[CuSample], sample: &CuSample) -> Option<u64> {
+    baseline
+        .iter()
+        .find(|baseline_sample| baseline_sample.same_measure(sample))
+        .and_then(|baseline_sample| baseline_sample.units_consumed)
+}
+
+impl std::fmt::Display for CuSample {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} pool:{} ({}) {}",
+            self.instruction, self.pool_id, self.token_programs, self.params
+        )?;
+        if let Some(ticks_crossed) = self.ticks_crossed {
+            write!(f, ", ticks crossed:{}", ticks_crossed)?;
+        }
+        match (self.units_consumed, &self.error) {
+            (Some(units_consumed), _) => write!(f, ": {} cu", units_consumed),
+            (None, Some(error)) => write!(f, ": failed, {}", error),
+            (None, None) => write!(f, ": units not reported"),
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::{anyhow, Result};
+use raydium_amm_v3::states::{AmmConfig, PoolState, TickArrayBitmapExtension, TickArrayState};
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
+use std::collections::VecDeque;
+
+use super::portfolio::ui_amount;
+use super::utils::{
+    get_out_put_amount_with_trace, get_transfer_fee_with_next_epoch,
+    get_transfer_inverse_fee_with_next_epoch, sqrt_price_x64_to_price, SwapStep,
+};
+
+/// A swap computed off-chain, amounts are raw token amounts
+#[derive(Debug, Clone)]
+pub struct SwapQuote {
+    pub zero_for_one: bool,
+    /// Paid by the user, including the input transfer fee
+    pub amount_in: u64,
+    /// Received by the user, after the output transfer fee
+    pub amount_out: u64,
+    pub transfer_fee_in: u64,
+    pub transfer_fee_out: u64,
+    /// The trade fee paid to the pool, in the input token
+    pub trade_fee: u64,
+    /// Prices of token_0 in token_1, decimal adjusted
+    pub price_before: f64,
+    pub price_after: f64,
+    /// The price of the swap without the trade fee, of token_0 in token_1
+    pub execution_price: f64,
+    pub steps: Vec<SwapStep>,
+    pub tick_array_indexs: VecDeque<i32>,
+}
+
+impl SwapQuote {
+    /// Relative difference between the execution price and the pool price before the swap
+    pub fn price_impact(&self) -> f64 {
+        (self.execution_price / self.price_before - 1.0).abs()
+    }
+
+    pub fn ticks_crossed(&self) -> usize {
+        self.steps.iter().filter(|step| step.crossed).count()
+    }
+}
+
+/// Quote a swap with the same math as the program. With `base_in` the amount is paid including
+/// the input transfer fee, otherwise it is received after the output transfer fee. The transfer
+/// fees are the larger ones of the current and next epoch.
+pub fn quote_swap(
+    amount: u64,
+    base_in: bool,
+    zero_for_one: bool,
+    amm_config: &AmmConfig,
+    pool_state: &PoolState,
+    tickarray_bitmap_extension: &TickArrayBitmapExtension,
+    tick_arrays: &mut VecDeque<TickArrayState>,
+    mint_states: (&StateWithExtensions<Mint>, &StateWithExtensions<Mint>),
+    epoch: u64,
+) -> Result<SwapQuote> {
+    let (mint_in, mint_out) = if zero_for_one {
+        mint_states
+    } else {
+        (mint_states.1, mint_states.0)
+    };
+    // the amount specified to the pool math, as the program computes it
+    let (amount_specified, transfer_fee) = if base_in {
+        let transfer_fee = get_transfer_fee_with_next_epoch(mint_in, epoch, amount);
+        (amount.checked_sub(transfer_fee).unwrap(), transfer_fee)
+    } else {
+        let transfer_fee = get_transfer_inverse_fee_with_next_epoch(mint_out, epoch, amount);
+        (amount.checked_add(transfer_fee).unwrap(), transfer_fee)
+    };
+    let (amount_calculated, tick_array_indexs, steps) = get_out_put_amount_with_trace(
+        amount_specified,
+        None,
+        zero_for_one,
+        base_in,
+        amm_config,
+        pool_state,
+        tickarray_bitmap_extension,
+        tick_arrays,
+    )
+    .map_err(|err| anyhow!(err))?;
+    let (amount_in, amount_out, transfer_fee_in, transfer_fee_out) = if base_in {
+        let transfer_fee_out = get_transfer_fee_with_next_epoch(mint_out, epoch, amount_calculated);
+        (
+            amount,
+            amount_calculated - transfer_fee_out,
+            transfer_fee,
+            transfer_fee_out,
+        )
+    } else {
+        let transfer_fee_in =
+            get_transfer_inverse_fee_with_next_epoch(mint_in, epoch, amount_calculated);
+        (
+            amount_calculated + transfer_fee_in,
+            amount,
+            transfer_fee_in,
+            transfer_fee,
+        )
+    };
+
+    let trade_fee: u64 = steps.iter().map(|step| step.fee_amount).sum();
+    let pool_amount_in: u64 = steps.iter().map(|step| step.amount_in).sum();
+    let pool_amount_out: u64 = steps.iter().map(|step| step.amount_out).sum();
+    let (decimals_0, decimals_1) = (pool_state.mint_decimals_0, pool_state.mint_decimals_1);
+    let execution_price = if zero_for_one {
+        ui_amount(pool_amount_out, decimals_1) / ui_amount(pool_amount_in, decimals_0)
+    } else {
+        ui_amount(pool_amount_in, decimals_1) / ui_amount(pool_amount_out, decimals_0)
+    };
+    Ok(SwapQuote {
+        zero_for_one,
+        amount_in,
+        amount_out,
+        transfer_fee_in,
+        transfer_fee_out,
+        trade_fee,
+        price_before: sqrt_price_x64_to_price(pool_state.sqrt_price_x64, decimals_0, decimals_1),
+        price_after: steps
+            .last()
+            .map(|step| sqrt_price_x64_to_price(step.sqrt_price_after_x64, decimals_0, decimals_1))
+            .unwrap_or_default(),
+        execution_price,
+        steps,
+        tick_array_indexs,
+    })
+}
+
+pub fn print_swap_quote(quote: &SwapQuote, decimals_0: u8, decimals_1: u8) {
+    let (decimals_in, decimals_out) = if quote.zero_for_one {
+        (decimals_0, decimals_1)
+    } else {
+        (decimals_1, decimals_0)
+    };
+    println!(
+        "amount in:{} ({}), transfer fee:{}",
+        quote.amount_in,
+        ui_amount(quote.amount_in, decimals_in),
+        quote.transfer_fee_in
+    );
+    println!(
+        "amount out:{} ({}), transfer fee:{}",
+        quote.amount_out,
+        ui_amount(quote.amount_out, decimals_out),
+        quote.transfer_fee_out
+    );
+    println!(
+        "trade fee:{} ({})",
+        quote.trade_fee,
+        ui_amount(quote.trade_fee, decimals_in)
+    );
+    println!(
+        "price before:{}, price after:{}, execution price:{}, price impact:{:.4}%",
+        quote.price_before,
+        quote.price_after,
+        quote.execution_price,
+        quote.price_impact() * 100.0
+    );
+    println!(
+        "ticks crossed:{}, tick arrays:{:?}",
+        quote.ticks_crossed(),
+        quote.tick_array_indexs
+    );
+}
@@ -0,0 +1,85 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::{format_err, Result};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The query of `GET /quote?in=<mint>&out=<mint>&amount=<u64>[&base_in=false]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuoteRequest {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    /// Paid with base_in, received otherwise
+    pub amount: u64,
+    pub base_in: bool,
+}
+
+impl QuoteRequest {
+    /// Parse the query of the request target, e.g. "/quote?in=...&out=...&amount=1000"
+    pub fn parse(target: &str) -> Result<Self> {
+        let query = target
+            .strip_prefix("/quote?")
+            .ok_or(format_err!("expected /quote?in=&out=&amount="))?;
+        let (mut input_mint, mut output_mint, mut amount, mut base_in) = (None, None, None, true);
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key {
+                "in" => input_mint = Some(Pubkey::from_str(value)?),
+                "out" => output_mint = Some(Pubkey::from_str(value)?),
+                "amount" => amount = Some(value.parse::<u64>()?),
+                "base_in" => base_in = value.parse::<bool>()?,
+                _ => return Err(format_err!("unknown parameter {}", key)),
+            }
+        }
+        Ok(Self {
+            input_mint: input_mint.ok_or(format_err!("in is missing"))?,
+            output_mint: output_mint.ok_or(format_err!("out is missing"))?,
+            amount: amount.ok_or(format_err!("amount is missing"))?,
+            base_in,
+        })
+    }
+}
+
+/// Serve the quotes on `addr` until the process exits, one request at a time. A quote which
+/// fails answers 400 with the error in a json body.
+pub fn serve_quotes(addr: &str, quote: impl Fn(&QuoteRequest) -> Result<Value>) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("quote server listening on http://{}/quote", addr);
+    for stream in listener.incoming().flatten() {
+        // a broken connection only fails its request
+        let _ = respond(stream, &quote);
+    }
+    Ok(())
+}
+
+fn respond(mut stream: TcpStream, quote: &impl Fn(&QuoteRequest) -> Result<Value>) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request = [0u8; 2048];
+    let len = stream.read(&mut request)?;
+    let request = String::from_utf8_lossy(&request[..len]);
+    let target = request
+        .strip_prefix("GET ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap_or_default();
+    let (status, body) = if target == "/quote" || target.starts_with("/quote?") {
+        match QuoteRequest::parse(target).and_then(|request| quote(&request)) {
+            Ok(body) => ("200 OK", body.to_string()),
+            Err(err) => (
+                "400 Bad Request",
+                serde_json::json!({ "error": err.to_string() }).to_string(),
+            ),
+        }
+    } else {
+        ("404 Not Found", String::new())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
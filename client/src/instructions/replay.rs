@@ -0,0 +1,198 @@
+use anchor_client::solana_account_decoder::UiAccountEncoding;
+use anchor_client::solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{
+        RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig, RpcTransactionConfig,
+    },
+};
+use anchor_client::solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    transaction::VersionedTransaction,
+};
+use anyhow::{format_err, Result};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use super::events_instructions_parse::program_log_event_data;
+use super::indexer::{decode_indexed_event, IndexedEventData};
+use super::rpc::{get_multiple_accounts, token_amount};
+
+/// The change of a token account in a transaction, negative when tokens leave the account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenDelta {
+    pub account: Pubkey,
+    pub mint: String,
+    pub delta: i128,
+}
+
+/// What a transaction did, or would do now
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOutcome {
+    pub err: Option<String>,
+    pub token_deltas: Vec<TokenDelta>,
+    /// The events of the program, in the order they were emitted
+    pub events: Vec<IndexedEventData>,
+}
+
+/// A historical transaction with its recorded outcome
+pub struct RecordedTransaction {
+    pub slot: u64,
+    pub transaction: VersionedTransaction,
+    pub outcome: ReplayOutcome,
+}
+
+/// Fetch the transaction and its outcome from the token balances and the logs of its meta
+pub fn load_recorded_transaction(
+    rpc_client: &RpcClient,
+    program_str: &str,
+    signature: &Signature,
+) -> Result<RecordedTransaction> {
+    let tx = rpc_client.get_transaction_with_config(
+        signature,
+        RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        },
+    )?;
+    let transaction = tx
+        .transaction
+        .transaction
+        .decode()
+        .ok_or(format_err!("transaction {} can't be decoded", signature))?;
+    let meta = tx
+        .transaction
+        .meta
+        .ok_or(format_err!("transaction {} has no meta", signature))?;
+
+    // the token balances index the static keys followed by the keys loaded from lookup tables
+    let mut account_keys = transaction.message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(addresses) = &meta.loaded_addresses {
+        for key in addresses.writable.iter().chain(addresses.readonly.iter()) {
+            account_keys.push(Pubkey::from_str(key)?);
+        }
+    }
+    let balances = |token_balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>| {
+        let mut amounts = BTreeMap::new();
+        if let OptionSerializer::Some(token_balances) = token_balances {
+            for balance in token_balances {
+                let amount = balance.ui_token_amount.amount.parse::<u64>().unwrap_or(0);
+                amounts.insert(balance.account_index, (balance.mint.clone(), amount));
+            }
+        }
+        amounts
+    };
+    let pre_balances = balances(&meta.pre_token_balances);
+    let post_balances = balances(&meta.post_token_balances);
+    let mut token_deltas = Vec::new();
+    for (account_index, mint) in pre_balances
+        .iter()
+        .chain(post_balances.iter())
+        .map(|(account_index, (mint, _))| (*account_index, mint))
+        .collect::<BTreeMap<u8, &String>>()
+    {
+        let account = *account_keys
+            .get(usize::from(account_index))
+            .ok_or(format_err!(
+                "token balance of unknown account {}",
+                account_index
+            ))?;
+        let amount = |balances: &BTreeMap<u8, (String, u64)>| {
+            balances.get(&account_index).map_or(0, |b| b.1)
+        };
+        token_deltas.push(TokenDelta {
+            account,
+            mint: mint.clone(),
+            delta: i128::from(amount(&post_balances)) - i128::from(amount(&pre_balances)),
+        });
+    }
+
+    let logs = match &meta.log_messages {
+        OptionSerializer::Some(logs) => logs.clone(),
+        _ => Vec::new(),
+    };
+    Ok(RecordedTransaction {
+        slot: tx.slot,
+        transaction,
+        outcome: ReplayOutcome {
+            err: meta.err.map(|err| format!("{:?}", err)),
+            token_deltas,
+            events: decode_log_events(program_str, &logs)?,
+        },
+    })
+}
+
+/// Simulate the recorded transaction against the current state, with a fresh blockhash and
+/// without checking its signatures, and measure the same token accounts
+pub fn replay_transaction(
+    rpc_client: &RpcClient,
+    program_str: &str,
+    recorded: &RecordedTransaction,
+) -> Result<ReplayOutcome> {
+    let token_accounts: Vec<Pubkey> = recorded
+        .outcome
+        .token_deltas
+        .iter()
+        .map(|token_delta| token_delta.account)
+        .collect();
+    let pre_accounts = get_multiple_accounts(rpc_client, &token_accounts)?;
+    let result = rpc_client
+        .simulate_transaction_with_config(
+            &recorded.transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(CommitmentConfig::confirmed()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: token_accounts.iter().map(|key| key.to_string()).collect(),
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+    let logs = result.logs.unwrap_or_default();
+    let events = decode_log_events(program_str, &logs)?;
+    if let Some(err) = result.err {
+        return Ok(ReplayOutcome {
+            err: Some(format!("{:?}", err)),
+            token_deltas: Vec::new(),
+            events,
+        });
+    }
+    let post_accounts = result.accounts.unwrap_or_default();
+    let mut token_deltas = Vec::with_capacity(token_accounts.len());
+    for ((recorded_delta, pre_account), post_account) in recorded
+        .outcome
+        .token_deltas
+        .iter()
+        .zip(pre_accounts.iter())
+        .zip(post_accounts)
+    {
+        let post_account = post_account.and_then(|ui_account| ui_account.decode::<Account>());
+        token_deltas.push(TokenDelta {
+            account: recorded_delta.account,
+            mint: recorded_delta.mint.clone(),
+            delta: i128::from(token_amount(post_account.as_ref())?)
+                - i128::from(token_amount(pre_account.as_ref())?),
+        });
+    }
+    Ok(ReplayOutcome {
+        err: None,
+        token_deltas,
+        events,
+    })
+}
+
+fn decode_log_events(program_str: &str, logs: &[String]) -> Result<Vec<IndexedEventData>> {
+    let mut events = Vec::new();
+    for log in program_log_event_data(program_str, logs)? {
+        if let Some(event) = decode_indexed_event(&log)? {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
@@ -0,0 +1,119 @@
+use anchor_client::solana_account_decoder::UiAccountEncoding;
+use anchor_client::solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anyhow::Result;
+use raydium_amm_v3::states::PoolState;
+use std::collections::BTreeMap;
+
+use super::indexer::{IndexedEventData, PoolIndex};
+use super::utils::deserialize_anchor_account;
+
+// offset of the amm config in the pool state data, after the discriminator and bump
+const POOL_AMM_CONFIG_OFFSET: usize = 8 + 1;
+
+/// The protocol revenue of a pool, in raw token amounts
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolRevenue {
+    pub pool_id: Pubkey,
+    pub mints: [Pubkey; 2],
+    pub decimals: [u8; 2],
+    /// Protocol fees accrued but not collected yet
+    pub protocol_fees: [u64; 2],
+    /// Fund fees accrued but not collected yet
+    pub fund_fees: [u64; 2],
+    /// Protocol and fund fees collected, from the indexed events. None without index.
+    pub collected: Option<[u64; 2]>,
+}
+
+impl PoolRevenue {
+    pub fn new(pool_id: Pubkey, pool: &PoolState, pool_index: Option<&PoolIndex>) -> Self {
+        Self {
+            pool_id,
+            mints: [pool.token_mint_0, pool.token_mint_1],
+            decimals: [pool.mint_decimals_0, pool.mint_decimals_1],
+            protocol_fees: [pool.protocol_fees_token_0, pool.protocol_fees_token_1],
+            fund_fees: [pool.fund_fees_token_0, pool.fund_fees_token_1],
+            collected: pool_index.map(collected_fees),
+        }
+    }
+}
+
+/// The revenue of all the pools in one token
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenRevenue {
+    pub decimals: u8,
+    pub protocol_fees: u64,
+    pub fund_fees: u64,
+    pub collected: u64,
+    /// Number of pools whose collected fees are unknown, they are not indexed
+    pub unindexed_pools: usize,
+}
+
+/// The protocol and fund fees collected from a pool according to its finalized events. Both
+/// collect instructions emit the collect protocol fee event, so they can't be told apart.
+pub fn collected_fees(pool_index: &PoolIndex) -> [u64; 2] {
+    let mut collected = [0u64; 2];
+    for indexed in pool_index.finalized_events() {
+        if let IndexedEventData::CollectProtocolFee {
+            amount_0, amount_1, ..
+        } = &indexed.event
+        {
+            collected[0] = collected[0].saturating_add(*amount_0);
+            collected[1] = collected[1].saturating_add(*amount_1);
+        }
+    }
+    collected
+}
+
+/// All the pools created with the amm config
+pub fn pools_by_config(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+) -> Result<Vec<(Pubkey, PoolState)>> {
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(PoolState::LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    POOL_AMM_CONFIG_OFFSET,
+                    &amm_config.to_bytes(),
+                )),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64Zstd),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: Some(false),
+            sort_results: None,
+        },
+    )?;
+    let mut pools = Vec::new();
+    for (pool_id, account) in accounts {
+        pools.push((pool_id, deserialize_anchor_account::<PoolState>(&account)?));
+    }
+    Ok(pools)
+}
+
+/// Sum the revenue of the pools by token mint
+pub fn revenue_by_token(revenues: &[PoolRevenue]) -> BTreeMap<Pubkey, TokenRevenue> {
+    let mut totals: BTreeMap<Pubkey, TokenRevenue> = BTreeMap::new();
+    for revenue in revenues {
+        for i in 0..2 {
+            let total = totals.entry(revenue.mints[i]).or_default();
+            total.decimals = revenue.decimals[i];
+            total.protocol_fees = total.protocol_fees.saturating_add(revenue.protocol_fees[i]);
+            total.fund_fees = total.fund_fees.saturating_add(revenue.fund_fees[i]);
+            match revenue.collected {
+                Some(collected) => total.collected = total.collected.saturating_add(collected[i]),
+                None => total.unindexed_pools += 1,
+            }
+        }
+    }
+    totals
+}
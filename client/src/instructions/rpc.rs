@@ -1,19 +1,37 @@
+use anchor_client::solana_account_decoder::UiAccountEncoding;
 use anchor_client::solana_client::{
-    rpc_client::RpcClient,
-    rpc_config::RpcSendTransactionConfig,
+    nonce_utils,
+    rpc_client::{RpcClient, SerializableTransaction},
+    rpc_config::{
+        RpcSendTransactionConfig, RpcSimulateTransactionAccountsConfig,
+        RpcSimulateTransactionConfig,
+    },
     rpc_request::RpcRequest,
     rpc_response::{RpcResult, RpcSimulateTransactionResult},
 };
 use anchor_client::solana_sdk::{
-    account::Account, commitment_config::CommitmentConfig, program_pack::Pack as TokenPack,
-    pubkey::Pubkey, signature::Signature, transaction::Transaction,
+    account::Account, commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction, hash::Hash, instruction::Instruction,
+    program_pack::Pack as TokenPack, pubkey::Pubkey, signature::Signature,
+    transaction::VersionedTransaction,
 };
 use anyhow::{anyhow, Result};
+use spl_token_2022::extension::StateWithExtensions;
 use std::convert::Into;
+use std::time::Duration;
 
-pub fn simulate_transaction(
+use super::confirm::{fatal_send_error, send_and_confirm, ConfirmError};
+use super::export::export_instead_of_send;
+use super::plan::plan_instead_of_send;
+use super::priority_fee::{
+    estimate_compute_unit_price, is_set_compute_unit_price, priority_fee_estimate,
+    writable_accounts,
+};
+use super::utils::get_multiple_accounts_chunked;
+
+pub fn simulate_transaction<T: serde::Serialize>(
     client: &RpcClient,
-    transaction: &Transaction,
+    transaction: &T,
     sig_verify: bool,
     cfg: CommitmentConfig,
 ) -> RpcResult<RpcSimulateTransactionResult> {
@@ -26,21 +44,254 @@ pub fn simulate_transaction(
     )
 }
 
-pub fn send_txn(client: &RpcClient, txn: &Transaction, wait_confirm: bool) -> Result<Signature> {
-    Ok(client.send_and_confirm_transaction_with_spinner_and_config(
+pub fn send_txn(
+    client: &RpcClient,
+    txn: &VersionedTransaction,
+    wait_confirm: bool,
+) -> Result<Signature> {
+    send_txn_with_retry(client, txn, wait_confirm, |_| None)
+}
+
+/// Send a transaction built by the `TransactionBuilder`, it is not signed again once expired
+pub fn send_versioned_txn(
+    client: &RpcClient,
+    txn: &VersionedTransaction,
+    wait_confirm: bool,
+) -> Result<Signature> {
+    if let Some(signature) = plan_instead_of_send(client, txn)? {
+        return Ok(signature);
+    }
+    if let Some(signature) = export_instead_of_send(txn)? {
+        return Ok(signature);
+    }
+    Ok(send_and_confirm(
+        client,
         txn,
-        if wait_confirm {
-            CommitmentConfig::confirmed()
-        } else {
-            CommitmentConfig::processed()
-        },
-        RpcSendTransactionConfig {
-            skip_preflight: true,
-            ..RpcSendTransactionConfig::default()
-        },
+        confirm_commitment(wait_confirm),
     )?)
 }
 
+fn confirm_commitment(wait_confirm: bool) -> CommitmentConfig {
+    if wait_confirm {
+        CommitmentConfig::confirmed()
+    } else {
+        CommitmentConfig::processed()
+    }
+}
+
+// times a transaction is signed again after its blockhash expired
+const MAX_SEND_RETRIES: u32 = 5;
+
+/// Send and confirm the transaction with the confirmation engine, see `send_and_confirm`. A
+/// transaction which expired without landing is re-signed with a new blockhash by `resign` and
+/// sent again, the structured `ConfirmError` of the last attempt is returned otherwise.
+pub fn send_txn_with_retry(
+    client: &RpcClient,
+    txn: &VersionedTransaction,
+    wait_confirm: bool,
+    resign: impl Fn(Hash) -> Option<VersionedTransaction>,
+) -> Result<Signature> {
+    if let Some(signature) = plan_instead_of_send(client, txn)? {
+        return Ok(signature);
+    }
+    if let Some(signature) = export_instead_of_send(txn)? {
+        return Ok(signature);
+    }
+    let commitment = confirm_commitment(wait_confirm);
+    let mut txn = txn.clone();
+    let mut retries = 0;
+    loop {
+        match send_and_confirm(client, &txn, commitment) {
+            Err(ConfirmError::Expired { signature }) if retries < MAX_SEND_RETRIES => {
+                match resign(client.get_latest_blockhash()?) {
+                    Some(resigned_txn) => {
+                        retries += 1;
+                        println!(
+                            "transaction {} expired, retry {} with a new blockhash",
+                            signature, retries
+                        );
+                        txn = resigned_txn;
+                    }
+                    None => return Err(ConfirmError::Expired { signature }.into()),
+                }
+            }
+            result => return Ok(result?),
+        }
+    }
+}
+
+// interval of the signature status polling while waiting for the confirmation
+const CONFIRM_POLL_INTERVAL_MS: u64 = 400;
+
+/// How the compute unit price is raised when a transaction doesn't land in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFeeEscalation {
+    /// Compute unit price of the first submission, in micro lamports
+    pub initial_compute_unit_price: u64,
+    /// The price is never raised above this one
+    pub max_compute_unit_price: u64,
+    /// Percentage added to the price at each resubmission
+    pub bump_percent: u64,
+    /// Slots waited for the confirmation before resubmitting
+    pub resubmit_slots: u64,
+}
+
+impl PriorityFeeEscalation {
+    pub fn next_compute_unit_price(&self, compute_unit_price: u64) -> u64 {
+        let bumped = compute_unit_price.saturating_mul(100 + self.bump_percent) / 100;
+        bumped
+            .max(compute_unit_price + 1)
+            .min(self.max_compute_unit_price)
+    }
+}
+
+/// Send the instructions with a compute unit price, and resubmit them with a higher price each
+/// time they are not confirmed within `resubmit_slots`, until they land or the blockhash expires.
+/// Once the price is capped the last version is resent as is.
+/// Every version is a distinct transaction which stays valid until the shared blockhash expires,
+/// so the statuses of all the versions are polled and the first landed one is returned. Only use
+/// it for instructions whose repeated execution is harmless or fails, e.g. bounded by slippage.
+/// `sign` signs the priced instructions with the blockhash.
+pub fn send_txn_with_priority_fee_escalation(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    escalation: &PriorityFeeEscalation,
+    sign: impl Fn(&[Instruction], Hash) -> Result<VersionedTransaction>,
+) -> Result<Signature> {
+    send_txn_with_priority_fee_escalation_and_record(client, instructions, escalation, sign, |_| {
+        Ok(())
+    })
+}
+
+/// Same as `send_txn_with_priority_fee_escalation`, `record` is called with each version before
+/// it is sent, e.g. to journal its signature
+pub fn send_txn_with_priority_fee_escalation_and_record(
+    client: &RpcClient,
+    instructions: &[Instruction],
+    escalation: &PriorityFeeEscalation,
+    sign: impl Fn(&[Instruction], Hash) -> Result<VersionedTransaction>,
+    mut record: impl FnMut(&VersionedTransaction) -> Result<()>,
+) -> Result<Signature> {
+    // the price is set by the escalation only
+    let instructions: Vec<Instruction> = instructions
+        .iter()
+        .filter(|instruction| !is_set_compute_unit_price(instruction))
+        .cloned()
+        .collect();
+    let blockhash = client.get_latest_blockhash()?;
+    let build_txn = |compute_unit_price: u64| {
+        let mut priced_instructions = vec![ComputeBudgetInstruction::set_compute_unit_price(
+            compute_unit_price,
+        )];
+        priced_instructions.extend(instructions.iter().cloned());
+        sign(&priced_instructions, blockhash)
+    };
+    let mut compute_unit_price = escalation.initial_compute_unit_price;
+    // start from the estimate when it is higher, the escalation raises it further
+    if let Some(estimate) = priority_fee_estimate() {
+        compute_unit_price = compute_unit_price.max(estimate_compute_unit_price(
+            client,
+            &writable_accounts(&instructions),
+            &estimate,
+        )?);
+    }
+    let mut txn = build_txn(compute_unit_price)?;
+    if let Some(signature) = plan_instead_of_send(client, &txn)? {
+        return Ok(signature);
+    }
+    if let Some(signature) = export_instead_of_send(&txn)? {
+        return Ok(signature);
+    }
+    let mut signatures = Vec::new();
+    loop {
+        let signature = txn.signatures[0];
+        if !signatures.contains(&signature) {
+            signatures.push(signature);
+        }
+        record(&txn)?;
+        println!(
+            "send {} with compute unit price {}",
+            signature, compute_unit_price
+        );
+        if let Err(err) = client.send_transaction_with_config(
+            &txn,
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            },
+        ) {
+            // a previous version may still land, so only a failing transaction stops the polling
+            if let Some(err) = fatal_send_error(&err) {
+                return Err(ConfirmError::Failed { signature, err }.into());
+            }
+            println!("send {} failed: {}", signature, err);
+        }
+
+        let resubmit_slot = client.get_slot()? + escalation.resubmit_slots;
+        loop {
+            std::thread::sleep(Duration::from_millis(CONFIRM_POLL_INTERVAL_MS));
+            let statuses = client.get_signature_statuses(&signatures)?.value;
+            for (signature, status) in signatures.iter().zip(statuses) {
+                if let Some(status) = status {
+                    if let Some(err) = status.err {
+                        return Err(ConfirmError::Failed {
+                            signature: *signature,
+                            err,
+                        }
+                        .into());
+                    }
+                    if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                        return Ok(*signature);
+                    }
+                }
+            }
+            if client.get_slot()? >= resubmit_slot {
+                break;
+            }
+        }
+
+        if !client.is_blockhash_valid(&blockhash, CommitmentConfig::confirmed())? {
+            // a version may have landed between the last poll and the expiry
+            let statuses = client.get_signature_statuses(&signatures)?.value;
+            for (signature, status) in signatures.iter().zip(statuses) {
+                if let Some(status) = status {
+                    return match status.err {
+                        Some(err) => Err(ConfirmError::Failed {
+                            signature: *signature,
+                            err,
+                        }
+                        .into()),
+                        None => Ok(*signature),
+                    };
+                }
+            }
+            println!(
+                "no version landed up to compute unit price {}",
+                compute_unit_price
+            );
+            return Err(ConfirmError::Expired {
+                signature: txn.signatures[0],
+            }
+            .into());
+        }
+        if compute_unit_price < escalation.max_compute_unit_price {
+            compute_unit_price = escalation.next_compute_unit_price(compute_unit_price);
+            txn = build_txn(compute_unit_price)?;
+        }
+    }
+}
+
+/// The blockhash stored in the durable nonce account and the authority of the nonce
+pub fn get_durable_nonce(client: &RpcClient, nonce_account: &Pubkey) -> Result<(Hash, Pubkey)> {
+    let account = nonce_utils::get_account_with_commitment(
+        client,
+        nonce_account,
+        CommitmentConfig::confirmed(),
+    )?;
+    let data = nonce_utils::data_from_account(&account)?;
+    Ok((data.blockhash(), data.authority))
+}
+
 pub fn get_token_account<T: TokenPack>(client: &RpcClient, addr: &Pubkey) -> Result<T> {
     let account = client
         .get_account_with_commitment(addr, CommitmentConfig::processed())?
@@ -49,9 +300,100 @@ pub fn get_token_account<T: TokenPack>(client: &RpcClient, addr: &Pubkey) -> Res
     T::unpack_from_slice(&account.data).map_err(Into::into)
 }
 
+/// Fetch any number of accounts, the requests of each chunk are sent one after the other
 pub fn get_multiple_accounts(
     client: &RpcClient,
     pubkeys: &[Pubkey],
 ) -> Result<Vec<Option<Account>>> {
-    Ok(client.get_multiple_accounts(pubkeys)?)
+    get_multiple_accounts_chunked(client, pubkeys, false)
+}
+
+/// Fetch any number of accounts, the requests of each chunk are sent concurrently
+pub fn get_multiple_accounts_concurrently(
+    client: &RpcClient,
+    pubkeys: &[Pubkey],
+) -> Result<Vec<Option<Account>>> {
+    get_multiple_accounts_chunked(client, pubkeys, true)
+}
+
+/// The amount of a token account, 0 when it doesn't exist
+pub fn token_amount(account: Option<&Account>) -> Result<u64> {
+    match account {
+        // the account may be created by the transaction
+        None => Ok(0),
+        Some(account) => Ok(
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)?
+                .base
+                .amount,
+        ),
+    }
+}
+
+/// Simulate the transaction and return the amount change of each token account
+pub fn simulate_token_deltas(
+    client: &RpcClient,
+    txn: &impl SerializableTransaction,
+    token_accounts: &[Pubkey],
+) -> Result<Vec<i128>> {
+    let pre_accounts = get_multiple_accounts(client, token_accounts)?;
+    let result = client
+        .simulate_transaction_with_config(
+            txn,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                commitment: Some(CommitmentConfig::confirmed()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: token_accounts.iter().map(|key| key.to_string()).collect(),
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )?
+        .value;
+    if let Some(err) = result.err {
+        return Err(anyhow!(
+            "simulation failed: {:?}, logs: {:#?}",
+            err,
+            result.logs
+        ));
+    }
+    let post_accounts = result.accounts.unwrap_or_default();
+    let mut deltas = Vec::with_capacity(token_accounts.len());
+    for (pre_account, post_account) in pre_accounts.iter().zip(post_accounts) {
+        let post_account = post_account.and_then(|ui_account| ui_account.decode::<Account>());
+        let pre_amount = token_amount(pre_account.as_ref())?;
+        let post_amount = token_amount(post_account.as_ref())?;
+        deltas.push(i128::from(post_amount) - i128::from(pre_amount));
+    }
+    Ok(deltas)
+}
+
+/// Simulate the transaction and compare the token deltas with the quote, each expectation is a
+/// token account and its expected delta, negative when tokens leave the account.
+/// Fails when a simulated delta deviates from the expected one by more than `slippage`.
+pub fn check_simulated_token_deltas(
+    client: &RpcClient,
+    txn: &impl SerializableTransaction,
+    expected_deltas: &[(Pubkey, i128)],
+    slippage: f64,
+) -> Result<()> {
+    let token_accounts: Vec<Pubkey> = expected_deltas.iter().map(|(key, _)| *key).collect();
+    let deltas = simulate_token_deltas(client, txn, &token_accounts)?;
+    for ((token_account, expected), simulated) in expected_deltas.iter().zip(deltas) {
+        println!(
+            "token_account:{}, expected delta:{}, simulated delta:{}",
+            token_account, expected, simulated
+        );
+        // one more unit for the rounding of the quote
+        let tolerance = (expected.unsigned_abs() as f64 * slippage) as u128 + 1;
+        if (simulated - expected).unsigned_abs() > tolerance {
+            return Err(anyhow!(
+                "simulated delta {} of token account {} deviates from the expected {} by more than the slippage, use --force to send anyway",
+                simulated,
+                token_account,
+                expected
+            ));
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,378 @@
+use anchor_client::solana_account_decoder::UiAccountEncoding;
+use anchor_client::solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+};
+use anchor_client::solana_sdk::{account::Account, pubkey::Pubkey};
+use anchor_lang::Discriminator;
+use anyhow::Result;
+use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use super::utils::deserialize_anchor_account;
+
+/// What is wrong with a program account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScanProblem {
+    /// The discriminator is known but the data doesn't deserialize
+    Corrupt,
+    /// Shorter than the current layout, written by an older program version
+    Legacy,
+    /// Longer than the current layout
+    UnknownSize,
+    /// The discriminator matches no account type of the program
+    Unknown,
+    /// Deserializes but its fields contradict each other or the accounts they refer to
+    Inconsistent,
+}
+
+impl fmt::Display for ScanProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ScanProblem::Corrupt => "corrupt",
+            ScanProblem::Legacy => "legacy",
+            ScanProblem::UnknownSize => "unknown size",
+            ScanProblem::Unknown => "unknown",
+            ScanProblem::Inconsistent => "inconsistent",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanIssue {
+    pub account: Pubkey,
+    pub account_type: &'static str,
+    pub problem: ScanProblem,
+    pub detail: String,
+}
+
+/// Result of an integrity scan of all the accounts of the program
+#[derive(Debug, Default, Clone)]
+pub struct ScanReport {
+    /// Number of accounts by type, including the ones with issues
+    pub counts: BTreeMap<&'static str, usize>,
+    pub issues: Vec<ScanIssue>,
+}
+
+impl ScanReport {
+    pub fn problem_counts(&self) -> BTreeMap<ScanProblem, usize> {
+        let mut counts = BTreeMap::new();
+        for issue in self.issues.iter() {
+            *counts.entry(issue.problem).or_default() += 1;
+        }
+        counts
+    }
+
+    fn push(
+        &mut self,
+        account: Pubkey,
+        account_type: &'static str,
+        problem: ScanProblem,
+        detail: String,
+    ) {
+        self.issues.push(ScanIssue {
+            account,
+            account_type,
+            problem,
+            detail,
+        });
+    }
+}
+
+// name, discriminator and current size of every account type of the program
+fn account_types() -> Vec<(&'static str, &'static [u8], usize)> {
+    vec![
+        ("AmmConfig", &AmmConfig::DISCRIMINATOR, AmmConfig::LEN),
+        ("PoolState", &PoolState::DISCRIMINATOR, PoolState::LEN),
+        (
+            "TickArrayState",
+            &TickArrayState::DISCRIMINATOR,
+            TickArrayState::LEN,
+        ),
+        (
+            "PersonalPositionState",
+            &PersonalPositionState::DISCRIMINATOR,
+            PersonalPositionState::LEN,
+        ),
+        (
+            "ProtocolPositionState",
+            &ProtocolPositionState::DISCRIMINATOR,
+            ProtocolPositionState::LEN,
+        ),
+        (
+            "ObservationState",
+            &ObservationState::DISCRIMINATOR,
+            ObservationState::LEN,
+        ),
+        (
+            "TickArrayBitmapExtension",
+            &TickArrayBitmapExtension::DISCRIMINATOR,
+            TickArrayBitmapExtension::LEN,
+        ),
+        (
+            "OperationState",
+            &OperationState::DISCRIMINATOR,
+            OperationState::LEN,
+        ),
+        (
+            "SupportMintAssociated",
+            &SupportMintAssociated::DISCRIMINATOR,
+            SupportMintAssociated::LEN,
+        ),
+        (
+            "PoolCreatorPermission",
+            &PoolCreatorPermission::DISCRIMINATOR,
+            PoolCreatorPermission::LEN,
+        ),
+        (
+            "FeeDiscountConfig",
+            &FeeDiscountConfig::DISCRIMINATOR,
+            FeeDiscountConfig::LEN,
+        ),
+        (
+            "EmergencyPause",
+            &EmergencyPause::DISCRIMINATOR,
+            EmergencyPause::LEN,
+        ),
+    ]
+}
+
+/// Fetch all the accounts of the program, check each one deserializes as the type of its
+/// discriminator with the current size, and cross check the pools, tick arrays and positions.
+pub fn scan_program_accounts(rpc_client: &RpcClient, program_id: &Pubkey) -> Result<ScanReport> {
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: None,
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64Zstd),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: Some(false),
+            sort_results: None,
+        },
+    )?;
+    Ok(scan_accounts(&accounts))
+}
+
+pub fn scan_accounts(accounts: &[(Pubkey, Account)]) -> ScanReport {
+    let account_types = account_types();
+    let mut report = ScanReport::default();
+    let mut amm_configs = HashSet::new();
+    let mut pools = HashMap::new();
+    let mut tick_arrays = Vec::new();
+    let mut positions = Vec::new();
+
+    for (key, account) in accounts {
+        let account_type = account_types
+            .iter()
+            .find(|(_, discriminator, _)| account.data.starts_with(discriminator));
+        let (name, _, len) = match account_type {
+            Some(account_type) => *account_type,
+            None => {
+                report.push(
+                    *key,
+                    "",
+                    ScanProblem::Unknown,
+                    format!("{} bytes", account.data.len()),
+                );
+                continue;
+            }
+        };
+        *report.counts.entry(name).or_default() += 1;
+        if account.data.len() != len {
+            let problem = if account.data.len() < len {
+                ScanProblem::Legacy
+            } else {
+                ScanProblem::UnknownSize
+            };
+            report.push(
+                *key,
+                name,
+                problem,
+                format!("{} bytes, expected {}", account.data.len(), len),
+            );
+            continue;
+        }
+        let deserialized = match name {
+            "AmmConfig" => deserialize_anchor_account::<AmmConfig>(account).map(|_| {
+                amm_configs.insert(*key);
+            }),
+            "PoolState" => deserialize_anchor_account::<PoolState>(account).map(|pool| {
+                pools.insert(*key, pool);
+            }),
+            "TickArrayState" => {
+                deserialize_anchor_account::<TickArrayState>(account).map(|tick_array| {
+                    tick_arrays.push((*key, tick_array));
+                })
+            }
+            "PersonalPositionState" => deserialize_anchor_account::<PersonalPositionState>(account)
+                .map(|position| {
+                    positions.push((
+                        *key,
+                        name,
+                        position.pool_id,
+                        position.tick_lower_index,
+                        position.tick_upper_index,
+                    ));
+                }),
+            "ProtocolPositionState" => deserialize_anchor_account::<ProtocolPositionState>(account)
+                .map(|position| {
+                    positions.push((
+                        *key,
+                        name,
+                        position.pool_id,
+                        position.tick_lower_index,
+                        position.tick_upper_index,
+                    ));
+                }),
+            "ObservationState" => {
+                deserialize_anchor_account::<ObservationState>(account).map(|_| ())
+            }
+            "TickArrayBitmapExtension" => {
+                deserialize_anchor_account::<TickArrayBitmapExtension>(account).map(|_| ())
+            }
+            "OperationState" => deserialize_anchor_account::<OperationState>(account).map(|_| ()),
+            "SupportMintAssociated" => {
+                deserialize_anchor_account::<SupportMintAssociated>(account).map(|_| ())
+            }
+            "PoolCreatorPermission" => {
+                deserialize_anchor_account::<PoolCreatorPermission>(account).map(|_| ())
+            }
+            "FeeDiscountConfig" => {
+                deserialize_anchor_account::<FeeDiscountConfig>(account).map(|_| ())
+            }
+            _ => deserialize_anchor_account::<EmergencyPause>(account).map(|_| ()),
+        };
+        if let Err(err) = deserialized {
+            report.push(*key, name, ScanProblem::Corrupt, err.to_string());
+        }
+    }
+
+    for (key, pool) in pools.iter() {
+        for detail in check_pool(pool, &amm_configs) {
+            report.push(*key, "PoolState", ScanProblem::Inconsistent, detail);
+        }
+    }
+    for (key, tick_array) in tick_arrays.iter() {
+        for detail in check_tick_array(tick_array, pools.get(&tick_array.pool_id)) {
+            report.push(*key, "TickArrayState", ScanProblem::Inconsistent, detail);
+        }
+    }
+    for (key, name, pool_id, tick_lower, tick_upper) in positions {
+        for detail in check_position_ticks(tick_lower, tick_upper, pools.get(&pool_id)) {
+            report.push(key, name, ScanProblem::Inconsistent, detail);
+        }
+    }
+    report
+}
+
+fn check_pool(pool: &PoolState, amm_configs: &HashSet<Pubkey>) -> Vec<String> {
+    let mut details = Vec::new();
+    if !amm_configs.contains(&pool.amm_config) {
+        details.push(format!("amm config {} not found", pool.amm_config));
+    }
+    if pool.token_mint_0 >= pool.token_mint_1 {
+        details.push("token mints not sorted".to_string());
+    }
+    // copy the fields out of the packed account
+    let tick_spacing = pool.tick_spacing;
+    let sqrt_price_x64 = pool.sqrt_price_x64;
+    let tick_current = pool.tick_current;
+    let (total_fees_token_0, total_fees_claimed_token_0) =
+        (pool.total_fees_token_0, pool.total_fees_claimed_token_0);
+    let (total_fees_token_1, total_fees_claimed_token_1) =
+        (pool.total_fees_token_1, pool.total_fees_claimed_token_1);
+    if tick_spacing == 0 {
+        details.push("zero tick spacing".to_string());
+    }
+    if sqrt_price_x64 < tick_math::MIN_SQRT_PRICE_X64
+        || sqrt_price_x64 > tick_math::MAX_SQRT_PRICE_X64
+    {
+        details.push(format!("sqrt price {} out of bounds", sqrt_price_x64));
+    } else if let Ok(tick) = tick_math::get_tick_at_sqrt_price(sqrt_price_x64) {
+        // a swap ending exactly on a tick downward leaves the current tick just below it
+        if tick_current != tick && tick_current != tick - 1 {
+            details.push(format!(
+                "tick current {} doesn't match the tick {} of the price",
+                tick_current, tick
+            ));
+        }
+    }
+    if total_fees_claimed_token_0 > total_fees_token_0
+        || total_fees_claimed_token_1 > total_fees_token_1
+    {
+        details.push("more fees claimed than collected".to_string());
+    }
+    details
+}
+
+fn check_tick_array(tick_array: &TickArrayState, pool: Option<&PoolState>) -> Vec<String> {
+    let pool = match pool {
+        Some(pool) => pool,
+        None => return vec![format!("pool {} not found", tick_array.pool_id)],
+    };
+    let mut details = Vec::new();
+    let tick_spacing = pool.tick_spacing;
+    let start_tick_index = tick_array.start_tick_index;
+    if tick_spacing == 0
+        || !TickArrayState::check_is_valid_start_index(start_tick_index, tick_spacing)
+    {
+        details.push(format!("invalid start index {}", start_tick_index));
+        return details;
+    }
+    let mut initialized_tick_count = 0;
+    for (i, tick_state) in tick_array.ticks.iter().enumerate() {
+        let liquidity_gross = tick_state.liquidity_gross;
+        if liquidity_gross == 0 {
+            continue;
+        }
+        initialized_tick_count += 1;
+        let tick = tick_state.tick;
+        if tick != start_tick_index + i as i32 * i32::from(tick_spacing) {
+            details.push(format!("tick {} at offset {}", tick, i));
+        }
+        let liquidity_net = tick_state.liquidity_net;
+        if liquidity_net.unsigned_abs() > liquidity_gross {
+            details.push(format!(
+                "tick {} net liquidity {} exceeds gross {}",
+                tick, liquidity_net, liquidity_gross
+            ));
+        }
+    }
+    if initialized_tick_count != tick_array.initialized_tick_count {
+        details.push(format!(
+            "initialized tick count {}, {} ticks have liquidity",
+            tick_array.initialized_tick_count, initialized_tick_count
+        ));
+    }
+    details
+}
+
+fn check_position_ticks(tick_lower: i32, tick_upper: i32, pool: Option<&PoolState>) -> Vec<String> {
+    let mut details = Vec::new();
+    if tick_lower >= tick_upper
+        || tick_lower < tick_math::MIN_TICK
+        || tick_upper > tick_math::MAX_TICK
+    {
+        details.push(format!("invalid range [{}, {})", tick_lower, tick_upper));
+    }
+    match pool {
+        Some(pool) => {
+            let tick_spacing = i32::from(pool.tick_spacing);
+            if tick_spacing != 0
+                && (tick_lower % tick_spacing != 0 || tick_upper % tick_spacing != 0)
+            {
+                details.push(format!(
+                    "ticks [{}, {}) not multiples of the tick spacing {}",
+                    tick_lower, tick_upper, tick_spacing
+                ));
+            }
+        }
+        None => details.push("pool not found".to_string()),
+    }
+    details
+}
@@ -0,0 +1,271 @@
+use anyhow::{format_err, Result};
+use clap::ValueEnum;
+use configparser::ini::Ini;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+// the environment variables overriding the file are CLMM_<SECTION>_<KEY>, e.g. CLMM_GLOBAL_HTTP_URL
+const ENV_PREFIX: &str = "CLMM_";
+const SECTIONS: [&str; 2] = ["global", "pool"];
+// the [Pool] section, the pool profiles are the [Pool.<name>] sections
+const POOL_SECTION: &str = "pool";
+
+/// A cluster the program is deployed on, its preset fills the endpoints and the program id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    /// A local validator, e.g. started by clone-localnet, with the mainnet program id
+    Localnet,
+}
+
+impl Cluster {
+    /// The keys of the [Global] section the preset sets
+    pub fn preset(self) -> [(&'static str, &'static str); 3] {
+        match self {
+            Cluster::Mainnet => [
+                ("http_url", "https://api.mainnet-beta.solana.com"),
+                ("ws_url", "wss://api.mainnet-beta.solana.com/"),
+                (
+                    "raydium_v3_program",
+                    "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK",
+                ),
+            ],
+            Cluster::Devnet => [
+                ("http_url", "https://api.devnet.solana.com"),
+                ("ws_url", "wss://api.devnet.solana.com/"),
+                (
+                    "raydium_v3_program",
+                    "devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH",
+                ),
+            ],
+            Cluster::Localnet => [
+                ("http_url", "http://127.0.0.1:8899"),
+                ("ws_url", "ws://127.0.0.1:8900"),
+                (
+                    "raydium_v3_program",
+                    "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK",
+                ),
+            ],
+        }
+    }
+}
+
+/// The client configuration layered from the file, the environment and the command line, each
+/// overriding the previous one. The file is toml when its extension is .toml and ini otherwise,
+/// both with the [Global] and [Pool] sections, and the named pool profiles in [Pool.<name>]
+/// sections. Sections and keys are case insensitive.
+pub struct Settings {
+    values: HashMap<(String, String), String>,
+}
+
+impl Settings {
+    /// Load the file and the keys of the pool profile over the [Pool] section, then apply the
+    /// preset of the cluster, the environment variables and the `overrides` of the command line,
+    /// each `key=value` for the Global section or `section.key=value`
+    pub fn load(
+        path: &str,
+        pool_profile: Option<&str>,
+        cluster: Option<Cluster>,
+        overrides: &[String],
+    ) -> Result<Self> {
+        let mut settings = Self {
+            values: load_file(path)?,
+        };
+        if let Some(pool_profile) = pool_profile {
+            settings.select_pool_profile(pool_profile)?;
+        }
+        if let Some(cluster) = cluster {
+            for (key, value) in cluster.preset() {
+                settings.set("global", key, value.to_string());
+            }
+        }
+        for (name, value) in std::env::vars() {
+            if let Some(name) = name.strip_prefix(ENV_PREFIX) {
+                let name = name.to_lowercase();
+                for section in SECTIONS {
+                    if let Some(key) = name
+                        .strip_prefix(section)
+                        .and_then(|key| key.strip_prefix('_'))
+                    {
+                        settings.set(section, key, value.clone());
+                    }
+                }
+            }
+        }
+        for item in overrides {
+            let (name, value) = item
+                .split_once('=')
+                .ok_or(format_err!("expected key=value, got `{}`", item))?;
+            let (section, key) = name
+                .trim()
+                .split_once('.')
+                .unwrap_or(("global", name.trim()));
+            settings.set(section, key, value.trim().to_string());
+        }
+        Ok(settings)
+    }
+
+    /// Set the keys of the [Pool.<name>] section in the [Pool] section, the keys the profile
+    /// doesn't set keep the value of the [Pool] section
+    fn select_pool_profile(&mut self, name: &str) -> Result<()> {
+        let section = format!("{}.{}", POOL_SECTION, name.to_lowercase());
+        let profile: Vec<(String, String)> = self
+            .values
+            .iter()
+            .filter(|((profile_section, _), _)| *profile_section == section)
+            .map(|((_, key), value)| (key.clone(), value.clone()))
+            .collect();
+        if profile.is_empty() {
+            let profiles = self.pool_profiles();
+            return Err(format_err!(
+                "no pool profile {}, the config defines: {}",
+                name,
+                if profiles.is_empty() {
+                    "none".to_string()
+                } else {
+                    profiles.join(", ")
+                }
+            ));
+        }
+        for (key, value) in profile {
+            self.set(POOL_SECTION, &key, value);
+        }
+        Ok(())
+    }
+
+    /// The names of the pool profiles defined, sorted
+    pub fn pool_profiles(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = self
+            .values
+            .keys()
+            .filter_map(|(section, _)| {
+                section
+                    .strip_prefix(POOL_SECTION)
+                    .and_then(|name| name.strip_prefix('.'))
+                    .map(str::to_string)
+            })
+            .collect();
+        profiles.sort();
+        profiles.dedup();
+        profiles
+    }
+
+    pub fn set(&mut self, section: &str, key: &str, value: String) {
+        self.values
+            .insert((section.to_lowercase(), key.to_lowercase()), value);
+    }
+
+    /// The value of the key, None when it is missing or empty
+    pub fn get(&self, section: &str, key: &str) -> Option<String> {
+        self.values
+            .get(&(section.to_lowercase(), key.to_lowercase()))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The value of the key, which must be set
+    pub fn required(&self, section: &str, key: &str) -> Result<String> {
+        self.get(section, key)
+            .ok_or(format_err!("{} must be set in [{}]", key, section))
+    }
+
+    /// The value of the key parsed, None when it is missing or empty
+    pub fn parse<T: FromStr>(&self, section: &str, key: &str) -> Result<Option<T>>
+    where
+        T::Err: Display,
+    {
+        self.get(section, key)
+            .map(|value| {
+                value.parse::<T>().map_err(|err| {
+                    format_err!("invalid {} `{}` in [{}]: {}", key, value, section, err)
+                })
+            })
+            .transpose()
+    }
+
+    /// The comma separated values of the key parsed, empty when it is missing
+    pub fn parse_list<T: FromStr>(&self, section: &str, key: &str) -> Result<Vec<T>>
+    where
+        T::Err: Display,
+    {
+        self.get(section, key)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value.parse::<T>().map_err(|err| {
+                    format_err!("invalid {} `{}` in [{}]: {}", key, value, section, err)
+                })
+            })
+            .collect()
+    }
+}
+
+fn load_file(path: &str) -> Result<HashMap<(String, String), String>> {
+    let mut values = HashMap::new();
+    if Path::new(path)
+        .extension()
+        .map_or(false, |extension| extension == "toml")
+    {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format_err!("can't read the config {}: {}", path, err))?;
+        let table: toml::Table = text
+            .parse()
+            .map_err(|err| format_err!("invalid config {}: {}", path, err))?;
+        for (section, keys) in &table {
+            let keys = keys.as_table().ok_or(format_err!(
+                "invalid config {}: {} is not a section",
+                path,
+                section
+            ))?;
+            insert_toml_section(&mut values, &section.to_lowercase(), keys);
+        }
+    } else {
+        let sections = Ini::new()
+            .load(path)
+            .map_err(|err| format_err!("can't load the config {}: {}", path, err))?;
+        for (section, keys) in sections {
+            for (key, value) in keys {
+                values.insert((section.clone(), key), value.unwrap_or_default());
+            }
+        }
+    }
+    Ok(values)
+}
+
+// the keys of the section, its tables are the sections named after it, e.g. [Pool.sol-usdc]
+fn insert_toml_section(
+    values: &mut HashMap<(String, String), String>,
+    section: &str,
+    keys: &toml::Table,
+) {
+    for (key, value) in keys {
+        match value {
+            toml::Value::Table(table) => insert_toml_section(
+                values,
+                &format!("{}.{}", section, key.to_lowercase()),
+                table,
+            ),
+            value => {
+                values.insert((section.to_string(), key.to_lowercase()), toml_value(value));
+            }
+        }
+    }
+}
+
+// the values as they are written in the ini file, the lists comma separated
+fn toml_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value.clone(),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(toml_value)
+            .collect::<Vec<String>>()
+            .join(","),
+        value => value.to_string(),
+    }
+}
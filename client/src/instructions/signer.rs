@@ -0,0 +1,138 @@
+use anchor_client::solana_sdk::{
+    derivation_path::DerivationPath,
+    pubkey::Pubkey,
+    signature::{self, Keypair, Signature, Signer, SignerError},
+    signer::keypair::keypair_from_seed_and_derivation_path,
+};
+use anyhow::{format_err, Result};
+use bip39::{Language, Mnemonic, Seed};
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+// the source of a keypair in an environment variable, e.g. env:PAYER_KEYPAIR
+const ENV_SOURCE_PREFIX: &str = "env:";
+// the source of a keypair on the standard input
+const STDIN_SOURCE: &str = "-";
+// the source of a keypair derived from a seed phrase typed on the terminal, e.g. prompt://?key=0/0
+const PROMPT_SOURCE_PREFIX: &str = "prompt:";
+
+// the standard input is read once, the payer and the admin may both be read from it
+static STDIN_KEYPAIR: OnceLock<String> = OnceLock::new();
+
+/// Any signer, e.g. a keypair file, an in-memory key, a hardware wallet or a remote signing
+/// service, shared between the instruction builders, the anchor client and the threads
+#[derive(Clone)]
+pub struct SharedSigner(Arc<dyn Signer + Send + Sync>);
+
+impl SharedSigner {
+    pub fn new(signer: impl Signer + Send + Sync + 'static) -> Self {
+        Self(Arc::new(signer))
+    }
+
+    pub fn from_arc(signer: Arc<dyn Signer + Send + Sync>) -> Self {
+        Self(signer)
+    }
+}
+
+impl Signer for SharedSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        self.0.try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.0.try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.0.is_interactive()
+    }
+}
+
+impl fmt::Debug for SharedSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SharedSigner({})", self.pubkey())
+    }
+}
+
+// signers are the same when they sign for the same key
+impl PartialEq for SharedSigner {
+    fn eq(&self, other: &Self) -> bool {
+        self.pubkey() == other.pubkey()
+    }
+}
+
+/// Whether reading the keypair of the source prompts on the terminal or consumes the standard input
+pub fn is_interactive_source(source: &str) -> bool {
+    source.starts_with(PROMPT_SOURCE_PREFIX) || source == STDIN_SOURCE
+}
+
+/// Read a keypair from its source: `env:NAME` for the environment variable, `-` for the standard
+/// input, `prompt://` for a seed phrase and a file path otherwise. The variable and the standard
+/// input hold the keypair as the file does, the json array of its bytes, or as the base58 string
+/// of the bytes.
+pub fn read_keypair(source: &str) -> Result<Keypair> {
+    if let Some(uri) = source.strip_prefix(PROMPT_SOURCE_PREFIX) {
+        return keypair_from_prompted_seed_phrase(source, uri);
+    }
+    if let Some(name) = source.strip_prefix(ENV_SOURCE_PREFIX) {
+        let text = std::env::var(name)
+            .map_err(|err| format_err!("failed to read keypair from {}: {}", source, err))?;
+        return parse_keypair(&text)
+            .map_err(|err| format_err!("invalid keypair in {}: {}", source, err));
+    }
+    if source == STDIN_SOURCE {
+        let text = match STDIN_KEYPAIR.get() {
+            Some(text) => text,
+            None => {
+                let text = std::io::read_to_string(std::io::stdin())
+                    .map_err(|err| format_err!("failed to read keypair from stdin: {}", err))?;
+                STDIN_KEYPAIR.get_or_init(|| text)
+            }
+        };
+        return parse_keypair(text).map_err(|err| format_err!("invalid keypair on stdin: {}", err));
+    }
+    signature::read_keypair_file(source)
+        .map_err(|_| format_err!("failed to read keypair from {}", source))
+}
+
+// the json array of the keypair bytes, or their base58 string
+fn parse_keypair(text: &str) -> Result<Keypair> {
+    let text = text.trim();
+    if text.starts_with('[') {
+        return signature::read_keypair(&mut text.as_bytes()).map_err(|err| format_err!("{}", err));
+    }
+    let bytes = bs58::decode(text).into_vec()?;
+    Keypair::from_bytes(&bytes).map_err(|err| format_err!("{}", err))
+}
+
+/// Derive the keypair from the bip39 seed phrase and passphrase typed on the terminal, as the
+/// solana cli does. The derivation path is set by the query of the source, `?key=0/0` for
+/// m/44'/501'/0'/0', the path of the browser wallets, or `?full-path=m/44/501/0/0`, and is
+/// m/44'/501' without it.
+fn keypair_from_prompted_seed_phrase(source: &str, uri: &str) -> Result<Keypair> {
+    let query = uri.trim_start_matches("//").trim_start_matches('?');
+    let derivation_path = if query.is_empty() {
+        None
+    } else {
+        let derivation_path = match query.split_once('=') {
+            Some(("key", key)) => DerivationPath::from_key_str(key),
+            Some(("full-path", path)) => DerivationPath::from_absolute_path_str(path),
+            _ => {
+                return Err(format_err!(
+                    "invalid {}, expected prompt://?key=<account>/<change> or prompt://?full-path=<path>",
+                    source
+                ))
+            }
+        };
+        Some(derivation_path.map_err(|err| format_err!("invalid {}: {}", source, err))?)
+    };
+    let seed_phrase = rpassword::prompt_password(format!("seed phrase for {}: ", source))?;
+    let mnemonic = Mnemonic::from_phrase(seed_phrase.trim(), Language::English)
+        .map_err(|err| format_err!("invalid seed phrase: {}", err))?;
+    let passphrase = rpassword::prompt_password("bip39 passphrase, empty for none: ")?;
+    let seed = Seed::new(&mnemonic, &passphrase);
+    let keypair = keypair_from_seed_and_derivation_path(seed.as_bytes(), derivation_path)
+        .map_err(|err| format_err!("failed to derive keypair from {}: {}", source, err))?;
+    eprintln!("derived {} from {}", keypair.pubkey(), source);
+    Ok(keypair)
+}
@@ -0,0 +1,192 @@
+use anchor_client::solana_client::rpc_client::RpcClient;
+use anchor_client::solana_sdk::{
+    hash::{hash, Hash},
+    instruction::{AccountMeta, Instruction},
+    message::v0,
+    pubkey::Pubkey,
+    system_program,
+};
+use anyhow::{format_err, Result};
+use std::str::FromStr;
+
+// the squads v4 multisig program
+pub const SQUADS_PROGRAM_ID: &str = "SQDS4ep65T869zMMBKyuUq6mD6EUGLKpvnD7KaKwEgJ";
+const SEED_PREFIX: &[u8] = b"multisig";
+const SEED_VAULT: &[u8] = b"vault";
+const SEED_TRANSACTION: &[u8] = b"transaction";
+const SEED_PROPOSAL: &[u8] = b"proposal";
+// discriminator, create key, config authority, threshold and time lock precede the index
+const TRANSACTION_INDEX_OFFSET: usize = 8 + 32 + 32 + 2 + 4;
+
+pub fn squads_program_id() -> Pubkey {
+    Pubkey::from_str(SQUADS_PROGRAM_ID).unwrap()
+}
+
+/// The vault of the multisig, the authority which signs the executed transactions
+pub fn squads_vault(multisig: &Pubkey, vault_index: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SEED_PREFIX, multisig.as_ref(), SEED_VAULT, &[vault_index]],
+        &squads_program_id(),
+    )
+    .0
+}
+
+pub fn squads_transaction(multisig: &Pubkey, transaction_index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SEED_PREFIX,
+            multisig.as_ref(),
+            SEED_TRANSACTION,
+            &transaction_index.to_le_bytes(),
+        ],
+        &squads_program_id(),
+    )
+    .0
+}
+
+pub fn squads_proposal(multisig: &Pubkey, transaction_index: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SEED_PREFIX,
+            multisig.as_ref(),
+            SEED_TRANSACTION,
+            &transaction_index.to_le_bytes(),
+            SEED_PROPOSAL,
+        ],
+        &squads_program_id(),
+    )
+    .0
+}
+
+/// The index of the next transaction of the multisig
+pub fn next_transaction_index(client: &RpcClient, multisig: &Pubkey) -> Result<u64> {
+    let data = client.get_account_data(multisig)?;
+    let bytes = data
+        .get(TRANSACTION_INDEX_OFFSET..TRANSACTION_INDEX_OFFSET + 8)
+        .ok_or_else(|| format_err!("{} is not a squads multisig", multisig))?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()) + 1)
+}
+
+fn anchor_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+/// The instructions as the transaction message stored by the multisig, executed by the vault.
+/// It is the compiled message with the vault as the fee payer, in the compact encoding of squads.
+pub fn vault_transaction_message(vault: &Pubkey, instructions: &[Instruction]) -> Result<Vec<u8>> {
+    let message = v0::Message::try_compile(vault, instructions, &[], Hash::default())?;
+    let header = message.header;
+    let num_keys = message.account_keys.len();
+    let num_signers = header.num_required_signatures;
+    let mut data = vec![
+        num_signers,
+        num_signers - header.num_readonly_signed_accounts,
+        (num_keys - usize::from(num_signers)) as u8 - header.num_readonly_unsigned_accounts,
+    ];
+    data.push(u8::try_from(num_keys)?);
+    for key in &message.account_keys {
+        data.extend_from_slice(key.as_ref());
+    }
+    data.push(u8::try_from(message.instructions.len())?);
+    for instruction in &message.instructions {
+        data.push(instruction.program_id_index);
+        data.push(u8::try_from(instruction.accounts.len())?);
+        data.extend_from_slice(&instruction.accounts);
+        data.extend_from_slice(&u16::try_from(instruction.data.len())?.to_le_bytes());
+        data.extend_from_slice(&instruction.data);
+    }
+    // no address lookup tables
+    data.push(0);
+    Ok(data)
+}
+
+/// Store the instructions as a vault transaction of the multisig, the creator must be a member
+/// with the initiate permission
+pub fn vault_transaction_create_instr(
+    multisig: &Pubkey,
+    vault_index: u8,
+    transaction_index: u64,
+    creator: &Pubkey,
+    instructions: &[Instruction],
+    memo: Option<String>,
+) -> Result<Instruction> {
+    let vault = squads_vault(multisig, vault_index);
+    let transaction_message = vault_transaction_message(&vault, instructions)?;
+    let mut data = anchor_discriminator("vault_transaction_create").to_vec();
+    data.push(vault_index);
+    // no ephemeral signers
+    data.push(0);
+    data.extend_from_slice(&(transaction_message.len() as u32).to_le_bytes());
+    data.extend_from_slice(&transaction_message);
+    match memo {
+        Some(memo) => {
+            data.push(1);
+            data.extend_from_slice(&(memo.len() as u32).to_le_bytes());
+            data.extend_from_slice(memo.as_bytes());
+        }
+        None => data.push(0),
+    }
+    Ok(Instruction {
+        program_id: squads_program_id(),
+        accounts: vec![
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(squads_transaction(multisig, transaction_index), false),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(*creator, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    })
+}
+
+/// Open the proposal of the vault transaction for the members to vote on
+pub fn proposal_create_instr(
+    multisig: &Pubkey,
+    transaction_index: u64,
+    creator: &Pubkey,
+) -> Instruction {
+    let mut data = anchor_discriminator("proposal_create").to_vec();
+    data.extend_from_slice(&transaction_index.to_le_bytes());
+    // not a draft
+    data.push(0);
+    Instruction {
+        program_id: squads_program_id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*multisig, false),
+            AccountMeta::new(squads_proposal(multisig, transaction_index), false),
+            AccountMeta::new_readonly(*creator, true),
+            AccountMeta::new(*creator, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+/// The instructions proposing the admin instructions to the multisig, and the index of the
+/// proposed transaction
+pub fn propose_vault_transaction_instrs(
+    client: &RpcClient,
+    multisig: &Pubkey,
+    vault_index: u8,
+    creator: &Pubkey,
+    instructions: &[Instruction],
+    memo: Option<String>,
+) -> Result<(Vec<Instruction>, u64)> {
+    let transaction_index = next_transaction_index(client, multisig)?;
+    Ok((
+        vec![
+            vault_transaction_create_instr(
+                multisig,
+                vault_index,
+                transaction_index,
+                creator,
+                instructions,
+                memo,
+            )?,
+            proposal_create_instr(multisig, transaction_index, creator),
+        ],
+        transaction_index,
+    ))
+}
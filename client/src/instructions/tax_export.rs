@@ -0,0 +1,237 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use raydium_amm_v3::states::REWARD_NUM;
+use std::collections::HashSet;
+
+use super::indexer::{IndexedEventData, PoolIndex};
+
+/// Header of the universal csv layout, accepted by the import of the common crypto tax tools
+pub const TAX_CSV_HEADER: &str = "Date,Sent Amount,Sent Currency,Received Amount,Received Currency,Fee Amount,Fee Currency,Label,Description,TxHash";
+
+/// Token exported in ui amounts, the mint is used as currency
+#[derive(Debug, Clone, Copy)]
+pub struct TaxToken {
+    pub mint: Pubkey,
+    pub decimals: u8,
+}
+
+/// Build the tax rows of the positions from the finalized events of a pool. Deposits are
+/// disposals of the tokens into the position, withdrawals are acquisitions and the collected fees
+/// and rewards are income. The rows of all positions are built when `position_nft_mints` is empty.
+pub fn tax_records(
+    pool_index: &PoolIndex,
+    tokens: &[TaxToken; 2],
+    reward_tokens: &[Option<TaxToken>; REWARD_NUM],
+    position_nft_mints: &[Pubkey],
+) -> Vec<String> {
+    let positions: HashSet<String> = position_nft_mints
+        .iter()
+        .map(|mint| mint.to_string())
+        .collect();
+    let is_exported =
+        |position_nft_mint: &str| positions.is_empty() || positions.contains(position_nft_mint);
+    let mut rows = Vec::new();
+    for indexed in pool_index.finalized_events() {
+        let date = indexed.block_time.map(format_utc).unwrap_or_default();
+        let mut push = |sent: Option<(u64, &TaxToken)>,
+                        received: Option<(u64, &TaxToken)>,
+                        fee: Option<(u64, &TaxToken)>,
+                        label: &str,
+                        position_nft_mint: &str| {
+            rows.push(format!(
+                "{},{},{},{},{},{},{},{},position {},{}",
+                date,
+                sent.map(|(amount, token)| ui_amount(amount, token.decimals))
+                    .unwrap_or_default(),
+                sent.map(|(_, token)| token.mint.to_string())
+                    .unwrap_or_default(),
+                received
+                    .map(|(amount, token)| ui_amount(amount, token.decimals))
+                    .unwrap_or_default(),
+                received
+                    .map(|(_, token)| token.mint.to_string())
+                    .unwrap_or_default(),
+                fee.filter(|(amount, _)| *amount > 0)
+                    .map(|(amount, token)| ui_amount(amount, token.decimals))
+                    .unwrap_or_default(),
+                fee.filter(|(amount, _)| *amount > 0)
+                    .map(|(_, token)| token.mint.to_string())
+                    .unwrap_or_default(),
+                label,
+                position_nft_mint,
+                indexed.signature
+            ));
+        };
+        match &indexed.event {
+            IndexedEventData::CreatePersonalPosition {
+                position_nft_mint,
+                deposit_amount_0,
+                deposit_amount_1,
+                deposit_amount_0_transfer_fee,
+                deposit_amount_1_transfer_fee,
+                ..
+            } => {
+                let position_nft_mint = position_nft_mint.as_deref().unwrap_or("unknown");
+                if !is_exported(position_nft_mint) {
+                    continue;
+                }
+                let deposits = [
+                    (*deposit_amount_0, *deposit_amount_0_transfer_fee),
+                    (*deposit_amount_1, *deposit_amount_1_transfer_fee),
+                ];
+                for ((amount, transfer_fee), token) in deposits.into_iter().zip(tokens) {
+                    if amount > 0 {
+                        push(
+                            Some((amount, token)),
+                            None,
+                            Some((transfer_fee, token)),
+                            "liquidity in",
+                            position_nft_mint,
+                        );
+                    }
+                }
+            }
+            IndexedEventData::IncreaseLiquidity {
+                position_nft_mint,
+                amount_0,
+                amount_1,
+                amount_0_transfer_fee,
+                amount_1_transfer_fee,
+                ..
+            } => {
+                if !is_exported(position_nft_mint) {
+                    continue;
+                }
+                let deposits = [
+                    (*amount_0, *amount_0_transfer_fee),
+                    (*amount_1, *amount_1_transfer_fee),
+                ];
+                for ((amount, transfer_fee), token) in deposits.into_iter().zip(tokens) {
+                    if amount > 0 {
+                        push(
+                            Some((amount, token)),
+                            None,
+                            Some((transfer_fee, token)),
+                            "liquidity in",
+                            position_nft_mint,
+                        );
+                    }
+                }
+            }
+            IndexedEventData::DecreaseLiquidity {
+                position_nft_mint,
+                decrease_amount_0,
+                decrease_amount_1,
+                fee_amount_0,
+                fee_amount_1,
+                reward_amounts,
+                transfer_fee_0,
+                transfer_fee_1,
+                ..
+            } => {
+                if !is_exported(position_nft_mint) {
+                    continue;
+                }
+                let withdrawals = [
+                    (*decrease_amount_0, *transfer_fee_0),
+                    (*decrease_amount_1, *transfer_fee_1),
+                ];
+                for ((amount, transfer_fee), token) in withdrawals.into_iter().zip(tokens) {
+                    if amount > 0 {
+                        push(
+                            None,
+                            Some((amount, token)),
+                            Some((transfer_fee, token)),
+                            "liquidity out",
+                            position_nft_mint,
+                        );
+                    }
+                }
+                for (amount, token) in [*fee_amount_0, *fee_amount_1].into_iter().zip(tokens) {
+                    if amount > 0 {
+                        push(
+                            None,
+                            Some((amount, token)),
+                            None,
+                            "income",
+                            position_nft_mint,
+                        );
+                    }
+                }
+                for (amount, token) in reward_amounts.iter().zip(reward_tokens) {
+                    if let (true, Some(token)) = (*amount > 0, token) {
+                        push(
+                            None,
+                            Some((*amount, token)),
+                            None,
+                            "reward",
+                            position_nft_mint,
+                        );
+                    }
+                }
+            }
+            IndexedEventData::CollectPersonalFee {
+                position_nft_mint,
+                amount_0,
+                amount_1,
+                ..
+            } => {
+                if !is_exported(position_nft_mint) {
+                    continue;
+                }
+                for (amount, token) in [*amount_0, *amount_1].into_iter().zip(tokens) {
+                    if amount > 0 {
+                        push(
+                            None,
+                            Some((amount, token)),
+                            None,
+                            "income",
+                            position_nft_mint,
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    rows
+}
+
+// exact decimal representation of a raw token amount
+fn ui_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+/// Unix timestamp to "YYYY-MM-DD HH:MM:SS UTC"
+// days to civil date from Howard Hinnant's algorithm
+pub fn format_utc(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86400);
+    let seconds = timestamp.rem_euclid(86400);
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
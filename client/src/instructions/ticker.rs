@@ -0,0 +1,89 @@
+use anchor_client::solana_account_decoder::UiAccountEncoding;
+use anchor_client::solana_client::{pubsub_client::PubsubClient, rpc_config::RpcAccountInfoConfig};
+use anchor_client::solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use anyhow::{format_err, Result};
+use arrayref::array_ref;
+
+// offsets of the price fields in the pool state data, after the discriminator, bump, the 7 keys,
+// the decimals and the tick spacing
+const POOL_LIQUIDITY_OFFSET: usize = 8 + 1 + 32 * 7 + 1 + 1 + 2;
+const POOL_SQRT_PRICE_X64_OFFSET: usize = POOL_LIQUIDITY_OFFSET + 16;
+const POOL_TICK_CURRENT_OFFSET: usize = POOL_SQRT_PRICE_X64_OFFSET + 16;
+
+/// The price of a pool as of a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolTick {
+    pub slot: u64,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+}
+
+impl PoolTick {
+    /// Read only the price fields of the pool state data
+    pub fn decode(slot: u64, data: &[u8]) -> Option<Self> {
+        if data.len() < POOL_TICK_CURRENT_OFFSET + 4 {
+            return None;
+        }
+        Some(Self {
+            slot,
+            liquidity: u128::from_le_bytes(*array_ref![data, POOL_LIQUIDITY_OFFSET, 16]),
+            sqrt_price_x64: u128::from_le_bytes(*array_ref![data, POOL_SQRT_PRICE_X64_OFFSET, 16]),
+            tick_current: i32::from_le_bytes(*array_ref![data, POOL_TICK_CURRENT_OFFSET, 4]),
+        })
+    }
+
+    pub fn same_price(&self, other: &PoolTick) -> bool {
+        self.sqrt_price_x64 == other.sqrt_price_x64 && self.liquidity == other.liquidity
+    }
+}
+
+/// Subscribe to the pool at processed commitment and call `on_tick` with each update of a newer
+/// slot, until the subscription is closed by the node
+pub fn subscribe_pool_ticks(
+    ws_url: &str,
+    pool_id: &Pubkey,
+    mut on_tick: impl FnMut(PoolTick),
+) -> Result<()> {
+    subscribe_pool_ticks_while(ws_url, pool_id, |tick| {
+        on_tick(tick);
+        true
+    })
+}
+
+/// Same as `subscribe_pool_ticks`, the subscription is also dropped once `on_tick` returns false
+pub fn subscribe_pool_ticks_while(
+    ws_url: &str,
+    pool_id: &Pubkey,
+    mut on_tick: impl FnMut(PoolTick) -> bool,
+) -> Result<()> {
+    let (_subscription, receiver) = PubsubClient::account_subscribe(
+        ws_url,
+        pool_id,
+        Some(RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::processed()),
+            ..RpcAccountInfoConfig::default()
+        }),
+    )
+    .map_err(|err| format_err!("subscribe to {} failed: {}", pool_id, err))?;
+    let mut last_slot = 0;
+    while let Ok(response) = receiver.recv() {
+        // a processed update may arrive after the one of a later slot
+        let slot = response.context.slot;
+        if slot < last_slot {
+            continue;
+        }
+        last_slot = slot;
+        let data = match response.value.data.decode() {
+            Some(data) => data,
+            None => continue,
+        };
+        if let Some(tick) = PoolTick::decode(slot, &data) {
+            if !on_tick(tick) {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,12 +1,8 @@
-use super::super::{read_keypair_file, ClientConfig};
+use super::super::ClientConfig;
 use anchor_client::solana_client::rpc_client::RpcClient;
 use anchor_client::solana_sdk::{
-    account::WritableAccount,
-    instruction::Instruction,
-    program_pack::Pack,
-    pubkey::Pubkey,
-    signature::{Keypair, Signer},
-    system_instruction,
+    account::WritableAccount, instruction::Instruction, program_pack::Pack, pubkey::Pubkey,
+    signature::Signer, system_instruction,
 };
 use anchor_client::{Client, Cluster};
 use anyhow::Result;
@@ -15,7 +11,7 @@ use spl_token_2022::{
     state::{Account, Mint},
 };
 use spl_token_client::token::ExtensionInitializationParams;
-use std::{rc::Rc, str::FromStr};
+use std::{str::FromStr, sync::Arc};
 
 pub fn create_and_init_mint_instr(
     config: &ClientConfig,
@@ -26,10 +22,10 @@ pub fn create_and_init_mint_instr(
     extension_init_params: Vec<ExtensionInitializationParams>,
     decimals: u8,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = if token_program == spl_token::id() {
         client.program(spl_token::id())?
     } else {
@@ -68,10 +64,10 @@ pub fn create_account_rent_exmpt_instr(
     owner: Pubkey,
     data_size: usize,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(owner)?;
     let instructions = program
         .request()
@@ -94,10 +90,10 @@ pub fn create_ata_token_account_instr(
     mint: &Pubkey,
     owner: &Pubkey,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(token_program)?;
     let instructions = program
         .request()
@@ -119,11 +115,11 @@ pub fn create_and_init_auxiliary_token(
     mint: &Pubkey,
     owner: &Pubkey,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     let mint_account = &mut RpcClient::new(config.http_url.to_string()).get_account(&mint)?;
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let (program, space) = if mint_account.owner == spl_token::id() {
         (
             client.program(spl_token::id())?,
@@ -175,12 +171,12 @@ pub fn close_token_account(
     config: &ClientConfig,
     close_account: &Pubkey,
     destination: &Pubkey,
-    owner: &Keypair,
+    owner: &dyn Signer,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(spl_token::id())?;
     let instructions = program
         .request()
@@ -201,12 +197,12 @@ pub fn spl_token_transfer_instr(
     from: &Pubkey,
     to: &Pubkey,
     amount: u64,
-    from_authority: &Keypair,
+    from_authority: &dyn Signer,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(spl_token::id())?;
     let instructions = program
         .request()
@@ -229,12 +225,12 @@ pub fn spl_token_mint_to_instr(
     mint: &Pubkey,
     to: &Pubkey,
     amount: u64,
-    mint_authority: &Keypair,
+    mint_authority: &dyn Signer,
 ) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = if token_program == spl_token::id() {
         client.program(spl_token::id())?
     } else {
@@ -256,14 +252,14 @@ pub fn spl_token_mint_to_instr(
 }
 
 pub fn wrap_sol_instr(config: &ClientConfig, amount: u64) -> Result<Vec<Instruction>> {
-    let payer = read_keypair_file(&config.payer_path)?;
+    let payer = config.payer()?;
     let wallet_key = payer.pubkey();
     let url = Cluster::Custom(config.http_url.clone(), config.ws_url.clone());
     let wsol_mint = Pubkey::from_str("So11111111111111111111111111111111111111112")?;
     let wsol_ata_account =
         spl_associated_token_account::get_associated_token_address(&wallet_key, &wsol_mint);
     // Client.
-    let client = Client::new(url, Rc::new(payer));
+    let client = Client::new(url, Arc::new(payer));
     let program = client.program(spl_token::id())?;
 
     let instructions = program
@@ -0,0 +1,72 @@
+use anyhow::{format_err, Result};
+use raydium_amm_v3::states::{Observation, ObservationState, OBSERVATION_NUM};
+
+/// The recorded observations, oldest first. The slots of a buffer which hasn't wrapped yet are
+/// left out.
+pub fn ordered_observations(observation_state: &ObservationState) -> Vec<Observation> {
+    if !observation_state.initialized {
+        return Vec::new();
+    }
+    // copied out of the packed account before indexing
+    let observations = observation_state.observations;
+    let latest = usize::from(observation_state.observation_index);
+    (1..=OBSERVATION_NUM)
+        .map(|offset| observations[(latest + offset) % OBSERVATION_NUM])
+        .filter(|observation| observation.block_timestamp != 0)
+        .collect()
+}
+
+/// The tick cumulative at the timestamp, interpolated between the observations around it and
+/// extrapolated with the current tick after the latest one, as the pool records the tick at its
+/// next update
+pub fn tick_cumulative_at(
+    observations: &[Observation],
+    tick_current: i32,
+    timestamp: u32,
+) -> Result<i64> {
+    let (oldest, latest) = match (observations.first(), observations.last()) {
+        (Some(oldest), Some(latest)) => (oldest, latest),
+        _ => return Err(format_err!("the oracle has no observation")),
+    };
+    if timestamp >= latest.block_timestamp {
+        let elapsed = i64::from(timestamp - latest.block_timestamp);
+        return Ok(latest
+            .tick_cumulative
+            .wrapping_add(i64::from(tick_current) * elapsed));
+    }
+    if timestamp < oldest.block_timestamp {
+        return Err(format_err!(
+            "the oldest observation is at {}, after {}",
+            oldest.block_timestamp,
+            timestamp
+        ));
+    }
+    let after = observations
+        .iter()
+        .position(|observation| observation.block_timestamp > timestamp)
+        .unwrap();
+    let (before, after) = (&observations[after - 1], &observations[after]);
+    let span = i64::from(after.block_timestamp - before.block_timestamp);
+    let elapsed = i64::from(timestamp - before.block_timestamp);
+    let delta = after.tick_cumulative.wrapping_sub(before.tick_cumulative);
+    Ok(before.tick_cumulative.wrapping_add(delta * elapsed / span))
+}
+
+/// The time weighted average tick over the `seconds` before `now`, the timestamps of the chain
+pub fn twap_tick(
+    observation_state: &ObservationState,
+    tick_current: i32,
+    now: u32,
+    seconds: u32,
+) -> Result<f64> {
+    if seconds == 0 {
+        return Err(format_err!("the window must be at least one second"));
+    }
+    let observations = ordered_observations(observation_state);
+    let start = now
+        .checked_sub(seconds)
+        .ok_or(format_err!("the window starts before the epoch"))?;
+    let cumulative_now = tick_cumulative_at(&observations, tick_current, now)?;
+    let cumulative_start = tick_cumulative_at(&observations, tick_current, start)?;
+    Ok(cumulative_now.wrapping_sub(cumulative_start) as f64 / f64::from(seconds))
+}
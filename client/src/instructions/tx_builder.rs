@@ -0,0 +1,291 @@
+use anchor_client::solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+    signers::Signers,
+    transaction::{Transaction, VersionedTransaction},
+};
+use anyhow::{format_err, Result};
+use std::str::FromStr;
+
+use super::priority_fee::writable_accounts;
+
+// a transaction can lock at most 64 accounts
+pub const MAX_TX_ACCOUNTS: usize = 64;
+const SIGNATURE_SIZE: usize = 64;
+
+/// The version of the transactions signed by the commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransactionVersion {
+    #[default]
+    Legacy,
+    V0,
+}
+
+impl FromStr for TransactionVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(version: &str) -> Result<Self> {
+        match version.to_lowercase().as_str() {
+            "legacy" => Ok(Self::Legacy),
+            "0" | "v0" => Ok(Self::V0),
+            _ => Err(format_err!(
+                "unknown transaction version {}, expected legacy or v0",
+                version
+            )),
+        }
+    }
+}
+
+/// Sign the instructions into one transaction of the version. The lookup tables resolve the
+/// accounts of a v0 transaction, a transaction with lookup tables is always a v0 one.
+pub fn sign_instructions<T: Signers + ?Sized>(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &T,
+    blockhash: Hash,
+    version: TransactionVersion,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    if version == TransactionVersion::Legacy && lookup_tables.is_empty() {
+        return Ok(Transaction::new_signed_with_payer(
+            instructions,
+            Some(payer),
+            signers,
+            blockhash,
+        )
+        .into());
+    }
+    let message = v0::Message::try_compile(payer, instructions, lookup_tables, blockhash)?;
+    Ok(VersionedTransaction::try_new(
+        VersionedMessage::V0(message),
+        signers,
+    )?)
+}
+
+/// Accumulates instructions and splits them into as many v0 transactions as the size and account
+/// limits require. Instructions added as a group, e.g. an ata creation and the instruction using
+/// the ata, always land in the same transaction and keep their order. The compute budget and memo
+/// instructions are prepended to every transaction.
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    payer: Pubkey,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+    memo: Option<String>,
+    max_accounts: usize,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    groups: Vec<Vec<Instruction>>,
+}
+
+/// A transaction built by `TransactionBuilder`, waiting for its signatures
+#[derive(Debug, Clone)]
+pub struct UnsignedTransaction {
+    pub message: v0::Message,
+}
+
+impl UnsignedTransaction {
+    /// The keys which must sign, the payer first
+    pub fn signers(&self) -> &[Pubkey] {
+        &self.message.account_keys[..self.message.header.num_required_signatures as usize]
+    }
+
+    pub fn sign<T: Signers + ?Sized>(&self, signers: &T) -> Result<VersionedTransaction> {
+        Ok(VersionedTransaction::try_new(
+            VersionedMessage::V0(self.message.clone()),
+            signers,
+        )?)
+    }
+}
+
+impl TransactionBuilder {
+    pub fn new(payer: Pubkey) -> Self {
+        Self {
+            payer,
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            memo: None,
+            max_accounts: MAX_TX_ACCOUNTS,
+            lookup_tables: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+
+    /// The compute unit price in micro lamports
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    pub fn memo(mut self, memo: &str) -> Self {
+        self.memo = Some(memo.to_string());
+        self
+    }
+
+    /// Lower the accounts of each transaction, e.g. to leave room for the accounts of a wallet
+    pub fn max_accounts(mut self, max_accounts: usize) -> Self {
+        self.max_accounts = max_accounts.min(MAX_TX_ACCOUNTS);
+        self
+    }
+
+    /// Resolve the accounts of the transactions through the lookup tables
+    pub fn lookup_tables(mut self, lookup_tables: Vec<AddressLookupTableAccount>) -> Self {
+        self.lookup_tables = lookup_tables;
+        self
+    }
+
+    pub fn add_instruction(&mut self, instruction: Instruction) -> &mut Self {
+        self.groups.push(vec![instruction]);
+        self
+    }
+
+    /// Add instructions which must be in the same transaction
+    pub fn add_group(&mut self, instructions: Vec<Instruction>) -> &mut Self {
+        if !instructions.is_empty() {
+            self.groups.push(instructions);
+        }
+        self
+    }
+
+    /// Create the associated token account of `owner` if it doesn't exist, paid by the payer
+    pub fn create_ata(
+        &mut self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> &mut Self {
+        self.add_instruction(
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &self.payer,
+                owner,
+                mint,
+                token_program,
+            ),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub fn has_compute_unit_price(&self) -> bool {
+        self.compute_unit_price.is_some()
+    }
+
+    /// The accounts written by the instructions, in the order of the instructions
+    pub fn writable_accounts(&self) -> Vec<Pubkey> {
+        writable_accounts(&self.groups.concat())
+    }
+
+    fn prefix_instructions(&self) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        if let Some(units) = self.compute_unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.compute_unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+                micro_lamports,
+            ));
+        }
+        if let Some(memo) = &self.memo {
+            instructions.push(spl_memo::build_memo(memo.as_bytes(), &[]));
+        }
+        instructions
+    }
+
+    // the message of the instructions if it fits the limits of a transaction
+    fn compile(&self, instructions: &[Instruction], blockhash: Hash) -> Option<v0::Message> {
+        let message =
+            v0::Message::try_compile(&self.payer, instructions, &self.lookup_tables, blockhash)
+                .ok()?;
+        // the accounts loaded from the lookup tables are locked as well
+        let loaded_accounts: usize = message
+            .address_table_lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+            .sum();
+        if message.account_keys.len() + loaded_accounts > self.max_accounts {
+            return None;
+        }
+        let signatures = usize::from(message.header.num_required_signatures);
+        let message_size = bincode::serialize(&VersionedMessage::V0(message.clone()))
+            .ok()?
+            .len();
+        // the signatures are prefixed by their compact u16 count, one byte up to 127
+        if 1 + signatures * SIGNATURE_SIZE + message_size > PACKET_DATA_SIZE {
+            return None;
+        }
+        Some(message)
+    }
+
+    /// Split the instructions into transactions, in order. Fails if a single group doesn't fit
+    /// in a transaction.
+    pub fn build(&self, blockhash: Hash) -> Result<Vec<UnsignedTransaction>> {
+        let prefix = self.prefix_instructions();
+        let mut transactions = Vec::new();
+        let mut instructions = prefix.clone();
+        let mut message: Option<v0::Message> = None;
+        for (i, group) in self.groups.iter().enumerate() {
+            let mut candidate = instructions.clone();
+            candidate.extend(group.iter().cloned());
+            if let Some(compiled) = self.compile(&candidate, blockhash) {
+                instructions = candidate;
+                message = Some(compiled);
+                continue;
+            }
+            // close the current transaction and start the next one with the group
+            if let Some(message) = message.take() {
+                transactions.push(UnsignedTransaction { message });
+            }
+            instructions = prefix.clone();
+            instructions.extend(group.iter().cloned());
+            message = Some(self.compile(&instructions, blockhash).ok_or_else(|| {
+                format_err!("instruction group {} doesn't fit in a transaction", i)
+            })?);
+        }
+        if let Some(message) = message {
+            transactions.push(UnsignedTransaction { message });
+        }
+        Ok(transactions)
+    }
+
+    /// Build and sign all the transactions, each signer signs the transactions requiring it
+    pub fn build_signed<T: Signers + ?Sized>(
+        &self,
+        blockhash: Hash,
+        signers: &T,
+    ) -> Result<Vec<VersionedTransaction>> {
+        let keys = signers.pubkeys();
+        let mut transactions = Vec::new();
+        for transaction in self.build(blockhash)? {
+            let mut signatures = Vec::new();
+            for signer in transaction.signers() {
+                let position = keys
+                    .iter()
+                    .position(|key| key == signer)
+                    .ok_or_else(|| format_err!("missing the signer {}", signer))?;
+                signatures.push(position);
+            }
+            let message = VersionedMessage::V0(transaction.message.clone());
+            let all_signatures: Vec<Signature> = signers.try_sign_message(&message.serialize())?;
+            transactions.push(VersionedTransaction {
+                signatures: signatures
+                    .into_iter()
+                    .map(|position| all_signatures[position])
+                    .collect(),
+                message,
+            });
+        }
+        Ok(transactions)
+    }
+}
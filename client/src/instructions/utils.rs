@@ -1,8 +1,8 @@
 use anchor_client::solana_client::rpc_client::RpcClient;
 use anchor_client::solana_sdk::program_pack::Pack;
 use anchor_client::solana_sdk::{account::Account, pubkey::Pubkey};
-use anchor_lang::AccountDeserialize;
-use anyhow::Result;
+use anchor_lang::{AccountDeserialize, ZeroCopy};
+use anyhow::{format_err, Result};
 use raydium_amm_v3::libraries::fixed_point_64;
 use raydium_amm_v3::libraries::*;
 use raydium_amm_v3::states::*;
@@ -20,16 +20,67 @@ use spl_token_2022::{
         transfer_fee::{TransferFeeAmount, TransferFeeConfig, MAX_FEE_BASIS_POINTS},
         BaseState, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
     },
-    state::Mint,
+    state::{AccountState, Mint},
 };
 use std::collections::VecDeque;
 use std::ops::{DerefMut, Mul, Neg};
 
+use super::async_rpc::{async_rpc, get_multiple_accounts_async};
+
+// getMultipleAccounts accepts at most 100 accounts per request
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Fetch any number of accounts in requests of at most MAX_MULTIPLE_ACCOUNTS and return them in
+/// the order of the keys. With `concurrent` the requests are sent together, through the async rpc
+/// when it is enabled.
+pub fn get_multiple_accounts_chunked(
+    rpc_client: &RpcClient,
+    pubkeys: &[Pubkey],
+    concurrent: bool,
+) -> Result<Vec<Option<Account>>> {
+    let mut accounts = Vec::with_capacity(pubkeys.len());
+    if !concurrent || pubkeys.len() <= MAX_MULTIPLE_ACCOUNTS {
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            accounts.extend(rpc_client.get_multiple_accounts(chunk)?);
+        }
+        return Ok(accounts);
+    }
+    if let Some(async_rpc) = async_rpc() {
+        return async_rpc.block_on(get_multiple_accounts_async(async_rpc.client(), pubkeys));
+    }
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = pubkeys
+            .chunks(MAX_MULTIPLE_ACCOUNTS)
+            .map(|chunk| scope.spawn(move || rpc_client.get_multiple_accounts(chunk)))
+            .collect();
+        for handle in handles {
+            accounts.extend(handle.join().unwrap()?);
+        }
+        Ok(accounts)
+    })
+}
+
 pub fn deserialize_anchor_account<T: AccountDeserialize>(account: &Account) -> Result<T> {
     let mut data: &[u8] = &account.data;
     T::try_deserialize(&mut data).map_err(Into::into)
 }
 
+/// Borrow a zero copy account from the data fetched, without copying it as
+/// `deserialize_anchor_account` does. For the bulk loads of the large accounts, e.g. the tick
+/// arrays of a depth scan.
+pub fn zero_copy_account<T: ZeroCopy>(data: &[u8]) -> Result<&T> {
+    let discriminator_len = T::DISCRIMINATOR.len();
+    if !data.starts_with(&T::DISCRIMINATOR) {
+        return Err(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    data.get(discriminator_len..discriminator_len + std::mem::size_of::<T>())
+        .and_then(|data| bytemuck::try_from_bytes(data).ok())
+        .ok_or(format_err!(
+            "account of {} bytes too short for its type",
+            data.len()
+        ))
+}
+
 #[derive(Debug)]
 pub enum ExtensionStruct {
     ConfidentialTransferAccount(ConfidentialTransferAccount),
@@ -80,12 +131,20 @@ pub fn get_pool_mints_inverse_fee(
         TransferFeeInfo {
             mint: token_mint_0,
             owner: mint0_account.owner,
-            transfer_fee: get_transfer_inverse_fee(&mint0_state, post_fee_amount_0, epoch),
+            transfer_fee: get_transfer_inverse_fee_with_next_epoch(
+                &mint0_state,
+                epoch,
+                post_fee_amount_0,
+            ),
         },
         TransferFeeInfo {
             mint: token_mint_1,
             owner: mint1_account.owner,
-            transfer_fee: get_transfer_inverse_fee(&mint1_state, post_fee_amount_1, epoch),
+            transfer_fee: get_transfer_inverse_fee_with_next_epoch(
+                &mint1_state,
+                epoch,
+                post_fee_amount_1,
+            ),
         },
     )
 }
@@ -108,12 +167,12 @@ pub fn get_pool_mints_transfer_fee(
         TransferFeeInfo {
             mint: token_mint_0,
             owner: mint0_account.owner,
-            transfer_fee: get_transfer_fee(&mint0_state, epoch, pre_fee_amount_0),
+            transfer_fee: get_transfer_fee_with_next_epoch(&mint0_state, epoch, pre_fee_amount_0),
         },
         TransferFeeInfo {
             mint: token_mint_1,
             owner: mint1_account.owner,
-            transfer_fee: get_transfer_fee(&mint1_state, epoch, pre_fee_amount_1),
+            transfer_fee: get_transfer_fee_with_next_epoch(&mint1_state, epoch, pre_fee_amount_1),
         },
     )
 }
@@ -155,6 +214,34 @@ pub fn get_transfer_fee<'data, S: BaseState + Pack>(
     fee
 }
 
+/// Calculate the fee for output amount with the larger fee of the current and next epoch.
+/// A newer transfer fee config takes effect at an epoch boundary, so the quote is still
+/// valid if the transaction lands after the boundary.
+pub fn get_transfer_inverse_fee_with_next_epoch<'data, S: BaseState + Pack>(
+    account_state: &StateWithExtensions<'data, S>,
+    epoch: u64,
+    post_fee_amount: u64,
+) -> u64 {
+    get_transfer_inverse_fee(account_state, epoch, post_fee_amount).max(get_transfer_inverse_fee(
+        account_state,
+        epoch + 1,
+        post_fee_amount,
+    ))
+}
+
+/// Calculate the fee for input amount with the larger fee of the current and next epoch.
+pub fn get_transfer_fee_with_next_epoch<'data, S: BaseState + Pack>(
+    account_state: &StateWithExtensions<'data, S>,
+    epoch: u64,
+    pre_fee_amount: u64,
+) -> u64 {
+    get_transfer_fee(account_state, epoch, pre_fee_amount).max(get_transfer_fee(
+        account_state,
+        epoch + 1,
+        pre_fee_amount,
+    ))
+}
+
 pub fn get_account_extensions<'data, S: BaseState + Pack>(
     account_state: &StateWithExtensions<'data, S>,
 ) -> Vec<ExtensionStruct> {
@@ -233,6 +320,81 @@ pub fn get_account_extensions<'data, S: BaseState + Pack>(
     extensions
 }
 
+/// Validate a token22 mint before it is supported by the program, the extensions which make the
+/// pool vaults unusable are rejected. Returns the extensions of the mint.
+pub fn check_support_mint_extensions(mint_account: &Account) -> Result<Vec<ExtensionType>> {
+    if mint_account.owner != spl_token_2022::id() {
+        anyhow::bail!("only token22 mints need to be supported");
+    }
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data)?;
+    let extension_types = mint.get_extension_types()?;
+    for extension_type in extension_types.iter() {
+        match extension_type {
+            ExtensionType::NonTransferable => {
+                anyhow::bail!("a non transferable mint can't be deposited into the pool vaults")
+            }
+            // the hook accounts are not passed along the transfers of the pool
+            ExtensionType::TransferHook => {
+                anyhow::bail!("a mint with a transfer hook can't be transferred by the pool")
+            }
+            ExtensionType::DefaultAccountState => {
+                let default_account_state = mint.get_extension::<DefaultAccountState>()?;
+                if default_account_state.state == AccountState::Frozen as u8 {
+                    anyhow::bail!("the pool vaults of the mint would be created frozen");
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(extension_types)
+}
+
+/// Names of the pool status bits, a set bit disables the capability
+pub const POOL_STATUS_CAPABILITIES: [(&str, PoolStatusBitIndex); 5] = [
+    (
+        "deposit",
+        PoolStatusBitIndex::OpenPositionOrIncreaseLiquidity,
+    ),
+    ("withdraw", PoolStatusBitIndex::DecreaseLiquidity),
+    ("collect_fee", PoolStatusBitIndex::CollectFee),
+    ("collect_reward", PoolStatusBitIndex::CollectReward),
+    ("swap", PoolStatusBitIndex::Swap),
+];
+
+/// Decode the pool status into each named capability and whether it is enabled
+pub fn decode_pool_status(status: u8) -> Vec<(&'static str, bool)> {
+    POOL_STATUS_CAPABILITIES
+        .into_iter()
+        .map(|(name, bit)| (name, status & (1u8 << bit as u8) == 0))
+        .collect()
+}
+
+/// Parse the pool status from either the raw bits or a comma separated list of the capabilities
+/// to disable, e.g. "swap,deposit". "none" enables all the capabilities.
+pub fn parse_pool_status(flags: &str) -> Result<u8> {
+    if let Ok(status) = flags.parse::<u8>() {
+        return Ok(status);
+    }
+    let mut status = 0u8;
+    for flag in flags.split(',').map(str::trim) {
+        if flag == "none" {
+            continue;
+        }
+        let (_, bit) = POOL_STATUS_CAPABILITIES
+            .into_iter()
+            .find(|(name, _)| *name == flag)
+            .ok_or_else(|| {
+                anyhow::format_err!(
+                    "unknown pool capability {}, expected one of {:?}",
+                    flag,
+                    POOL_STATUS_CAPABILITIES.map(|(name, _)| name)
+                )
+            })?;
+        status |= 1u8 << bit as u8;
+    }
+    Ok(status)
+}
+
 pub const Q_RATIO: f64 = 1.0001;
 
 pub fn tick_to_price(tick: i32) -> f64 {
@@ -255,6 +417,42 @@ pub fn tick_with_spacing(tick: i32, tick_spacing: i32) -> i32 {
     compressed * tick_spacing
 }
 
+/// Round the price to the nearest tick that is a multiple of `tick_spacing`,
+/// rounding down when `round_up` is false and up otherwise.
+/// Return the valid tick and the exact price of that tick.
+pub fn price_to_tick_with_spacing(
+    price: f64,
+    decimals_0: u8,
+    decimals_1: u8,
+    tick_spacing: u16,
+    round_up: bool,
+) -> Result<(i32, f64)> {
+    let tick_spacing = i32::from(tick_spacing);
+    let sqrt_price_x64 = price_to_sqrt_price_x64(price, decimals_0, decimals_1)
+        .max(tick_math::MIN_SQRT_PRICE_X64)
+        .min(tick_math::MAX_SQRT_PRICE_X64 - 1);
+    let mut tick = tick_with_spacing(
+        tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?,
+        tick_spacing,
+    );
+    if round_up && tick_math::get_sqrt_price_at_tick(tick)? < sqrt_price_x64 {
+        tick += tick_spacing;
+    }
+    // keep the tick inside the range which can be used to open a position
+    let mut min_tick = tick_with_spacing(tick_math::MIN_TICK, tick_spacing);
+    if min_tick < tick_math::MIN_TICK {
+        min_tick += tick_spacing;
+    }
+    let max_tick = tick_with_spacing(tick_math::MAX_TICK, tick_spacing);
+    let tick = tick.max(min_tick).min(max_tick);
+    let price = sqrt_price_x64_to_price(
+        tick_math::get_sqrt_price_at_tick(tick)?,
+        decimals_0,
+        decimals_1,
+    );
+    Ok((tick, price))
+}
+
 pub fn multipler(decimals: u8) -> f64 {
     (10_i32).checked_pow(decimals.try_into().unwrap()).unwrap() as f64
 }
@@ -308,6 +506,39 @@ struct StepComputations {
     fee_amount: u64,
 }
 
+/// One step of a quoted swap, the swap moves the price from the start tick toward the next
+/// initialized tick with the liquidity in effect in between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub tick_start: i32,
+    pub tick_next: i32,
+    pub liquidity: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub sqrt_price_after_x64: u128,
+    /// Whether the step ends on the initialized next tick and changes the liquidity
+    pub crossed: bool,
+}
+
+/// Print the steps of a quoted swap, the price after each step is decimal adjusted
+pub fn print_swap_trace(steps: &[SwapStep], decimals_0: u8, decimals_1: u8) {
+    for (i, step) in steps.iter().enumerate() {
+        println!(
+            "step:{}, ticks:{}->{}, liquidity:{}, amount_in:{}, amount_out:{}, fee:{}, price after:{}{}",
+            i,
+            step.tick_start,
+            step.tick_next,
+            step.liquidity,
+            step.amount_in,
+            step.amount_out,
+            step.fee_amount,
+            sqrt_price_x64_to_price(step.sqrt_price_after_x64, decimals_0, decimals_1),
+            if step.crossed { ", crossed" } else { "" }
+        );
+    }
+}
+
 pub fn get_out_put_amount_and_remaining_accounts(
     input_amount: u64,
     sqrt_price_limit_x64: Option<u128>,
@@ -318,10 +549,35 @@ pub fn get_out_put_amount_and_remaining_accounts(
     tickarray_bitmap_extension: &TickArrayBitmapExtension,
     tick_arrays: &mut VecDeque<TickArrayState>,
 ) -> Result<(u64, VecDeque<i32>), &'static str> {
+    let (amount_calculated, tick_array_start_index_vec, _) = get_out_put_amount_with_trace(
+        input_amount,
+        sqrt_price_limit_x64,
+        zero_for_one,
+        is_base_input,
+        pool_config,
+        pool_state,
+        tickarray_bitmap_extension,
+        tick_arrays,
+    )?;
+    Ok((amount_calculated, tick_array_start_index_vec))
+}
+
+/// Same as `get_out_put_amount_and_remaining_accounts`, with the steps of the swap
+pub fn get_out_put_amount_with_trace(
+    input_amount: u64,
+    sqrt_price_limit_x64: Option<u128>,
+    zero_for_one: bool,
+    is_base_input: bool,
+    pool_config: &AmmConfig,
+    pool_state: &PoolState,
+    tickarray_bitmap_extension: &TickArrayBitmapExtension,
+    tick_arrays: &mut VecDeque<TickArrayState>,
+) -> Result<(u64, VecDeque<i32>, Vec<SwapStep>), &'static str> {
     let (is_pool_current_tick_array, current_valid_tick_array_start_index) = pool_state
         .get_first_initialized_tick_array(&Some(*tickarray_bitmap_extension), zero_for_one)
         .unwrap();
 
+    let mut steps = Vec::new();
     let (amount_calculated, tick_array_start_index_vec) = swap_compute(
         zero_for_one,
         is_base_input,
@@ -333,10 +589,11 @@ pub fn get_out_put_amount_and_remaining_accounts(
         pool_state,
         tickarray_bitmap_extension,
         tick_arrays,
+        &mut steps,
     )?;
     println!("tick_array_start_index:{:?}", tick_array_start_index_vec);
 
-    Ok((amount_calculated, tick_array_start_index_vec))
+    Ok((amount_calculated, tick_array_start_index_vec, steps))
 }
 
 fn swap_compute(
@@ -350,6 +607,7 @@ fn swap_compute(
     pool_state: &PoolState,
     tickarray_bitmap_extension: &TickArrayBitmapExtension,
     tick_arrays: &mut VecDeque<TickArrayState>,
+    steps: &mut Vec<SwapStep>,
 ) -> Result<(u64, VecDeque<i32>), &'static str> {
     if amount_specified == 0 {
         return Result::Err("amountSpecified must not be 0");
@@ -388,7 +646,11 @@ fn swap_compute(
         liquidity: pool_state.liquidity,
     };
 
-    let mut tick_array_current = tick_arrays.pop_front().unwrap();
+    // every step ends at an initialized tick or at the boundary of the loaded tick arrays
+    let max_loop_count = tick_arrays.len() * (TICK_ARRAY_SIZE_USIZE + 1);
+    let mut tick_array_current = tick_arrays
+        .pop_front()
+        .ok_or("not enough tick arrays to complete the swap")?;
     if tick_array_current.start_tick_index != current_valid_tick_array_start_index {
         return Result::Err("tick array start tick index does not match");
     }
@@ -401,7 +663,7 @@ fn swap_compute(
         && state.tick < tick_math::MAX_TICK
         && state.tick > tick_math::MIN_TICK
     {
-        if loop_count > 10 {
+        if loop_count > max_loop_count {
             return Result::Err("loop_count limit");
         }
         let mut step = StepComputations::default();
@@ -432,7 +694,9 @@ fn swap_compute(
                     zero_for_one,
                 )
                 .unwrap();
-            tick_array_current = tick_arrays.pop_front().unwrap();
+            tick_array_current = tick_arrays
+                .pop_front()
+                .ok_or("not enough tick arrays to complete the swap")?;
             if current_valid_tick_array_start_index.is_none() {
                 return Result::Err("tick array start tick index out of range limit");
             }
@@ -500,6 +764,17 @@ fn swap_compute(
                 .unwrap();
         }
 
+        steps.push(SwapStep {
+            tick_start: state.tick,
+            tick_next: step.tick_next,
+            liquidity: state.liquidity,
+            amount_in: step.amount_in,
+            amount_out: step.amount_out,
+            fee_amount: step.fee_amount,
+            sqrt_price_after_x64: state.sqrt_price_x64,
+            crossed: step.initialized && state.sqrt_price_x64 == step.sqrt_price_next_x64,
+        });
+
         if state.sqrt_price_x64 == step.sqrt_price_next_x64 {
             // if the tick is initialized, run the tick transition
             if step.initialized {
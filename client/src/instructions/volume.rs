@@ -0,0 +1,135 @@
+use anchor_client::solana_client::rpc_client::{
+    GetConfirmedSignaturesForAddress2Config, RpcClient,
+};
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+};
+use anyhow::Result;
+use raydium_amm_v3::states::FEE_RATE_DENOMINATOR_VALUE;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use super::indexer::{
+    get_transactions_throttled, record_transaction_events, IndexedEvent, IndexedEventData,
+    PoolIndex,
+};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// The swap events of the pool in the transactions since the slot, oldest first. The signatures
+/// of the pool are paged backwards until one older than `since_slot`.
+pub fn load_pool_swaps(
+    rpc_client: &RpcClient,
+    program_str: &str,
+    pool_id: &Pubkey,
+    since_slot: u64,
+    limit: usize,
+    concurrency: usize,
+    requests_per_second: u32,
+) -> Result<Vec<IndexedEvent>> {
+    let mut signatures = Vec::new();
+    let mut before = None;
+    loop {
+        let page = rpc_client.get_signatures_for_address_with_config(
+            pool_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(limit),
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )?;
+        let page_len = page.len();
+        let last = page
+            .last()
+            .map(|signature_info| signature_info.signature.clone());
+        let mut reached_since_slot = false;
+        for signature_info in page {
+            if signature_info.slot < since_slot {
+                reached_since_slot = true;
+                break;
+            }
+            if signature_info.err.is_none() {
+                signatures.push(signature_info.signature);
+            }
+        }
+        match last {
+            Some(last) if !reached_since_slot && page_len >= limit => {
+                before = Some(Signature::from_str(&last)?)
+            }
+            _ => break,
+        }
+    }
+
+    let txs =
+        get_transactions_throttled(rpc_client, &signatures, concurrency, requests_per_second)?;
+    let mut pool_index = PoolIndex::default();
+    for (signature, tx) in signatures.iter().zip(txs) {
+        record_transaction_events(program_str, &mut pool_index, signature, tx)?;
+    }
+    pool_index.events.sort_by_key(|event| event.slot);
+    // a route swaps through other pools in the same transaction
+    let pool_str = pool_id.to_string();
+    Ok(pool_index
+        .events
+        .into_iter()
+        .filter(|indexed| {
+            matches!(&indexed.event, IndexedEventData::Swap { pool_state, .. } if *pool_state == pool_str)
+        })
+        .collect())
+}
+
+/// The swaps of a pool in one UTC day, raw amounts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DailyVolume {
+    /// Days since the unix epoch
+    pub day: i64,
+    pub swaps: u64,
+    /// Amounts swapped in of each token
+    pub volume_0: u128,
+    pub volume_1: u128,
+    /// Trade fees paid in each token, the protocol and fund shares included
+    pub fees_0: u128,
+    pub fees_1: u128,
+}
+
+/// Aggregate the swap events by UTC day, oldest first. The fee of a swap is charged on its input
+/// amount at the trade fee rate of the pool. Events without a block time are left out.
+pub fn daily_volumes(events: &[IndexedEvent], trade_fee_rate: u32) -> Vec<DailyVolume> {
+    let mut days: BTreeMap<i64, DailyVolume> = BTreeMap::new();
+    for indexed in events {
+        let (amount_0, amount_1, zero_for_one) = match &indexed.event {
+            IndexedEventData::Swap {
+                amount_0,
+                amount_1,
+                zero_for_one,
+                ..
+            } => (*amount_0, *amount_1, *zero_for_one),
+            _ => continue,
+        };
+        let block_time = match indexed.block_time {
+            Some(block_time) => block_time,
+            None => continue,
+        };
+        let day = block_time.div_euclid(SECONDS_PER_DAY);
+        let daily = days.entry(day).or_insert(DailyVolume {
+            day,
+            ..Default::default()
+        });
+        daily.swaps += 1;
+        if zero_for_one {
+            daily.volume_0 += u128::from(amount_0);
+            daily.fees_0 += swap_fee(amount_0, trade_fee_rate);
+        } else {
+            daily.volume_1 += u128::from(amount_1);
+            daily.fees_1 += swap_fee(amount_1, trade_fee_rate);
+        }
+    }
+    days.into_values().collect()
+}
+
+// the fee rounds up, as the pool charges it
+fn swap_fee(amount_in: u64, trade_fee_rate: u32) -> u128 {
+    let denominator = u128::from(FEE_RATE_DENOMINATOR_VALUE);
+    (u128::from(amount_in) * u128::from(trade_fee_rate) + denominator - 1) / denominator
+}
@@ -1,59 +1,125 @@
 #![allow(dead_code)]
 use anchor_client::solana_account_decoder::{
     parse_token::{TokenAccountType, UiAccountState},
+    parse_token_extension::UiExtension,
     UiAccountData, UiAccountEncoding,
 };
 use anchor_client::solana_client::{
     rpc_client::RpcClient,
-    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+    rpc_config::{
+        RpcAccountInfoConfig, RpcBlockConfig, RpcProgramAccountsConfig, RpcTransactionConfig,
+    },
     rpc_filter::{Memcmp, RpcFilterType},
     rpc_request::TokenAccountsFilter,
 };
 use anchor_client::solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
     commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
     message::Message,
     program_pack::Pack,
     pubkey::Pubkey,
     signature::{Keypair, Signature, Signer},
-    transaction::Transaction,
+    signer::null_signer::NullSigner,
+    signers::Signers,
+    system_instruction,
+    transaction::VersionedTransaction,
 };
 use anchor_client::{Client, Cluster};
 use anchor_lang::prelude::AccountMeta;
 use anyhow::{format_err, Result};
 use arrayref::array_ref;
 use clap::Parser;
-use configparser::ini::Ini;
-use solana_transaction_status::UiTransactionEncoding;
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedTransaction, TransactionDetails,
+    UiTransactionEncoding,
+};
 use std::path::Path;
-use std::rc::Rc;
 use std::str::FromStr;
-use std::{collections::VecDeque, convert::identity, mem::size_of};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    convert::identity,
+    mem::size_of,
+    sync::{Arc, OnceLock},
+};
 
 mod instructions;
+mod state;
 use bincode::serialize;
 use instructions::amm_instructions::*;
+use instructions::apr::*;
+use instructions::async_rpc::{async_rpc, enable_async_rpc};
+use instructions::bitmap::*;
+use instructions::cache::{AccountCache, SlotCache};
+use instructions::compute_units::{
+    enable_simulated_compute_unit_limit, with_simulated_compute_unit_limit,
+};
+use instructions::confirm::ConfirmError;
+#[cfg(feature = "sqlite")]
+use instructions::event_db::EventDb;
 use instructions::events_instructions_parse::*;
+use instructions::export::{enable_export_mode, export_path};
+use instructions::failover::{FailoverSender, RetryPolicy};
+use instructions::health::HealthMonitor;
+use instructions::indexer::*;
+use instructions::jito::{send_bundle_and_confirm, JitoClient};
+use instructions::journal::{Journal, JournalStatus};
+use instructions::jupiter::*;
+use instructions::keeper::*;
+use instructions::ladder::*;
+use instructions::liquidity::*;
+use instructions::localnet::*;
+use instructions::lookup_table::*;
+use instructions::metrics::*;
+use instructions::monitor::*;
+use instructions::plan::{enable_plan_mode, plan_mode_program};
+use instructions::pools::*;
+use instructions::portfolio::*;
+use instructions::position_history::*;
+use instructions::priority_fee::*;
+use instructions::profile::*;
+use instructions::quote::*;
+use instructions::quote_server::*;
+use instructions::replay::*;
+use instructions::revenue::*;
 use instructions::rpc::*;
+use instructions::scan::*;
+use instructions::settings::{Cluster, Settings};
+use instructions::signer::{is_interactive_source, read_keypair, SharedSigner};
+use instructions::squads::*;
+use instructions::tax_export::*;
+use instructions::ticker::*;
 use instructions::token_instructions::*;
+use instructions::twap::*;
+use instructions::tx_builder::{sign_instructions, TransactionBuilder, TransactionVersion};
 use instructions::utils::*;
+use instructions::volume::*;
 use raydium_amm_v3::{
     libraries::{fixed_point_64, liquidity_math, tick_math},
     states::{PoolState, TickArrayBitmapExtension, TickArrayState, POOL_TICK_ARRAY_BITMAP_SEED},
 };
 use spl_associated_token_account::get_associated_token_address;
 use spl_token_2022::{
-    extension::StateWithExtensions,
+    extension::{ExtensionType, StateWithExtensions},
     state::Mint,
     state::{Account, AccountState},
 };
 use spl_token_client::token::ExtensionInitializationParams;
 
 use crate::instructions::utils;
+use state::*;
 #[derive(Clone, Debug, PartialEq)]
 pub struct ClientConfig {
+    /// The first of the configured endpoints
     http_url: String,
     ws_url: String,
+    /// All the configured endpoints, the rpc requests and subscriptions fail over between them
+    http_urls: Vec<String>,
+    ws_urls: Vec<String>,
+    /// How the rpc requests failing on every endpoint are retried
+    rpc_retry: RetryPolicy,
     payer_path: String,
     admin_path: String,
     raydium_v3_program: Pubkey,
@@ -65,6 +131,45 @@ pub struct ClientConfig {
     pool_id_account: Option<Pubkey>,
     tickarray_bitmap_extension: Option<Pubkey>,
     amm_config_index: u16,
+    /// Set when the config has the mints but no amm config index, the pool of the mints is
+    /// discovered from all the configs on first use, shared by the clones of the config
+    pool_discovery: Option<Arc<OnceLock<Option<(u16, Pubkey)>>>>,
+
+    cache_dir: String,
+    cache_ttl: u64,
+    /// Slots after which the daemons fetch again an account of the slot cache
+    slot_cache_max_age: u64,
+    index_dir: String,
+    journal_dir: String,
+    owner: Option<Pubkey>,
+    priority_fee: PriorityFeeEscalation,
+    /// Percentile of the recent prioritization fees to price the transactions with, 0 to disable
+    priority_fee_percentile: u8,
+    /// Percentage added to the simulated compute units for the compute unit limit, 0 to disable
+    compute_unit_margin_percent: u32,
+    /// The swap and position transactions are v0 ones resolving their accounts through these
+    lookup_tables: Vec<Pubkey>,
+    /// The version of the transactions the commands sign, v0 with lookup tables
+    transaction_version: TransactionVersion,
+    /// The transactions use the durable nonce of this account instead of a recent blockhash
+    nonce_account: Option<Pubkey>,
+    /// The admin instructions are proposed to this squads multisig, whose vault is the admin
+    squads_multisig: Option<Pubkey>,
+    squads_vault_index: u8,
+    /// The yellowstone grpc endpoint mirroring the pool accounts, with its access token
+    geyser_url: Option<String>,
+    geyser_x_token: Option<String>,
+    /// The jupiter quote api the swaps are compared with, and how much worse a pool quote may be
+    jupiter_url: String,
+    jupiter_max_shortfall_bps: u64,
+    /// The jito block engine the swaps with --jito are bundled to, and the lamports they tip
+    jito_url: String,
+    jito_tip_lamports: u64,
+    /// The sqlite database Index writes the events to, the reports read them from it when set
+    index_db: Option<String>,
+    /// Sign instead of the keypair files when set
+    payer_signer: Option<SharedSigner>,
+    admin_signer: Option<SharedSigner>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
@@ -77,59 +182,227 @@ pub struct PoolAccounts {
     pool_tick_arrays: Vec<Pubkey>,
 }
 
-fn load_cfg(client_config: &String) -> Result<ClientConfig> {
-    let mut config = Ini::new();
-    let _map = config.load(client_config).unwrap();
-    let http_url = config.get("Global", "http_url").unwrap();
-    if http_url.is_empty() {
-        panic!("http_url must not be empty");
+impl ClientConfig {
+    /// The websocket endpoint of the attempt, a subscription which failed reconnects to the next
+    pub fn ws_endpoint(&self, attempt: usize) -> &str {
+        &self.ws_urls[attempt % self.ws_urls.len()]
+    }
+
+    /// The payer, the keypair of `payer_path` unless another signer is set
+    pub fn payer(&self) -> Result<SharedSigner> {
+        match &self.payer_signer {
+            Some(signer) => Ok(signer.clone()),
+            None => Ok(SharedSigner::new(read_keypair(&self.payer_path)?)),
+        }
+    }
+
+    /// The admin, the keypair of `admin_path` unless another signer is set
+    pub fn admin(&self) -> Result<SharedSigner> {
+        match &self.admin_signer {
+            Some(signer) => Ok(signer.clone()),
+            None => Ok(SharedSigner::new(read_keypair(&self.admin_path)?)),
+        }
+    }
+
+    /// The vault of the squads multisig holding the admin authority, when one is configured
+    pub fn squads_vault(&self) -> Option<Pubkey> {
+        self.squads_multisig
+            .map(|multisig| squads_vault(&multisig, self.squads_vault_index))
     }
-    let ws_url = config.get("Global", "ws_url").unwrap();
-    if ws_url.is_empty() {
-        panic!("ws_url must not be empty");
+
+    /// The config signing with any signers, e.g. remote signers or hardware wallets
+    pub fn with_signers(&self, payer: SharedSigner, admin: SharedSigner) -> ClientConfig {
+        ClientConfig {
+            payer_signer: Some(payer),
+            admin_signer: Some(admin),
+            ..self.clone()
+        }
     }
-    let payer_path = config.get("Global", "payer_path").unwrap();
-    if payer_path.is_empty() {
-        panic!("payer_path must not be empty");
+
+    /// The config with the pool accounts of another pool of the program
+    fn with_pool(&self, pool_id: Pubkey, pool_state: &PoolState) -> ClientConfig {
+        ClientConfig {
+            amm_config_key: pool_state.amm_config,
+            mint0: Some(pool_state.token_mint_0),
+            mint1: Some(pool_state.token_mint_1),
+            pool_id_account: Some(pool_id),
+            tickarray_bitmap_extension: Some(tickarray_bitmap_extension_key(
+                &self.raydium_v3_program,
+                &pool_id,
+            )),
+            pool_discovery: None,
+            ..self.clone()
+        }
     }
-    let admin_path = config.get("Global", "admin_path").unwrap();
-    if admin_path.is_empty() {
-        panic!("admin_path must not be empty");
+
+    /// The amm config index and the pool discovered for the mints, through the failover
+    /// endpoints, the first time a command uses the configured pool. None when the config sets the
+    /// amm config index or when no pool is found.
+    fn discovered_pool(&self) -> Option<(u16, Pubkey)> {
+        let pool_discovery = self.pool_discovery.as_ref()?;
+        *pool_discovery.get_or_init(|| {
+            let (mint0, mint1) = (self.mint0?, self.mint1?);
+            let rpc_client = RpcClient::new_sender(
+                FailoverSender::new(&self.http_urls, self.rpc_retry, false),
+                anchor_client::solana_client::rpc_client::RpcClientConfig::with_commitment(
+                    CommitmentConfig::default(),
+                ),
+            );
+            match discover_pool(&rpc_client, &self.raydium_v3_program, &mint0, &mint1) {
+                Ok(pool) => pool,
+                Err(err) => {
+                    println!("no pool discovered for {} and {}: {}", mint0, mint1, err);
+                    None
+                }
+            }
+        })
     }
 
-    let raydium_v3_program_str = config.get("Global", "raydium_v3_program").unwrap();
-    if raydium_v3_program_str.is_empty() {
-        panic!("raydium_v3_program must not be empty");
+    /// The amm config of the configured pool
+    pub fn amm_config_key(&self) -> Pubkey {
+        match self.discovered_pool() {
+            Some((index, _)) => amm_config_key(&self.raydium_v3_program, index),
+            None => self.amm_config_key,
+        }
     }
-    let raydium_v3_program = Pubkey::from_str(&raydium_v3_program_str).unwrap();
-    let slippage = config.getfloat("Global", "slippage").unwrap().unwrap();
 
-    let mut mint0 = None;
-    let mint0_str = config.get("Pool", "mint0").unwrap();
-    if !mint0_str.is_empty() {
-        mint0 = Some(Pubkey::from_str(&mint0_str).unwrap());
+    /// The configured pool, the one of the mints and of the amm config index
+    pub fn pool_id_account(&self) -> Option<Pubkey> {
+        match self.discovered_pool() {
+            Some((_, pool_id)) => Some(pool_id),
+            None => self.pool_id_account,
+        }
     }
-    let mut mint1 = None;
-    let mint1_str = config.get("Pool", "mint1").unwrap();
-    if !mint1_str.is_empty() {
-        mint1 = Some(Pubkey::from_str(&mint1_str).unwrap());
+
+    /// The tick array bitmap extension of the configured pool
+    pub fn tickarray_bitmap_extension(&self) -> Option<Pubkey> {
+        match self.discovered_pool() {
+            Some((_, pool_id)) => Some(tickarray_bitmap_extension_key(
+                &self.raydium_v3_program,
+                &pool_id,
+            )),
+            None => self.tickarray_bitmap_extension,
+        }
     }
-    let amm_config_index = config.getuint("Pool", "amm_config_index").unwrap().unwrap() as u16;
 
-    let (amm_config_key, __bump) = Pubkey::find_program_address(
-        &[
-            raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
-            &amm_config_index.to_be_bytes(),
-        ],
-        &raydium_v3_program,
-    );
+    /// The max number of tick arrays a swap passes, all of them only fit a v0 transaction
+    /// resolving its accounts through the lookup tables
+    pub fn max_swap_tick_array_num(&self) -> usize {
+        if self.transaction_version == TransactionVersion::V0 && !self.lookup_tables.is_empty() {
+            MAX_SWAP_TICK_ARRAY_NUM
+        } else {
+            MAX_LEGACY_SWAP_TICK_ARRAY_NUM
+        }
+    }
+}
 
-    let pool_id_account = if mint0 != None && mint1 != None {
-        if mint0.unwrap() > mint1.unwrap() {
-            let temp_mint = mint0;
-            mint0 = mint1;
-            mint1 = temp_mint;
+fn load_cfg(
+    client_config: &str,
+    pool_profile: Option<&str>,
+    cluster: Option<Cluster>,
+    overrides: &[String],
+) -> Result<ClientConfig> {
+    let config = Settings::load(client_config, pool_profile, cluster, overrides)?;
+    // comma separated endpoints, the first one is tried first
+    let http_urls: Vec<String> = config.parse_list("Global", "http_url")?;
+    if http_urls.is_empty() {
+        return Err(format_err!("http_url must be set in [Global]"));
+    }
+    let ws_urls: Vec<String> = config.parse_list("Global", "ws_url")?;
+    if ws_urls.is_empty() {
+        return Err(format_err!("ws_url must be set in [Global]"));
+    }
+    let (http_url, ws_url) = (http_urls[0].clone(), ws_urls[0].clone());
+    let rpc_retry = RetryPolicy {
+        max_attempts: config
+            .parse::<u32>("Global", "rpc_max_attempts")?
+            .unwrap_or(3)
+            .max(1),
+        base_delay_ms: config.parse("Global", "rpc_retry_base_ms")?.unwrap_or(500),
+        max_delay_ms: config
+            .parse("Global", "rpc_retry_max_ms")?
+            .unwrap_or(10_000),
+    };
+    let payer_path = config.required("Global", "payer_path")?;
+    let admin_path = config.required("Global", "admin_path")?;
+    let raydium_v3_program = config
+        .parse("Global", "raydium_v3_program")?
+        .ok_or(format_err!("raydium_v3_program must be set in [Global]"))?;
+    let slippage = config
+        .parse("Global", "slippage")?
+        .ok_or(format_err!("slippage must be set in [Global]"))?;
+    let cache_dir = config
+        .get("Global", "cache_dir")
+        .unwrap_or(".client_cache".to_string());
+    let cache_ttl = config.parse("Global", "cache_ttl")?.unwrap_or(24 * 60 * 60);
+    let slot_cache_max_age = config.parse("Global", "slot_cache_max_age")?.unwrap_or(2);
+    let index_dir = config
+        .get("Global", "index_dir")
+        .unwrap_or(".client_index".to_string());
+    let journal_dir = config
+        .get("Global", "journal_dir")
+        .unwrap_or(".client_journal".to_string());
+    let owner = config.parse("Global", "owner")?;
+    let priority_fee = PriorityFeeEscalation {
+        initial_compute_unit_price: config.parse("Global", "compute_unit_price")?.unwrap_or(0),
+        max_compute_unit_price: config
+            .parse("Global", "max_compute_unit_price")?
+            .unwrap_or(0),
+        bump_percent: config
+            .parse("Global", "priority_fee_bump_percent")?
+            .unwrap_or(25),
+        resubmit_slots: config
+            .parse("Global", "priority_fee_resubmit_slots")?
+            .unwrap_or(20),
+    };
+    let priority_fee_percentile = config
+        .parse("Global", "priority_fee_percentile")?
+        .unwrap_or(0);
+    let compute_unit_margin_percent = config
+        .parse("Global", "compute_unit_margin_percent")?
+        .unwrap_or(0);
+    let lookup_tables = config.parse_list("Global", "lookup_tables")?;
+    let transaction_version = config
+        .parse("Global", "transaction_version")?
+        .unwrap_or(TransactionVersion::Legacy);
+    let squads_multisig = config.parse("Global", "squads_multisig")?;
+    let squads_vault_index = config.parse("Global", "squads_vault_index")?.unwrap_or(0);
+    let geyser_url = config.get("Global", "geyser_url");
+    let geyser_x_token = config.get("Global", "geyser_x_token");
+    let jupiter_url = config
+        .get("Global", "jupiter_url")
+        .unwrap_or("https://quote-api.jup.ag/v6".to_string());
+    let jupiter_max_shortfall_bps = config
+        .parse("Global", "jupiter_max_shortfall_bps")?
+        .unwrap_or(50);
+    let jito_url = config
+        .get("Global", "jito_url")
+        .unwrap_or("https://mainnet.block-engine.jito.wtf".to_string());
+    let jito_tip_lamports = config
+        .parse("Global", "jito_tip_lamports")?
+        .unwrap_or(10000);
+    let index_db = config.get("Global", "index_db");
+
+    let mut mint0: Option<Pubkey> = config.parse("Pool", "mint0")?;
+    let mut mint1: Option<Pubkey> = config.parse("Pool", "mint1")?;
+    if let (Some(token_mint_0), Some(token_mint_1)) = (mint0, mint1) {
+        if token_mint_0 > token_mint_1 {
+            mint0 = Some(token_mint_1);
+            mint1 = Some(token_mint_0);
         }
+    }
+    let configured_amm_config_index: Option<u16> = config.parse("Pool", "amm_config_index")?;
+    // without a config index the pool of the pair is discovered from all the configs, when a
+    // command first uses it
+    let pool_discovery = match (configured_amm_config_index, mint0, mint1) {
+        (None, Some(_), Some(_)) => Some(Arc::new(OnceLock::new())),
+        _ => None,
+    };
+    let amm_config_index = configured_amm_config_index.unwrap_or_default();
+    let amm_config_key = amm_config_key(&raydium_v3_program, amm_config_index);
+
+    let pool_id_account = if mint0 != None && mint1 != None && configured_amm_config_index.is_some()
+    {
         Some(
             Pubkey::find_program_address(
                 &[
@@ -145,24 +418,15 @@ fn load_cfg(client_config: &String) -> Result<ClientConfig> {
     } else {
         None
     };
-    let tickarray_bitmap_extension = if pool_id_account != None {
-        Some(
-            Pubkey::find_program_address(
-                &[
-                    POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(),
-                    pool_id_account.unwrap().to_bytes().as_ref(),
-                ],
-                &raydium_v3_program,
-            )
-            .0,
-        )
-    } else {
-        None
-    };
+    let tickarray_bitmap_extension = pool_id_account
+        .map(|pool_id| tickarray_bitmap_extension_key(&raydium_v3_program, &pool_id));
 
     Ok(ClientConfig {
         http_url,
         ws_url,
+        http_urls,
+        ws_urls,
+        rpc_retry,
         payer_path,
         admin_path,
         raydium_v3_program,
@@ -173,11 +437,217 @@ fn load_cfg(client_config: &String) -> Result<ClientConfig> {
         pool_id_account,
         tickarray_bitmap_extension,
         amm_config_index,
+        pool_discovery,
+        cache_dir,
+        cache_ttl,
+        slot_cache_max_age,
+        index_dir,
+        journal_dir,
+        owner,
+        priority_fee,
+        priority_fee_percentile,
+        compute_unit_margin_percent,
+        lookup_tables,
+        transaction_version,
+        nonce_account: None,
+        squads_multisig,
+        squads_vault_index,
+        geyser_url,
+        geyser_x_token,
+        jupiter_url,
+        jupiter_max_shortfall_bps,
+        jito_url,
+        jito_tip_lamports,
+        index_db,
+        payer_signer: None,
+        admin_signer: None,
     })
 }
-fn read_keypair_file(s: &str) -> Result<Keypair> {
-    anchor_client::solana_sdk::signature::read_keypair_file(s)
-        .map_err(|_| format_err!("failed to read keypair from {}", s))
+/// The rpc client failing over between the configured endpoints, serving the accounts of the
+/// configured pool from a yellowstone grpc mirror when a geyser endpoint is set
+#[cfg(feature = "yellowstone")]
+fn new_rpc_client(pool_config: &ClientConfig, rpc_trace: bool) -> Result<RpcClient> {
+    let sender = FailoverSender::new(&pool_config.http_urls, pool_config.rpc_retry, rpc_trace);
+    let geyser_url = match &pool_config.geyser_url {
+        Some(geyser_url) => geyser_url,
+        None => {
+            return Ok(RpcClient::new_sender(
+                sender,
+                anchor_client::solana_client::rpc_client::RpcClientConfig::with_commitment(
+                    CommitmentConfig::default(),
+                ),
+            ))
+        }
+    };
+    let pool_ids: Vec<Pubkey> = pool_config.pool_id_account().into_iter().collect();
+    let mirror = instructions::geyser::spawn_geyser_mirror(
+        geyser_url,
+        pool_config.geyser_x_token.clone(),
+        &pool_config.raydium_v3_program,
+        &pool_ids,
+    )?;
+    Ok(RpcClient::new_sender(
+        instructions::geyser::MirrorSender::new(sender, mirror),
+        anchor_client::solana_client::rpc_client::RpcClientConfig::with_commitment(
+            CommitmentConfig::default(),
+        ),
+    ))
+}
+
+#[cfg(not(feature = "yellowstone"))]
+fn new_rpc_client(pool_config: &ClientConfig, rpc_trace: bool) -> Result<RpcClient> {
+    if pool_config.geyser_url.is_some() {
+        return Err(format_err!(
+            "geyser_url is set but the client is built without the yellowstone feature"
+        ));
+    }
+    Ok(RpcClient::new_sender(
+        FailoverSender::new(&pool_config.http_urls, pool_config.rpc_retry, rpc_trace),
+        anchor_client::solana_client::rpc_client::RpcClientConfig::with_commitment(
+            CommitmentConfig::default(),
+        ),
+    ))
+}
+
+/// The events of the transactions of the pool in the Index database, None without database
+#[cfg(feature = "sqlite")]
+fn indexed_pool_index(pool_config: &ClientConfig, pool_id: &Pubkey) -> Result<Option<PoolIndex>> {
+    match &pool_config.index_db {
+        Some(path) => Ok(Some(EventDb::open(path)?.pool_index(pool_id)?)),
+        None => Ok(None),
+    }
+}
+
+/// The swaps of the pool since the slot in the Index database, None without database
+#[cfg(feature = "sqlite")]
+fn indexed_pool_swaps(
+    pool_config: &ClientConfig,
+    pool_id: &Pubkey,
+    since_slot: u64,
+) -> Result<Option<Vec<IndexedEvent>>> {
+    match &pool_config.index_db {
+        Some(path) => Ok(Some(EventDb::open(path)?.pool_swaps(pool_id, since_slot)?)),
+        None => Ok(None),
+    }
+}
+
+/// The events of the position in the Index database, None without database
+#[cfg(feature = "sqlite")]
+fn indexed_position_events(
+    pool_config: &ClientConfig,
+    position_nft_mint: &Pubkey,
+) -> Result<Option<Vec<IndexedEvent>>> {
+    match &pool_config.index_db {
+        Some(path) => Ok(Some(
+            EventDb::open(path)?.position_events(position_nft_mint)?,
+        )),
+        None => Ok(None),
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn without_index_db<T>(pool_config: &ClientConfig) -> Result<Option<T>> {
+    if pool_config.index_db.is_some() {
+        return Err(format_err!(
+            "index_db is set but the client is built without the sqlite feature"
+        ));
+    }
+    Ok(None)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn indexed_pool_index(pool_config: &ClientConfig, _pool_id: &Pubkey) -> Result<Option<PoolIndex>> {
+    without_index_db(pool_config)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn indexed_pool_swaps(
+    pool_config: &ClientConfig,
+    _pool_id: &Pubkey,
+    _since_slot: u64,
+) -> Result<Option<Vec<IndexedEvent>>> {
+    without_index_db(pool_config)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn indexed_position_events(
+    pool_config: &ClientConfig,
+    _position_nft_mint: &Pubkey,
+) -> Result<Option<Vec<IndexedEvent>>> {
+    without_index_db(pool_config)
+}
+
+/// Pick the most liquid pool of the pair over all the amm configs, returns the config index and
+/// the pool id
+fn amm_config_key(program_id: &Pubkey, index: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
+            &index.to_be_bytes(),
+        ],
+        program_id,
+    )
+    .0
+}
+
+fn tickarray_bitmap_extension_key(program_id: &Pubkey, pool_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(),
+            pool_id.to_bytes().as_ref(),
+        ],
+        program_id,
+    )
+    .0
+}
+
+fn discover_pool(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    mint0: &Pubkey,
+    mint1: &Pubkey,
+) -> Result<Option<(u16, Pubkey)>> {
+    let pools = find_pools_by_mints(rpc_client, program_id, mint0, mint1)?;
+    if pools.is_empty() {
+        println!("no pool of {} and {} in any amm config", mint0, mint1);
+        return Ok(None);
+    }
+    let mut amm_config_keys: Vec<Pubkey> = pools.iter().map(|(_, pool)| pool.amm_config).collect();
+    amm_config_keys.sort();
+    amm_config_keys.dedup();
+    let mut amm_configs = BTreeMap::new();
+    for (key, account) in amm_config_keys
+        .iter()
+        .zip(get_multiple_accounts(rpc_client, &amm_config_keys)?)
+    {
+        if let Some(account) = account {
+            amm_configs.insert(
+                *key,
+                deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(&account)?,
+            );
+        }
+    }
+    let mut selected = None;
+    for (pool_id, pool) in pools.iter() {
+        let amm_config = match amm_configs.get(&pool.amm_config) {
+            Some(amm_config) => amm_config,
+            None => continue,
+        };
+        println!(
+            "pool:{}, amm_config_index:{}, trade_fee_rate:{}, liquidity:{}",
+            pool_id,
+            amm_config.index,
+            amm_config.trade_fee_rate,
+            identity(pool.liquidity)
+        );
+        if selected.is_none() {
+            selected = Some((amm_config.index, *pool_id));
+        }
+    }
+    if let Some((index, pool_id)) = selected {
+        println!("selected pool:{}, amm_config_index:{}", pool_id, index);
+    }
+    Ok(selected)
 }
 fn write_keypair_file(keypair: &Keypair, outfile: &str) -> Result<String> {
     anchor_client::solana_sdk::signature::write_keypair_file(keypair, outfile)
@@ -187,13 +657,40 @@ fn path_is_exist(path: &str) -> bool {
     Path::new(path).exists()
 }
 
-fn load_cur_and_next_five_tick_array(
+// The max number of tick arrays loaded for a swap quote of a v0 transaction, only the tick
+// arrays which the quote actually crosses are passed to the swap instruction.
+const MAX_SWAP_TICK_ARRAY_NUM: usize = 16;
+// The max number of tick arrays of a swap without lookup tables, the account keys of a
+// legacy transaction don't fit more.
+const MAX_LEGACY_SWAP_TICK_ARRAY_NUM: usize = 6;
+
+fn load_cur_and_next_tick_arrays(
     rpc_client: &RpcClient,
     pool_config: &ClientConfig,
     pool_state: &PoolState,
     tickarray_bitmap_extension: &TickArrayBitmapExtension,
     zero_for_one: bool,
+    max_tick_array_num: usize,
 ) -> VecDeque<TickArrayState> {
+    let tick_array_keys = cur_and_next_tick_array_keys(
+        pool_config,
+        pool_state,
+        tickarray_bitmap_extension,
+        zero_for_one,
+        max_tick_array_num,
+    );
+    let tick_array_rsps = get_multiple_accounts(rpc_client, &tick_array_keys).unwrap();
+    deserialize_tick_arrays(&tick_array_rsps)
+}
+
+/// The keys of the initialized tick arrays a swap in the direction crosses first
+fn cur_and_next_tick_array_keys(
+    pool_config: &ClientConfig,
+    pool_state: &PoolState,
+    tickarray_bitmap_extension: &TickArrayBitmapExtension,
+    zero_for_one: bool,
+    max_tick_array_num: usize,
+) -> Vec<Pubkey> {
     let (_, mut current_valid_tick_array_start_index) = pool_state
         .get_first_initialized_tick_array(&Some(*tickarray_bitmap_extension), zero_for_one)
         .unwrap();
@@ -202,14 +699,14 @@ fn load_cur_and_next_five_tick_array(
         Pubkey::find_program_address(
             &[
                 raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
-                pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                 &current_valid_tick_array_start_index.to_be_bytes(),
             ],
             &pool_config.raydium_v3_program,
         )
         .0,
     );
-    let mut max_array_size = 5;
+    let mut max_array_size = max_tick_array_num.saturating_sub(1);
     while max_array_size != 0 {
         let next_tick_array_index = pool_state
             .next_initialized_tick_array_start_index(
@@ -226,7 +723,7 @@ fn load_cur_and_next_five_tick_array(
             Pubkey::find_program_address(
                 &[
                     raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
-                    pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                    pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                     &current_valid_tick_array_start_index.to_be_bytes(),
                 ],
                 &pool_config.raydium_v3_program,
@@ -235,9 +732,14 @@ fn load_cur_and_next_five_tick_array(
         );
         max_array_size -= 1;
     }
-    let tick_array_rsps = rpc_client.get_multiple_accounts(&tick_array_keys).unwrap();
+    tick_array_keys
+}
+
+fn deserialize_tick_arrays(
+    tick_array_rsps: &[Option<anchor_client::solana_sdk::account::Account>],
+) -> VecDeque<TickArrayState> {
     let mut tick_arrays = VecDeque::new();
-    for (index, tick_array) in tick_array_rsps.iter().enumerate() {
+    for tick_array in tick_array_rsps.iter() {
         let tick_array_state =
             deserialize_anchor_account::<raydium_amm_v3::states::TickArrayState>(
                 &tick_array.clone().unwrap(),
@@ -248,6 +750,741 @@ fn load_cur_and_next_five_tick_array(
     tick_arrays
 }
 
+/// The fee discount accounts of the swap signer and the amm config with the discounted trade fee
+/// for the quote, no account is passed when the amm config has no fee discount
+fn load_fee_discount(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+    amm_config_state: &raydium_amm_v3::states::AmmConfig,
+    signer: &Pubkey,
+) -> Result<(Vec<AccountMeta>, raydium_amm_v3::states::AmmConfig)> {
+    let (fee_discount_key, __bump) = Pubkey::find_program_address(
+        &[
+            raydium_amm_v3::states::FEE_DISCOUNT_SEED.as_bytes(),
+            pool_config.amm_config_key().to_bytes().as_ref(),
+        ],
+        &pool_config.raydium_v3_program,
+    );
+    let fee_discount_account = match rpc_client
+        .get_account_with_commitment(&fee_discount_key, CommitmentConfig::confirmed())?
+        .value
+    {
+        Some(account) => account,
+        None => return Ok((Vec::new(), amm_config_state.clone())),
+    };
+    let fee_discount_config = deserialize_anchor_account::<
+        raydium_amm_v3::states::FeeDiscountConfig,
+    >(&fee_discount_account)?;
+    let mut accounts = vec![AccountMeta::new_readonly(fee_discount_key, false)];
+    let mut fee_discount = raydium_amm_v3::states::FeeDiscount {
+        discount_rate: 0,
+        config: fee_discount_key,
+        token_account: None,
+    };
+    let discount_mint_account = rpc_client.get_account(&fee_discount_config.mint)?;
+    let discount_token_account =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            signer,
+            &fee_discount_config.mint,
+            &discount_mint_account.owner,
+        );
+    if let Some(account) = rpc_client
+        .get_account_with_commitment(&discount_token_account, CommitmentConfig::confirmed())?
+        .value
+    {
+        let amount = StateWithExtensions::<Account>::unpack(&account.data)?
+            .base
+            .amount;
+        fee_discount.discount_rate = fee_discount_config.discount_rate(amount);
+        fee_discount.token_account = Some(discount_token_account);
+        accounts.push(AccountMeta::new_readonly(discount_token_account, false));
+    }
+    println!(
+        "fee discount:{}, held discount mint:{}",
+        fee_discount.discount_rate,
+        fee_discount.token_account.is_some()
+    );
+    Ok((accounts, fee_discount.apply(amm_config_state)))
+}
+
+/// Quote a swap on each of the pools, returns the pools with the quoted other amount, the best
+/// execution first: the largest output for a base input swap and the smallest input otherwise.
+/// The pools without liquidity or enough of it are left out.
+fn quote_swap_pools(
+    rpc_client: &RpcClient,
+    account_cache: &AccountCache,
+    pool_config: &ClientConfig,
+    pool_ids: &[Pubkey],
+    input_mint: Pubkey,
+    amount: u64,
+    base_in: bool,
+) -> Result<Vec<(Pubkey, u64)>> {
+    let mut quotes = Vec::new();
+    for pool_id in pool_ids {
+        let pool_state =
+            deserialize_anchor_account::<PoolState>(&rpc_client.get_account(pool_id)?)?;
+        let liquidity = pool_state.liquidity;
+        if liquidity == 0 {
+            println!("pool:{}, no liquidity", pool_id);
+            continue;
+        }
+        let pool_config = pool_config.with_pool(*pool_id, &pool_state);
+        let tickarray_bitmap_extension = deserialize_anchor_account::<TickArrayBitmapExtension>(
+            &rpc_client.get_account(&pool_config.tickarray_bitmap_extension().unwrap())?,
+        )?;
+        let amm_config_state = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
+            &account_cache.get_account(rpc_client, &pool_state.amm_config)?,
+        )?;
+        let zero_for_one = input_mint == pool_state.token_mint_0;
+        let mut tick_arrays = load_cur_and_next_tick_arrays(
+            rpc_client,
+            &pool_config,
+            &pool_state,
+            &tickarray_bitmap_extension,
+            zero_for_one,
+            pool_config.max_swap_tick_array_num(),
+        );
+        match utils::get_out_put_amount_and_remaining_accounts(
+            amount,
+            None,
+            zero_for_one,
+            base_in,
+            &amm_config_state,
+            &pool_state,
+            &tickarray_bitmap_extension,
+            &mut tick_arrays,
+        ) {
+            Ok((other_amount, _)) => {
+                println!(
+                    "pool:{}, trade_fee_rate:{}, liquidity:{}, {}:{}",
+                    pool_id,
+                    amm_config_state.trade_fee_rate,
+                    liquidity,
+                    if base_in { "amount_out" } else { "amount_in" },
+                    other_amount
+                );
+                quotes.push((*pool_id, other_amount));
+            }
+            Err(err) => println!("pool:{}, no quote: {}", pool_id, err),
+        }
+    }
+    if base_in {
+        quotes.sort_by_key(|(_, other_amount)| std::cmp::Reverse(*other_amount));
+    } else {
+        quotes.sort_by_key(|(_, other_amount)| *other_amount);
+    }
+    Ok(quotes)
+}
+
+/// The accounts of a pool quoted by the quote server, reloaded in the background
+struct WarmPool {
+    pool_id: Pubkey,
+    pool_state: PoolState,
+    tickarray_bitmap_extension: TickArrayBitmapExtension,
+    amm_config_state: raydium_amm_v3::states::AmmConfig,
+    mint_datas: (Vec<u8>, Vec<u8>),
+    /// The tick arrays a swap crosses, in the zero for one direction and in the other one
+    tick_arrays: (VecDeque<TickArrayState>, VecDeque<TickArrayState>),
+    epoch: u64,
+    slot: u64,
+}
+
+impl WarmPool {
+    /// The pool is always fetched, the other accounts are served by the slot cache while they are
+    /// recent enough
+    fn load(
+        rpc_client: &RpcClient,
+        account_cache: &AccountCache,
+        slot_cache: &SlotCache,
+        pool_config: &ClientConfig,
+        pool_id: Pubkey,
+    ) -> Result<Self> {
+        let slot = rpc_client.get_slot()?;
+        let pool_state =
+            deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+        let pool_config = pool_config.with_pool(pool_id, &pool_state);
+        let tickarray_bitmap_extension = deserialize_anchor_account::<TickArrayBitmapExtension>(
+            slot_cache.get_multiple_accounts(
+                rpc_client,
+                &[pool_config.tickarray_bitmap_extension().unwrap()],
+                slot,
+            )?[0]
+                .as_ref()
+                .ok_or(format_err!("tickarray bitmap extension not found"))?,
+        )?;
+        // amm config and mints rarely change, load them from the cache
+        let static_rsps = slot_cache.get_immutable_accounts(
+            rpc_client,
+            account_cache,
+            &[
+                pool_state.amm_config,
+                pool_state.token_mint_0,
+                pool_state.token_mint_1,
+            ],
+        )?;
+        let [amm_config_account, mint0_account, mint1_account] = array_ref![static_rsps, 0, 3];
+        let amm_config_state = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
+            amm_config_account.as_ref().unwrap(),
+        )?;
+        let tick_arrays = |zero_for_one| -> Result<VecDeque<TickArrayState>> {
+            let tick_array_keys = cur_and_next_tick_array_keys(
+                &pool_config,
+                &pool_state,
+                &tickarray_bitmap_extension,
+                zero_for_one,
+                pool_config.max_swap_tick_array_num(),
+            );
+            Ok(deserialize_tick_arrays(&slot_cache.get_multiple_accounts(
+                rpc_client,
+                &tick_array_keys,
+                slot,
+            )?))
+        };
+        Ok(Self {
+            pool_id,
+            pool_state,
+            tickarray_bitmap_extension,
+            amm_config_state,
+            mint_datas: (
+                mint0_account.clone().unwrap().data,
+                mint1_account.clone().unwrap().data,
+            ),
+            tick_arrays: (tick_arrays(true)?, tick_arrays(false)?),
+            epoch: rpc_client.get_epoch_info()?.epoch,
+            slot,
+        })
+    }
+
+    /// Quote the swap without any fee discount, None when the pool is not of the mints
+    fn quote(&self, request: &QuoteRequest) -> Result<Option<SwapQuote>> {
+        let (mint_0, mint_1) = (self.pool_state.token_mint_0, self.pool_state.token_mint_1);
+        let zero_for_one = match (request.input_mint, request.output_mint) {
+            (input, output) if input == mint_0 && output == mint_1 => true,
+            (input, output) if input == mint_1 && output == mint_0 => false,
+            _ => return Ok(None),
+        };
+        let mut tick_arrays = if zero_for_one {
+            self.tick_arrays.0.clone()
+        } else {
+            self.tick_arrays.1.clone()
+        };
+        let mint0_state = StateWithExtensions::<Mint>::unpack(&self.mint_datas.0)?;
+        let mint1_state = StateWithExtensions::<Mint>::unpack(&self.mint_datas.1)?;
+        Ok(Some(quote_swap(
+            request.amount,
+            request.base_in,
+            zero_for_one,
+            &self.amm_config_state,
+            &self.pool_state,
+            &self.tickarray_bitmap_extension,
+            &mut tick_arrays,
+            (&mint0_state, &mint1_state),
+            self.epoch,
+        )?))
+    }
+}
+
+/// The best quote of the pools of the mints, the largest output with base_in and the smallest
+/// input otherwise
+fn best_warm_quote<'a>(
+    pools: &'a [WarmPool],
+    request: &QuoteRequest,
+) -> Result<(&'a WarmPool, SwapQuote)> {
+    let mut best: Option<(&WarmPool, SwapQuote)> = None;
+    for pool in pools.iter() {
+        let quote = match pool.quote(request) {
+            Ok(Some(quote)) => quote,
+            // a pool without enough liquidity is skipped
+            _ => continue,
+        };
+        let better = best.as_ref().map_or(true, |(_, best_quote)| {
+            if request.base_in {
+                quote.amount_out > best_quote.amount_out
+            } else {
+                quote.amount_in < best_quote.amount_in
+            }
+        });
+        if better {
+            best = Some((pool, quote));
+        }
+    }
+    best.ok_or(format_err!(
+        "no pool quotes {} to {}",
+        request.input_mint,
+        request.output_mint
+    ))
+}
+
+/// Quotes from warm pools and swaps with the payer as the hot wallet, for the grpc service
+#[cfg(feature = "grpc")]
+struct GrpcBackend {
+    rpc_client: RpcClient,
+    account_cache: AccountCache,
+    slot_cache: SlotCache,
+    pool_config: ClientConfig,
+    payer: SharedSigner,
+    pools: std::sync::RwLock<Vec<WarmPool>>,
+}
+
+#[cfg(feature = "grpc")]
+impl instructions::grpc::ClmmBackend for GrpcBackend {
+    fn quote(&self, request: &QuoteRequest) -> Result<(Pubkey, u64, SwapQuote)> {
+        let pools = self.pools.read().unwrap();
+        let (pool, quote) = best_warm_quote(&pools, request)?;
+        Ok((pool.pool_id, pool.slot, quote))
+    }
+
+    fn execute_swap(&self, request: &QuoteRequest) -> Result<(Signature, Pubkey, u64, SwapQuote)> {
+        if !request.base_in {
+            return Err(format_err!("only the swaps of an exact input are executed"));
+        }
+        let pool_id = best_warm_quote(&self.pools.read().unwrap(), request)?
+            .0
+            .pool_id;
+        // quoted again from the latest state of the pool, the swap is bounded by the slippage
+        let pool = WarmPool::load(
+            &self.rpc_client,
+            &self.account_cache,
+            &self.slot_cache,
+            &self.pool_config,
+            pool_id,
+        )?;
+        let quote = pool
+            .quote(request)?
+            .ok_or(format_err!("pool {} is not of the mints", pool_id))?;
+        let pool_config = self.pool_config.with_pool(pool_id, &pool.pool_state);
+        let payer = self.payer.pubkey();
+        let output_token_program = self
+            .account_cache
+            .get_account(&self.rpc_client, &request.output_mint)?
+            .owner;
+        let mut instructions = vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &payer,
+                &payer,
+                &request.output_mint,
+                &output_token_program,
+            ),
+        ];
+        instructions.extend(zap_out_instrs(
+            &pool_config,
+            &self.rpc_client,
+            &payer,
+            &pool.pool_state,
+            &request.output_mint,
+            request.amount,
+        )?);
+        let lookup_tables = load_lookup_tables(&self.rpc_client, &pool_config.lookup_tables)?;
+        let txn = new_signed_txn(
+            &self.rpc_client,
+            &pool_config,
+            &instructions,
+            &payer,
+            &[&self.payer],
+            self.rpc_client.get_latest_blockhash()?,
+            &lookup_tables,
+        )?;
+        let signature = send_txn(&self.rpc_client, &txn, true)?;
+        Ok((signature, pool_id, pool.slot, quote))
+    }
+
+    fn pool_decimals(&self, pool_id: &Pubkey) -> Option<(u8, u8)> {
+        self.pools
+            .read()
+            .unwrap()
+            .iter()
+            .find(|pool| pool.pool_id == *pool_id)
+            .map(|pool| {
+                (
+                    pool.pool_state.mint_decimals_0,
+                    pool.pool_state.mint_decimals_1,
+                )
+            })
+    }
+
+    fn ws_url(&self) -> String {
+        self.pool_config.ws_url.clone()
+    }
+}
+
+/// Sign the instructions into a transaction of the configured version, priced with the priority fee
+/// estimate and limited to the compute units of its simulation when they are enabled. With a nonce
+/// account the transaction advances the nonce, whose authority is the payer, and is signed with it
+/// instead of the blockhash.
+fn new_signed_txn<T: Signers + ?Sized>(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &T,
+    blockhash: Hash,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let instructions = with_estimated_priority_fee(rpc_client, instructions)?;
+    let mut instructions =
+        with_simulated_compute_unit_limit(rpc_client, &instructions, payer, lookup_tables)?;
+    let blockhash = match pool_config.nonce_account {
+        Some(nonce_account) => {
+            let (nonce_blockhash, authority) = get_durable_nonce(rpc_client, &nonce_account)?;
+            if authority != *payer {
+                return Err(format_err!(
+                    "the authority of the nonce account {} is {}, not the payer",
+                    nonce_account,
+                    authority
+                ));
+            }
+            // the nonce advance must be the first instruction
+            instructions.insert(
+                0,
+                system_instruction::advance_nonce_account(&nonce_account, payer),
+            );
+            nonce_blockhash
+        }
+        None => blockhash,
+    };
+    sign_instructions(
+        &instructions,
+        payer,
+        signers,
+        blockhash,
+        pool_config.transaction_version,
+        lookup_tables,
+    )
+}
+
+/// Send the admin instructions signed by the admin, or propose them to the squads multisig of
+/// the config, signed by the payer who must be a member of it
+fn send_admin_instrs(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+    instructions: &[Instruction],
+    payer: &SharedSigner,
+    admin: &SharedSigner,
+) -> Result<()> {
+    let recent_hash = rpc_client.get_latest_blockhash()?;
+    let multisig = match pool_config.squads_multisig {
+        Some(multisig) => multisig,
+        None => {
+            let signers = vec![payer, admin];
+            let txn = new_signed_txn(
+                rpc_client,
+                pool_config,
+                instructions,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(rpc_client, &txn, true)?;
+            println!("{}", signature);
+            return Ok(());
+        }
+    };
+    let (propose_instrs, transaction_index) = propose_vault_transaction_instrs(
+        rpc_client,
+        &multisig,
+        pool_config.squads_vault_index,
+        &payer.pubkey(),
+        instructions,
+        None,
+    )?;
+    let signers = vec![payer];
+    let txn = new_signed_txn(
+        rpc_client,
+        pool_config,
+        &propose_instrs,
+        &payer.pubkey(),
+        &signers,
+        recent_hash,
+        &[],
+    )?;
+    let signature = send_txn(rpc_client, &txn, true)?;
+    println!(
+        "proposed transaction {} to the multisig {}, executed by the vault {} once approved",
+        transaction_index,
+        multisig,
+        admin.pubkey()
+    );
+    println!("{}", signature);
+    Ok(())
+}
+
+/// The accounts of the configured pool used by the swaps and the positions, with the tick arrays
+/// around its price, for a lookup table
+fn pool_lookup_addresses(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+) -> Result<Vec<Pubkey>> {
+    let pool_id = pool_config.pool_id_account().unwrap();
+    let tickarray_bitmap_extension = pool_config.tickarray_bitmap_extension().unwrap();
+    let rsps = rpc_client.get_multiple_accounts(&[pool_id, tickarray_bitmap_extension])?;
+    let [pool_account, tickarray_bitmap_extension_account] = array_ref![rsps, 0, 2];
+    let pool_state = deserialize_anchor_account::<PoolState>(pool_account.as_ref().unwrap())?;
+    let tickarray_bitmap_extension_state = deserialize_anchor_account::<TickArrayBitmapExtension>(
+        tickarray_bitmap_extension_account.as_ref().unwrap(),
+    )?;
+    let mut addresses = vec![
+        pool_state.amm_config,
+        pool_id,
+        pool_state.token_vault_0,
+        pool_state.token_vault_1,
+        pool_state.token_mint_0,
+        pool_state.token_mint_1,
+        pool_state.observation_key,
+        tickarray_bitmap_extension,
+        spl_token::id(),
+        spl_token_2022::id(),
+        spl_memo::id(),
+        spl_associated_token_account::id(),
+        anchor_client::solana_sdk::system_program::id(),
+        anchor_client::solana_sdk::sysvar::rent::id(),
+    ];
+    for zero_for_one in [true, false] {
+        addresses.extend(load_cur_and_next_five_tick_array_keys(
+            rpc_client,
+            pool_config,
+            &pool_state,
+            &tickarray_bitmap_extension_state,
+            zero_for_one,
+        ));
+    }
+    Ok(missing_addresses(None, &addresses))
+}
+
+/// One hop of a routed swap, through the pool of the two mints in the configured amm config
+struct RouteHop {
+    pool_config: ClientConfig,
+    pool_state: PoolState,
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    /// The token programs of the input and output mints
+    token_programs: (Pubkey, Pubkey),
+    quote: SwapQuote,
+    fee_discount_accounts: Vec<AccountMeta>,
+}
+
+impl RouteHop {
+    /// Quote a base input swap of `amount` from `input_mint` to `output_mint`
+    fn quote(
+        rpc_client: &RpcClient,
+        account_cache: &AccountCache,
+        pool_config: &ClientConfig,
+        payer: &Pubkey,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount: u64,
+    ) -> Result<Self> {
+        let (mint0, mint1) = if input_mint < output_mint {
+            (input_mint, output_mint)
+        } else {
+            (output_mint, input_mint)
+        };
+        let (pool_id, _) = Pubkey::find_program_address(
+            &[
+                raydium_amm_v3::states::POOL_SEED.as_bytes(),
+                pool_config.amm_config_key().as_ref(),
+                mint0.as_ref(),
+                mint1.as_ref(),
+            ],
+            &pool_config.raydium_v3_program,
+        );
+        let pool_state =
+            deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id).map_err(
+                |_| format_err!("no pool of {} and {} in the amm config", mint0, mint1),
+            )?)?;
+        Self::quote_in_pool(
+            rpc_client,
+            account_cache,
+            pool_config,
+            payer,
+            pool_id,
+            pool_state,
+            input_mint,
+            amount,
+        )
+    }
+
+    /// Quote a base input swap of `amount` of `input_mint`, a mint of the pool, through the pool
+    fn quote_in_pool(
+        rpc_client: &RpcClient,
+        account_cache: &AccountCache,
+        pool_config: &ClientConfig,
+        payer: &Pubkey,
+        pool_id: Pubkey,
+        pool_state: PoolState,
+        input_mint: Pubkey,
+        amount: u64,
+    ) -> Result<Self> {
+        let (mint0, mint1) = (pool_state.token_mint_0, pool_state.token_mint_1);
+        let zero_for_one = if input_mint == mint0 {
+            true
+        } else if input_mint == mint1 {
+            false
+        } else {
+            return Err(format_err!(
+                "{} is not a mint of pool {}",
+                input_mint,
+                pool_id
+            ));
+        };
+        let output_mint = if zero_for_one { mint1 } else { mint0 };
+        let pool_config = pool_config.with_pool(pool_id, &pool_state);
+        let tickarray_bitmap_extension = deserialize_anchor_account::<TickArrayBitmapExtension>(
+            &rpc_client.get_account(&pool_config.tickarray_bitmap_extension().unwrap())?,
+        )?;
+        let static_rsps = account_cache
+            .get_multiple_accounts(rpc_client, &[pool_state.amm_config, mint0, mint1])?;
+        let [amm_config_account, mint0_account, mint1_account] = array_ref![static_rsps, 0, 3];
+        let amm_config_state = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
+            amm_config_account.as_ref().unwrap(),
+        )?;
+        let (fee_discount_accounts, amm_config_state) =
+            load_fee_discount(rpc_client, &pool_config, &amm_config_state, payer)?;
+        let (mint0_account, mint1_account) = (
+            mint0_account.clone().unwrap(),
+            mint1_account.clone().unwrap(),
+        );
+        let mint0_state = StateWithExtensions::<Mint>::unpack(&mint0_account.data)?;
+        let mint1_state = StateWithExtensions::<Mint>::unpack(&mint1_account.data)?;
+        let mut tick_arrays = load_cur_and_next_tick_arrays(
+            rpc_client,
+            &pool_config,
+            &pool_state,
+            &tickarray_bitmap_extension,
+            zero_for_one,
+            pool_config.max_swap_tick_array_num(),
+        );
+        let quote = quote_swap(
+            amount,
+            true,
+            zero_for_one,
+            &amm_config_state,
+            &pool_state,
+            &tickarray_bitmap_extension,
+            &mut tick_arrays,
+            (&mint0_state, &mint1_state),
+            rpc_client.get_epoch_info()?.epoch,
+        )?;
+        let token_programs = if zero_for_one {
+            (mint0_account.owner, mint1_account.owner)
+        } else {
+            (mint1_account.owner, mint0_account.owner)
+        };
+        Ok(Self {
+            pool_config,
+            pool_state,
+            input_mint,
+            output_mint,
+            token_programs,
+            quote,
+            fee_discount_accounts,
+        })
+    }
+
+    /// The swap instruction of the hop, with the tick arrays the quote crosses
+    fn swap_instrs(
+        &self,
+        owner: &Pubkey,
+        amount: u64,
+        other_amount_threshold: u64,
+    ) -> Result<Vec<Instruction>> {
+        let pool_id = self.pool_config.pool_id_account().unwrap();
+        let mut remaining_accounts = vec![AccountMeta::new_readonly(
+            self.pool_config.tickarray_bitmap_extension().unwrap(),
+            false,
+        )];
+        remaining_accounts.extend(self.quote.tick_array_indexs.iter().map(|index| {
+            AccountMeta::new(
+                Pubkey::find_program_address(
+                    &[
+                        raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
+                        pool_id.as_ref(),
+                        &index.to_be_bytes(),
+                    ],
+                    &self.pool_config.raydium_v3_program,
+                )
+                .0,
+                false,
+            )
+        }));
+        remaining_accounts.extend(self.fee_discount_accounts.iter().cloned());
+        let (input_vault, output_vault) = if self.quote.zero_for_one {
+            (self.pool_state.token_vault_0, self.pool_state.token_vault_1)
+        } else {
+            (self.pool_state.token_vault_1, self.pool_state.token_vault_0)
+        };
+        swap_v2_instr(
+            &self.pool_config,
+            self.pool_state.amm_config,
+            pool_id,
+            input_vault,
+            output_vault,
+            self.pool_state.observation_key,
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                &self.input_mint,
+                &self.token_programs.0,
+            ),
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                &self.output_mint,
+                &self.token_programs.1,
+            ),
+            self.input_mint,
+            self.output_mint,
+            remaining_accounts,
+            amount,
+            other_amount_threshold,
+            None,
+            true,
+        )
+    }
+}
+
+/// The transaction of the two hops, the second one swaps `intermediate_amount` and fails to
+/// receive less than `amount_out_min`
+fn two_hop_txn(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+    payer: &SharedSigner,
+    (first_hop, second_hop): (&RouteHop, &RouteHop),
+    amount: u64,
+    intermediate_amount: u64,
+    amount_out_min: u64,
+) -> Result<VersionedTransaction> {
+    let mut instructions = first_hop.swap_instrs(&payer.pubkey(), amount, intermediate_amount)?;
+    instructions.extend(second_hop.swap_instrs(
+        &payer.pubkey(),
+        intermediate_amount,
+        amount_out_min,
+    )?);
+    let mut builder = TransactionBuilder::new(payer.pubkey()).compute_unit_limit(1_400_000u32);
+    let mut group = vec![
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &first_hop.output_mint,
+            &first_hop.token_programs.1,
+        ),
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &second_hop.output_mint,
+            &second_hop.token_programs.1,
+        ),
+    ];
+    group.extend(instructions);
+    builder.add_group(group);
+    let builder = price_transaction_builder(rpc_client, builder)?
+        .lookup_tables(load_lookup_tables(rpc_client, &pool_config.lookup_tables)?);
+    let signers = vec![payer];
+    let mut txns = builder.build_signed(rpc_client.get_latest_blockhash()?, &signers)?;
+    if txns.len() != 1 {
+        return Err(format_err!("the two hops don't fit in one transaction"));
+    }
+    Ok(txns.remove(0))
+}
+
 pub fn load_cur_and_next_five_tick_array_keys(
     rpc_client: &RpcClient,
     pool_config: &ClientConfig,
@@ -263,7 +1500,7 @@ pub fn load_cur_and_next_five_tick_array_keys(
         Pubkey::find_program_address(
             &[
                 raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
-                pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                 &current_valid_tick_array_start_index.to_be_bytes(),
             ],
             &pool_config.raydium_v3_program,
@@ -287,7 +1524,7 @@ pub fn load_cur_and_next_five_tick_array_keys(
             Pubkey::find_program_address(
                 &[
                     raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
-                    pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                    pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                     &current_valid_tick_array_start_index.to_be_bytes(),
                 ],
                 &pool_config.raydium_v3_program,
@@ -296,7 +1533,7 @@ pub fn load_cur_and_next_five_tick_array_keys(
         );
         max_array_size -= 1;
     }
-    let tick_array_rsps = rpc_client.get_multiple_accounts(&tick_array_keys).unwrap();
+    let tick_array_rsps = get_multiple_accounts(rpc_client, &tick_array_keys).unwrap();
     let mut tick_arrays = VecDeque::new();
     for (index, tick_array) in tick_array_rsps.iter().enumerate() {
         let tick_array_state =
@@ -318,26 +1555,323 @@ struct PositionNftTokenInfo {
     amount: u64,
     decimals: u8,
 }
-fn get_all_nft_and_position_by_owner(
-    client: &RpcClient,
+/// The reports of the positions of the nfts held by the owner
+fn load_owned_position_reports(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
     owner: &Pubkey,
-    raydium_amm_v3_program: &Pubkey,
-) -> Vec<PositionNftTokenInfo> {
-    let mut spl_nfts = get_nft_account_and_position_by_owner(
-        client,
-        owner,
-        spl_token::id(),
-        raydium_amm_v3_program,
+    position_nft_infos: &[PositionNftTokenInfo],
+) -> Result<Vec<PositionReport>> {
+    let owned_positions: Vec<(Pubkey, Pubkey)> = position_nft_infos
+        .iter()
+        .map(|item| (*owner, item.position))
+        .collect();
+    load_position_reports(
+        rpc_client,
+        &pool_config.raydium_v3_program,
+        &owned_positions,
+    )
+}
+
+/// Collect the fees and rewards of the positions, packing as many positions per transaction as
+/// fit
+fn send_position_collections(
+    rpc_client: &RpcClient,
+    account_cache: &AccountCache,
+    pool_config: &ClientConfig,
+    payer: &SharedSigner,
+    position_nft_infos: &[PositionNftTokenInfo],
+    reports: &[&PositionReport],
+) -> Result<()> {
+    if reports.is_empty() {
+        println!("nothing to collect");
+        return Ok(());
+    }
+    let positions: Vec<Pubkey> = reports.iter().map(|report| report.position).collect();
+    let mut pool_keys: Vec<Pubkey> = reports.iter().map(|report| report.pool_id).collect();
+    pool_keys.sort();
+    pool_keys.dedup();
+    let mut pools = HashMap::new();
+    for (pool_id, rsp) in pool_keys
+        .iter()
+        .zip(get_multiple_accounts_concurrently(rpc_client, &pool_keys)?)
+    {
+        let pool_state = deserialize_anchor_account::<PoolState>(&rsp.unwrap())?;
+        pools.insert(*pool_id, pool_state);
+    }
+    // the token programs of the pool and reward mints, for their token accounts
+    let mut mints: Vec<Pubkey> = reports
+        .iter()
+        .flat_map(|report| {
+            [report.mint_0, report.mint_1]
+                .into_iter()
+                .chain(report.rewards.iter().map(|(mint, _)| *mint))
+        })
+        .collect();
+    mints.sort();
+    mints.dedup();
+    let token_programs: HashMap<Pubkey, Pubkey> = mints
+        .iter()
+        .zip(account_cache.get_multiple_accounts(rpc_client, &mints)?)
+        .map(|(mint, rsp)| (*mint, rsp.unwrap().owner))
+        .collect();
+
+    let mut builder = TransactionBuilder::new(payer.pubkey()).compute_unit_limit(1_400_000u32);
+    for (report, rsp) in reports
+        .iter()
+        .zip(get_multiple_accounts_concurrently(rpc_client, &positions)?)
+    {
+        let pool_state = &pools[&report.pool_id];
+        let position = deserialize_anchor_account::<raydium_amm_v3::states::PersonalPositionState>(
+            &rsp.unwrap(),
+        )?;
+        let nft_token_info = position_nft_infos
+            .iter()
+            .find(|nft_info| nft_info.position == report.position)
+            .unwrap();
+        builder.add_group(collect_position_fees_instrs(
+            &pool_config.with_pool(report.pool_id, pool_state),
+            &payer.pubkey(),
+            pool_state,
+            &position,
+            nft_token_info.key,
+            &token_programs,
+        )?);
+    }
+    let builder = price_transaction_builder(rpc_client, builder)?
+        .lookup_tables(load_lookup_tables(rpc_client, &pool_config.lookup_tables)?);
+    let signers = vec![payer];
+    let txns = builder.build_signed(rpc_client.get_latest_blockhash()?, &signers)?;
+    println!(
+        "collect {} positions in {} transactions",
+        reports.len(),
+        txns.len()
     );
-    let spl_2022_nfts = get_nft_account_and_position_by_owner(
-        client,
-        owner,
-        spl_token_2022::id(),
-        raydium_amm_v3_program,
+    for txn in txns {
+        let signature = send_versioned_txn(rpc_client, &txn, true)?;
+        println!("{}", signature);
+    }
+    Ok(())
+}
+
+/// Reinvest the pending fees of the position in it: collect them, swap them to the proportions
+/// of its range and increase its liquidity with what was collected. Rewards in other tokens than
+/// the ones of the pool are collected and left in the wallet.
+fn compound_position(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+    payer: &SharedSigner,
+    nft_info: &PositionNftTokenInfo,
+    journal: &mut Journal,
+) -> Result<()> {
+    let position = deserialize_anchor_account::<raydium_amm_v3::states::PersonalPositionState>(
+        &rpc_client.get_account(&nft_info.position)?,
+    )?;
+    let pool_id = position.pool_id;
+    let pool_state = deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+    let position_config = pool_config.with_pool(pool_id, &pool_state);
+    let mut mints = vec![pool_state.token_mint_0, pool_state.token_mint_1];
+    for reward_info in pool_state.reward_infos.iter() {
+        if reward_info.token_mint != Pubkey::default() && !mints.contains(&reward_info.token_mint) {
+            mints.push(reward_info.token_mint);
+        }
+    }
+    let mut token_programs = HashMap::new();
+    for (mint, rsp) in mints.iter().zip(get_multiple_accounts(rpc_client, &mints)?) {
+        let account = rsp.ok_or(format_err!("mint {} not found", mint))?;
+        token_programs.insert(*mint, account.owner);
+    }
+    let [user_token_account_0, user_token_account_1] =
+        [pool_state.token_mint_0, pool_state.token_mint_1].map(|mint| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &payer.pubkey(),
+                &mint,
+                &token_programs[&mint],
+            )
+        });
+    // a missing token account holds nothing. Read at the commitment the steps are confirmed at,
+    // the finalized balances don't hold the last step yet
+    let token_balances = || {
+        [user_token_account_0, user_token_account_1].map(|token_account| {
+            rpc_client
+                .get_token_account_balance_with_commitment(
+                    &token_account,
+                    CommitmentConfig::confirmed(),
+                )
+                .map_or(0, |balance| {
+                    balance.value.amount.parse::<u64>().unwrap_or(0)
+                })
+        })
+    };
+    let mut send = |instructions: Vec<Instruction>, step: &str| -> Result<()> {
+        let mut priced = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            1_400_000u32,
+        )];
+        priced.extend(instructions);
+        let signature = send_keeper_instrs(rpc_client, pool_config, payer, &priced, journal, step)?;
+        println!("{}:{}", step, signature);
+        Ok(())
+    };
+
+    let balances_before = token_balances();
+    send(
+        collect_position_fees_instrs(
+            &position_config,
+            &payer.pubkey(),
+            &pool_state,
+            &position,
+            nft_info.key,
+            &token_programs,
+        )?,
+        "collect",
+    )?;
+    let balances = token_balances();
+    let mut amount_0 = balances[0].saturating_sub(balances_before[0]);
+    let mut amount_1 = balances[1].saturating_sub(balances_before[1]);
+    println!("collected amount_0:{}, amount_1:{}", amount_0, amount_1);
+
+    let pool_state = match rebalance_swap_amount(
+        amount_0,
+        amount_1,
+        pool_state.sqrt_price_x64,
+        position.tick_lower_index,
+        position.tick_upper_index,
+    )? {
+        Some((zero_for_one, amount_in)) => {
+            let target_mint = if zero_for_one {
+                pool_state.token_mint_1
+            } else {
+                pool_state.token_mint_0
+            };
+            println!("swap {} into {}", amount_in, target_mint);
+            let swap_instrs = zap_out_instrs(
+                &position_config,
+                rpc_client,
+                &payer.pubkey(),
+                &pool_state,
+                &target_mint,
+                amount_in,
+            )?;
+            let balances_before = token_balances();
+            send(swap_instrs, "swap")?;
+            let balances = token_balances();
+            amount_0 = (amount_0 + balances[0]).saturating_sub(balances_before[0]);
+            amount_1 = (amount_1 + balances[1]).saturating_sub(balances_before[1]);
+            let pool_account = rpc_client
+                .get_account_with_commitment(&pool_id, CommitmentConfig::confirmed())?
+                .value
+                .ok_or(format_err!("pool {} not found", pool_id))?;
+            deserialize_anchor_account::<PoolState>(&pool_account)?
+        }
+        None => pool_state,
+    };
+
+    let liquidity = liquidity_for_amounts(
+        &position_config,
+        rpc_client,
+        &pool_state,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        amount_0,
+        amount_1,
+    )?;
+    if liquidity == 0 {
+        println!("nothing to reinvest in position {}", nft_info.position);
+        return Ok(());
+    }
+    println!(
+        "reinvest amount_0:{}, amount_1:{}, liquidity:{}",
+        amount_0, amount_1, liquidity
     );
-    spl_nfts.extend(spl_2022_nfts);
-    spl_nfts
+    let increase_instrs = increase_liquidity_instr(
+        &position_config,
+        pool_id,
+        pool_state.token_vault_0,
+        pool_state.token_vault_1,
+        pool_state.token_mint_0,
+        pool_state.token_mint_1,
+        position.nft_mint,
+        nft_info.key,
+        user_token_account_0,
+        user_token_account_1,
+        vec![AccountMeta::new(
+            position_config.tickarray_bitmap_extension().unwrap(),
+            false,
+        )],
+        liquidity,
+        amount_0,
+        amount_1,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        raydium_amm_v3::states::TickArrayState::get_array_start_index(
+            position.tick_lower_index,
+            pool_state.tick_spacing,
+        ),
+        raydium_amm_v3::states::TickArrayState::get_array_start_index(
+            position.tick_upper_index,
+            pool_state.tick_spacing,
+        ),
+    )?;
+    send(increase_instrs, "increase")
+}
+
+fn get_all_nft_and_position_by_owner(
+    client: &RpcClient,
+    owner: &Pubkey,
+    raydium_amm_v3_program: &Pubkey,
+) -> Vec<PositionNftTokenInfo> {
+    // the token accounts of both token programs are loaded at the same time
+    std::thread::scope(|scope| {
+        let spl_2022_nfts = scope.spawn(|| {
+            get_nft_account_and_position_by_owner(
+                client,
+                owner,
+                spl_token_2022::id(),
+                raydium_amm_v3_program,
+            )
+        });
+        let mut spl_nfts = get_nft_account_and_position_by_owner(
+            client,
+            owner,
+            spl_token::id(),
+            raydium_amm_v3_program,
+        );
+        spl_nfts.extend(spl_2022_nfts.join().unwrap());
+        spl_nfts
+    })
+}
+/// The token accounts of the owner holding nothing which the owner can close, the token-2022
+/// accounts with withheld transfer fees are left out as they can't be closed
+fn get_empty_token_accounts_by_owner(
+    client: &RpcClient,
+    owner: &Pubkey,
+    token_program: Pubkey,
+) -> Result<Vec<Pubkey>> {
+    let all_tokens =
+        client.get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(token_program))?;
+    let mut empty_accounts = Vec::new();
+    for keyed_account in all_tokens {
+        if let UiAccountData::Json(parsed_account) = keyed_account.account.data {
+            if let Ok(TokenAccountType::Account(ui_token_account)) =
+                serde_json::from_value(parsed_account.parsed)
+            {
+                let closable = ui_token_account.token_amount.amount == "0"
+                    && ui_token_account.state != UiAccountState::Frozen
+                    && ui_token_account
+                        .close_authority
+                        .map_or(true, |authority| authority == owner.to_string())
+                    && !ui_token_account.extensions.iter().any(|extension| {
+                        matches!(extension, UiExtension::TransferFeeAmount(fee) if fee.withheld_amount > 0)
+                    });
+                if closable {
+                    empty_accounts.push(keyed_account.pubkey.parse::<Pubkey>()?);
+                }
+            }
+        }
+    }
+    Ok(empty_accounts)
 }
+
 fn get_nft_account_and_position_by_owner(
     client: &RpcClient,
     owner: &Pubkey,
@@ -399,8 +1933,688 @@ fn get_nft_account_and_position_by_owner(
     position_nft_accounts
 }
 
+// send with the priority fee escalation when it is configured, as keepers run under congestion.
+// The transaction is journaled before it is sent, an error leaves it pending in the journal until
+// the next run reconciles it.
+fn send_keeper_instrs(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+    payer: &SharedSigner,
+    instructions: &[Instruction],
+    journal: &mut Journal,
+    step: &str,
+) -> Result<Signature> {
+    // planned or exported transactions are not sent, there is nothing to journal
+    let journaled = plan_mode_program().is_none() && export_path().is_none();
+    let result = if pool_config.priority_fee.max_compute_unit_price > 0 {
+        send_txn_with_priority_fee_escalation_and_record(
+            rpc_client,
+            instructions,
+            &pool_config.priority_fee,
+            |instructions, blockhash| {
+                new_signed_txn(
+                    rpc_client,
+                    pool_config,
+                    instructions,
+                    &payer.pubkey(),
+                    &[payer],
+                    blockhash,
+                    &[],
+                )
+            },
+            |txn| {
+                if journaled {
+                    journal.record(step, txn)?;
+                }
+                Ok(())
+            },
+        )
+    } else {
+        let txn = new_signed_txn(
+            rpc_client,
+            pool_config,
+            instructions,
+            &payer.pubkey(),
+            &[payer],
+            rpc_client.get_latest_blockhash()?,
+            &[],
+        )?;
+        if journaled {
+            journal.record(step, &txn)?;
+        }
+        send_txn(rpc_client, &txn, true)
+    };
+    let signature = match result {
+        Ok(signature) => signature,
+        Err(err) => {
+            // a transaction the rpc lost track of stays pending, the next run reconciles it
+            match err.downcast_ref::<ConfirmError>() {
+                Some(ConfirmError::Failed { signature, .. }) if journaled => journal.finish(
+                    step,
+                    JournalStatus::Failed(err.to_string()),
+                    Some(*signature),
+                )?,
+                Some(ConfirmError::Expired { .. }) if journaled => {
+                    journal.finish(step, JournalStatus::Expired, None)?
+                }
+                _ => {}
+            }
+            return Err(err);
+        }
+    };
+    if journaled {
+        journal.finish(step, JournalStatus::Confirmed, Some(signature))?;
+    }
+    Ok(signature)
+}
+
+/// Reconcile the journal a previous run of a keeper job left, true when the exit of the position
+/// already landed and the job must not run again
+fn resume_keeper_job(
+    rpc_client: &RpcClient,
+    journal: &mut Journal,
+    zap_out_to: Option<Pubkey>,
+) -> Result<bool> {
+    journal.reconcile(rpc_client)?;
+    let exit = match journal.last("exit") {
+        Some(entry) if entry.status == JournalStatus::Confirmed => entry,
+        _ => return Ok(false),
+    };
+    println!(
+        "exit already landed in a previous run:{}",
+        exit.landed.clone().unwrap_or_default()
+    );
+    if let Some(mint) = zap_out_to {
+        match journal.last("zap-out") {
+            Some(entry) if entry.status == JournalStatus::Confirmed => println!(
+                "zap out into {}:{}",
+                mint,
+                entry.landed.clone().unwrap_or_default()
+            ),
+            _ => println!(
+                "the zap out into {} was not done, swap the tokens received with SwapV2",
+                mint
+            ),
+        }
+    }
+    Ok(true)
+}
+
+// tick spacings on each side of the current tick of the positions opened by Profile
+const PROFILE_POSITION_TICK_SPACINGS: i32 = 10;
+
+/// Simulate the instructions of the program on the pool with the varied parameters and record the
+/// compute units each consumes, nothing is sent. The swaps and deposits are paid from the token
+/// accounts of the payer, the increase, decrease and collection use the first position it holds
+/// in the pool, the validator has to fund them beforehand.
+fn profile_pool(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+    payer: &Pubkey,
+    pool_id: Pubkey,
+    amounts: &[u64],
+) -> Result<Vec<CuSample>> {
+    let pool_state = deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+    let pool_config = pool_config.with_pool(pool_id, &pool_state);
+    let load_accounts = vec![
+        pool_state.amm_config,
+        pool_state.token_mint_0,
+        pool_state.token_mint_1,
+        pool_config.tickarray_bitmap_extension().unwrap(),
+    ];
+    let mut accounts = Vec::with_capacity(load_accounts.len());
+    for (key, rsp) in load_accounts
+        .iter()
+        .zip(get_multiple_accounts(rpc_client, &load_accounts)?)
+    {
+        accounts.push(rsp.ok_or(format_err!("account {} not found", key))?);
+    }
+    let amm_config_state =
+        deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(&accounts[0])?;
+    let mint0_state = StateWithExtensions::<Mint>::unpack(&accounts[1].data)?;
+    let mint1_state = StateWithExtensions::<Mint>::unpack(&accounts[2].data)?;
+    let tickarray_bitmap_extension =
+        deserialize_anchor_account::<TickArrayBitmapExtension>(&accounts[3])?;
+    let (token_program_0, token_program_1) = (accounts[1].owner, accounts[2].owner);
+    let epoch = rpc_client.get_epoch_info()?.epoch;
+    let lookup_tables = load_lookup_tables(rpc_client, &pool_config.lookup_tables)?;
+    let mut profiler = CuProfiler::new(
+        rpc_client,
+        *payer,
+        &lookup_tables,
+        pool_id,
+        (&token_program_0, &token_program_1),
+    );
+    let user_token_account_0 =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            payer,
+            &pool_state.token_mint_0,
+            &token_program_0,
+        );
+    let user_token_account_1 =
+        spl_associated_token_account::get_associated_token_address_with_program_id(
+            payer,
+            &pool_state.token_mint_1,
+            &token_program_1,
+        );
+    let tick_array_key = |start_index: i32| {
+        Pubkey::find_program_address(
+            &[
+                raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
+                pool_id.as_ref(),
+                &start_index.to_be_bytes(),
+            ],
+            &pool_config.raydium_v3_program,
+        )
+        .0
+    };
+    let bitmap_extension = pool_config.tickarray_bitmap_extension().unwrap();
+
+    // the first version of swap only moves spl tokens
+    let spl_only = token_program_0 == spl_token::id() && token_program_1 == spl_token::id();
+    for &amount in amounts {
+        for (zero_for_one, base_in) in [(true, true), (false, true), (true, false), (false, false)]
+        {
+            let params = format!(
+                "amount:{}, zero_for_one:{}, base_in:{}",
+                amount, zero_for_one, base_in
+            );
+            let mut tick_arrays = load_cur_and_next_tick_arrays(
+                rpc_client,
+                &pool_config,
+                &pool_state,
+                &tickarray_bitmap_extension,
+                zero_for_one,
+                pool_config.max_swap_tick_array_num(),
+            );
+            let quote = match quote_swap(
+                amount,
+                base_in,
+                zero_for_one,
+                &amm_config_state,
+                &pool_state,
+                &tickarray_bitmap_extension,
+                &mut tick_arrays,
+                (&mint0_state, &mint1_state),
+                epoch,
+            ) {
+                Ok(quote) => quote,
+                Err(err) => {
+                    profiler.measure("swap_v2", params, None, Err(err))?;
+                    continue;
+                }
+            };
+            let ticks_crossed = Some(quote.ticks_crossed());
+            let (input_vault, output_vault) = if zero_for_one {
+                (pool_state.token_vault_0, pool_state.token_vault_1)
+            } else {
+                (pool_state.token_vault_1, pool_state.token_vault_0)
+            };
+            let (input_mint, output_mint) = if zero_for_one {
+                (pool_state.token_mint_0, pool_state.token_mint_1)
+            } else {
+                (pool_state.token_mint_1, pool_state.token_mint_0)
+            };
+            let (user_input_token, user_output_token) = if zero_for_one {
+                (user_token_account_0, user_token_account_1)
+            } else {
+                (user_token_account_1, user_token_account_0)
+            };
+            // no slippage bound, the swap is measured whatever it returns
+            let other_amount_threshold = if base_in { 0 } else { u64::MAX };
+            let mut tick_array_keys: VecDeque<Pubkey> = quote
+                .tick_array_indexs
+                .iter()
+                .map(|start_index| tick_array_key(*start_index))
+                .collect();
+            let remaining_accounts = |tick_array_keys: &VecDeque<Pubkey>| -> Vec<AccountMeta> {
+                std::iter::once(AccountMeta::new_readonly(bitmap_extension, false))
+                    .chain(
+                        tick_array_keys
+                            .iter()
+                            .map(|key| AccountMeta::new(*key, false)),
+                    )
+                    .collect()
+            };
+            profiler.measure(
+                "swap_v2",
+                params.clone(),
+                ticks_crossed,
+                swap_v2_instr(
+                    &pool_config,
+                    pool_state.amm_config,
+                    pool_id,
+                    input_vault,
+                    output_vault,
+                    pool_state.observation_key,
+                    user_input_token,
+                    user_output_token,
+                    input_mint,
+                    output_mint,
+                    remaining_accounts(&tick_array_keys),
+                    amount,
+                    other_amount_threshold,
+                    None,
+                    base_in,
+                ),
+            )?;
+            if spl_only {
+                let tick_array = tick_array_keys.pop_front().unwrap();
+                profiler.measure(
+                    "swap",
+                    params,
+                    ticks_crossed,
+                    swap_instr(
+                        &pool_config,
+                        pool_state.amm_config,
+                        pool_id,
+                        input_vault,
+                        output_vault,
+                        pool_state.observation_key,
+                        user_input_token,
+                        user_output_token,
+                        tick_array,
+                        remaining_accounts(&tick_array_keys),
+                        amount,
+                        other_amount_threshold,
+                        None,
+                        base_in,
+                    ),
+                )?;
+            }
+        }
+    }
+
+    let tick_spacing = i32::from(pool_state.tick_spacing);
+    let tick_current = tick_with_spacing(pool_state.tick_current, tick_spacing);
+    let tick_lower_index = tick_current - PROFILE_POSITION_TICK_SPACINGS * tick_spacing;
+    let tick_upper_index = tick_current + (PROFILE_POSITION_TICK_SPACINGS + 1) * tick_spacing;
+    let liquidity = liquidity_math::get_liquidity_from_single_amount_0(
+        pool_state.sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(tick_lower_index)?,
+        tick_math::get_sqrt_price_at_tick(tick_upper_index)?,
+        amounts[0],
+    );
+    let params = format!(
+        "ticks:{}..{}, liquidity:{}",
+        tick_lower_index, tick_upper_index, liquidity
+    );
+    for with_token22_nft in [false, true] {
+        let nft_mint = Keypair::new();
+        let tick_array_lower_start_index =
+            TickArrayState::get_array_start_index(tick_lower_index, pool_state.tick_spacing);
+        let tick_array_upper_start_index =
+            TickArrayState::get_array_start_index(tick_upper_index, pool_state.tick_spacing);
+        let remaining_accounts = vec![AccountMeta::new(bitmap_extension, false)];
+        if with_token22_nft {
+            profiler.measure(
+                "open_position_with_token22_nft",
+                params.clone(),
+                None,
+                open_position_with_token22_nft_instr(
+                    &pool_config,
+                    pool_id,
+                    pool_state.token_vault_0,
+                    pool_state.token_vault_1,
+                    pool_state.token_mint_0,
+                    pool_state.token_mint_1,
+                    nft_mint.pubkey(),
+                    *payer,
+                    user_token_account_0,
+                    user_token_account_1,
+                    remaining_accounts,
+                    liquidity,
+                    u64::MAX,
+                    u64::MAX,
+                    tick_lower_index,
+                    tick_upper_index,
+                    tick_array_lower_start_index,
+                    tick_array_upper_start_index,
+                    false,
+                ),
+            )?;
+        } else {
+            profiler.measure(
+                "open_position_v2",
+                params.clone(),
+                None,
+                open_position_instr(
+                    &pool_config,
+                    pool_id,
+                    pool_state.token_vault_0,
+                    pool_state.token_vault_1,
+                    pool_state.token_mint_0,
+                    pool_state.token_mint_1,
+                    nft_mint.pubkey(),
+                    *payer,
+                    user_token_account_0,
+                    user_token_account_1,
+                    remaining_accounts,
+                    liquidity,
+                    u64::MAX,
+                    u64::MAX,
+                    tick_lower_index,
+                    tick_upper_index,
+                    tick_array_lower_start_index,
+                    tick_array_upper_start_index,
+                    false,
+                ),
+            )?;
+        }
+    }
+
+    let reward_infos: Vec<_> = pool_state
+        .reward_infos
+        .iter()
+        .filter(|reward_info| reward_info.token_mint != Pubkey::default())
+        .collect();
+    profiler.measure(
+        "update_reward_infos",
+        format!("rewards:{}", reward_infos.len()),
+        None,
+        update_reward_infos_instr(&pool_config, pool_id),
+    )?;
+
+    let position_nft_infos =
+        get_all_nft_and_position_by_owner(rpc_client, payer, &pool_config.raydium_v3_program);
+    let positions: Vec<Pubkey> = position_nft_infos
+        .iter()
+        .map(|item| item.position)
+        .collect();
+    let held_position = position_nft_infos
+        .iter()
+        .zip(get_multiple_accounts(rpc_client, &positions)?)
+        .filter_map(|(nft_info, rsp)| {
+            let position =
+                deserialize_anchor_account::<raydium_amm_v3::states::PersonalPositionState>(&rsp?)
+                    .ok()?;
+            Some((nft_info, position))
+        })
+        .find(|(_, position)| position.pool_id == pool_id);
+    let (nft_info, position) = match held_position {
+        Some(held_position) => held_position,
+        None => {
+            eprintln!(
+                "pool:{}, the payer holds no position, increase and decrease not profiled",
+                pool_id
+            );
+            return Ok(profiler.samples);
+        }
+    };
+    // the decrease collects the rewards of the position
+    let reward_mints: Vec<Pubkey> = reward_infos
+        .iter()
+        .map(|reward_info| reward_info.token_mint)
+        .collect();
+    let mut reward_accounts = vec![AccountMeta::new(bitmap_extension, false)];
+    for (reward_info, rsp) in reward_infos
+        .iter()
+        .zip(get_multiple_accounts(rpc_client, &reward_mints)?)
+    {
+        let reward_mint_account = rsp.ok_or(format_err!(
+            "reward mint {} not found",
+            reward_info.token_mint
+        ))?;
+        reward_accounts.push(AccountMeta::new(reward_info.token_vault, false));
+        reward_accounts.push(AccountMeta::new(
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                payer,
+                &reward_info.token_mint,
+                &reward_mint_account.owner,
+            ),
+            false,
+        ));
+        reward_accounts.push(AccountMeta::new(reward_info.token_mint, false));
+    }
+    let params = format!(
+        "ticks:{}..{}, rewards:{}",
+        position.tick_lower_index,
+        position.tick_upper_index,
+        reward_infos.len()
+    );
+    let tick_array_lower_start_index =
+        TickArrayState::get_array_start_index(position.tick_lower_index, pool_state.tick_spacing);
+    let tick_array_upper_start_index =
+        TickArrayState::get_array_start_index(position.tick_upper_index, pool_state.tick_spacing);
+    profiler.measure(
+        "increase_liquidity_v2",
+        format!("{}, liquidity:{}", params, liquidity),
+        None,
+        increase_liquidity_instr(
+            &pool_config,
+            pool_id,
+            pool_state.token_vault_0,
+            pool_state.token_vault_1,
+            pool_state.token_mint_0,
+            pool_state.token_mint_1,
+            position.nft_mint,
+            nft_info.key,
+            user_token_account_0,
+            user_token_account_1,
+            vec![AccountMeta::new(bitmap_extension, false)],
+            liquidity,
+            u64::MAX,
+            u64::MAX,
+            position.tick_lower_index,
+            position.tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+        ),
+    )?;
+    // a decrease of no liquidity only collects the fees and the rewards
+    for (instruction, decreased_liquidity) in [
+        ("decrease_liquidity_v2", position.liquidity / 2),
+        ("collect_fees_and_rewards", 0),
+    ] {
+        profiler.measure(
+            instruction,
+            format!("{}, liquidity:{}", params, decreased_liquidity),
+            None,
+            decrease_liquidity_instr(
+                &pool_config,
+                pool_id,
+                pool_state.token_vault_0,
+                pool_state.token_vault_1,
+                pool_state.token_mint_0,
+                pool_state.token_mint_1,
+                position.nft_mint,
+                nft_info.key,
+                user_token_account_0,
+                user_token_account_1,
+                reward_accounts.clone(),
+                decreased_liquidity,
+                0,
+                0,
+                position.tick_lower_index,
+                position.tick_upper_index,
+                tick_array_lower_start_index,
+                tick_array_upper_start_index,
+            ),
+        )?;
+    }
+    Ok(profiler.samples)
+}
+
+/// Remove `liquidity` from the position of the configured pool, all of it and close the position
+/// if not set, then optionally swap the other token received into `zap_out_to`
+fn exit_position(
+    rpc_client: &RpcClient,
+    pool_config: &ClientConfig,
+    payer: &SharedSigner,
+    position_nft_mint: &Pubkey,
+    liquidity: Option<u128>,
+    zap_out_to: Option<Pubkey>,
+    journal: &mut Journal,
+) -> Result<()> {
+    let pool_id = pool_config.pool_id_account().unwrap();
+    let (position_key, _) = Pubkey::find_program_address(
+        &[
+            raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+            position_nft_mint.as_ref(),
+        ],
+        &pool_config.raydium_v3_program,
+    );
+    let position = deserialize_anchor_account::<raydium_amm_v3::states::PersonalPositionState>(
+        &rpc_client.get_account(&position_key)?,
+    )?;
+    if position.pool_id != pool_id {
+        return Err(format_err!(
+            "position {} is not in the configured pool {}",
+            position_key,
+            pool_id
+        ));
+    }
+    let pool_state = deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+    let nft_info = get_all_nft_and_position_by_owner(
+        rpc_client,
+        &payer.pubkey(),
+        &pool_config.raydium_v3_program,
+    )
+    .into_iter()
+    .find(|nft_info| nft_info.mint == *position_nft_mint)
+    .ok_or(format_err!(
+        "position nft {} is not held by {}",
+        position_nft_mint,
+        payer.pubkey()
+    ))?;
+    let exit = exit_position_instrs(
+        pool_config,
+        rpc_client,
+        &payer.pubkey(),
+        &pool_state,
+        &position,
+        (nft_info.key, nft_info.program),
+        liquidity.unwrap_or(position.liquidity),
+    )?;
+    // the amount received of the other token is swapped, not the balance held before
+    let zap_out_account = match zap_out_to {
+        Some(mint) if mint == pool_state.token_mint_0 => Some(exit.user_token_account_1),
+        Some(mint) if mint == pool_state.token_mint_1 => Some(exit.user_token_account_0),
+        Some(mint) => {
+            return Err(format_err!(
+                "zap out mint {} is not a token of the pool",
+                mint
+            ));
+        }
+        None => None,
+    };
+    // a missing token account holds nothing. Read at the commitment the exit is confirmed at, the
+    // finalized balance doesn't hold the exit yet
+    let token_balance = |token_account: &Pubkey| {
+        rpc_client
+            .get_token_account_balance_with_commitment(token_account, CommitmentConfig::confirmed())
+            .map_or(0, |balance| {
+                balance.value.amount.parse::<u64>().unwrap_or(0)
+            })
+    };
+    let balance_before = zap_out_account.as_ref().map(token_balance);
+
+    let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        1_400_000u32,
+    )];
+    instructions.extend(exit.instructions);
+    let signature = send_keeper_instrs(
+        rpc_client,
+        pool_config,
+        payer,
+        &instructions,
+        journal,
+        "exit",
+    )?;
+    println!("exit:{}", signature);
+
+    if let (Some(mint), Some(account), Some(balance_before)) =
+        (zap_out_to, zap_out_account, balance_before)
+    {
+        let received = token_balance(&account).saturating_sub(balance_before);
+        if received > 0 {
+            let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+                1_400_000u32,
+            )];
+            instructions.extend(zap_out_instrs(
+                pool_config,
+                rpc_client,
+                &payer.pubkey(),
+                &pool_state,
+                &mint,
+                received,
+            )?);
+            let signature = send_keeper_instrs(
+                rpc_client,
+                pool_config,
+                payer,
+                &instructions,
+                journal,
+                "zap-out",
+            )?;
+            println!("zap out {} into {}:{}", received, mint, signature);
+        } else {
+            println!("nothing received to zap out into {}", mint);
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 pub struct Opts {
+    /// The config file, toml with the .toml extension and ini otherwise. client_config.toml when
+    /// it exists, client_config.ini otherwise
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+    /// Override a key of the config file and of the CLMM_<SECTION>_<KEY> environment variables,
+    /// `key=value` for the Global section or `section.key=value`
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    pub config_overrides: Vec<String>,
+    /// The pool profile of the [Pool.<name>] section of the config to use, its keys override the
+    /// [Pool] section
+    #[arg(long, global = true, value_name = "NAME")]
+    pub pool: Option<String>,
+    /// Use the endpoints and the program id of the cluster instead of those of the config file,
+    /// the environment variables and --set still override them
+    #[arg(long, global = true, value_enum)]
+    pub cluster: Option<Cluster>,
+    /// The payer keypair, overrides the payer_path config: a file path, `env:NAME` for the
+    /// environment variable or `-` for the standard input, each holding the json array of the
+    /// keypair bytes or their base58 string, or `prompt://?key=0/0` to derive it from a seed
+    /// phrase
+    #[arg(long, global = true, value_name = "SOURCE")]
+    pub payer: Option<String>,
+    /// Watch-only mode, no keypair is read and the inspection commands use this owner
+    #[arg(long, global = true)]
+    pub owner: Option<Pubkey>,
+    /// Serve the health endpoint of the daemon modes on this address, e.g. 0.0.0.0:8080
+    #[arg(long, global = true)]
+    pub health_addr: Option<String>,
+    /// Exit with status 1 when a daemon mode makes no progress for longer, in seconds
+    #[arg(long, global = true, default_value_t = 600)]
+    pub max_stall_secs: u64,
+    /// Print the accounts each transaction touches, their pda seeds and the rent to pay, instead
+    /// of sending it
+    #[arg(long, global = true)]
+    pub plan: bool,
+    /// Price the transactions with this percentile of the recent prioritization fees of their
+    /// writable accounts, overrides the priority_fee_percentile config
+    #[arg(long, global = true, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub priority_fee_percentile: Option<u8>,
+    /// Sign the transactions with the durable nonce of this account, whose authority is the
+    /// payer, instead of a recent blockhash, e.g. to collect the signatures over hours. Only for
+    /// the commands sending a single transaction at a time.
+    #[arg(long, global = true)]
+    pub nonce_account: Option<Pubkey>,
+    /// Write each transaction as base64 to the file, or to the standard output without a file,
+    /// instead of sending it, to sign it later with a cold wallet or a multisig. With --owner the
+    /// transactions are built for the owner and left unsigned by it.
+    #[arg(long, global = true, num_args = 0..=1, default_missing_value = "-")]
+    pub export_tx: Option<String>,
+    /// Print the endpoint serving each rpc request and its duration to the standard error
+    #[arg(long, global = true)]
+    pub rpc_trace: bool,
+    /// Fetch every account, neither the disk cache nor the slot cache of the daemons is read
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+    /// Output format of the read-only P* commands
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
     #[clap(subcommand)]
     pub command: CommandsName,
 }
@@ -414,14 +2628,16 @@ pub enum CommandsName {
         token_2022: bool,
         #[arg(short, long)]
         enable_freeze: bool,
-        #[arg(short, long)]
+        #[arg(long)]
         enable_close: bool,
-        #[arg(short, long)]
+        #[arg(long)]
         enable_non_transferable: bool,
-        #[arg(short, long)]
+        #[arg(long)]
         enable_permanent_delegate: bool,
         rate_bps: Option<i16>,
         default_account_state: Option<String>,
+        /// The transfer fee basis points and maximum fee of a token-2022 mint
+        #[arg(long, num_args = 2, value_names = ["BASIS_POINTS", "MAXIMUM_FEE"])]
         transfer_fee: Option<Vec<u64>>,
         confidential_transfer_auto_approve: Option<bool>,
     },
@@ -460,6 +2676,19 @@ pub enum CommandsName {
         param: u8,
         keys: Vec<Pubkey>,
     },
+    CreatePoolCreatorPermission {
+        config_index: u16,
+        creator: Pubkey,
+    },
+    ClosePoolCreatorPermission {
+        config_index: u16,
+        creator: Pubkey,
+    },
+    /// Set the lamports a pool creator pays to the fund owner of the config, zero for none
+    UpdateCreatePoolFee {
+        config_index: u16,
+        lamports: u64,
+    },
     CreatePool {
         config_index: u16,
         price: f64,
@@ -468,6 +2697,12 @@ pub enum CommandsName {
         #[arg(short, long, default_value_t = 0)]
         open_time: u64,
     },
+    InitTickArrays {
+        #[arg(short, long)]
+        pool_id: Option<Pubkey>,
+        #[arg(allow_negative_numbers = true)]
+        start_indices: Vec<i32>,
+    },
     InitReward {
         open_time: u64,
         end_time: u64,
@@ -489,13 +2724,31 @@ pub enum CommandsName {
         authority: Option<Pubkey>,
     },
     OpenPosition {
-        tick_lower_price: f64,
-        tick_upper_price: f64,
+        /// The lower and upper prices of the range then the input amount, or only the input
+        /// amount with --range-pct
+        #[arg(
+            num_args = 1..=3,
+            required = true,
+            value_names = ["TICK_LOWER_PRICE", "TICK_UPPER_PRICE", "INPUT_AMOUNT"]
+        )]
+        range_and_amount: Vec<String>,
+        /// Open the range this percent below and above the current price of the pool
+        #[arg(long)]
+        range_pct: Option<f64>,
         #[arg(short, long)]
         is_base_0: bool,
-        input_amount: u64,
         #[arg(short, long)]
         with_metadata: bool,
+        /// Send even if the simulated token deltas deviate from the quote
+        #[arg(long)]
+        force: bool,
+        /// Keypair file of the position nft mint, e.g. a vanity address, a new keypair if not set
+        #[arg(long)]
+        position_nft_keypair: Option<String>,
+        /// Owner of the position nft, which doesn't sign and may be a pda, the payer pays the
+        /// rent and the deposit. The payer if not set.
+        #[arg(long)]
+        position_owner: Option<Pubkey>,
     },
     IncreaseLiquidity {
         tick_lower_price: f64,
@@ -503,13 +2756,27 @@ pub enum CommandsName {
         #[arg(short, long)]
         is_base_0: bool,
         imput_amount: u64,
+        /// Send even if the simulated token deltas deviate from the quote
+        #[arg(long)]
+        force: bool,
     },
     DecreaseLiquidity {
         tick_lower_index: i32,
         tick_upper_index: i32,
+        /// All the liquidity of the position if neither this nor --percent is set
         liquidity: Option<u128>,
+        /// Remove this percent of the liquidity of the position
+        #[arg(
+            long,
+            conflicts_with = "liquidity",
+            value_parser = clap::value_parser!(u8).range(1..=100)
+        )]
+        percent: Option<u8>,
         #[arg(short, long)]
         simulate: bool,
+        /// Send even if the simulated token deltas deviate from the quote
+        #[arg(long)]
+        force: bool,
     },
     Swap {
         input_token: Pubkey,
@@ -520,6 +2787,26 @@ pub enum CommandsName {
         simulate: bool,
         amount: u64,
         limit_price: Option<f64>,
+        /// Send even if the simulated token deltas deviate from the quote
+        #[arg(long)]
+        force: bool,
+        /// Swap in this pool, otherwise all the pools of the pair are quoted and the best is used
+        #[arg(long)]
+        pool_id: Option<Pubkey>,
+        /// Print each step of the quoted swap
+        #[arg(long)]
+        trace: bool,
+        /// Quote the swap on jupiter first and abort when the pool is worse by more than
+        /// jupiter_max_shortfall_bps
+        #[arg(long)]
+        compare_jupiter: bool,
+        /// Submit the swap as a bundle to the jito block engine, out of the public mempool
+        #[arg(long)]
+        jito: bool,
+        /// Lamports tipped to the block engine in the swap transaction, jito_tip_lamports by
+        /// default, 0 for no tip
+        #[arg(long, requires = "jito")]
+        jito_tip: Option<u64>,
     },
     SwapV2 {
         input_token: Pubkey,
@@ -528,16 +2815,41 @@ pub enum CommandsName {
         base_in: bool,
         #[arg(short, long)]
         simulate: bool,
+        /// The amount paid with base_in, including the input transfer fee. Otherwise the amount
+        /// received, after the output transfer fee
         amount: u64,
         limit_price: Option<f64>,
+        /// Send even if the simulated token deltas deviate from the quote
+        #[arg(long)]
+        force: bool,
+        /// Print each step of the quoted swap
+        #[arg(long)]
+        trace: bool,
+        /// Quote the swap on jupiter first and abort when the pool is worse by more than
+        /// jupiter_max_shortfall_bps
+        #[arg(long)]
+        compare_jupiter: bool,
+        /// Submit the swap as a bundle to the jito block engine, out of the public mempool
+        #[arg(long)]
+        jito: bool,
+        /// Lamports tipped to the block engine in the swap transaction, jito_tip_lamports by
+        /// default, 0 for no tip
+        #[arg(long, requires = "jito")]
+        jito_tip: Option<u64>,
     },
     PPositionByOwner {
-        user_wallet: Pubkey,
+        user_wallet: Option<Pubkey>,
     },
     PTickState {
         tick: i32,
         pool_id: Option<Pubkey>,
     },
+    LiquidityProfile {
+        pool_id: Option<Pubkey>,
+        /// Ticks below and above the current tick to profile
+        #[arg(short, long, default_value_t = 1000)]
+        range_ticks: u32,
+    },
     CompareKey {
         key0: Pubkey,
         key1: Pubkey,
@@ -563,6 +2875,14 @@ pub enum CommandsName {
         tick: i32,
         tick_spacing: u16,
     },
+    PriceToTickWithSpacing {
+        price: f64,
+        tick_spacing: u16,
+        #[arg(short, long)]
+        round_up: bool,
+        decimals_0: Option<u8>,
+        decimals_1: Option<u8>,
+    },
     TickArraryStartIndex {
         tick: i32,
         tick_spacing: u16,
@@ -584,6 +2904,12 @@ pub enum CommandsName {
     PPool {
         pool_id: Option<Pubkey>,
     },
+    /// Set the status bits of a pool, `flags` is the raw status or the comma separated
+    /// capabilities to disable: deposit, withdraw, collect_fee, collect_reward, swap or none
+    SetPoolStatus {
+        pool_id: Pubkey,
+        flags: String,
+    },
     PBitmapExtension {
         bitmap_extension: Option<Pubkey>,
     },
@@ -602,40 +2928,678 @@ pub enum CommandsName {
     DecodeTxLog {
         tx_id: String,
     },
+    DecodeBlock {
+        slot: u64,
+    },
+    /// Simulate a historical transaction against the current state and print the difference of
+    /// its token changes and swaps with the recorded ones
+    Replay {
+        tx_id: String,
+    },
+    /// Decode the events of the program in the confirmed blocks of the slot range, both
+    /// included, and print them as json lines
+    DecodeEvents {
+        from_slot: u64,
+        to_slot: u64,
+        /// Only the transactions of this pool
+        #[arg(long)]
+        pool_id: Option<Pubkey>,
+    },
+    IndexEvents {
+        pool_ids: Vec<Pubkey>,
+        /// Seconds between two polls
+        #[arg(short, long, default_value_t = 10)]
+        interval: u64,
+        /// Max number of signatures fetched in each request, also the number of latest
+        /// transactions scanned when a pool has no checkpoint yet
+        #[arg(short, long, default_value_t = 1000)]
+        limit: usize,
+        /// Number of slots before the checkpoint which are scanned again after a restart
+        #[arg(short, long, default_value_t = 150)]
+        overlap_slots: u64,
+    },
+    /// Tail the transactions of the program, or only the ones of the pools, and write their events
+    /// to the index_db sqlite database. The reports read the events offline from it.
+    Index {
+        pool_ids: Vec<Pubkey>,
+        /// Seconds between two polls
+        #[arg(short, long, default_value_t = 10)]
+        interval: u64,
+        /// Max number of signatures fetched in each request, also the number of latest
+        /// transactions scanned without checkpoint yet
+        #[arg(short, long, default_value_t = 1000)]
+        limit: usize,
+        /// Number of slots before the checkpoint which are scanned again after a restart
+        #[arg(short, long, default_value_t = 150)]
+        overlap_slots: u64,
+    },
+    Backfill {
+        pool_id: Pubkey,
+        /// Unix timestamp of the oldest transaction to backfill
+        from_time: i64,
+        /// Number of signatures fetched in each page
+        #[arg(short, long, default_value_t = 1000)]
+        limit: usize,
+        /// Max number of transactions fetched at the same time
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Max number of transaction requests per second
+        #[arg(short, long, default_value_t = 10)]
+        requests_per_second: u32,
+    },
+    Portfolio {
+        /// Owners of the positions, the owner or payer if neither owners nor owners_file is set
+        #[arg(short, long)]
+        owners: Vec<Pubkey>,
+        /// File with one owner pubkey per line
+        #[arg(short = 'f', long)]
+        owners_file: Option<String>,
+    },
+    TaxExport {
+        pool_id: Pubkey,
+        /// Positions to export, all positions of the pool if empty
+        #[arg(short, long)]
+        position_nft_mints: Vec<Pubkey>,
+        /// Csv file to write, printed if not set
+        #[arg(short, long)]
+        out_file: Option<String>,
+    },
+    StopLoss {
+        position_nft_mint: Pubkey,
+        /// Exit when the price of token_0 in token_1 falls below
+        #[arg(long)]
+        below: Option<f64>,
+        /// Exit when the price of token_0 in token_1 rises above
+        #[arg(long)]
+        above: Option<f64>,
+        /// Exit when the position has been out of range for longer, in seconds
+        #[arg(long)]
+        out_of_range_secs: Option<u64>,
+        /// Swap the other token received into this mint of the pool after the exit
+        #[arg(long)]
+        zap_out_to: Option<Pubkey>,
+        /// Seconds between the checks
+        #[arg(short, long, default_value_t = 10)]
+        interval: u64,
+    },
+    TakeProfit {
+        position_nft_mint: Pubkey,
+        /// Exit when the value of the liquidity and fees exceeds this one, in token_1
+        #[arg(long)]
+        value_above: Option<f64>,
+        /// Exit when the value of the fees exceeds this one, in token_1
+        #[arg(long)]
+        fees_above: Option<f64>,
+        /// Exit when the position, out of range at start, is fully converted to the other token
+        #[arg(long)]
+        range_order: bool,
+        /// Percentage of the liquidity removed, the position is closed at 100
+        #[arg(short, long, default_value_t = 100, value_parser = clap::value_parser!(u8).range(1..=100))]
+        percent: u8,
+        /// Swap the other token received into this mint of the pool after the exit
+        #[arg(long)]
+        zap_out_to: Option<Pubkey>,
+        /// Seconds between the checks
+        #[arg(short, long, default_value_t = 10)]
+        interval: u64,
+    },
     GetSupportmintPda {
         mint: Pubkey,
     },
-}
-// #[cfg(not(feature = "async"))]
-fn main() -> Result<()> {
-    println!("Starting...");
-    let client_config = "client_config.ini";
-    let pool_config = load_cfg(&client_config.to_string()).unwrap();
-    // Admin and cluster params.
-    let payer = read_keypair_file(&pool_config.payer_path)?;
-    let admin = read_keypair_file(&pool_config.admin_path)?;
-    // solana rpc client
-    let rpc_client = RpcClient::new(pool_config.http_url.to_string());
-
-    // anchor client.
-    let anchor_config = pool_config.clone();
-    let url = Cluster::Custom(anchor_config.http_url, anchor_config.ws_url);
-    let wallet = read_keypair_file(&pool_config.payer_path)?;
-    let anchor_client = Client::new(url, Rc::new(wallet));
-    let program = anchor_client.program(pool_config.raydium_v3_program)?;
-
-    let opts = Opts::parse();
-    match opts.command {
-        CommandsName::GetSupportmintPda { mint } => {
-            let pda = Pubkey::find_program_address(
-                &[
-                    raydium_amm_v3::states::SUPPORT_MINT_SEED.as_bytes(),
-                    mint.to_bytes().as_ref(),
-                ],
-                &program.id(),
-            )
-            .0;
-            println!("{}", pda);
+    /// Set the trade fee discounts of a config for the holders of `mint`, each tier is
+    /// "min_amount:discount_rate" with the discount in hundredths of a bip, no tier disables them
+    SetFeeDiscount {
+        config_index: u16,
+        mint: Pubkey,
+        tiers: Vec<String>,
+    },
+    /// Pause the swaps and deposits of all the pools for `pause_secs`, zero lifts the pause
+    SetEmergencyPause {
+        pause_secs: u64,
+    },
+    /// Settle the fees earned by a position since its fee growth checkpoints. With `--repair` the
+    /// admin resets a checkpoint which gives more fees than the pool holds unclaimed.
+    SettlePositionFeeGrowth {
+        /// The personal position account
+        position: Pubkey,
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Check every account of the program deserializes with the current layout and is consistent
+    /// with the accounts it refers to, e.g. after a program upgrade
+    ScanState,
+    /// Run a local validator with the program and the accounts of a pool cloned from the
+    /// configured cluster, swaps against it see the real liquidity
+    CloneLocalnet {
+        pool_id: Option<Pubkey>,
+        /// Tick arrays cloned on each side of the current tick
+        #[arg(short, long, default_value_t = 10)]
+        tick_arrays: u16,
+        #[arg(long, default_value = "test-ledger")]
+        ledger: String,
+        /// Start from a new ledger
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Simulate the instructions of the program on pools of a local validator, e.g. the one of
+    /// CloneLocalnet, and report the compute units each consumes: the swaps crossing more ticks
+    /// as the amount grows, the positions opened, increased, decreased and collected, and the
+    /// reward update. Pools of spl and token-2022 mints measure both token programs.
+    Profile {
+        /// The configured pool when none
+        pool_ids: Vec<Pubkey>,
+        /// The swap amounts, the first one also sizes the liquidity deposited
+        #[arg(long, value_delimiter = ',', default_value = "1000,1000000,1000000000")]
+        amounts: Vec<u64>,
+        /// Write the report as json to the file
+        #[arg(long)]
+        out: Option<String>,
+        /// A report written by --out before, the change of each measure is printed
+        #[arg(long)]
+        baseline: Option<String>,
+    },
+    /// Stream the pool price at processed commitment, each update with its slot and the time
+    /// since the previous one
+    Ticker {
+        pool_id: Option<Pubkey>,
+    },
+    /// Watch the pool state over the websocket and print each change of its tick, price or
+    /// liquidity, as json lines with --output json
+    Watch {
+        pool_id: Option<Pubkey>,
+    },
+    /// Add liquidity to the position of the nft, paid by the payer whoever owns the position
+    DonateLiquidity {
+        position_nft_mint: Pubkey,
+        #[arg(short, long)]
+        is_base_0: bool,
+        input_amount: u64,
+    },
+    /// The protocol and fund fees accrued in the pools of a config or in one pool, with the
+    /// amounts already collected from the pools indexed by IndexEvents or Backfill
+    Revenue {
+        #[arg(long, conflicts_with = "pool_id")]
+        config_index: Option<u16>,
+        #[arg(long)]
+        pool_id: Option<Pubkey>,
+    },
+    /// Quote a swap of the configured pool off-chain with the program math, nothing is sent
+    Quote {
+        /// Mint of the token paid
+        input_token: Pubkey,
+        /// Mint of the token received
+        output_token: Pubkey,
+        /// The amount paid with base_in, including the input transfer fee. Otherwise the amount
+        /// received, after the output transfer fee
+        amount: u64,
+        #[arg(short, long)]
+        base_in: bool,
+        /// Print each step of the quoted swap
+        #[arg(long)]
+        trace: bool,
+    },
+    /// Swap from the input mint to the intermediate mint, then to the output mint, through the
+    /// pools of the configured amm config in one transaction. The second hop swaps the minimum
+    /// output of the first one, the intermediate tokens received above it are left in the account.
+    SwapRoute {
+        input_mint: Pubkey,
+        intermediate_mint: Pubkey,
+        output_mint: Pubkey,
+        /// The amount paid, including the input transfer fee
+        amount: u64,
+        #[arg(short, long)]
+        simulate: bool,
+    },
+    /// Quote the round trips of token_0 through both pools of the pair, sold in one and bought back
+    /// in the other, and report the ones returning more than the threshold after the fees
+    ArbScan {
+        pool_a: Pubkey,
+        pool_b: Pubkey,
+        /// The amount of token_0 sold, including its transfer fee
+        amount: u64,
+        /// Minimum profit of a round trip to report, in basis points of the amount
+        #[arg(short, long, default_value_t = 10)]
+        threshold_bps: u64,
+        /// Milliseconds between two scans
+        #[arg(short, long, default_value_t = 2000)]
+        interval_ms: u64,
+        /// Send the two swaps of a reported round trip in one transaction, which fails unless
+        /// it returns the threshold
+        #[arg(long)]
+        execute: bool,
+    },
+    /// Create an address lookup table of the payer with the addresses, or with the accounts of the
+    /// configured pool and the tick arrays around its price when none is given. Add it to
+    /// lookup_tables in the config to use it.
+    CreateLookupTable {
+        addresses: Vec<Pubkey>,
+    },
+    /// Add the addresses missing from the lookup table, or the missing accounts of the configured
+    /// pool and tick arrays around its price when none is given, e.g. after the price moved
+    ExtendLookupTable {
+        table: Pubkey,
+        addresses: Vec<Pubkey>,
+    },
+    /// Deactivate the lookup table before closing it
+    DeactivateLookupTable {
+        table: Pubkey,
+    },
+    /// Close a lookup table deactivated for long enough, the rent goes back to the payer
+    CloseLookupTable {
+        table: Pubkey,
+    },
+    /// Create a durable nonce account of the payer, to sign with --nonce-account
+    CreateNonceAccount,
+    /// Collect the fees and rewards of every position of the wallet, as many positions per
+    /// transaction as fit. The positions without fees or rewards to collect are skipped.
+    CollectAllFees,
+    /// Remove all the liquidity of the position, collect its fees and rewards and close it in one
+    /// transaction. The wrapped SOL received is unwrapped.
+    ClosePosition {
+        position_nft_mint: Pubkey,
+        /// Then close the empty token accounts of the wallet to reclaim their rent
+        #[arg(long)]
+        close_empty_accounts: bool,
+    },
+    /// Withdraw the position when it is out of range and open a new one centered on the current
+    /// price, with the tokens withdrawn and the fees collected
+    Rebalance {
+        position_nft_mint: Pubkey,
+        /// Half width of the new range, in percent of the current price
+        new_range_pct: f64,
+        /// Swap the withdrawn tokens to the proportions of the new range before depositing
+        #[arg(long)]
+        swap: bool,
+        /// Rebalance even if the position is in range
+        #[arg(long)]
+        force: bool,
+    },
+    /// Open a ladder of positions in the configured pool, the range split into buckets of
+    /// consecutive ticks with the liquidity spread by the distribution. As much liquidity is
+    /// deposited as the amounts allow, the slippage is paid on top of them.
+    OpenLadder {
+        lower_price: f64,
+        upper_price: f64,
+        buckets: u16,
+        /// The most token_0 deposited over all the buckets
+        amount_0: u64,
+        /// The most token_1 deposited over all the buckets
+        amount_1: u64,
+        #[arg(long, value_enum, default_value_t = LadderDistribution::Uniform)]
+        distribution: LadderDistribution,
+    },
+    /// Watch the pools of the positions of the wallet over the websocket and alert when the price
+    /// leaves the range of a position or comes within the buffer of its bounds. The positions are
+    /// loaded at start.
+    Monitor {
+        /// Post each alert as json to this url
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Ticks from a bound of the range at which the position is near the bound
+        #[arg(long, default_value_t = 0)]
+        buffer_ticks: u32,
+    },
+    /// Periodically reinvest the fees of the positions of the wallet: collect the fees and
+    /// rewards, swap them to the proportions of the range and increase the liquidity
+    Autocompound {
+        /// Positions to compound, all positions of the wallet if empty
+        #[arg(short, long)]
+        position_nft_mints: Vec<Pubkey>,
+        /// Minimum value of the pending fees of a position to compound it, in token_1 ui amount
+        #[arg(long, default_value_t = 0.0)]
+        min_fees_value: f64,
+        /// Wait for the next round while the estimated compute unit price exceeds this one, in
+        /// micro lamports. 0 for no limit
+        #[arg(long, default_value_t = 0)]
+        max_compute_unit_price: u64,
+        /// Seconds between the rounds
+        #[arg(short, long, default_value_t = 3600)]
+        interval: u64,
+    },
+    /// Rebuild the deposits, withdrawals and fee collections of a position from its transactions
+    /// and print its fees, impermanent loss and net P&L at the current price
+    PositionReport {
+        position_nft_mint: Pubkey,
+        /// Max number of transactions fetched at the same time
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Max number of transaction requests per second
+        #[arg(short, long, default_value_t = 10)]
+        requests_per_second: u32,
+    },
+    /// Print the fees collected from a position over time, from its transactions, as csv to the
+    /// file or to stdout
+    FeeHistory {
+        position_nft_mint: Pubkey,
+        /// Max number of transactions fetched at the same time
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Max number of transaction requests per second
+        #[arg(short, long, default_value_t = 10)]
+        requests_per_second: u32,
+        #[arg(short, long)]
+        out_file: Option<String>,
+    },
+    /// Estimate the fee APR of the pool, of a position and of a range from the recent swap volume
+    EstimateApr {
+        /// Position to estimate, its pool is used instead of the configured one
+        #[arg(long)]
+        position_nft_mint: Option<Pubkey>,
+        /// Estimate a new position of this half width, in percent of the current price
+        #[arg(long)]
+        range_pct: Option<f64>,
+        /// Number of the latest transactions of the pool sampled
+        #[arg(short, long, default_value_t = 1000)]
+        limit: usize,
+        /// Max number of transactions fetched at the same time
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Max number of transaction requests per second
+        #[arg(short, long, default_value_t = 10)]
+        requests_per_second: u32,
+    },
+    /// List the pools of the program, the most liquid first
+    ListPools {
+        /// Only the pools of this mint, on either side
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        /// Only the pools of the amm config of this index
+        #[arg(long)]
+        config_index: Option<u16>,
+    },
+    /// List the amm configs of the program, the fee tiers available to the pools
+    ListConfigs,
+    /// Write the liquidity depth around the current price as csv, one row per initialized tick
+    /// with its price, net liquidity and the active liquidity from it up to the next row
+    ExportDepth {
+        pool_id: Option<Pubkey>,
+        /// Ticks below and above the current tick to export
+        #[arg(short, long, default_value_t = 1000)]
+        range_ticks: u32,
+        /// Csv file to write, printed if not set
+        #[arg(short, long)]
+        out_file: Option<String>,
+    },
+    /// Map the tick arrays around the current tick as flagged in the bitmap of the pool and its
+    /// extension, with the flagged arrays whose account is missing and the gaps without liquidity
+    TickArrayMap {
+        pool_id: Option<Pubkey>,
+        /// Tick arrays below and above the current one to map
+        #[arg(short, long, default_value_t = 20)]
+        arrays: u32,
+    },
+    /// Print the time weighted average price of the pool over the last seconds, interpolated from
+    /// the tick cumulatives of its observation account
+    Twap {
+        pool_id: Option<Pubkey>,
+        /// Length of the window in seconds
+        #[arg(short, long, default_value_t = 300)]
+        seconds: u32,
+    },
+    /// Reconstruct the daily swap volume and fees of the pool from the swap events of its
+    /// transactions since the slot
+    Volume {
+        pool_id: Option<Pubkey>,
+        /// Only the swaps from this slot
+        #[arg(long)]
+        since_slot: u64,
+        /// Number of signatures fetched in each page
+        #[arg(short, long, default_value_t = 1000)]
+        limit: usize,
+        /// Max number of transactions fetched at the same time
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Max number of transaction requests per second
+        #[arg(short, long, default_value_t = 10)]
+        requests_per_second: u32,
+    },
+    /// Serve prometheus gauges of the price and liquidity of the pools of the wallet positions and
+    /// of the configured pool, with the range status and pending fees of each position
+    MetricsExporter {
+        /// Address of the /metrics endpoint
+        #[arg(long, default_value = "0.0.0.0:9100")]
+        addr: String,
+        /// Seconds between two refreshes
+        #[arg(short, long, default_value_t = 15)]
+        interval: u64,
+    },
+    /// Serve quotes of the pools over http at GET /quote?in=&out=&amount=, computed with the swap
+    /// math from their accounts kept in memory. The best pool of the mints is quoted, without fee
+    /// discount. Add &base_in=false to quote the amount received.
+    Serve {
+        /// Pools to quote, the configured one by default
+        pool_ids: Vec<Pubkey>,
+        /// Address of the quote endpoint
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+        /// Milliseconds between two reloads of the pool accounts
+        #[arg(long, default_value_t = 1000)]
+        refresh_ms: u64,
+    },
+    /// Serve the grpc service of proto/clmm.proto: quotes of the pools kept in memory, their price
+    /// streams, and swaps signed by the payer as the hot wallet. Needs the grpc feature.
+    GrpcServe {
+        /// Pools to serve, the configured one by default
+        pool_ids: Vec<Pubkey>,
+        /// Address of the grpc service
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        addr: String,
+        /// Milliseconds between two reloads of the pool accounts
+        #[arg(long, default_value_t = 1000)]
+        refresh_ms: u64,
+    },
+    /// Claim the pending rewards of every position of the wallet, as many positions per
+    /// transaction as fit, creating the missing reward token accounts. The fees of the positions
+    /// are collected with the rewards.
+    HarvestRewards,
+    /// Collect the protocol fees of the pool, or the fund fees with --fund, all of them by
+    /// default, to the admin token accounts unless the recipients are given
+    CollectProtocolFee {
+        pool_id: Option<Pubkey>,
+        #[arg(long)]
+        fund: bool,
+        #[arg(long, default_value_t = u64::MAX)]
+        amount_0: u64,
+        #[arg(long, default_value_t = u64::MAX)]
+        amount_1: u64,
+        #[arg(long)]
+        recipient_0: Option<Pubkey>,
+        #[arg(long)]
+        recipient_1: Option<Pubkey>,
+    },
+    SupportMintAdd {
+        mint: Pubkey,
+    },
+    SupportMintRemove {
+        mint: Pubkey,
+    },
+}
+
+impl CommandsName {
+    /// Commands which sign transactions, they are rejected in watch-only mode
+    fn requires_keypair(&self) -> bool {
+        matches!(
+            self,
+            CommandsName::NewMint { .. }
+                | CommandsName::NewToken { .. }
+                | CommandsName::MintTo { .. }
+                | CommandsName::WrapSol { .. }
+                | CommandsName::UnWrapSol { .. }
+                | CommandsName::CreateConfig { .. }
+                | CommandsName::UpdateConfig { .. }
+                | CommandsName::CreateOperation
+                | CommandsName::UpdateOperation { .. }
+                | CommandsName::SetPoolStatus { .. }
+                | CommandsName::SetFeeDiscount { .. }
+                | CommandsName::SetEmergencyPause { .. }
+                | CommandsName::SettlePositionFeeGrowth { .. }
+                | CommandsName::DonateLiquidity { .. }
+                | CommandsName::SupportMintAdd { .. }
+                | CommandsName::SupportMintRemove { .. }
+                | CommandsName::CreatePoolCreatorPermission { .. }
+                | CommandsName::ClosePoolCreatorPermission { .. }
+                | CommandsName::UpdateCreatePoolFee { .. }
+                | CommandsName::CreatePool { .. }
+                | CommandsName::InitTickArrays { .. }
+                | CommandsName::InitReward { .. }
+                | CommandsName::SetRewardParams { .. }
+                | CommandsName::TransferRewardOwner { .. }
+                | CommandsName::OpenPosition { .. }
+                | CommandsName::IncreaseLiquidity { .. }
+                | CommandsName::DecreaseLiquidity { .. }
+                | CommandsName::Swap { .. }
+                | CommandsName::SwapV2 { .. }
+                | CommandsName::SwapRoute { .. }
+                | CommandsName::ArbScan { execute: true, .. }
+                | CommandsName::CreateLookupTable { .. }
+                | CommandsName::ExtendLookupTable { .. }
+                | CommandsName::DeactivateLookupTable { .. }
+                | CommandsName::CloseLookupTable { .. }
+                | CommandsName::CreateNonceAccount
+                | CommandsName::CollectAllFees
+                | CommandsName::HarvestRewards
+                | CommandsName::ClosePosition { .. }
+                | CommandsName::Rebalance { .. }
+                | CommandsName::OpenLadder { .. }
+                | CommandsName::GrpcServe { .. }
+                | CommandsName::Autocompound { .. }
+                | CommandsName::CollectProtocolFee { .. }
+                | CommandsName::StopLoss { .. }
+                | CommandsName::TakeProfit { .. }
+                | CommandsName::Profile { .. }
+        )
+    }
+}
+// #[cfg(not(feature = "async"))]
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    // the json output is only the document
+    if opts.output == OutputFormat::Text {
+        println!("Starting...");
+    }
+    let client_config = match &opts.config {
+        Some(path) => path.as_str(),
+        None if Path::new("client_config.toml").exists() => "client_config.toml",
+        None => "client_config.ini",
+    };
+    let mut config_overrides = opts.config_overrides.clone();
+    if let Some(payer) = &opts.payer {
+        config_overrides.push(format!("payer_path={}", payer));
+    }
+    let pool_config = load_cfg(
+        client_config,
+        opts.pool.as_deref(),
+        opts.cluster,
+        &config_overrides,
+    )?;
+    // in watch-only mode no private key is read, the inspection commands use the owner pubkey
+    let watch_only_owner = opts.owner.or(pool_config.owner);
+    if watch_only_owner.is_some() && opts.command.requires_keypair() && opts.export_tx.is_none() {
+        return Err(format_err!(
+            "the command signs transactions, which is not possible in watch-only mode"
+        ));
+    }
+    if opts.plan {
+        enable_plan_mode(pool_config.raydium_v3_program);
+    }
+    if let Some(path) = &opts.export_tx {
+        enable_export_mode(path.clone());
+    }
+    let priority_fee_percentile = opts
+        .priority_fee_percentile
+        .unwrap_or(pool_config.priority_fee_percentile);
+    if priority_fee_percentile > 0 {
+        enable_priority_fee_estimate(PriorityFeeEstimate {
+            percentile: priority_fee_percentile.min(100),
+            max_compute_unit_price: pool_config.priority_fee.max_compute_unit_price,
+        });
+    }
+    if pool_config.compute_unit_margin_percent > 0 {
+        enable_simulated_compute_unit_limit(pool_config.compute_unit_margin_percent);
+    }
+    let load_signer = |source: &str| {
+        if let Some(owner) = watch_only_owner {
+            // the exported transactions are built for the owner, who signs them later
+            if opts.export_tx.is_some() {
+                return Ok(SharedSigner::new(NullSigner::new(&owner)));
+            }
+            // never used to sign
+            Ok(SharedSigner::new(Keypair::new()))
+        } else if !opts.command.requires_keypair() && is_interactive_source(source) {
+            // the seed phrase or the standard input is only read by the commands which sign
+            Err(format_err!(
+                "the command doesn't sign, {} is not read: set the owner to inspect with --owner or owner = in [Global]",
+                source
+            ))
+        } else {
+            read_keypair(source).map(SharedSigner::new)
+        }
+    };
+    // Admin and cluster params.
+    let payer = load_signer(&pool_config.payer_path)?;
+    // with a squads multisig the admin is its vault, the admin instructions are proposed to it
+    let admin = match pool_config.squads_vault() {
+        Some(vault) => SharedSigner::new(NullSigner::new(&vault)),
+        // only the commands which sign use the admin, its seed phrase isn't prompted for the others
+        None if !opts.command.requires_keypair()
+            && is_interactive_source(&pool_config.admin_path) =>
+        {
+            SharedSigner::new(NullSigner::new(&Pubkey::default()))
+        }
+        None => load_signer(&pool_config.admin_path)?,
+    };
+    // the instruction builders sign with the same signers
+    let pool_config = pool_config.with_signers(payer.clone(), admin.clone());
+    let pool_config = ClientConfig {
+        nonce_account: opts.nonce_account,
+        ..pool_config
+    };
+    let owner = watch_only_owner.unwrap_or(payer.pubkey());
+    // solana rpc client
+    let rpc_client = new_rpc_client(&pool_config, opts.rpc_trace)?;
+    // the accounts the geyser mirror serves are read through the blocking client only
+    if pool_config.geyser_url.is_none() {
+        enable_async_rpc(FailoverSender::new(
+            &pool_config.http_urls,
+            pool_config.rpc_retry,
+            opts.rpc_trace,
+        ))?;
+    }
+    // cache for the accounts which essentially never change, without a ttl only the accounts
+    // fetched in the same second are read
+    let account_cache = AccountCache::new(
+        &pool_config.cache_dir,
+        &pool_config.http_url,
+        if opts.no_cache {
+            0
+        } else {
+            pool_config.cache_ttl
+        },
+    );
+
+    // anchor client.
+    let anchor_config = pool_config.clone();
+    let url = Cluster::Custom(anchor_config.http_url, anchor_config.ws_url);
+    let anchor_client = Client::new(url, Arc::new(payer.clone()));
+    let program = anchor_client.program(pool_config.raydium_v3_program)?;
+    // liveness of the daemon modes
+    let health = opts
+        .health_addr
+        .as_ref()
+        .map(|addr| HealthMonitor::serve(addr, opts.max_stall_secs))
+        .transpose()?;
+
+    match opts.command {
+        CommandsName::GetSupportmintPda { mint } => {
+            let pda = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::SUPPORT_MINT_SEED.as_bytes(),
+                    mint.to_bytes().as_ref(),
+                ],
+                &program.id(),
+            )
+            .0;
+            println!("{}", pda);
         }
         CommandsName::NewMint {
             authority,
@@ -723,14 +3687,17 @@ fn main() -> Result<()> {
                 decimals as u8,
             )?;
             // send
-            let signers = vec![&payer, &mint];
+            let signers: Vec<&dyn Signer> = vec![&payer, &mint];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &create_and_init_instr,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &[],
+            )?;
             let signature = send_txn(&rpc_client, &txn, true)?;
             println!("{}", signature);
         }
@@ -739,7 +3706,7 @@ fn main() -> Result<()> {
             authority,
             not_ata,
         } => {
-            let mut signers = vec![&payer];
+            let mut signers: Vec<&dyn Signer> = vec![&payer];
             let auxiliary_token_keypair = Keypair::new();
             let create_ata_instr = if not_ata {
                 signers.push(&auxiliary_token_keypair);
@@ -760,12 +3727,15 @@ fn main() -> Result<()> {
             };
             // send
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &create_ata_instr,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &[],
+            )?;
             let signature = send_txn(&rpc_client, &txn, true)?;
             println!("{}", signature);
         }
@@ -786,12 +3756,15 @@ fn main() -> Result<()> {
             // send
             let signers = vec![&payer];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &mint_to_instr,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &[],
+            )?;
             let signature = send_txn(&rpc_client, &txn, true)?;
             println!("{}", signature);
         }
@@ -800,12 +3773,15 @@ fn main() -> Result<()> {
             // send
             let signers = vec![&payer];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &wrap_sol_instr,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &[],
+            )?;
             let signature = send_txn(&rpc_client, &txn, true)?;
             println!("{}", signature);
         }
@@ -815,12 +3791,15 @@ fn main() -> Result<()> {
             // send
             let signers = vec![&payer];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &unwrap_sol_instr,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &[],
+            )?;
             let signature = send_txn(&rpc_client, &txn, true)?;
             println!("{}", signature);
         }
@@ -839,17 +3818,7 @@ fn main() -> Result<()> {
                 protocol_fee_rate,
                 fund_fee_rate,
             )?;
-            // send
-            let signers = vec![&payer, &admin];
-            let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
-                &create_instr,
-                Some(&payer.pubkey()),
-                &signers,
-                recent_hash,
-            );
-            let signature = send_txn(&rpc_client, &txn, true)?;
-            println!("{}", signature);
+            send_admin_instrs(&rpc_client, &pool_config, &create_instr, &payer, &admin)?;
         }
         CommandsName::UpdateConfig {
             config_index,
@@ -872,6 +3841,8 @@ fn main() -> Result<()> {
                     let remaining_key = remaining.unwrap();
                     remaing_accounts.push(AccountMeta::new_readonly(remaining_key, false));
                 }
+                // pool creation mode
+                Some(5) => update_value = value,
                 _ => panic!("error input"),
             }
             let (amm_config_key, __bump) = Pubkey::find_program_address(
@@ -888,45 +3859,35 @@ fn main() -> Result<()> {
                 param,
                 update_value,
             )?;
-            // send
-            let signers = vec![&payer, &admin];
-            let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            send_admin_instrs(
+                &rpc_client,
+                &pool_config,
                 &update_amm_config_instr,
-                Some(&payer.pubkey()),
-                &signers,
-                recent_hash,
-            );
-            let signature = send_txn(&rpc_client, &txn, true)?;
-            println!("{}", signature);
+                &payer,
+                &admin,
+            )?;
+            account_cache.invalidate(&amm_config_key);
         }
         CommandsName::CreateOperation => {
             let create_instr = create_operation_account_instr(&pool_config.clone())?;
             // send
             let signers = vec![&payer, &admin];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &create_instr,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &[],
+            )?;
             let signature = send_txn(&rpc_client, &txn, true)?;
             println!("{}", signature);
         }
         CommandsName::UpdateOperation { param, keys } => {
             let create_instr = update_operation_account_instr(&pool_config.clone(), param, keys)?;
-            // send
-            let signers = vec![&payer, &admin];
-            let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
-                &create_instr,
-                Some(&payer.pubkey()),
-                &signers,
-                recent_hash,
-            );
-            let signature = send_txn(&rpc_client, &txn, true)?;
-            println!("{}", signature);
+            send_admin_instrs(&rpc_client, &pool_config, &create_instr, &payer, &admin)?;
         }
         CommandsName::CreatePool {
             config_index,
@@ -944,7 +3905,7 @@ fn main() -> Result<()> {
             }
             println!("mint0:{}, mint1:{}, price:{}", mint0, mint1, price);
             let load_pubkeys = vec![mint0, mint1];
-            let rsps = rpc_client.get_multiple_accounts(&load_pubkeys)?;
+            let rsps = account_cache.get_multiple_accounts(&rpc_client, &load_pubkeys)?;
             let mint0_owner = rsps[0].clone().unwrap().owner;
             let mint1_owner = rsps[1].clone().unwrap().owner;
             let mint0_account =
@@ -966,6 +3927,34 @@ fn main() -> Result<()> {
                 tick, price, sqrt_price_x64, amm_config_key
             );
 
+            // the permission and the fee receiver are only read when the config requires them
+            let amm_config = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
+                &rpc_client.get_account(&amm_config_key)?,
+            )?;
+            let mut remaining_accounts = Vec::new();
+            if amm_config.pool_creation_mode()?
+                == raydium_amm_v3::states::PoolCreationMode::Whitelist
+            {
+                remaining_accounts.push(AccountMeta::new_readonly(
+                    Pubkey::find_program_address(
+                        &[
+                            raydium_amm_v3::states::POOL_CREATOR_PERMISSION_SEED.as_bytes(),
+                            amm_config_key.as_ref(),
+                            payer.pubkey().as_ref(),
+                        ],
+                        &pool_config.raydium_v3_program,
+                    )
+                    .0,
+                    false,
+                ));
+            }
+            if amm_config.create_pool_fee > 0 && !amm_config.is_owner(payer.pubkey()) {
+                println!(
+                    "create_pool_fee:{} lamports to {}",
+                    amm_config.create_pool_fee, amm_config.fund_owner
+                );
+                remaining_accounts.push(AccountMeta::new(amm_config.fund_owner, false));
+            }
             let create_pool_instr = create_pool_instr(
                 &pool_config.clone(),
                 amm_config_key,
@@ -973,7 +3962,8 @@ fn main() -> Result<()> {
                 mint1,
                 mint0_owner,
                 mint1_owner,
-                pool_config.tickarray_bitmap_extension.unwrap(),
+                pool_config.tickarray_bitmap_extension().unwrap(),
+                remaining_accounts,
                 sqrt_price_x64,
                 open_time,
             )?;
@@ -981,15 +3971,89 @@ fn main() -> Result<()> {
             // send
             let signers = vec![&payer];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &create_pool_instr,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &[],
+            )?;
             let signature = send_txn(&rpc_client, &txn, true)?;
             println!("{}", signature);
         }
+        CommandsName::InitTickArrays {
+            pool_id,
+            start_indices,
+        } => {
+            let pool_id = pool_id.unwrap_or(pool_config.pool_id_account().unwrap());
+            let pool: raydium_amm_v3::states::PoolState = program.account(pool_id)?;
+            // any tick can be input, convert them to the start index of the tick array they sit on
+            let mut tick_array_start_indexs = Vec::new();
+            for tick in start_indices {
+                let start_index = raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                    tick,
+                    pool.tick_spacing,
+                );
+                if !tick_array_start_indexs.contains(&start_index) {
+                    tick_array_start_indexs.push(start_index);
+                }
+            }
+            let tick_array_keys: Vec<Pubkey> = tick_array_start_indexs
+                .iter()
+                .map(|start_index| {
+                    Pubkey::find_program_address(
+                        &[
+                            raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
+                            pool_id.to_bytes().as_ref(),
+                            &start_index.to_be_bytes(),
+                        ],
+                        &pool_config.raydium_v3_program,
+                    )
+                    .0
+                })
+                .collect();
+            let rsps = get_multiple_accounts(&rpc_client, &tick_array_keys)?;
+            let mut uninitialized_start_indexs = Vec::new();
+            for (start_index, rsp) in tick_array_start_indexs.into_iter().zip(rsps) {
+                if rsp.is_some() {
+                    println!("tick array {} is already initialized", start_index);
+                } else {
+                    uninitialized_start_indexs.push(start_index);
+                }
+            }
+            if uninitialized_start_indexs.is_empty() {
+                println!("no tick array need to be initialized");
+            } else {
+                let rent = rpc_client.get_minimum_balance_for_rent_exemption(
+                    raydium_amm_v3::states::TickArrayState::LEN,
+                )?;
+                println!(
+                    "initialize tick arrays:{:?}, rent:{} lamports",
+                    uninitialized_start_indexs,
+                    rent * uninitialized_start_indexs.len() as u64
+                );
+                // one tick array per instruction, the builder fills each transaction
+                let mut builder =
+                    TransactionBuilder::new(payer.pubkey()).compute_unit_limit(1400_000u32);
+                for start_index in uninitialized_start_indexs {
+                    builder.add_group(initialize_tick_arrays_instr(
+                        &pool_config.clone(),
+                        pool_id,
+                        vec![start_index],
+                    )?);
+                }
+                // send
+                let builder = price_transaction_builder(&rpc_client, builder)?;
+                let signers = vec![&payer];
+                let recent_hash = rpc_client.get_latest_blockhash()?;
+                for txn in builder.build_signed(recent_hash, &signers)? {
+                    let signature = send_versioned_txn(&rpc_client, &txn, true)?;
+                    println!("{}", signature);
+                }
+            }
+        }
         CommandsName::InitReward {
             open_time,
             end_time,
@@ -999,9 +4063,9 @@ fn main() -> Result<()> {
             let mint_account = rpc_client.get_account(&reward_mint)?;
             let emissions_per_second_x64 = (emissions * fixed_point_64::Q64 as f64) as u128;
             let program = anchor_client.program(pool_config.raydium_v3_program)?;
-            println!("{}", pool_config.pool_id_account.unwrap());
+            println!("{}", pool_config.pool_id_account().unwrap());
             let pool_account: raydium_amm_v3::states::PoolState =
-                program.account(pool_config.pool_id_account.unwrap())?;
+                program.account(pool_config.pool_id_account().unwrap())?;
             let operator_account_key = Pubkey::find_program_address(
                 &[raydium_amm_v3::states::OPERATION_SEED.as_bytes()],
                 &program.id(),
@@ -1011,7 +4075,7 @@ fn main() -> Result<()> {
             let reward_token_vault = Pubkey::find_program_address(
                 &[
                     raydium_amm_v3::states::POOL_REWARD_VAULT_SEED.as_bytes(),
-                    pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                    pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                     reward_mint.to_bytes().as_ref(),
                 ],
                 &program.id(),
@@ -1020,7 +4084,7 @@ fn main() -> Result<()> {
             let user_reward_token = get_associated_token_address(&admin.pubkey(), &reward_mint);
             let create_instr = initialize_reward_instr(
                 &pool_config.clone(),
-                pool_config.pool_id_account.unwrap(),
+                pool_config.pool_id_account().unwrap(),
                 pool_account.amm_config,
                 operator_account_key,
                 reward_mint,
@@ -1034,12 +4098,15 @@ fn main() -> Result<()> {
             // send
             let signers = vec![&payer, &admin];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &create_instr,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &[],
+            )?;
             let signature = send_txn(&rpc_client, &txn, true)?;
             println!("{}", signature);
         }
@@ -1053,9 +4120,9 @@ fn main() -> Result<()> {
             let emissions_per_second_x64 = (emissions * fixed_point_64::Q64 as f64) as u128;
 
             let program = anchor_client.program(pool_config.raydium_v3_program)?;
-            println!("{}", pool_config.pool_id_account.unwrap());
+            println!("{}", pool_config.pool_id_account().unwrap());
             let pool_account: raydium_amm_v3::states::PoolState =
-                program.account(pool_config.pool_id_account.unwrap())?;
+                program.account(pool_config.pool_id_account().unwrap())?;
             let operator_account_key = Pubkey::find_program_address(
                 &[raydium_amm_v3::states::OPERATION_SEED.as_bytes()],
                 &program.id(),
@@ -1065,7 +4132,7 @@ fn main() -> Result<()> {
             let reward_token_vault = Pubkey::find_program_address(
                 &[
                     raydium_amm_v3::states::POOL_REWARD_VAULT_SEED.as_bytes(),
-                    pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                    pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                     reward_mint.to_bytes().as_ref(),
                 ],
                 &program.id(),
@@ -1075,7 +4142,7 @@ fn main() -> Result<()> {
             let create_instr = set_reward_params_instr(
                 &pool_config.clone(),
                 pool_account.amm_config,
-                pool_config.pool_id_account.unwrap(),
+                pool_config.pool_id_account().unwrap(),
                 reward_token_vault,
                 user_reward_token,
                 operator_account_key,
@@ -1084,17 +4151,7 @@ fn main() -> Result<()> {
                 end_time,
                 emissions_per_second_x64,
             )?;
-            // send
-            let signers = vec![&payer, &admin];
-            let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
-                &create_instr,
-                Some(&payer.pubkey()),
-                &signers,
-                recent_hash,
-            );
-            let signature = send_txn(&rpc_client, &txn, true)?;
-            println!("{}", signature);
+            send_admin_instrs(&rpc_client, &pool_config, &create_instr, &payer, &admin)?;
         }
         CommandsName::TransferRewardOwner {
             pool_id,
@@ -1118,44 +4175,93 @@ fn main() -> Result<()> {
                 // send
                 let signers = vec![&payer, &admin];
                 let recent_hash = rpc_client.get_latest_blockhash()?;
-                let txn = Transaction::new_signed_with_payer(
+                let txn = new_signed_txn(
+                    &rpc_client,
+                    &pool_config,
                     &transfer_reward_owner_instrs,
-                    Some(&payer.pubkey()),
+                    &payer.pubkey(),
                     &signers,
                     recent_hash,
-                );
+                    &[],
+                )?;
                 let signature = send_txn(&rpc_client, &txn, true)?;
                 println!("{}", signature);
             }
         }
         CommandsName::OpenPosition {
-            tick_lower_price,
-            tick_upper_price,
+            range_and_amount,
+            range_pct,
             is_base_0,
-            input_amount,
             with_metadata,
+            force,
+            position_nft_keypair,
+            position_owner,
         } => {
+            let position_owner = position_owner.unwrap_or(payer.pubkey());
+            // read before any request, so a wrong path fails fast
+            let nft_mint = match &position_nft_keypair {
+                Some(path) => read_keypair(path)?,
+                None => Keypair::new(),
+            };
             // load pool to get observation
             let pool: raydium_amm_v3::states::PoolState =
-                program.account(pool_config.pool_id_account.unwrap())?;
+                program.account(pool_config.pool_id_account().unwrap())?;
+            let (tick_lower_price, tick_upper_price, input_amount) =
+                match (range_pct, range_and_amount.as_slice()) {
+                    (None, [lower, upper, amount]) => (
+                        lower.parse::<f64>()?,
+                        upper.parse::<f64>()?,
+                        amount.parse::<u64>()?,
+                    ),
+                    (Some(range_pct), [amount]) => {
+                        if range_pct <= 0.0 || range_pct >= 100.0 {
+                            return Err(format_err!("--range-pct must be between 0 and 100"));
+                        }
+                        let price = sqrt_price_x64_to_price(
+                            pool.sqrt_price_x64,
+                            pool.mint_decimals_0,
+                            pool.mint_decimals_1,
+                        );
+                        println!("price:{}, range:±{}%", price, range_pct);
+                        (
+                            price * (1.0 - range_pct / 100.0),
+                            price * (1.0 + range_pct / 100.0),
+                            amount.parse::<u64>()?,
+                        )
+                    }
+                    (None, _) => {
+                        return Err(format_err!(
+                            "expected the lower price, the upper price and the input amount"
+                        ))
+                    }
+                    (Some(_), _) => {
+                        return Err(format_err!(
+                            "expected only the input amount with --range-pct"
+                        ))
+                    }
+                };
 
-            let tick_lower_price_x64 = price_to_sqrt_price_x64(
+            // both prices are rounded down to valid tick boundaries
+            let (tick_lower_index, tick_lower_price_rounded) = price_to_tick_with_spacing(
                 tick_lower_price,
                 pool.mint_decimals_0,
                 pool.mint_decimals_1,
-            );
-            let tick_upper_price_x64 = price_to_sqrt_price_x64(
+                pool.tick_spacing,
+                false,
+            )?;
+            let (tick_upper_index, tick_upper_price_rounded) = price_to_tick_with_spacing(
                 tick_upper_price,
                 pool.mint_decimals_0,
                 pool.mint_decimals_1,
-            );
-            let tick_lower_index = tick_with_spacing(
-                tick_math::get_tick_at_sqrt_price(tick_lower_price_x64)?,
-                pool.tick_spacing.into(),
-            );
-            let tick_upper_index = tick_with_spacing(
-                tick_math::get_tick_at_sqrt_price(tick_upper_price_x64)?,
-                pool.tick_spacing.into(),
+                pool.tick_spacing,
+                false,
+            )?;
+            println!(
+                "tick_lower_price:{} rounded to {}, tick_upper_price:{} rounded to {}",
+                tick_lower_price,
+                tick_lower_price_rounded,
+                tick_upper_price,
+                tick_upper_price_rounded
             );
             println!(
                 "tick_lower_index:{}, tick_upper_index:{}",
@@ -1226,14 +4332,14 @@ fn main() -> Result<()> {
             // load position
             let position_nft_infos = get_all_nft_and_position_by_owner(
                 &rpc_client,
-                &payer.pubkey(),
+                &position_owner,
                 &pool_config.raydium_v3_program,
             );
             let positions: Vec<Pubkey> = position_nft_infos
                 .iter()
                 .map(|item| item.position)
                 .collect();
-            let rsps = rpc_client.get_multiple_accounts(&positions)?;
+            let rsps = get_multiple_accounts_concurrently(&rpc_client, &positions)?;
             let mut user_positions = Vec::new();
             for rsp in rsps {
                 match rsp {
@@ -1248,7 +4354,7 @@ fn main() -> Result<()> {
             }
             let mut find_position = raydium_amm_v3::states::PersonalPositionState::default();
             for position in user_positions {
-                if position.pool_id == pool_config.pool_id_account.unwrap()
+                if position.pool_id == pool_config.pool_id_account().unwrap()
                     && position.tick_lower_index == tick_lower_index
                     && position.tick_upper_index == tick_upper_index
                 {
@@ -1257,11 +4363,24 @@ fn main() -> Result<()> {
             }
             if find_position.nft_mint == Pubkey::default() {
                 // personal position not exist
-                // new nft mint
-                let nft_mint = Keypair::new();
+                if rpc_client
+                    .get_account_with_commitment(&nft_mint.pubkey(), CommitmentConfig::confirmed())?
+                    .value
+                    .is_some()
+                {
+                    return Err(format_err!(
+                        "the position nft mint {} already exists",
+                        nft_mint.pubkey()
+                    ));
+                }
+                println!(
+                    "position nft mint:{}, owner:{}",
+                    nft_mint.pubkey(),
+                    position_owner
+                );
                 let mut remaining_accounts = Vec::new();
                 remaining_accounts.push(AccountMeta::new(
-                    pool_config.tickarray_bitmap_extension.unwrap(),
+                    pool_config.tickarray_bitmap_extension().unwrap(),
                     false,
                 ));
 
@@ -1269,25 +4388,29 @@ fn main() -> Result<()> {
                 let request_inits_instr =
                     ComputeBudgetInstruction::set_compute_unit_limit(1400_000u32);
                 instructions.push(request_inits_instr);
-                let open_position_instr = open_position_with_token22_nft_instr(
-                    &pool_config.clone(),
-                    pool_config.pool_id_account.unwrap(),
-                    pool.token_vault_0,
-                    pool.token_vault_1,
-                    pool.token_mint_0,
-                    pool.token_mint_1,
-                    nft_mint.pubkey(),
-                    payer.pubkey(),
+                let user_token_account_0 =
                     spl_associated_token_account::get_associated_token_address_with_program_id(
                         &payer.pubkey(),
                         &pool_config.mint0.unwrap(),
                         &transfer_fee.0.owner,
-                    ),
+                    );
+                let user_token_account_1 =
                     spl_associated_token_account::get_associated_token_address_with_program_id(
                         &payer.pubkey(),
                         &pool_config.mint1.unwrap(),
                         &transfer_fee.1.owner,
-                    ),
+                    );
+                let open_position_instr = open_position_with_token22_nft_instr(
+                    &pool_config.clone(),
+                    pool_config.pool_id_account().unwrap(),
+                    pool.token_vault_0,
+                    pool.token_vault_1,
+                    pool.token_mint_0,
+                    pool.token_mint_1,
+                    nft_mint.pubkey(),
+                    position_owner,
+                    user_token_account_0,
+                    user_token_account_1,
                     remaining_accounts,
                     liquidity,
                     amount_0_max,
@@ -1300,14 +4423,43 @@ fn main() -> Result<()> {
                 )?;
                 instructions.extend(open_position_instr);
                 // send
-                let signers = vec![&payer, &nft_mint];
+                let signers: Vec<&dyn Signer> = vec![&payer, &nft_mint];
                 let recent_hash = rpc_client.get_latest_blockhash()?;
-                let txn = Transaction::new_signed_with_payer(
+                let lookup_tables = load_lookup_tables(&rpc_client, &pool_config.lookup_tables)?;
+                let txn = new_signed_txn(
+                    &rpc_client,
+                    &pool_config,
                     &instructions,
-                    Some(&payer.pubkey()),
+                    &payer.pubkey(),
                     &signers,
                     recent_hash,
-                );
+                    &lookup_tables,
+                )?;
+                if !force {
+                    // the quoted deposit without slippage, the transfer fee is paid on top
+                    let deposit_fee = get_pool_mints_inverse_fee(
+                        &rpc_client,
+                        pool.token_mint_0,
+                        pool.token_mint_1,
+                        amount_0,
+                        amount_1,
+                    );
+                    check_simulated_token_deltas(
+                        &rpc_client,
+                        &txn,
+                        &[
+                            (
+                                user_token_account_0,
+                                -i128::from(amount_0 + deposit_fee.0.transfer_fee),
+                            ),
+                            (
+                                user_token_account_1,
+                                -i128::from(amount_1 + deposit_fee.1.transfer_fee),
+                            ),
+                        ],
+                        pool_config.slippage,
+                    )?;
+                }
                 let signature = send_txn(&rpc_client, &txn, true)?;
                 println!("{}", signature);
             } else {
@@ -1320,10 +4472,11 @@ fn main() -> Result<()> {
             tick_upper_price,
             is_base_0,
             imput_amount,
+            force,
         } => {
             // load pool to get observation
             let pool: raydium_amm_v3::states::PoolState =
-                program.account(pool_config.pool_id_account.unwrap())?;
+                program.account(pool_config.pool_id_account().unwrap())?;
 
             // load position
             let position_nft_infos = get_all_nft_and_position_by_owner(
@@ -1335,7 +4488,7 @@ fn main() -> Result<()> {
                 .iter()
                 .map(|item| item.position)
                 .collect();
-            let rsps = rpc_client.get_multiple_accounts(&positions)?;
+            let rsps = get_multiple_accounts_concurrently(&rpc_client, &positions)?;
             let mut user_positions = Vec::new();
             for rsp in rsps {
                 match rsp {
@@ -1349,23 +4502,27 @@ fn main() -> Result<()> {
                 }
             }
 
-            let tick_lower_price_x64 = price_to_sqrt_price_x64(
+            // both prices are rounded down to valid tick boundaries
+            let (tick_lower_index, tick_lower_price_rounded) = price_to_tick_with_spacing(
                 tick_lower_price,
                 pool.mint_decimals_0,
                 pool.mint_decimals_1,
-            );
-            let tick_upper_price_x64 = price_to_sqrt_price_x64(
+                pool.tick_spacing,
+                false,
+            )?;
+            let (tick_upper_index, tick_upper_price_rounded) = price_to_tick_with_spacing(
                 tick_upper_price,
                 pool.mint_decimals_0,
                 pool.mint_decimals_1,
-            );
-            let tick_lower_index = tick_with_spacing(
-                tick_math::get_tick_at_sqrt_price(tick_lower_price_x64)?,
-                pool.tick_spacing.into(),
-            );
-            let tick_upper_index = tick_with_spacing(
-                tick_math::get_tick_at_sqrt_price(tick_upper_price_x64)?,
-                pool.tick_spacing.into(),
+                pool.tick_spacing,
+                false,
+            )?;
+            println!(
+                "tick_lower_price:{} rounded to {}, tick_upper_price:{} rounded to {}",
+                tick_lower_price,
+                tick_lower_price_rounded,
+                tick_upper_price,
+                tick_upper_price_rounded
             );
             println!(
                 "tick_lower_index:{}, tick_upper_index:{}",
@@ -1435,7 +4592,7 @@ fn main() -> Result<()> {
                 );
             let mut find_position = raydium_amm_v3::states::PersonalPositionState::default();
             for position in user_positions {
-                if position.pool_id == pool_config.pool_id_account.unwrap()
+                if position.pool_id == pool_config.pool_id_account().unwrap()
                     && position.tick_lower_index == tick_lower_index
                     && position.tick_upper_index == tick_upper_index
                 {
@@ -1443,7 +4600,7 @@ fn main() -> Result<()> {
                 }
             }
             if find_position.nft_mint != Pubkey::default()
-                && find_position.pool_id == pool_config.pool_id_account.unwrap()
+                && find_position.pool_id == pool_config.pool_id_account().unwrap()
             {
                 let user_nft_token_info = position_nft_infos
                     .iter()
@@ -1452,29 +4609,33 @@ fn main() -> Result<()> {
                 // personal position exist
                 let mut remaining_accounts = Vec::new();
                 remaining_accounts.push(AccountMeta::new_readonly(
-                    pool_config.tickarray_bitmap_extension.unwrap(),
+                    pool_config.tickarray_bitmap_extension().unwrap(),
                     false,
                 ));
 
-                let increase_instr = increase_liquidity_instr(
-                    &pool_config.clone(),
-                    pool_config.pool_id_account.unwrap(),
-                    pool.token_vault_0,
-                    pool.token_vault_1,
-                    pool.token_mint_0,
-                    pool.token_mint_1,
-                    find_position.nft_mint,
-                    user_nft_token_info.key,
+                let user_token_account_0 =
                     spl_associated_token_account::get_associated_token_address_with_program_id(
                         &payer.pubkey(),
                         &pool_config.mint0.unwrap(),
                         &transfer_fee.0.owner,
-                    ),
+                    );
+                let user_token_account_1 =
                     spl_associated_token_account::get_associated_token_address_with_program_id(
                         &payer.pubkey(),
                         &pool_config.mint1.unwrap(),
                         &transfer_fee.0.owner,
-                    ),
+                    );
+                let increase_instr = increase_liquidity_instr(
+                    &pool_config.clone(),
+                    pool_config.pool_id_account().unwrap(),
+                    pool.token_vault_0,
+                    pool.token_vault_1,
+                    pool.token_mint_0,
+                    pool.token_mint_1,
+                    find_position.nft_mint,
+                    user_nft_token_info.key,
+                    user_token_account_0,
+                    user_token_account_1,
                     remaining_accounts,
                     liquidity,
                     amount_0_max,
@@ -1487,12 +4648,41 @@ fn main() -> Result<()> {
                 // send
                 let signers = vec![&payer];
                 let recent_hash = rpc_client.get_latest_blockhash()?;
-                let txn = Transaction::new_signed_with_payer(
+                let lookup_tables = load_lookup_tables(&rpc_client, &pool_config.lookup_tables)?;
+                let txn = new_signed_txn(
+                    &rpc_client,
+                    &pool_config,
                     &increase_instr,
-                    Some(&payer.pubkey()),
+                    &payer.pubkey(),
                     &signers,
                     recent_hash,
-                );
+                    &lookup_tables,
+                )?;
+                if !force {
+                    // the quoted deposit without slippage, the transfer fee is paid on top
+                    let deposit_fee = get_pool_mints_inverse_fee(
+                        &rpc_client,
+                        pool.token_mint_0,
+                        pool.token_mint_1,
+                        amount_0,
+                        amount_1,
+                    );
+                    check_simulated_token_deltas(
+                        &rpc_client,
+                        &txn,
+                        &[
+                            (
+                                user_token_account_0,
+                                -i128::from(amount_0 + deposit_fee.0.transfer_fee),
+                            ),
+                            (
+                                user_token_account_1,
+                                -i128::from(amount_1 + deposit_fee.1.transfer_fee),
+                            ),
+                        ],
+                        pool_config.slippage,
+                    )?;
+                }
                 let signature = send_txn(&rpc_client, &txn, true)?;
                 println!("{}", signature);
             } else {
@@ -1504,11 +4694,13 @@ fn main() -> Result<()> {
             tick_lower_index,
             tick_upper_index,
             liquidity,
+            percent,
             simulate,
+            force,
         } => {
             // load pool to get observation
             let pool: raydium_amm_v3::states::PoolState =
-                program.account(pool_config.pool_id_account.unwrap())?;
+                program.account(pool_config.pool_id_account().unwrap())?;
 
             let tick_array_lower_start_index =
                 raydium_amm_v3::states::TickArrayState::get_array_start_index(
@@ -1530,7 +4722,7 @@ fn main() -> Result<()> {
                 .iter()
                 .map(|item| item.position)
                 .collect();
-            let rsps = rpc_client.get_multiple_accounts(&positions)?;
+            let rsps = get_multiple_accounts_concurrently(&rpc_client, &positions)?;
             let mut user_positions = Vec::new();
             for rsp in rsps {
                 match rsp {
@@ -1545,7 +4737,7 @@ fn main() -> Result<()> {
             }
             let mut find_position = raydium_amm_v3::states::PersonalPositionState::default();
             for position in user_positions {
-                if position.pool_id == pool_config.pool_id_account.unwrap()
+                if position.pool_id == pool_config.pool_id_account().unwrap()
                     && position.tick_lower_index == tick_lower_index
                     && position.tick_upper_index == tick_upper_index
                 {
@@ -1554,7 +4746,7 @@ fn main() -> Result<()> {
                 }
             }
             if find_position.nft_mint != Pubkey::default()
-                && find_position.pool_id == pool_config.pool_id_account.unwrap()
+                && find_position.pool_id == pool_config.pool_id_account().unwrap()
             {
                 let user_nft_token_info = position_nft_infos
                     .iter()
@@ -1573,6 +4765,8 @@ fn main() -> Result<()> {
                 }
                 let liquidity = if let Some(liquidity) = liquidity {
                     liquidity
+                } else if let Some(percent) = percent {
+                    find_position.liquidity * u128::from(percent) / 100
                 } else {
                     find_position.liquidity
                 };
@@ -1603,7 +4797,7 @@ fn main() -> Result<()> {
 
                 let mut remaining_accounts = Vec::new();
                 remaining_accounts.push(AccountMeta::new(
-                    pool_config.tickarray_bitmap_extension.unwrap(),
+                    pool_config.tickarray_bitmap_extension().unwrap(),
                     false,
                 ));
 
@@ -1613,25 +4807,29 @@ fn main() -> Result<()> {
                     .collect();
                 remaining_accounts.append(&mut accounts);
                 // personal position exist
-                let mut decrease_instr = decrease_liquidity_instr(
-                    &pool_config.clone(),
-                    pool_config.pool_id_account.unwrap(),
-                    pool.token_vault_0,
-                    pool.token_vault_1,
-                    pool.token_mint_0,
-                    pool.token_mint_1,
-                    find_position.nft_mint,
-                    user_nft_token_info.key,
+                let user_token_account_0 =
                     spl_associated_token_account::get_associated_token_address_with_program_id(
                         &payer.pubkey(),
                         &pool_config.mint0.unwrap(),
                         &transfer_fee.0.owner,
-                    ),
+                    );
+                let user_token_account_1 =
                     spl_associated_token_account::get_associated_token_address_with_program_id(
                         &payer.pubkey(),
                         &pool_config.mint1.unwrap(),
                         &transfer_fee.1.owner,
-                    ),
+                    );
+                let mut decrease_instr = decrease_liquidity_instr(
+                    &pool_config.clone(),
+                    pool_config.pool_id_account().unwrap(),
+                    pool.token_vault_0,
+                    pool.token_vault_1,
+                    pool.token_mint_0,
+                    pool.token_mint_1,
+                    find_position.nft_mint,
+                    user_nft_token_info.key,
+                    user_token_account_0,
+                    user_token_account_1,
                     remaining_accounts,
                     liquidity,
                     amount_0_min,
@@ -1653,12 +4851,15 @@ fn main() -> Result<()> {
                 // send
                 let signers = vec![&payer];
                 let recent_hash = rpc_client.get_latest_blockhash()?;
-                let txn = Transaction::new_signed_with_payer(
+                let txn = new_signed_txn(
+                    &rpc_client,
+                    &pool_config,
                     &decrease_instr,
-                    Some(&payer.pubkey()),
+                    &payer.pubkey(),
                     &signers,
                     recent_hash,
-                );
+                    &[],
+                )?;
                 if simulate {
                     let ret = simulate_transaction(
                         &rpc_client,
@@ -1668,6 +4869,38 @@ fn main() -> Result<()> {
                     )?;
                     println!("{:#?}", ret);
                 } else {
+                    if !force {
+                        // the pending fees are collected together with the liquidity
+                        let (fees_0, fees_1) = load_position_reports(
+                            &rpc_client,
+                            &pool_config.raydium_v3_program,
+                            &[(payer.pubkey(), user_nft_token_info.position)],
+                        )?
+                        .first()
+                        .map_or((0, 0), |report| (report.fees_0, report.fees_1));
+                        let withdraw_fee = get_pool_mints_transfer_fee(
+                            &rpc_client,
+                            pool.token_mint_0,
+                            pool.token_mint_1,
+                            amount_0 + fees_0,
+                            amount_1 + fees_1,
+                        );
+                        check_simulated_token_deltas(
+                            &rpc_client,
+                            &txn,
+                            &[
+                                (
+                                    user_token_account_0,
+                                    i128::from(amount_0 + fees_0 - withdraw_fee.0.transfer_fee),
+                                ),
+                                (
+                                    user_token_account_1,
+                                    i128::from(amount_1 + fees_1 - withdraw_fee.1.transfer_fee),
+                                ),
+                            ],
+                            pool_config.slippage,
+                        )?;
+                    }
                     let signature = send_txn(&rpc_client, &txn, true)?;
                     println!("{}", signature);
                 }
@@ -1683,18 +4916,69 @@ fn main() -> Result<()> {
             simulate,
             amount,
             limit_price,
+            force,
+            pool_id,
+            trace,
+            compare_jupiter,
+            jito,
+            jito_tip,
         } => {
+            let pool_id = match pool_id {
+                Some(pool_id) => pool_id,
+                None => {
+                    // route to the pool of the pair giving the best execution for the amount
+                    let token_mint = |token: &Pubkey| -> Result<Pubkey> {
+                        let data = rpc_client.get_account(token)?.data;
+                        Ok(StateWithExtensions::<Account>::unpack(&data)?.base.mint)
+                    };
+                    let input_mint = token_mint(&input_token)?;
+                    let output_mint = token_mint(&output_token)?;
+                    let (mint0, mint1) = if input_mint < output_mint {
+                        (input_mint, output_mint)
+                    } else {
+                        (output_mint, input_mint)
+                    };
+                    let pool_ids: Vec<Pubkey> = find_pools_by_mints(
+                        &rpc_client,
+                        &pool_config.raydium_v3_program,
+                        &mint0,
+                        &mint1,
+                    )?
+                    .into_iter()
+                    .map(|(pool_id, _)| pool_id)
+                    .collect();
+                    let quotes = quote_swap_pools(
+                        &rpc_client,
+                        &account_cache,
+                        &pool_config,
+                        &pool_ids,
+                        input_mint,
+                        amount,
+                        base_in,
+                    )?;
+                    let (best_pool_id, _) = *quotes
+                        .first()
+                        .ok_or_else(|| format_err!("no pool of the pair can quote the swap"))?;
+                    println!("best execution pool:{}", best_pool_id);
+                    best_pool_id
+                }
+            };
+            let pool_config = pool_config.with_pool(
+                pool_id,
+                &deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?,
+            );
             // load mult account
             let load_accounts = vec![
                 input_token,
                 output_token,
-                pool_config.amm_config_key,
-                pool_config.pool_id_account.unwrap(),
-                pool_config.tickarray_bitmap_extension.unwrap(),
+                pool_config.pool_id_account().unwrap(),
+                pool_config.tickarray_bitmap_extension().unwrap(),
             ];
             let rsps = rpc_client.get_multiple_accounts(&load_accounts)?;
-            let [user_input_account, user_output_account, amm_config_account, pool_account, tickarray_bitmap_extension_account] =
-                array_ref![rsps, 0, 5];
+            let [user_input_account, user_output_account, pool_account, tickarray_bitmap_extension_account] =
+                array_ref![rsps, 0, 4];
+            let amm_config_account =
+                &Some(account_cache.get_account(&rpc_client, &pool_config.amm_config_key())?);
             let user_input_state =
                 StateWithExtensions::<Account>::unpack(&user_input_account.as_ref().unwrap().data)
                     .unwrap();
@@ -1704,6 +4988,12 @@ fn main() -> Result<()> {
             let amm_config_state = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
                 amm_config_account.as_ref().unwrap(),
             )?;
+            let (mut fee_discount_accounts, amm_config_state) = load_fee_discount(
+                &rpc_client,
+                &pool_config,
+                &amm_config_state,
+                &payer.pubkey(),
+            )?;
             let pool_state = deserialize_anchor_account::<raydium_amm_v3::states::PoolState>(
                 pool_account.as_ref().unwrap(),
             )?;
@@ -1714,12 +5004,13 @@ fn main() -> Result<()> {
             let zero_for_one = user_input_state.base.mint == pool_state.token_mint_0
                 && user_output_state.base.mint == pool_state.token_mint_1;
             // load tick_arrays
-            let mut tick_arrays = load_cur_and_next_five_tick_array(
+            let mut tick_arrays = load_cur_and_next_tick_arrays(
                 &rpc_client,
                 &pool_config,
                 &pool_state,
                 &tickarray_bitmap_extension,
                 zero_for_one,
+                pool_config.max_swap_tick_array_num(),
             );
 
             let mut sqrt_price_limit_x64 = None;
@@ -1732,8 +5023,8 @@ fn main() -> Result<()> {
                 sqrt_price_limit_x64 = Some(sqrt_price_x64);
             }
 
-            let (mut other_amount_threshold, mut tick_array_indexs) =
-                utils::get_out_put_amount_and_remaining_accounts(
+            let (mut other_amount_threshold, mut tick_array_indexs, swap_steps) =
+                utils::get_out_put_amount_with_trace(
                     amount,
                     sqrt_price_limit_x64,
                     zero_for_one,
@@ -1744,10 +5035,29 @@ fn main() -> Result<()> {
                     &mut tick_arrays,
                 )
                 .unwrap();
+            if trace {
+                print_swap_trace(
+                    &swap_steps,
+                    pool_state.mint_decimals_0,
+                    pool_state.mint_decimals_1,
+                );
+            }
             println!(
                 "amount:{}, other_amount_threshold:{}",
                 amount, other_amount_threshold
             );
+            let quote_amount = other_amount_threshold;
+            if compare_jupiter {
+                check_against_jupiter(
+                    &pool_config.jupiter_url,
+                    &user_input_state.base.mint,
+                    &user_output_state.base.mint,
+                    amount,
+                    base_in,
+                    quote_amount,
+                    pool_config.jupiter_max_shortfall_bps,
+                )?;
+            }
             if base_in {
                 // min out
                 other_amount_threshold =
@@ -1761,7 +5071,7 @@ fn main() -> Result<()> {
             let current_or_next_tick_array_key = Pubkey::find_program_address(
                 &[
                     raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
-                    pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                    pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                     &tick_array_indexs.pop_front().unwrap().to_be_bytes(),
                 ],
                 &pool_config.raydium_v3_program,
@@ -1769,7 +5079,7 @@ fn main() -> Result<()> {
             .0;
             let mut remaining_accounts = Vec::new();
             remaining_accounts.push(AccountMeta::new_readonly(
-                pool_config.tickarray_bitmap_extension.unwrap(),
+                pool_config.tickarray_bitmap_extension().unwrap(),
                 false,
             ));
             let mut accounts = tick_array_indexs
@@ -1779,7 +5089,7 @@ fn main() -> Result<()> {
                         Pubkey::find_program_address(
                             &[
                                 raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
-                                pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                                pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                                 &index.to_be_bytes(),
                             ],
                             &pool_config.raydium_v3_program,
@@ -1790,13 +5100,14 @@ fn main() -> Result<()> {
                 })
                 .collect();
             remaining_accounts.append(&mut accounts);
+            remaining_accounts.append(&mut fee_discount_accounts);
             let mut instructions = Vec::new();
             let request_inits_instr = ComputeBudgetInstruction::set_compute_unit_limit(1400_000u32);
             instructions.push(request_inits_instr);
             let swap_instr = swap_instr(
                 &pool_config.clone(),
                 pool_state.amm_config,
-                pool_config.pool_id_account.unwrap(),
+                pool_config.pool_id_account().unwrap(),
                 if zero_for_one {
                     pool_state.token_vault_0
                 } else {
@@ -1819,21 +5130,73 @@ fn main() -> Result<()> {
             )
             .unwrap();
             instructions.extend(swap_instr);
+            // the tip is paid by the swap transaction, it lands only with the swap
+            let jito_client = if jito {
+                let jito_client = JitoClient::new(&pool_config.jito_url)?;
+                let tip = jito_tip.unwrap_or(pool_config.jito_tip_lamports);
+                if tip > 0 {
+                    instructions.push(jito_client.tip_instruction(&payer.pubkey(), tip)?);
+                }
+                Some(jito_client)
+            } else {
+                None
+            };
             // send
             let signers = vec![&payer];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let lookup_tables = load_lookup_tables(&rpc_client, &pool_config.lookup_tables)?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &instructions,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &lookup_tables,
+            )?;
             if simulate {
                 let ret =
                     simulate_transaction(&rpc_client, &txn, true, CommitmentConfig::confirmed())?;
                 println!("{:#?}", ret);
             } else {
-                let signature = send_txn(&rpc_client, &txn, true)?;
+                if !force {
+                    let (input_delta, output_delta) = if base_in {
+                        (amount, quote_amount)
+                    } else {
+                        (quote_amount, amount)
+                    };
+                    check_simulated_token_deltas(
+                        &rpc_client,
+                        &txn,
+                        &[
+                            (input_token, -i128::from(input_delta)),
+                            (output_token, i128::from(output_delta)),
+                        ],
+                        pool_config.slippage,
+                    )?;
+                }
+                let signature = if let Some(jito_client) = &jito_client {
+                    send_bundle_and_confirm(&rpc_client, jito_client, &[txn])?
+                } else if pool_config.priority_fee.max_compute_unit_price > 0 {
+                    send_txn_with_priority_fee_escalation(
+                        &rpc_client,
+                        &instructions,
+                        &pool_config.priority_fee,
+                        |instructions, blockhash| {
+                            new_signed_txn(
+                                &rpc_client,
+                                &pool_config,
+                                instructions,
+                                &payer.pubkey(),
+                                &signers,
+                                blockhash,
+                                &lookup_tables,
+                            )
+                        },
+                    )?
+                } else {
+                    send_txn(&rpc_client, &txn, true)?
+                };
                 println!("{}", signature);
             }
         }
@@ -1844,21 +5207,31 @@ fn main() -> Result<()> {
             simulate,
             amount,
             limit_price,
+            force,
+            trace,
+            compare_jupiter,
+            jito,
+            jito_tip,
         } => {
             // load mult account
             let load_accounts = vec![
                 input_token,
                 output_token,
-                pool_config.amm_config_key,
-                pool_config.pool_id_account.unwrap(),
-                pool_config.tickarray_bitmap_extension.unwrap(),
-                pool_config.mint0.unwrap(),
-                pool_config.mint1.unwrap(),
+                pool_config.pool_id_account().unwrap(),
+                pool_config.tickarray_bitmap_extension().unwrap(),
             ];
             let rsps = rpc_client.get_multiple_accounts(&load_accounts)?;
             let epoch = rpc_client.get_epoch_info().unwrap().epoch;
-            let [user_input_account, user_output_account, amm_config_account, pool_account, tickarray_bitmap_extension_account, mint0_account, mint1_account] =
-                array_ref![rsps, 0, 7];
+            let [user_input_account, user_output_account, pool_account, tickarray_bitmap_extension_account] =
+                array_ref![rsps, 0, 4];
+            // amm config and mints rarely change, load them from the cache
+            let static_accounts = vec![
+                pool_config.amm_config_key(),
+                pool_config.mint0.unwrap(),
+                pool_config.mint1.unwrap(),
+            ];
+            let static_rsps = account_cache.get_multiple_accounts(&rpc_client, &static_accounts)?;
+            let [amm_config_account, mint0_account, mint1_account] = array_ref![static_rsps, 0, 3];
 
             let user_input_token_data = user_input_account.clone().unwrap().data;
             let user_input_state = StateWithExtensions::<Account>::unpack(&user_input_token_data)?;
@@ -1872,6 +5245,12 @@ fn main() -> Result<()> {
             let amm_config_state = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
                 amm_config_account.as_ref().unwrap(),
             )?;
+            let (mut fee_discount_accounts, amm_config_state) = load_fee_discount(
+                &rpc_client,
+                &pool_config,
+                &amm_config_state,
+                &payer.pubkey(),
+            )?;
             let pool_state = deserialize_anchor_account::<raydium_amm_v3::states::PoolState>(
                 pool_account.as_ref().unwrap(),
             )?;
@@ -1882,23 +5261,37 @@ fn main() -> Result<()> {
             let zero_for_one = user_input_state.base.mint == pool_state.token_mint_0
                 && user_output_state.base.mint == pool_state.token_mint_1;
 
-            let transfer_fee = if base_in {
-                if zero_for_one {
-                    get_transfer_fee(&mint0_state, epoch, amount)
+            // the pool receives the input without its transfer fee, and sends the output with
+            // its transfer fee so that the user receives exactly the amount out
+            let amount_specified = if base_in {
+                let transfer_fee = if zero_for_one {
+                    get_transfer_fee_with_next_epoch(&mint0_state, epoch, amount)
                 } else {
-                    get_transfer_fee(&mint1_state, epoch, amount)
-                }
+                    get_transfer_fee_with_next_epoch(&mint1_state, epoch, amount)
+                };
+                amount.checked_sub(transfer_fee).unwrap()
             } else {
-                0
+                let transfer_fee = if zero_for_one {
+                    get_transfer_inverse_fee_with_next_epoch(&mint1_state, epoch, amount)
+                } else {
+                    get_transfer_inverse_fee_with_next_epoch(&mint0_state, epoch, amount)
+                };
+                if transfer_fee > 0 {
+                    println!(
+                        "amount out:{}, grossed up by the output transfer fee:{}",
+                        amount, transfer_fee
+                    );
+                }
+                amount.checked_add(transfer_fee).unwrap()
             };
-            let amount_specified = amount.checked_sub(transfer_fee).unwrap();
             // load tick_arrays
-            let mut tick_arrays = load_cur_and_next_five_tick_array(
+            let mut tick_arrays = load_cur_and_next_tick_arrays(
                 &rpc_client,
                 &pool_config,
                 &pool_state,
                 &tickarray_bitmap_extension,
                 zero_for_one,
+                pool_config.max_swap_tick_array_num(),
             );
 
             let mut sqrt_price_limit_x64 = None;
@@ -1911,8 +5304,8 @@ fn main() -> Result<()> {
                 sqrt_price_limit_x64 = Some(sqrt_price_x64);
             }
 
-            let (mut other_amount_threshold, tick_array_indexs) =
-                utils::get_out_put_amount_and_remaining_accounts(
+            let (mut other_amount_threshold, tick_array_indexs, swap_steps) =
+                utils::get_out_put_amount_with_trace(
                     amount_specified,
                     sqrt_price_limit_x64,
                     zero_for_one,
@@ -1923,10 +5316,29 @@ fn main() -> Result<()> {
                     &mut tick_arrays,
                 )
                 .unwrap();
+            if trace {
+                print_swap_trace(
+                    &swap_steps,
+                    pool_state.mint_decimals_0,
+                    pool_state.mint_decimals_1,
+                );
+            }
             println!(
                 "amount:{}, other_amount_threshold:{}",
                 amount, other_amount_threshold
             );
+            let quote_amount = other_amount_threshold;
+            if compare_jupiter {
+                check_against_jupiter(
+                    &pool_config.jupiter_url,
+                    &user_input_state.base.mint,
+                    &user_output_state.base.mint,
+                    amount,
+                    base_in,
+                    quote_amount,
+                    pool_config.jupiter_max_shortfall_bps,
+                )?;
+            }
             if base_in {
                 // calc mint out amount with slippage
                 other_amount_threshold =
@@ -1937,16 +5349,24 @@ fn main() -> Result<()> {
                     amount_with_slippage(other_amount_threshold, pool_config.slippage, true);
                 // calc max in with transfer_fee
                 let transfer_fee = if zero_for_one {
-                    get_transfer_inverse_fee(&mint0_state, epoch, other_amount_threshold)
+                    get_transfer_inverse_fee_with_next_epoch(
+                        &mint0_state,
+                        epoch,
+                        other_amount_threshold,
+                    )
                 } else {
-                    get_transfer_inverse_fee(&mint1_state, epoch, other_amount_threshold)
+                    get_transfer_inverse_fee_with_next_epoch(
+                        &mint1_state,
+                        epoch,
+                        other_amount_threshold,
+                    )
                 };
                 other_amount_threshold += transfer_fee;
             }
 
             let mut remaining_accounts = Vec::new();
             remaining_accounts.push(AccountMeta::new_readonly(
-                pool_config.tickarray_bitmap_extension.unwrap(),
+                pool_config.tickarray_bitmap_extension().unwrap(),
                 false,
             ));
             let mut accounts = tick_array_indexs
@@ -1956,7 +5376,7 @@ fn main() -> Result<()> {
                         Pubkey::find_program_address(
                             &[
                                 raydium_amm_v3::states::TICK_ARRAY_SEED.as_bytes(),
-                                pool_config.pool_id_account.unwrap().to_bytes().as_ref(),
+                                pool_config.pool_id_account().unwrap().to_bytes().as_ref(),
                                 &index.to_be_bytes(),
                             ],
                             &pool_config.raydium_v3_program,
@@ -1967,13 +5387,14 @@ fn main() -> Result<()> {
                 })
                 .collect();
             remaining_accounts.append(&mut accounts);
+            remaining_accounts.append(&mut fee_discount_accounts);
             let mut instructions = Vec::new();
             let request_inits_instr = ComputeBudgetInstruction::set_compute_unit_limit(1400_000u32);
             instructions.push(request_inits_instr);
             let swap_instr = swap_v2_instr(
                 &pool_config.clone(),
                 pool_state.amm_config,
-                pool_config.pool_id_account.unwrap(),
+                pool_config.pool_id_account().unwrap(),
                 if zero_for_one {
                     pool_state.token_vault_0
                 } else {
@@ -2005,25 +5426,92 @@ fn main() -> Result<()> {
             )
             .unwrap();
             instructions.extend(swap_instr);
+            // the tip is paid by the swap transaction, it lands only with the swap
+            let jito_client = if jito {
+                let jito_client = JitoClient::new(&pool_config.jito_url)?;
+                let tip = jito_tip.unwrap_or(pool_config.jito_tip_lamports);
+                if tip > 0 {
+                    instructions.push(jito_client.tip_instruction(&payer.pubkey(), tip)?);
+                }
+                Some(jito_client)
+            } else {
+                None
+            };
             // send
             let signers = vec![&payer];
             let recent_hash = rpc_client.get_latest_blockhash()?;
-            let txn = Transaction::new_signed_with_payer(
+            let lookup_tables = load_lookup_tables(&rpc_client, &pool_config.lookup_tables)?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
                 &instructions,
-                Some(&payer.pubkey()),
+                &payer.pubkey(),
                 &signers,
                 recent_hash,
-            );
+                &lookup_tables,
+            )?;
             if simulate {
                 let ret =
                     simulate_transaction(&rpc_client, &txn, true, CommitmentConfig::confirmed())?;
                 println!("{:#?}", ret);
             } else {
-                let signature = send_txn(&rpc_client, &txn, true)?;
+                if !force {
+                    let (input_mint_state, output_mint_state) = if zero_for_one {
+                        (&mint0_state, &mint1_state)
+                    } else {
+                        (&mint1_state, &mint0_state)
+                    };
+                    // the transfer fee of the input is charged on top of the amount swapped and
+                    // the one of the output is deducted from the amount received
+                    let (input_delta, output_delta) = if base_in {
+                        (
+                            amount,
+                            quote_amount - get_transfer_fee(output_mint_state, epoch, quote_amount),
+                        )
+                    } else {
+                        (
+                            quote_amount
+                                + get_transfer_inverse_fee(input_mint_state, epoch, quote_amount),
+                            amount,
+                        )
+                    };
+                    check_simulated_token_deltas(
+                        &rpc_client,
+                        &txn,
+                        &[
+                            (input_token, -i128::from(input_delta)),
+                            (output_token, i128::from(output_delta)),
+                        ],
+                        pool_config.slippage,
+                    )?;
+                }
+                let signature = if let Some(jito_client) = &jito_client {
+                    send_bundle_and_confirm(&rpc_client, jito_client, &[txn])?
+                } else if pool_config.priority_fee.max_compute_unit_price > 0 {
+                    send_txn_with_priority_fee_escalation(
+                        &rpc_client,
+                        &instructions,
+                        &pool_config.priority_fee,
+                        |instructions, blockhash| {
+                            new_signed_txn(
+                                &rpc_client,
+                                &pool_config,
+                                instructions,
+                                &payer.pubkey(),
+                                &signers,
+                                blockhash,
+                                &lookup_tables,
+                            )
+                        },
+                    )?
+                } else {
+                    send_txn(&rpc_client, &txn, true)?
+                };
                 println!("{}", signature);
             }
         }
         CommandsName::PPositionByOwner { user_wallet } => {
+            let user_wallet = user_wallet.unwrap_or(owner);
             // load position
             let position_nft_infos = get_all_nft_and_position_by_owner(
                 &rpc_client,
@@ -2034,7 +5522,8 @@ fn main() -> Result<()> {
                 .iter()
                 .map(|item| item.position)
                 .collect();
-            let rsps = rpc_client.get_multiple_accounts(&positions)?;
+            let rsps = get_multiple_accounts_concurrently(&rpc_client, &positions)?;
+            let mut printer = AccountPrinter::new(opts.output);
             let mut user_positions = Vec::new();
             for rsp in rsps {
                 match rsp {
@@ -2050,19 +5539,25 @@ fn main() -> Result<()> {
                             ],
                             &program.id(),
                         );
-                        println!("id:{}, lower:{}, upper:{}, liquidity:{}, fees_owed_0:{}, fees_owed_1:{}, fee_growth_inside_0:{}, fee_growth_inside_1:{}", personal_position_key, position.tick_lower_index, position.tick_upper_index, position.liquidity, position.token_fees_owed_0, position.token_fees_owed_1, position.fee_growth_inside_0_last_x64, position.fee_growth_inside_1_last_x64);
+                        printer.account(
+                            None,
+                            &PersonalPositionDisplay::new(&position)
+                                .with_key(personal_position_key),
+                        );
                         user_positions.push(position);
                     }
                 }
             }
+            printer.finish(false);
         }
         CommandsName::PTickState { tick, pool_id } => {
             let pool_id = if let Some(pool_id) = pool_id {
                 pool_id
             } else {
-                pool_config.pool_id_account.unwrap()
+                pool_config.pool_id_account().unwrap()
             };
-            println!("pool_id:{}", pool_id);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(format!("pool_id:{}", pool_id));
             let pool: raydium_amm_v3::states::PoolState = program.account(pool_id)?;
 
             let tick_array_start_index =
@@ -2084,7 +5579,81 @@ fn main() -> Result<()> {
             let tick_state = tick_array_account
                 .get_tick_state_mut(tick, pool.tick_spacing.into())
                 .unwrap();
-            println!("{:?}", tick_state);
+            printer.text(format!("{:?}", tick_state));
+            let tick_json = TickDisplay(tick_state).to_json();
+
+            let ticks = load_initialized_ticks(&rpc_client, &program.id(), &pool_id)?;
+            let (below, above) = neighbor_ticks(&ticks, tick);
+            for (side, neighbor) in [("below", below), ("above", above)] {
+                match neighbor {
+                    Some(neighbor) => printer.text(format!(
+                        "nearest initialized tick {}:{}, liquidity_net:{}, liquidity_gross:{}",
+                        side, neighbor.tick, neighbor.liquidity_net, neighbor.liquidity_gross
+                    )),
+                    None => printer.text(format!("nearest initialized tick {}:none", side)),
+                }
+            }
+            let neighbor_json = |neighbor: Option<InitializedTick>| {
+                neighbor.map(|neighbor| {
+                    serde_json::json!({
+                        "tick": neighbor.tick,
+                        "liquidity_net": neighbor.liquidity_net.to_string(),
+                        "liquidity_gross": neighbor.liquidity_gross.to_string(),
+                    })
+                })
+            };
+
+            // walk the initialized ticks between the tick and the current tick
+            let tick_current = pool.tick_current;
+            let (from, to) = (tick.min(tick_current), tick.max(tick_current));
+            printer.text(format!("tick_current:{}", tick_current));
+            let mut ticks_between = Vec::new();
+            for t in ticks.iter().filter(|t| from <= t.tick && t.tick <= to) {
+                let active_liquidity = active_liquidity_at(&ticks, t.tick);
+                printer.text(format!(
+                    "tick:{}, liquidity_net:{}, liquidity_gross:{}, active_liquidity:{}",
+                    t.tick, t.liquidity_net, t.liquidity_gross, active_liquidity
+                ));
+                ticks_between.push(serde_json::json!({
+                    "tick": t.tick,
+                    "liquidity_net": t.liquidity_net.to_string(),
+                    "liquidity_gross": t.liquidity_gross.to_string(),
+                    "active_liquidity": active_liquidity.to_string(),
+                }));
+            }
+            let check = check_liquidity(&ticks, tick_current, pool.liquidity);
+            printer.text(format!(
+                "liquidity_net_sum:{}, reconstructed_liquidity:{}, pool_liquidity:{}",
+                check.liquidity_net_sum, check.reconstructed_liquidity, check.pool_liquidity
+            ));
+            if !check.gross_violations.is_empty() {
+                printer.text(format!(
+                    "liquidity_net exceeds liquidity_gross at ticks:{:?}",
+                    check.gross_violations
+                ));
+            }
+            printer.text(format!(
+                "liquidity accounting is {}",
+                if check.is_consistent() {
+                    "consistent"
+                } else {
+                    "inconsistent"
+                }
+            ));
+            printer.json(serde_json::json!({
+                "pool_id": pool_id.to_string(),
+                "tick": tick_json,
+                "below": neighbor_json(below),
+                "above": neighbor_json(above),
+                "tick_current": tick_current,
+                "ticks_between": ticks_between,
+                "liquidity_net_sum": check.liquidity_net_sum.to_string(),
+                "reconstructed_liquidity": check.reconstructed_liquidity.to_string(),
+                "pool_liquidity": check.pool_liquidity.to_string(),
+                "gross_violations": check.gross_violations,
+                "consistent": check.is_consistent(),
+            }));
+            printer.finish(true);
         }
         CommandsName::CompareKey { key0, key1 } => {
             let mut token_mint_0 = key0;
@@ -2095,36 +5664,58 @@ fn main() -> Result<()> {
             println!("mint0:{}, mint1:{}", token_mint_0, token_mint_1);
         }
         CommandsName::PMint { mint } => {
-            let mint_data = &rpc_client.get_account_data(&mint)?;
+            let mint_data = &account_cache.get_account(&rpc_client, &mint)?.data;
             let mint_state = StateWithExtensions::<Mint>::unpack(mint_data)?;
-            println!("mint_state:{:?}", mint_state);
-            let extensions = get_account_extensions(&mint_state);
-            println!("mint_extensions:{:#?}", extensions);
+            if opts.output == OutputFormat::Json {
+                let mut printer = AccountPrinter::new(opts.output);
+                printer.account(Some(("mint", mint)), &MintDisplay(&mint_state));
+                printer.finish(true);
+            } else {
+                println!("mint_state:{:?}", mint_state);
+                let extensions = get_account_extensions(&mint_state);
+                println!("mint_extensions:{:#?}", extensions);
+            }
         }
         CommandsName::PToken { token } => {
             let token_data = &rpc_client.get_account_data(&token)?;
             let token_state = StateWithExtensions::<Account>::unpack(token_data)?;
-            println!("token_state:{:?}", token_state);
-            let extensions = get_account_extensions(&token_state);
-            println!("token_extensions:{:#?}", extensions);
+            if opts.output == OutputFormat::Json {
+                let mut printer = AccountPrinter::new(opts.output);
+                printer.account(Some(("token", token)), &TokenAccountDisplay(&token_state));
+                printer.finish(true);
+            } else {
+                println!("token_state:{:?}", token_state);
+                let extensions = get_account_extensions(&token_state);
+                println!("token_extensions:{:#?}", extensions);
+            }
         }
         CommandsName::POperation => {
             let (operation_account_key, __bump) = Pubkey::find_program_address(
                 &[raydium_amm_v3::states::OPERATION_SEED.as_bytes()],
                 &program.id(),
             );
-            println!("{}", operation_account_key);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(operation_account_key);
             let operation_account: raydium_amm_v3::states::OperationState =
                 program.account(operation_account_key)?;
-            println!("{:#?}", operation_account);
+            printer.account(
+                Some(("operation", operation_account_key)),
+                &OperationDisplay(&operation_account),
+            );
+            printer.finish(true);
         }
         CommandsName::PObservation => {
             let pool: raydium_amm_v3::states::PoolState =
-                program.account(pool_config.pool_id_account.unwrap())?;
-            println!("{}", pool.observation_key);
+                program.account(pool_config.pool_id_account().unwrap())?;
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(pool.observation_key);
             let observation_account: raydium_amm_v3::states::ObservationState =
                 program.account(pool.observation_key)?;
-            println!("{:#?}", observation_account);
+            printer.account(
+                Some(("observation", pool.observation_key)),
+                &ObservationDisplay(&observation_account),
+            );
+            printer.finish(true);
         }
         CommandsName::PConfig { config_index } => {
             let (amm_config_key, __bump) = Pubkey::find_program_address(
@@ -2134,10 +5725,16 @@ fn main() -> Result<()> {
                 ],
                 &program.id(),
             );
-            println!("{}", amm_config_key);
-            let amm_config_account: raydium_amm_v3::states::AmmConfig =
-                program.account(amm_config_key)?;
-            println!("{:#?}", amm_config_account);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(amm_config_key);
+            let amm_config_account = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
+                &account_cache.get_account(&rpc_client, &amm_config_key)?,
+            )?;
+            printer.account(
+                Some(("amm_config", amm_config_key)),
+                &AmmConfigDisplay(&amm_config_account),
+            );
+            printer.finish(true);
         }
         CommandsName::PriceToTick { price } => {
             println!("price:{}, tick:{}", price, price_to_tick(price));
@@ -2153,6 +5750,25 @@ fn main() -> Result<()> {
                 tick_with_spacing(tick, tick_spacing as i32)
             );
         }
+        CommandsName::PriceToTickWithSpacing {
+            price,
+            tick_spacing,
+            round_up,
+            decimals_0,
+            decimals_1,
+        } => {
+            let (tick, tick_price) = price_to_tick_with_spacing(
+                price,
+                decimals_0.unwrap_or(0),
+                decimals_1.unwrap_or(0),
+                tick_spacing,
+                round_up,
+            )?;
+            println!(
+                "price:{}, tick_spacing:{}, round_up:{}, tick:{}, tick_price:{}",
+                price, tick_spacing, round_up, tick, tick_price
+            );
+        }
         CommandsName::TickArraryStartIndex { tick, tick_spacing } => {
             println!(
                 "tick:{}, tick_spacing:{},tick_array_start_index:{}",
@@ -2167,7 +5783,7 @@ fn main() -> Result<()> {
             liquidity,
         } => {
             let pool_account: raydium_amm_v3::states::PoolState =
-                program.account(pool_config.pool_id_account.unwrap())?;
+                program.account(pool_config.pool_id_account().unwrap())?;
             let amounts = raydium_amm_v3::libraries::get_delta_amounts_signed(
                 pool_account.tick_current,
                 pool_account.sqrt_price_x64,
@@ -2181,29 +5797,52 @@ fn main() -> Result<()> {
             let pool_id = if let Some(pool_id) = pool_id {
                 pool_id
             } else {
-                pool_config.pool_id_account.unwrap()
+                pool_config.pool_id_account().unwrap()
             };
-            println!("pool_id:{}", pool_id);
-            let position_accounts_by_pool = rpc_client.get_program_accounts_with_config(
-                &pool_config.raydium_v3_program,
-                RpcProgramAccountsConfig {
-                    filters: Some(vec![
-                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
-                            8 + 1 + size_of::<Pubkey>(),
-                            &pool_id.to_bytes(),
-                        )),
-                        RpcFilterType::DataSize(
-                            raydium_amm_v3::states::PersonalPositionState::LEN as u64,
-                        ),
-                    ]),
-                    account_config: RpcAccountInfoConfig {
-                        encoding: Some(UiAccountEncoding::Base64),
-                        ..RpcAccountInfoConfig::default()
-                    },
-                    with_context: Some(false),
-                    sort_results: None,
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(format!("pool_id:{}", pool_id));
+            let positions_config = RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                        8 + 1 + size_of::<Pubkey>(),
+                        &pool_id.to_bytes(),
+                    )),
+                    RpcFilterType::DataSize(
+                        raydium_amm_v3::states::PersonalPositionState::LEN as u64,
+                    ),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..RpcAccountInfoConfig::default()
                 },
-            )?;
+                with_context: Some(false),
+                sort_results: None,
+            };
+            // the pool is loaded while the program accounts are scanned
+            let (pool_account, position_accounts_by_pool) = match async_rpc() {
+                Some(async_rpc) => {
+                    let client = async_rpc.client();
+                    let (pool_account, position_accounts) = async_rpc.block_on(async {
+                        tokio::join!(
+                            client.get_account(&pool_id),
+                            client.get_program_accounts_with_config(
+                                &pool_config.raydium_v3_program,
+                                positions_config,
+                            )
+                        )
+                    });
+                    (pool_account?, position_accounts?)
+                }
+                None => (
+                    rpc_client.get_account(&pool_id)?,
+                    rpc_client.get_program_accounts_with_config(
+                        &pool_config.raydium_v3_program,
+                        positions_config,
+                    )?,
+                ),
+            };
+            let pool =
+                deserialize_anchor_account::<raydium_amm_v3::states::PoolState>(&pool_account)?;
 
             let mut total_fees_owed_0 = 0;
             let mut total_fees_owed_1 = 0;
@@ -2213,36 +5852,31 @@ fn main() -> Result<()> {
                     raydium_amm_v3::states::PersonalPositionState,
                 >(&position.1)?;
                 if personal_position.pool_id == pool_id {
-                    println!(
-                        "personal_position:{}, lower:{}, upper:{}, liquidity:{}, token_fees_owed_0:{}, token_fees_owed_1:{}, reward_amount_owed:{}, fee_growth_inside:{}, fee_growth_inside_1:{}, reward_inside:{}",
-                        position.0,
-                        personal_position.tick_lower_index,
-                        personal_position.tick_upper_index,
-                        personal_position.liquidity,
-                        personal_position.token_fees_owed_0,
-                        personal_position.token_fees_owed_1,
-                        personal_position.reward_infos[0].reward_amount_owed,
-                        personal_position.fee_growth_inside_0_last_x64,
-                        personal_position.fee_growth_inside_1_last_x64,
-                        personal_position.reward_infos[0].growth_inside_last_x64,
+                    printer.account(
+                        None,
+                        &PersonalPositionDisplay::new(&personal_position)
+                            .with_key(position.0)
+                            .with_decimals(pool.mint_decimals_0, pool.mint_decimals_1),
                     );
                     total_fees_owed_0 += personal_position.token_fees_owed_0;
                     total_fees_owed_1 += personal_position.token_fees_owed_1;
                     total_reward_owed += personal_position.reward_infos[0].reward_amount_owed;
                 }
             }
-            println!(
+            printer.text(format!(
                 "total_fees_owed_0:{}, total_fees_owed_1:{}, total_reward_owed:{}",
                 total_fees_owed_0, total_fees_owed_1, total_reward_owed
-            );
+            ));
+            printer.finish(false);
         }
         CommandsName::PProtocolPositionByPool { pool_id } => {
             let pool_id = if let Some(pool_id) = pool_id {
                 pool_id
             } else {
-                pool_config.pool_id_account.unwrap()
+                pool_config.pool_id_account().unwrap()
             };
-            println!("pool_id:{}", pool_id);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(format!("pool_id:{}", pool_id));
             let position_accounts_by_pool = rpc_client.get_program_accounts_with_config(
                 &pool_config.raydium_v3_program,
                 RpcProgramAccountsConfig {
@@ -2269,23 +5903,23 @@ fn main() -> Result<()> {
                     raydium_amm_v3::states::ProtocolPositionState,
                 >(&position.1)?;
                 if protocol_position.pool_id == pool_id {
-                    println!(
-                        "protocol_position:{} lower_index:{}, upper_index:{}, liquidity:{}",
-                        position.0,
-                        protocol_position.tick_lower_index,
-                        protocol_position.tick_upper_index,
-                        protocol_position.liquidity,
+                    printer.text(format!("protocol_position:{}", position.0));
+                    printer.account(
+                        Some(("protocol_position", position.0)),
+                        &ProtocolPositionDisplay(&protocol_position),
                     );
                 }
             }
+            printer.finish(false);
         }
         CommandsName::PTickArrayByPool { pool_id } => {
             let pool_id = if let Some(pool_id) = pool_id {
                 pool_id
             } else {
-                pool_config.pool_id_account.unwrap()
+                pool_config.pool_id_account().unwrap()
             };
-            println!("pool_id:{}", pool_id);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(format!("pool_id:{}", pool_id));
             let tick_arrays_by_pool = rpc_client.get_program_accounts_with_config(
                 &pool_config.raydium_v3_program,
                 RpcProgramAccountsConfig {
@@ -2302,55 +5936,81 @@ fn main() -> Result<()> {
                 },
             )?;
 
-            for tick_array in tick_arrays_by_pool {
-                let tick_array_state = deserialize_anchor_account::<
-                    raydium_amm_v3::states::TickArrayState,
-                >(&tick_array.1)?;
+            for tick_array in tick_arrays_by_pool.iter() {
+                let tick_array_state = zero_copy_account::<raydium_amm_v3::states::TickArrayState>(
+                    &tick_array.1.data,
+                )?;
                 if tick_array_state.pool_id == pool_id {
-                    println!(
+                    printer.text(format!(
                         "tick_array:{}, {}, {}",
                         tick_array.0,
                         identity(tick_array_state.start_tick_index),
                         identity(tick_array_state.initialized_tick_count)
-                    );
-                    for tick_state in tick_array_state.ticks {
+                    ));
+                    let mut ticks = Vec::new();
+                    for tick_state in tick_array_state.ticks.iter() {
                         if tick_state.liquidity_gross != 0 {
-                            println!("{:#?}", tick_state);
+                            printer.text(TickDisplay(tick_state));
+                            ticks.push(TickDisplay(tick_state).to_json());
                         }
                     }
+                    printer.json(serde_json::json!({
+                        "tick_array": tick_array.0.to_string(),
+                        "start_tick_index": identity(tick_array_state.start_tick_index),
+                        "initialized_tick_count": identity(tick_array_state.initialized_tick_count),
+                        "ticks": ticks,
+                    }));
                 }
             }
+            printer.finish(false);
         }
         CommandsName::PPool { pool_id } => {
             let pool_id = if let Some(pool_id) = pool_id {
                 pool_id
             } else {
-                pool_config.pool_id_account.unwrap()
+                pool_config.pool_id_account().unwrap()
             };
-            println!("pool_id:{}", pool_id);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(format!("pool_id:{}", pool_id));
             let pool_account: raydium_amm_v3::states::PoolState = program.account(pool_id)?;
-            println!("{:#?}", pool_account);
+            printer.account(Some(("pool_id", pool_id)), &PoolDisplay(&pool_account));
+            printer.finish(true);
         }
         CommandsName::PBitmapExtension { bitmap_extension } => {
             let bitmap_extension = if let Some(bitmap_extension) = bitmap_extension {
                 bitmap_extension
             } else {
-                pool_config.tickarray_bitmap_extension.unwrap()
+                pool_config.tickarray_bitmap_extension().unwrap()
             };
-            println!("bitmap_extension:{}", bitmap_extension);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.text(format!("bitmap_extension:{}", bitmap_extension));
             let bitmap_extension_account: raydium_amm_v3::states::TickArrayBitmapExtension =
                 program.account(bitmap_extension)?;
-            println!("{:#?}", bitmap_extension_account);
+            printer.account(
+                Some(("bitmap_extension", bitmap_extension)),
+                &BitmapExtensionDisplay(&bitmap_extension_account),
+            );
+            printer.finish(true);
         }
         CommandsName::PProtocol { protocol_id } => {
             let protocol_account: raydium_amm_v3::states::ProtocolPositionState =
                 program.account(protocol_id)?;
-            println!("{:#?}", protocol_account);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.account(
+                Some(("protocol_position", protocol_id)),
+                &ProtocolPositionDisplay(&protocol_account),
+            );
+            printer.finish(true);
         }
         CommandsName::PPersonal { personal_id } => {
             let personal_account: raydium_amm_v3::states::PersonalPositionState =
                 program.account(personal_id)?;
-            println!("{:#?}", personal_account);
+            let mut printer = AccountPrinter::new(opts.output);
+            printer.account(
+                None,
+                &PersonalPositionDisplay::new(&personal_account).with_key(personal_id),
+            );
+            printer.finish(true);
         }
         CommandsName::DecodeInstruction { instr_hex_data } => {
             handle_program_instruction(&instr_hex_data, InstructionDecodeType::BaseHex)?;
@@ -2390,7 +6050,3401 @@ fn main() -> Result<()> {
             // decode logs
             parse_program_event(&pool_config.raydium_v3_program.to_string(), meta.clone())?;
         }
-    }
-
-    Ok(())
+        CommandsName::DecodeBlock { slot } => {
+            let program_str = pool_config.raydium_v3_program.to_string();
+            let block = rpc_client.get_block_with_config(
+                slot,
+                RpcBlockConfig {
+                    encoding: Some(UiTransactionEncoding::Json),
+                    transaction_details: Some(TransactionDetails::Full),
+                    rewards: Some(false),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )?;
+            let transactions = block.transactions.unwrap_or_default();
+            let tx_count = transactions.len();
+            let invoke_log = format!("Program {} invoke", program_str);
+            let mut summary = BTreeMap::new();
+            let mut program_tx_count = 0;
+            let mut failed_tx_count = 0;
+            for tx in transactions {
+                let meta = match tx.meta {
+                    Some(meta) => meta,
+                    None => continue,
+                };
+                // the program is invoked directly or by cpi, possibly via a lookup table
+                let touches_program = match &meta.log_messages {
+                    OptionSerializer::Some(logs) => logs.iter().any(|l| l.starts_with(&invoke_log)),
+                    _ => false,
+                };
+                if !touches_program {
+                    continue;
+                }
+                program_tx_count += 1;
+                let signature = match &tx.transaction {
+                    EncodedTransaction::Json(ui_tx) => ui_tx.signatures[0].clone(),
+                    _ => String::new(),
+                };
+                println!("transaction {}", signature);
+                if meta.err.is_some() {
+                    failed_tx_count += 1;
+                    println!("failed: {:?}", meta.err);
+                }
+                // decode instruction data
+                parse_program_instruction(&program_str, tx.transaction, Some(meta.clone()))?;
+                // decode logs, failed transactions don't change any pool
+                parse_program_event(&program_str, Some(meta.clone()))?;
+                if meta.err.is_none() {
+                    aggregate_program_event(&program_str, &meta, &mut summary)?;
+                }
+            }
+            println!(
+                "slot:{}, block_time:{:?}, transactions:{}, program transactions:{}, failed:{}",
+                slot, block.block_time, tx_count, program_tx_count, failed_tx_count
+            );
+            for (pool_id, activity) in summary {
+                println!("pool:{}", pool_id);
+                println!("    transactions:{}", activity.transactions);
+                if activity.created {
+                    println!("    created in this block");
+                }
+                println!(
+                    "    swaps:{}, swappers:{}, amount_0:{}, amount_1:{}",
+                    activity.swaps,
+                    activity.swappers.len(),
+                    activity.swap_amount_0,
+                    activity.swap_amount_1
+                );
+                if let (Some(first), Some(last)) =
+                    (activity.first_swap_tick, activity.last_swap_tick)
+                {
+                    println!(
+                        "    tick after first swap:{}, after last swap:{}",
+                        first, last
+                    );
+                }
+                println!(
+                    "    liquidity changes:{}, positions opened:{}, protocol fee collects:{}",
+                    activity.liquidity_changes,
+                    activity.positions_opened,
+                    activity.protocol_fee_collects
+                );
+            }
+        }
+        CommandsName::Replay { tx_id } => {
+            let program_str = pool_config.raydium_v3_program.to_string();
+            let recorded = load_recorded_transaction(
+                &rpc_client,
+                &program_str,
+                &Signature::from_str(&tx_id)?,
+            )?;
+            let replayed = replay_transaction(&rpc_client, &program_str, &recorded)?;
+            println!(
+                "transaction {} of slot {}, recorded:{}, replayed:{}",
+                tx_id,
+                recorded.slot,
+                recorded.outcome.err.as_deref().unwrap_or("success"),
+                replayed.err.as_deref().unwrap_or("success")
+            );
+            if replayed.err.is_none() {
+                for (recorded_delta, replayed_delta) in recorded
+                    .outcome
+                    .token_deltas
+                    .iter()
+                    .zip(replayed.token_deltas.iter())
+                {
+                    println!(
+                        "token_account:{}, mint:{}, recorded delta:{}, replayed delta:{}, difference:{}",
+                        recorded_delta.account,
+                        recorded_delta.mint,
+                        recorded_delta.delta,
+                        replayed_delta.delta,
+                        replayed_delta.delta - recorded_delta.delta
+                    );
+                }
+            }
+            let swaps = |events: &[IndexedEventData]| -> Vec<(String, u64, u64, u128, i32)> {
+                events
+                    .iter()
+                    .filter_map(|event| match event {
+                        IndexedEventData::Swap {
+                            pool_state,
+                            amount_0,
+                            amount_1,
+                            sqrt_price_x64,
+                            tick,
+                            ..
+                        } => Some((
+                            pool_state.clone(),
+                            *amount_0,
+                            *amount_1,
+                            *sqrt_price_x64,
+                            *tick,
+                        )),
+                        _ => None,
+                    })
+                    .collect()
+            };
+            let recorded_swaps = swaps(&recorded.outcome.events);
+            let replayed_swaps = swaps(&replayed.events);
+            let mut pool_decimals = HashMap::new();
+            for (pool_str, ..) in recorded_swaps.iter().chain(replayed_swaps.iter()) {
+                if !pool_decimals.contains_key(pool_str) {
+                    let pool_state = deserialize_anchor_account::<PoolState>(
+                        &rpc_client.get_account(&Pubkey::from_str(pool_str)?)?,
+                    )?;
+                    pool_decimals.insert(
+                        pool_str.clone(),
+                        (pool_state.mint_decimals_0, pool_state.mint_decimals_1),
+                    );
+                }
+            }
+            let describe = |swap: Option<&(String, u64, u64, u128, i32)>| match swap {
+                Some((pool_str, amount_0, amount_1, sqrt_price_x64, tick)) => {
+                    let (decimals_0, decimals_1) = pool_decimals[pool_str];
+                    format!(
+                        "amount_0:{}, amount_1:{}, tick after:{}, price after:{}",
+                        amount_0,
+                        amount_1,
+                        tick,
+                        sqrt_price_x64_to_price(*sqrt_price_x64, decimals_0, decimals_1)
+                    )
+                }
+                None => "none".to_string(),
+            };
+            for i in 0..recorded_swaps.len().max(replayed_swaps.len()) {
+                let pool_str = recorded_swaps
+                    .get(i)
+                    .or(replayed_swaps.get(i))
+                    .map(|swap| swap.0.clone())
+                    .unwrap_or_default();
+                println!("swap {} in pool {}", i, pool_str);
+                println!("    recorded {}", describe(recorded_swaps.get(i)));
+                println!("    replayed {}", describe(replayed_swaps.get(i)));
+            }
+        }
+        CommandsName::DecodeEvents {
+            from_slot,
+            to_slot,
+            pool_id,
+        } => {
+            if from_slot > to_slot {
+                return Err(format_err!("from_slot is after to_slot"));
+            }
+            let program_str = pool_config.raydium_v3_program.to_string();
+            // the skipped slots have no block
+            let slots = rpc_client.get_blocks_with_commitment(
+                from_slot,
+                Some(to_slot),
+                CommitmentConfig::confirmed(),
+            )?;
+            let mut event_count = 0;
+            for slot in slots.iter() {
+                for event in block_events(&rpc_client, &program_str, *slot, pool_id.as_ref())? {
+                    println!("{}", serde_json::to_string(&event)?);
+                    event_count += 1;
+                }
+            }
+            // stderr keeps stdout parseable as json lines
+            eprintln!(
+                "{} blocks from slot {} to {}, {} events",
+                slots.len(),
+                from_slot,
+                to_slot,
+                event_count
+            );
+        }
+        CommandsName::IndexEvents {
+            pool_ids,
+            interval,
+            limit,
+            overlap_slots,
+        } => {
+            let program_str = pool_config.raydium_v3_program.to_string();
+            let event_index = EventIndex::new(&pool_config.index_dir, &pool_config.http_url);
+            loop {
+                for pool_id in pool_ids.iter() {
+                    let mut pool_index = event_index.load(pool_id)?;
+                    // events are recorded at confirmed and only finalized once their slot is
+                    let new_events = index_pool_events(
+                        &rpc_client,
+                        &program_str,
+                        pool_id,
+                        &mut pool_index,
+                        limit,
+                        overlap_slots,
+                    )?;
+                    let (finalized, rolled_back) =
+                        finalize_pool_events(&rpc_client, &mut pool_index)?;
+                    event_index.save(pool_id, &pool_index)?;
+                    println!(
+                        "pool:{}, new:{}, finalized:{}, rolled back:{}, provisional:{}, checkpoint slot:{:?}",
+                        pool_id,
+                        new_events,
+                        finalized,
+                        rolled_back,
+                        pool_index.provisional_count(),
+                        pool_index.checkpoint.as_ref().map(|checkpoint| checkpoint.slot)
+                    );
+                    if let Some(health) = &health {
+                        health.heartbeat(
+                            pool_index
+                                .checkpoint
+                                .as_ref()
+                                .map(|checkpoint| checkpoint.slot),
+                        );
+                    }
+                }
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+            }
+        }
+        #[cfg(feature = "sqlite")]
+        CommandsName::Index {
+            pool_ids,
+            interval,
+            limit,
+            overlap_slots,
+        } => {
+            let index_db = pool_config
+                .index_db
+                .as_ref()
+                .ok_or(format_err!("index_db is not set in the config"))?;
+            let mut event_db = EventDb::open(index_db)?;
+            let program_str = pool_config.raydium_v3_program.to_string();
+            // the transactions of the program are the ones of all its pools
+            let addresses = if pool_ids.is_empty() {
+                vec![pool_config.raydium_v3_program]
+            } else {
+                pool_ids
+            };
+            loop {
+                for address in addresses.iter() {
+                    let mut pending = event_db.load_pending(address, overlap_slots)?;
+                    let new_events = index_pool_events(
+                        &rpc_client,
+                        &program_str,
+                        address,
+                        &mut pending,
+                        limit,
+                        overlap_slots,
+                    )?;
+                    let (finalized, rolled_back) = finalize_pool_events(&rpc_client, &mut pending)?;
+                    event_db.save_pending(address, &pending)?;
+                    println!(
+                        "address:{}, new:{}, finalized:{}, rolled back:{}, provisional:{}, checkpoint slot:{:?}",
+                        address,
+                        new_events,
+                        finalized,
+                        rolled_back,
+                        pending.provisional_count(),
+                        pending.checkpoint.as_ref().map(|checkpoint| checkpoint.slot)
+                    );
+                    if let Some(health) = &health {
+                        health.heartbeat(
+                            pending
+                                .checkpoint
+                                .as_ref()
+                                .map(|checkpoint| checkpoint.slot),
+                        );
+                    }
+                }
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+            }
+        }
+        #[cfg(not(feature = "sqlite"))]
+        CommandsName::Index { .. } => {
+            return Err(format_err!(
+                "Index needs a client built with --features sqlite"
+            ));
+        }
+        CommandsName::Backfill {
+            pool_id,
+            from_time,
+            limit,
+            concurrency,
+            requests_per_second,
+        } => {
+            let program_str = pool_config.raydium_v3_program.to_string();
+            let event_index = EventIndex::new(&pool_config.index_dir, &pool_config.http_url);
+            let backfill_config = BackfillConfig {
+                from_time,
+                limit,
+                concurrency,
+                requests_per_second,
+            };
+            let mut pool_index = event_index.load(&pool_id)?;
+            // an interrupted backfill resumes from its cursor
+            let mut before = pool_index
+                .backfill_cursor
+                .as_ref()
+                .map(|cursor| Signature::from_str(cursor))
+                .transpose()?;
+            loop {
+                let (new_events, next_before) = backfill_pool_events(
+                    &rpc_client,
+                    &program_str,
+                    &pool_id,
+                    &mut pool_index,
+                    before,
+                    &backfill_config,
+                )?;
+                pool_index.backfill_cursor = next_before.map(|signature| signature.to_string());
+                event_index.save(&pool_id, &pool_index)?;
+                println!(
+                    "pool:{}, backfilled events:{}, cursor:{:?}",
+                    pool_id, new_events, pool_index.backfill_cursor
+                );
+                match next_before {
+                    Some(next_before) => before = Some(next_before),
+                    None => break,
+                }
+            }
+            let (finalized, rolled_back) = finalize_pool_events(&rpc_client, &mut pool_index)?;
+            event_index.save(&pool_id, &pool_index)?;
+            println!(
+                "pool:{}, events:{}, finalized:{}, rolled back:{}, live indexing resumes from slot:{:?}",
+                pool_id,
+                pool_index.events.len(),
+                finalized,
+                rolled_back,
+                pool_index.checkpoint.as_ref().map(|checkpoint| checkpoint.slot)
+            );
+        }
+        CommandsName::Portfolio {
+            mut owners,
+            owners_file,
+        } => {
+            if let Some(owners_file) = owners_file {
+                for line in std::fs::read_to_string(&owners_file)?.lines() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    owners.push(Pubkey::from_str(line)?);
+                }
+            }
+            if owners.is_empty() {
+                owners.push(owner);
+            }
+            owners.sort();
+            owners.dedup();
+            // the nft accounts of all the owners are loaded at the same time
+            let owned_positions: Vec<(Pubkey, Pubkey)> = std::thread::scope(|scope| {
+                let handles: Vec<_> = owners
+                    .iter()
+                    .map(|owner| {
+                        let rpc_client = &rpc_client;
+                        let program_id = &pool_config.raydium_v3_program;
+                        scope.spawn(move || {
+                            get_all_nft_and_position_by_owner(rpc_client, owner, program_id)
+                                .into_iter()
+                                .map(|nft| (*owner, nft.position))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().unwrap())
+                    .collect()
+            });
+            let reports = load_position_reports(
+                &rpc_client,
+                &pool_config.raydium_v3_program,
+                &owned_positions,
+            )?;
+            for owner in owners.iter() {
+                let owner_reports: Vec<&PositionReport> = reports
+                    .iter()
+                    .filter(|report| report.owner == *owner)
+                    .collect();
+                println!(
+                    "owner:{}, positions:{}, in range:{}",
+                    owner,
+                    owner_reports.len(),
+                    owner_reports
+                        .iter()
+                        .filter(|report| report.in_range())
+                        .count()
+                );
+                for report in owner_reports {
+                    println!(
+                        "    position:{}, pool:{}, range:[{}, {}), tick_current:{}, in_range:{}, amount_0:{}, amount_1:{}, fees_0:{}, fees_1:{}, value_in_token_1:{}",
+                        report.position,
+                        report.pool_id,
+                        report.tick_lower,
+                        report.tick_upper,
+                        report.tick_current,
+                        report.in_range(),
+                        ui_amount(report.amount_0, report.decimals_0),
+                        ui_amount(report.amount_1, report.decimals_1),
+                        ui_amount(report.fees_0, report.decimals_0),
+                        ui_amount(report.fees_1, report.decimals_1),
+                        report.value_in_token_1()
+                    );
+                }
+            }
+            println!(
+                "owners:{}, positions:{}, in range:{}",
+                owners.len(),
+                reports.len(),
+                reports.iter().filter(|report| report.in_range()).count()
+            );
+            for (mint, total) in token_totals(&reports) {
+                println!(
+                    "mint:{}, liquidity amount:{}, unclaimed fees:{}",
+                    mint,
+                    ui_amount(total.amount, total.decimals),
+                    ui_amount(total.fees, total.decimals)
+                );
+            }
+        }
+        CommandsName::TaxExport {
+            pool_id,
+            position_nft_mints,
+            out_file,
+        } => {
+            let event_index = EventIndex::new(&pool_config.index_dir, &pool_config.http_url);
+            let pool_index = match indexed_pool_index(&pool_config, &pool_id)? {
+                Some(pool_index) => pool_index,
+                None => event_index.load(&pool_id)?,
+            };
+            if pool_index.events.is_empty() {
+                println!("no indexed events for pool {}, run Backfill first", pool_id);
+                return Ok(());
+            }
+            let pool_state = deserialize_anchor_account::<raydium_amm_v3::states::PoolState>(
+                &rpc_client.get_account(&pool_id)?,
+            )?;
+            let tokens = [
+                TaxToken {
+                    mint: pool_state.token_mint_0,
+                    decimals: pool_state.mint_decimals_0,
+                },
+                TaxToken {
+                    mint: pool_state.token_mint_1,
+                    decimals: pool_state.mint_decimals_1,
+                },
+            ];
+            let mut reward_tokens = [None; raydium_amm_v3::states::REWARD_NUM];
+            for (reward_info, reward_token) in
+                pool_state.reward_infos.iter().zip(reward_tokens.iter_mut())
+            {
+                if reward_info.token_mint == Pubkey::default() {
+                    continue;
+                }
+                let mint_data = &account_cache
+                    .get_account(&rpc_client, &reward_info.token_mint)?
+                    .data;
+                let mint_state = StateWithExtensions::<Mint>::unpack(mint_data)?;
+                *reward_token = Some(TaxToken {
+                    mint: reward_info.token_mint,
+                    decimals: mint_state.base.decimals,
+                });
+            }
+            let rows = tax_records(&pool_index, &tokens, &reward_tokens, &position_nft_mints);
+            let csv = std::iter::once(TAX_CSV_HEADER.to_string())
+                .chain(rows.iter().cloned())
+                .collect::<Vec<String>>()
+                .join("\n");
+            match out_file {
+                Some(out_file) => {
+                    std::fs::write(&out_file, csv + "\n")?;
+                    println!("{} records written to {}", rows.len(), out_file);
+                }
+                None => println!("{}", csv),
+            }
+            if pool_index.provisional_count() > 0 {
+                println!(
+                    "{} provisional events are not exported until finalized",
+                    pool_index.provisional_count()
+                );
+            }
+        }
+        CommandsName::StopLoss {
+            position_nft_mint,
+            below,
+            above,
+            out_of_range_secs,
+            zap_out_to,
+            interval,
+        } => {
+            let policy = StopLossPolicy {
+                price_below: below,
+                price_above: above,
+                max_out_of_range_secs: out_of_range_secs,
+            };
+            if policy == StopLossPolicy::default() {
+                return Err(format_err!(
+                    "at least one of --below, --above and --out-of-range-secs must be set"
+                ));
+            }
+            let (position_key, _) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+                    position_nft_mint.as_ref(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let mut journal = Journal::open(
+                &pool_config.journal_dir,
+                &pool_config.http_url,
+                &format!("stop-loss-{}", position_nft_mint),
+            )?;
+            if resume_keeper_job(&rpc_client, &mut journal, zap_out_to)? {
+                journal.clear()?;
+                return Ok(());
+            }
+            let mut out_of_range_since = None;
+            loop {
+                let reports = load_position_reports(
+                    &rpc_client,
+                    &pool_config.raydium_v3_program,
+                    &[(payer.pubkey(), position_key)],
+                );
+                // a failed load is retried in the next interval, a daemon which keeps failing
+                // stops heartbeating and exits on the stall timeout
+                let report = match reports.map(|mut reports| reports.pop()) {
+                    Ok(Some(report)) => report,
+                    Ok(None) => {
+                        println!("position {} is closed", position_key);
+                        break;
+                    }
+                    Err(err) => {
+                        println!("failed to load position {}: {}", position_key, err);
+                        std::thread::sleep(std::time::Duration::from_secs(interval));
+                        continue;
+                    }
+                };
+                if let Some(health) = &health {
+                    health.heartbeat(None);
+                }
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs();
+                if report.in_range() {
+                    out_of_range_since = None;
+                } else if out_of_range_since.is_none() {
+                    out_of_range_since = Some(now);
+                }
+                println!(
+                    "price:{}, in_range:{}, value_in_token_1:{}",
+                    report.price,
+                    report.in_range(),
+                    report.value_in_token_1()
+                );
+                if let Some(trigger) = policy.check(&report, out_of_range_since, now) {
+                    println!("stop-loss triggered: {:?}", trigger);
+                    if let Some(health) = &health {
+                        health.set_pending_txs(1);
+                    }
+                    exit_position(
+                        &rpc_client,
+                        &pool_config,
+                        &payer,
+                        &position_nft_mint,
+                        None,
+                        zap_out_to,
+                        &mut journal,
+                    )?;
+                    if let Some(health) = &health {
+                        health.set_pending_txs(0);
+                    }
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+            }
+            // the job is done, a later run starts afresh
+            journal.clear()?;
+        }
+        CommandsName::TakeProfit {
+            position_nft_mint,
+            value_above,
+            fees_above,
+            range_order,
+            percent,
+            zap_out_to,
+            interval,
+        } => {
+            let (position_key, _) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+                    position_nft_mint.as_ref(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let load_report = || -> Result<Option<PositionReport>> {
+                Ok(load_position_reports(
+                    &rpc_client,
+                    &pool_config.raydium_v3_program,
+                    &[(payer.pubkey(), position_key)],
+                )?
+                .pop())
+            };
+            let mut policy = TakeProfitPolicy {
+                value_above,
+                fees_above,
+                range_order: None,
+            };
+            if range_order {
+                // the side of the range order is the one of the position at start
+                let report =
+                    load_report()?.ok_or(format_err!("position {} not found", position_key))?;
+                policy.range_order = Some(RangeOrder::of(&report).ok_or(format_err!(
+                    "position {} is in range, it is not a single-sided range order",
+                    position_key
+                ))?);
+            }
+            if policy == TakeProfitPolicy::default() {
+                return Err(format_err!(
+                    "at least one of --value-above, --fees-above and --range-order must be set"
+                ));
+            }
+            let mut journal = Journal::open(
+                &pool_config.journal_dir,
+                &pool_config.http_url,
+                &format!("take-profit-{}", position_nft_mint),
+            )?;
+            // a partial exit which landed before a crash must not be repeated
+            if resume_keeper_job(&rpc_client, &mut journal, zap_out_to)? {
+                journal.clear()?;
+                return Ok(());
+            }
+            loop {
+                let report = match load_report()? {
+                    Some(report) => report,
+                    None => {
+                        println!("position {} is closed", position_key);
+                        break;
+                    }
+                };
+                if let Some(health) = &health {
+                    health.heartbeat(None);
+                }
+                println!(
+                    "price:{}, in_range:{}, value_in_token_1:{}, fees_in_token_1:{}",
+                    report.price,
+                    report.in_range(),
+                    report.value_in_token_1(),
+                    report.fees_in_token_1()
+                );
+                if let Some(trigger) = policy.check(&report) {
+                    println!("take-profit triggered: {:?}, exit {}%", trigger, percent);
+                    if let Some(health) = &health {
+                        health.set_pending_txs(1);
+                    }
+                    // a partial exit happens once, the rest of the position is left as is
+                    let liquidity = if percent < 100 {
+                        Some(report.liquidity * u128::from(percent) / 100)
+                    } else {
+                        None
+                    };
+                    exit_position(
+                        &rpc_client,
+                        &pool_config,
+                        &payer,
+                        &position_nft_mint,
+                        liquidity,
+                        zap_out_to,
+                        &mut journal,
+                    )?;
+                    if let Some(health) = &health {
+                        health.set_pending_txs(0);
+                    }
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+            }
+            // the job is done, a later run starts afresh
+            journal.clear()?;
+        }
+        CommandsName::CreatePoolCreatorPermission {
+            config_index,
+            creator,
+        } => {
+            let (amm_config_key, __bump) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
+                    &config_index.to_be_bytes(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let create_permission_instr = create_pool_creator_permission_instr(
+                &pool_config.clone(),
+                amm_config_key,
+                creator,
+            )?;
+            // send
+            let signers = vec![&payer, &admin];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &create_permission_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::UpdateCreatePoolFee {
+            config_index,
+            lamports,
+        } => {
+            let (amm_config_key, _) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
+                    &config_index.to_be_bytes(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let update_fee_instr =
+                update_create_pool_fee_instr(&pool_config.clone(), amm_config_key, lamports)?;
+            send_admin_instrs(&rpc_client, &pool_config, &update_fee_instr, &payer, &admin)?;
+            account_cache.invalidate(&amm_config_key);
+        }
+        CommandsName::ClosePoolCreatorPermission {
+            config_index,
+            creator,
+        } => {
+            let (amm_config_key, __bump) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
+                    &config_index.to_be_bytes(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let close_permission_instr =
+                close_pool_creator_permission_instr(&pool_config.clone(), amm_config_key, creator)?;
+            // send
+            let signers = vec![&payer, &admin];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &close_permission_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::SupportMintAdd { mint } => {
+            let mint_account = rpc_client.get_account(&mint)?;
+            let extension_types = check_support_mint_extensions(&mint_account)?;
+            println!("extension_types:{:?}", extension_types);
+            if extension_types.contains(&ExtensionType::PermanentDelegate) {
+                println!(
+                    "warning: the permanent delegate can move the tokens out of the pool vaults"
+                );
+            }
+            let create_instr = create_support_mint_associated_instr(&pool_config.clone(), mint)?;
+            // send
+            let signers = vec![&payer, &admin];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &create_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::SupportMintRemove { mint } => {
+            let close_instr = close_support_mint_associated_instr(&pool_config.clone(), mint)?;
+            // send
+            let signers = vec![&payer, &admin];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &close_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::SetPoolStatus { pool_id, flags } => {
+            let status = parse_pool_status(&flags)?;
+            for (capability, enabled) in decode_pool_status(status) {
+                println!(
+                    "{:<16}{}",
+                    capability,
+                    if enabled { "enabled" } else { "disabled" }
+                );
+            }
+            let update_pool_status_instr =
+                update_pool_status_instr(&pool_config.clone(), pool_id, status)?;
+            // send
+            let signers = vec![&payer, &admin];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &update_pool_status_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::LiquidityProfile {
+            pool_id,
+            range_ticks,
+        } => {
+            let pool_id = if let Some(pool_id) = pool_id {
+                pool_id
+            } else {
+                pool_config.pool_id_account().unwrap()
+            };
+            println!("pool_id:{}", pool_id);
+            let pool: raydium_amm_v3::states::PoolState = program.account(pool_id)?;
+            let tick_current = pool.tick_current;
+            let ticks = load_initialized_ticks(&rpc_client, &program.id(), &pool_id)?;
+            println!(
+                "{:>10} {:>24} {:>40} {:>40}",
+                "tick", "price", "liquidity_net", "active_liquidity"
+            );
+            for level in liquidity_profile(&ticks, tick_current, range_ticks) {
+                let price = tick_to_price(level.tick) * multipler(pool.mint_decimals_0)
+                    / multipler(pool.mint_decimals_1);
+                println!(
+                    "{:>10} {:>24.12} {:>40} {:>40}",
+                    level.tick, price, level.liquidity_net, level.active_liquidity
+                );
+            }
+            println!(
+                "tick_current:{}, pool_liquidity:{}",
+                tick_current,
+                identity(pool.liquidity)
+            );
+        }
+        CommandsName::SetFeeDiscount {
+            config_index,
+            mint,
+            tiers,
+        } => {
+            if tiers.len() > raydium_amm_v3::states::FEE_DISCOUNT_TIER_NUM {
+                panic!(
+                    "at most {} tiers",
+                    raydium_amm_v3::states::FEE_DISCOUNT_TIER_NUM
+                );
+            }
+            let mut fee_discount_tiers = [raydium_amm_v3::states::FeeDiscountTier::default();
+                raydium_amm_v3::states::FEE_DISCOUNT_TIER_NUM];
+            for (tier, fee_discount_tier) in tiers.iter().zip(fee_discount_tiers.iter_mut()) {
+                let (min_amount, discount_rate) = tier
+                    .split_once(':')
+                    .ok_or_else(|| format_err!("invalid tier {}", tier))?;
+                fee_discount_tier.min_amount = min_amount.parse()?;
+                fee_discount_tier.discount_rate = discount_rate.parse()?;
+            }
+            raydium_amm_v3::states::FeeDiscountConfig::validate_tiers(&fee_discount_tiers)?;
+            let (amm_config_key, __bump) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
+                    &config_index.to_be_bytes(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let set_fee_discount_instr = set_fee_discount_instr(
+                &pool_config.clone(),
+                amm_config_key,
+                mint,
+                fee_discount_tiers,
+            )?;
+            // send
+            let signers = vec![&payer, &admin];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &set_fee_discount_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::SetEmergencyPause { pause_secs } => {
+            if pause_secs > raydium_amm_v3::states::MAX_EMERGENCY_PAUSE_SECS {
+                panic!(
+                    "the pause lasts at most {}s",
+                    raydium_amm_v3::states::MAX_EMERGENCY_PAUSE_SECS
+                );
+            }
+            let set_emergency_pause_instr =
+                set_emergency_pause_instr(&pool_config.clone(), pause_secs)?;
+            // send
+            let signers = vec![&payer, &admin];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &set_emergency_pause_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::SettlePositionFeeGrowth { position, repair } => {
+            let personal_position: raydium_amm_v3::states::PersonalPositionState =
+                program.account(position)?;
+            let pool: raydium_amm_v3::states::PoolState =
+                program.account(personal_position.pool_id)?;
+            let tick_array_lower_start_index =
+                raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                    personal_position.tick_lower_index,
+                    pool.tick_spacing,
+                );
+            let tick_array_upper_start_index =
+                raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                    personal_position.tick_upper_index,
+                    pool.tick_spacing,
+                );
+            let settle_instr = settle_position_fee_growth_instr(
+                &pool_config.clone(),
+                personal_position.pool_id,
+                position,
+                tick_array_lower_start_index,
+                tick_array_upper_start_index,
+                repair,
+            )?;
+            // send
+            let signers = if repair {
+                vec![&payer, &admin]
+            } else {
+                vec![&payer]
+            };
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &settle_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::ScanState => {
+            let report = scan_program_accounts(&rpc_client, &pool_config.raydium_v3_program)?;
+            for (account_type, count) in report.counts.iter() {
+                println!("{:<26} {:>10}", account_type, count);
+            }
+            for issue in report.issues.iter() {
+                println!(
+                    "{} {} {}: {}",
+                    issue.account, issue.account_type, issue.problem, issue.detail
+                );
+            }
+            if report.issues.is_empty() {
+                println!("no issue found");
+            } else {
+                for (problem, count) in report.problem_counts() {
+                    println!("{}: {}", problem, count);
+                }
+            }
+        }
+        CommandsName::CloneLocalnet {
+            pool_id,
+            tick_arrays,
+            ledger,
+            reset,
+        } => {
+            let pool_id = if let Some(pool_id) = pool_id {
+                pool_id
+            } else {
+                pool_config.pool_id_account().unwrap()
+            };
+            let accounts = pool_clone_accounts(
+                &rpc_client,
+                &pool_config.raydium_v3_program,
+                &pool_id,
+                tick_arrays,
+            )?;
+            for account in accounts.iter() {
+                println!("clone {}", account);
+            }
+            println!(
+                "use the validator with --cluster localnet, or point http_url to http://127.0.0.1:8899 and ws_url to ws://127.0.0.1:8900"
+            );
+            run_cloning_validator(
+                &pool_config.http_url,
+                &pool_config.raydium_v3_program,
+                &accounts,
+                &ledger,
+                reset,
+            )?;
+        }
+        CommandsName::Profile {
+            pool_ids,
+            amounts,
+            out,
+            baseline,
+        } => {
+            if amounts.is_empty() {
+                return Err(format_err!("expected at least one amount"));
+            }
+            let pool_ids = if pool_ids.is_empty() {
+                pool_config.pool_id_account().into_iter().collect()
+            } else {
+                pool_ids
+            };
+            let baseline = baseline.map(|path| read_cu_report(&path)).transpose()?;
+            let mut samples = Vec::new();
+            for pool_id in pool_ids {
+                samples.extend(profile_pool(
+                    &rpc_client,
+                    &pool_config,
+                    &payer.pubkey(),
+                    pool_id,
+                    &amounts,
+                )?);
+            }
+            let mut printer = AccountPrinter::new(opts.output);
+            for sample in samples.iter() {
+                let baseline_cu = baseline
+                    .as_ref()
+                    .and_then(|baseline| baseline_units(baseline, sample));
+                match (sample.units_consumed, baseline_cu) {
+                    (Some(units_consumed), Some(baseline_cu)) => printer.text(format!(
+                        "{}, {:+} from {}",
+                        sample,
+                        i128::from(units_consumed) - i128::from(baseline_cu),
+                        baseline_cu
+                    )),
+                    _ => printer.text(sample),
+                }
+                let mut value = serde_json::to_value(sample)?;
+                if let Some(baseline_cu) = baseline_cu {
+                    value["baseline_units"] = baseline_cu.into();
+                }
+                printer.json(value);
+            }
+            printer.finish(false);
+            if let Some(out) = out {
+                write_cu_report(&out, &samples)?;
+            }
+        }
+        CommandsName::Ticker { pool_id } => {
+            let pool_id = if let Some(pool_id) = pool_id {
+                pool_id
+            } else {
+                pool_config.pool_id_account().unwrap()
+            };
+            let pool: raydium_amm_v3::states::PoolState = program.account(pool_id)?;
+            let (decimals_0, decimals_1) = (pool.mint_decimals_0, pool.mint_decimals_1);
+            let mut last_tick: Option<PoolTick> = None;
+            let mut last_update = std::time::Instant::now();
+            let mut attempt = 0;
+            loop {
+                if let Some(health) = &health {
+                    health.set_subscribed(true);
+                }
+                let result =
+                    subscribe_pool_ticks(pool_config.ws_endpoint(attempt), &pool_id, |tick| {
+                        if let Some(health) = &health {
+                            health.heartbeat(Some(tick.slot));
+                        }
+                        if last_tick.map_or(false, |last_tick| last_tick.same_price(&tick)) {
+                            return;
+                        }
+                        println!(
+                            "slot:{} (+{}ms), price:{}, tick:{}, liquidity:{}",
+                            tick.slot,
+                            last_update.elapsed().as_millis(),
+                            sqrt_price_x64_to_price(tick.sqrt_price_x64, decimals_0, decimals_1),
+                            tick.tick_current,
+                            tick.liquidity
+                        );
+                        last_tick = Some(tick);
+                        last_update = std::time::Instant::now();
+                    });
+                if let Some(health) = &health {
+                    health.set_subscribed(false);
+                }
+                match result {
+                    Ok(()) => println!("subscription closed, reconnecting"),
+                    Err(err) => {
+                        attempt += 1;
+                        println!("{}, reconnecting", err)
+                    }
+                }
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        }
+        CommandsName::Watch { pool_id } => {
+            let pool_id = if let Some(pool_id) = pool_id {
+                pool_id
+            } else {
+                pool_config.pool_id_account().unwrap()
+            };
+            let pool: raydium_amm_v3::states::PoolState = program.account(pool_id)?;
+            let (decimals_0, decimals_1) = (pool.mint_decimals_0, pool.mint_decimals_1);
+            let mut last_tick: Option<PoolTick> = None;
+            let mut attempt = 0;
+            loop {
+                if let Some(health) = &health {
+                    health.set_subscribed(true);
+                }
+                let result =
+                    subscribe_pool_ticks(pool_config.ws_endpoint(attempt), &pool_id, |tick| {
+                        if let Some(health) = &health {
+                            health.heartbeat(Some(tick.slot));
+                        }
+                        if last_tick.map_or(false, |last_tick| last_tick.same_price(&tick)) {
+                            return;
+                        }
+                        let price =
+                            sqrt_price_x64_to_price(tick.sqrt_price_x64, decimals_0, decimals_1);
+                        let tick_delta = last_tick.map_or(0, |last_tick| {
+                            i64::from(tick.tick_current) - i64::from(last_tick.tick_current)
+                        });
+                        let liquidity_delta = last_tick.map_or(0, |last_tick| {
+                            tick.liquidity as i128 - last_tick.liquidity as i128
+                        });
+                        if opts.output == OutputFormat::Json {
+                            println!(
+                                "{}",
+                                serde_json::json!({
+                                    "pool_id": pool_id.to_string(),
+                                    "slot": tick.slot,
+                                    "tick": tick.tick_current,
+                                    "tick_delta": tick_delta,
+                                    "sqrt_price_x64": tick.sqrt_price_x64.to_string(),
+                                    "price": price,
+                                    "liquidity": tick.liquidity.to_string(),
+                                    "liquidity_delta": liquidity_delta.to_string(),
+                                })
+                            );
+                        } else {
+                            println!(
+                                "slot:{}, tick:{} ({:+}), price:{}, liquidity:{} ({:+})",
+                                tick.slot,
+                                tick.tick_current,
+                                tick_delta,
+                                price,
+                                tick.liquidity,
+                                liquidity_delta
+                            );
+                        }
+                        last_tick = Some(tick);
+                    });
+                if let Some(health) = &health {
+                    health.set_subscribed(false);
+                }
+                // status messages stay off stdout, which carries the json lines
+                match result {
+                    Ok(()) => eprintln!("subscription closed, reconnecting"),
+                    Err(err) => {
+                        attempt += 1;
+                        eprintln!("{}, reconnecting", err)
+                    }
+                }
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        }
+        CommandsName::Revenue {
+            config_index,
+            pool_id,
+        } => {
+            let pools = match config_index {
+                Some(config_index) => {
+                    let (amm_config_key, __bump) = Pubkey::find_program_address(
+                        &[
+                            raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
+                            &config_index.to_be_bytes(),
+                        ],
+                        &pool_config.raydium_v3_program,
+                    );
+                    pools_by_config(
+                        &rpc_client,
+                        &pool_config.raydium_v3_program,
+                        &amm_config_key,
+                    )?
+                }
+                None => {
+                    let pool_id = if let Some(pool_id) = pool_id {
+                        pool_id
+                    } else {
+                        pool_config.pool_id_account().unwrap()
+                    };
+                    let pool_state = deserialize_anchor_account::<raydium_amm_v3::states::PoolState>(
+                        &rpc_client.get_account(&pool_id)?,
+                    )?;
+                    vec![(pool_id, pool_state)]
+                }
+            };
+            let event_index = EventIndex::new(&pool_config.index_dir, &pool_config.http_url);
+            let mut revenues = Vec::new();
+            for (pool_id, pool_state) in pools.iter() {
+                let pool_index = match indexed_pool_index(&pool_config, pool_id)? {
+                    Some(pool_index) => pool_index,
+                    None => event_index.load(pool_id)?,
+                };
+                // a pool never indexed has no checkpoint, its collected fees are unknown
+                let indexed = pool_index.checkpoint.is_some() || !pool_index.events.is_empty();
+                let revenue =
+                    PoolRevenue::new(*pool_id, pool_state, indexed.then_some(&pool_index));
+                println!(
+                    "pool:{}, protocol fees:{}/{}, fund fees:{}/{}, collected:{}",
+                    pool_id,
+                    ui_amount(revenue.protocol_fees[0], revenue.decimals[0]),
+                    ui_amount(revenue.protocol_fees[1], revenue.decimals[1]),
+                    ui_amount(revenue.fund_fees[0], revenue.decimals[0]),
+                    ui_amount(revenue.fund_fees[1], revenue.decimals[1]),
+                    revenue
+                        .collected
+                        .map_or("not indexed".to_string(), |collected| {
+                            format!(
+                                "{}/{}",
+                                ui_amount(collected[0], revenue.decimals[0]),
+                                ui_amount(collected[1], revenue.decimals[1])
+                            )
+                        })
+                );
+                revenues.push(revenue);
+            }
+            println!("pools:{}", revenues.len());
+            for (mint, total) in revenue_by_token(&revenues) {
+                println!(
+                    "mint:{}, protocol fees:{}, fund fees:{}, collected:{}, unindexed pools:{}",
+                    mint,
+                    ui_amount(total.protocol_fees, total.decimals),
+                    ui_amount(total.fund_fees, total.decimals),
+                    ui_amount(total.collected, total.decimals),
+                    total.unindexed_pools
+                );
+            }
+        }
+        CommandsName::DonateLiquidity {
+            position_nft_mint,
+            is_base_0,
+            input_amount,
+        } => {
+            let (personal_position_key, __bump) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+                    position_nft_mint.to_bytes().as_ref(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let position: raydium_amm_v3::states::PersonalPositionState =
+                program.account(personal_position_key)?;
+            let pool_id = position.pool_id;
+            let pool: raydium_amm_v3::states::PoolState = program.account(pool_id)?;
+            let (tick_lower_index, tick_upper_index) =
+                (position.tick_lower_index, position.tick_upper_index);
+            let tick_lower_price_x64 = tick_math::get_sqrt_price_at_tick(tick_lower_index)?;
+            let tick_upper_price_x64 = tick_math::get_sqrt_price_at_tick(tick_upper_index)?;
+            let liquidity = if is_base_0 {
+                liquidity_math::get_liquidity_from_single_amount_0(
+                    pool.sqrt_price_x64,
+                    tick_lower_price_x64,
+                    tick_upper_price_x64,
+                    input_amount,
+                )
+            } else {
+                liquidity_math::get_liquidity_from_single_amount_1(
+                    pool.sqrt_price_x64,
+                    tick_lower_price_x64,
+                    tick_upper_price_x64,
+                    input_amount,
+                )
+            };
+            let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
+                pool.tick_current,
+                pool.sqrt_price_x64,
+                tick_lower_index,
+                tick_upper_index,
+                liquidity as i128,
+            )?;
+            println!(
+                "position owner unchanged, amount_0:{}, amount_1:{}, liquidity:{}",
+                amount_0, amount_1, liquidity
+            );
+            let amount_0_with_slippage =
+                amount_with_slippage(amount_0 as u64, pool_config.slippage, true);
+            let amount_1_with_slippage =
+                amount_with_slippage(amount_1 as u64, pool_config.slippage, true);
+            let transfer_fee = get_pool_mints_inverse_fee(
+                &rpc_client,
+                pool.token_mint_0,
+                pool.token_mint_1,
+                amount_0_with_slippage,
+                amount_1_with_slippage,
+            );
+            let amount_0_max = amount_0_with_slippage
+                .checked_add(transfer_fee.0.transfer_fee)
+                .unwrap();
+            let amount_1_max = amount_1_with_slippage
+                .checked_add(transfer_fee.1.transfer_fee)
+                .unwrap();
+            let tick_array_lower_start_index =
+                raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                    tick_lower_index,
+                    pool.tick_spacing.into(),
+                );
+            let tick_array_upper_start_index =
+                raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                    tick_upper_index,
+                    pool.tick_spacing.into(),
+                );
+            let remaining_accounts = vec![AccountMeta::new_readonly(
+                Pubkey::find_program_address(
+                    &[POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_id.as_ref()],
+                    &pool_config.raydium_v3_program,
+                )
+                .0,
+                false,
+            )];
+            let user_token_account_0 =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &payer.pubkey(),
+                    &pool.token_mint_0,
+                    &transfer_fee.0.owner,
+                );
+            let user_token_account_1 =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &payer.pubkey(),
+                    &pool.token_mint_1,
+                    &transfer_fee.1.owner,
+                );
+            let donate_instr = donate_liquidity_instr(
+                &pool_config.clone(),
+                pool_id,
+                pool.token_vault_0,
+                pool.token_vault_1,
+                pool.token_mint_0,
+                pool.token_mint_1,
+                position_nft_mint,
+                user_token_account_0,
+                user_token_account_1,
+                remaining_accounts,
+                liquidity,
+                amount_0_max,
+                amount_1_max,
+                tick_lower_index,
+                tick_upper_index,
+                tick_array_lower_start_index,
+                tick_array_upper_start_index,
+            )?;
+            // send
+            let signers = vec![&payer];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &donate_instr,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::Quote {
+            input_token,
+            output_token,
+            amount,
+            base_in,
+            trace,
+        } => {
+            let pool_id = pool_config.pool_id_account().unwrap();
+            let load_accounts = vec![pool_id, pool_config.tickarray_bitmap_extension().unwrap()];
+            let rsps = rpc_client.get_multiple_accounts(&load_accounts)?;
+            let [pool_account, tickarray_bitmap_extension_account] = array_ref![rsps, 0, 2];
+            let pool_state = deserialize_anchor_account::<PoolState>(
+                pool_account
+                    .as_ref()
+                    .ok_or(format_err!("pool {} not found", pool_id))?,
+            )?;
+            let zero_for_one = if input_token == pool_state.token_mint_0
+                && output_token == pool_state.token_mint_1
+            {
+                true
+            } else if input_token == pool_state.token_mint_1
+                && output_token == pool_state.token_mint_0
+            {
+                false
+            } else {
+                return Err(format_err!(
+                    "{} and {} are not the mints of the pool {}",
+                    input_token,
+                    output_token,
+                    pool_id
+                ));
+            };
+            let tickarray_bitmap_extension = deserialize_anchor_account::<TickArrayBitmapExtension>(
+                tickarray_bitmap_extension_account.as_ref().unwrap(),
+            )?;
+            // amm config and mints rarely change, load them from the cache
+            let static_accounts = vec![
+                pool_state.amm_config,
+                pool_state.token_mint_0,
+                pool_state.token_mint_1,
+            ];
+            let static_rsps = account_cache.get_multiple_accounts(&rpc_client, &static_accounts)?;
+            let [amm_config_account, mint0_account, mint1_account] = array_ref![static_rsps, 0, 3];
+            let amm_config_state = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
+                amm_config_account.as_ref().unwrap(),
+            )?;
+            // the trade fee of the owner, with its discount
+            let (_, amm_config_state) =
+                load_fee_discount(&rpc_client, &pool_config, &amm_config_state, &owner)?;
+            let mint0_data = mint0_account.clone().unwrap().data;
+            let mint0_state = StateWithExtensions::<Mint>::unpack(&mint0_data)?;
+            let mint1_data = mint1_account.clone().unwrap().data;
+            let mint1_state = StateWithExtensions::<Mint>::unpack(&mint1_data)?;
+            let epoch = rpc_client.get_epoch_info()?.epoch;
+            let mut tick_arrays = load_cur_and_next_tick_arrays(
+                &rpc_client,
+                &pool_config,
+                &pool_state,
+                &tickarray_bitmap_extension,
+                zero_for_one,
+                pool_config.max_swap_tick_array_num(),
+            );
+            let quote = quote_swap(
+                amount,
+                base_in,
+                zero_for_one,
+                &amm_config_state,
+                &pool_state,
+                &tickarray_bitmap_extension,
+                &mut tick_arrays,
+                (&mint0_state, &mint1_state),
+                epoch,
+            )?;
+            if trace {
+                print_swap_trace(
+                    &quote.steps,
+                    pool_state.mint_decimals_0,
+                    pool_state.mint_decimals_1,
+                );
+            }
+            print_swap_quote(
+                &quote,
+                pool_state.mint_decimals_0,
+                pool_state.mint_decimals_1,
+            );
+        }
+        CommandsName::SwapRoute {
+            input_mint,
+            intermediate_mint,
+            output_mint,
+            amount,
+            simulate,
+        } => {
+            let first_hop = RouteHop::quote(
+                &rpc_client,
+                &account_cache,
+                &pool_config,
+                &payer.pubkey(),
+                input_mint,
+                intermediate_mint,
+                amount,
+            )?;
+            // the second hop swaps what the first one receives at least
+            let intermediate_amount =
+                amount_with_slippage(first_hop.quote.amount_out, pool_config.slippage, false);
+            let second_hop = RouteHop::quote(
+                &rpc_client,
+                &account_cache,
+                &pool_config,
+                &payer.pubkey(),
+                intermediate_mint,
+                output_mint,
+                intermediate_amount,
+            )?;
+            let amount_out_min =
+                amount_with_slippage(second_hop.quote.amount_out, pool_config.slippage, false);
+            for (i, hop) in [&first_hop, &second_hop].into_iter().enumerate() {
+                println!(
+                    "hop:{}, pool:{}, amount_in:{}, amount_out:{}, price_impact:{:.4}%",
+                    i,
+                    hop.pool_config.pool_id_account().unwrap(),
+                    hop.quote.amount_in,
+                    hop.quote.amount_out,
+                    hop.quote.price_impact() * 100.0
+                );
+            }
+            println!(
+                "amount in:{}, intermediate amount:{}, amount out:{}, minimum amount out:{}",
+                amount, intermediate_amount, second_hop.quote.amount_out, amount_out_min
+            );
+
+            let txn = two_hop_txn(
+                &rpc_client,
+                &pool_config,
+                &payer,
+                (&first_hop, &second_hop),
+                amount,
+                intermediate_amount,
+                amount_out_min,
+            )?;
+            if simulate {
+                let ret = rpc_client.simulate_transaction(&txn)?;
+                println!("{:#?}", ret);
+            } else {
+                let signature = send_versioned_txn(&rpc_client, &txn, true)?;
+                println!("{}", signature);
+            }
+        }
+        CommandsName::ArbScan {
+            pool_a,
+            pool_b,
+            amount,
+            threshold_bps,
+            interval_ms,
+            execute,
+        } => {
+            let pool_ids = [pool_a, pool_b];
+            let load_pool_states = || -> Result<Vec<PoolState>> {
+                pool_ids
+                    .iter()
+                    .map(|pool_id| {
+                        deserialize_anchor_account::<PoolState>(&rpc_client.get_account(pool_id)?)
+                    })
+                    .collect()
+            };
+            let pool_states = load_pool_states()?;
+            let (mint_0, mint_1) = (pool_states[0].token_mint_0, pool_states[0].token_mint_1);
+            if (pool_states[1].token_mint_0, pool_states[1].token_mint_1) != (mint_0, mint_1) {
+                return Err(format_err!(
+                    "pools {} and {} are not of the same mints",
+                    pool_a,
+                    pool_b
+                ));
+            }
+            let (decimals_0, decimals_1) = (
+                pool_states[0].mint_decimals_0,
+                pool_states[0].mint_decimals_1,
+            );
+            // the round trip fails unless it returns the amount and the threshold
+            let amount_out_min = amount + amount * threshold_bps / 10000;
+            loop {
+                let pool_states = load_pool_states()?;
+                let prices: Vec<f64> = pool_states
+                    .iter()
+                    .map(|pool_state| {
+                        sqrt_price_x64_to_price(pool_state.sqrt_price_x64, decimals_0, decimals_1)
+                    })
+                    .collect();
+                let spread_bps = (prices[0] / prices[1] - 1.0).abs() * 10000.0;
+                for (first, second) in [(0, 1), (1, 0)] {
+                    let first_hop = RouteHop::quote_in_pool(
+                        &rpc_client,
+                        &account_cache,
+                        &pool_config,
+                        &payer.pubkey(),
+                        pool_ids[first],
+                        pool_states[first],
+                        mint_0,
+                        amount,
+                    )?;
+                    // the second swap spends what the first one receives at least
+                    let intermediate_amount = amount_with_slippage(
+                        first_hop.quote.amount_out,
+                        pool_config.slippage,
+                        false,
+                    );
+                    let second_hop = RouteHop::quote_in_pool(
+                        &rpc_client,
+                        &account_cache,
+                        &pool_config,
+                        &payer.pubkey(),
+                        pool_ids[second],
+                        pool_states[second],
+                        mint_1,
+                        intermediate_amount,
+                    )?;
+                    let amount_out = second_hop.quote.amount_out;
+                    if amount_out < amount_out_min {
+                        continue;
+                    }
+                    println!(
+                        "sell in:{}, buy back in:{}, prices:{}/{}, spread:{:.1}bps, trade fees:{}/{}, amount in:{}, amount out:{}, profit:{} ({:.1}bps)",
+                        pool_ids[first],
+                        pool_ids[second],
+                        prices[first],
+                        prices[second],
+                        spread_bps,
+                        ui_amount(first_hop.quote.trade_fee, decimals_0),
+                        ui_amount(second_hop.quote.trade_fee, decimals_1),
+                        ui_amount(amount, decimals_0),
+                        ui_amount(amount_out, decimals_0),
+                        ui_amount(amount_out - amount, decimals_0),
+                        (amount_out - amount) as f64 / amount as f64 * 10000.0
+                    );
+                    if execute {
+                        let txn = two_hop_txn(
+                            &rpc_client,
+                            &pool_config,
+                            &payer,
+                            (&first_hop, &second_hop),
+                            amount,
+                            intermediate_amount,
+                            amount_out_min,
+                        )?;
+                        // a round trip gone by the time it lands only fails, the scan goes on
+                        match send_versioned_txn(&rpc_client, &txn, true) {
+                            Ok(signature) => println!("round trip sent: {}", signature),
+                            Err(err) => println!("round trip failed: {}", err),
+                        }
+                    }
+                }
+                if let Some(health) = &health {
+                    health.heartbeat(None);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(interval_ms));
+            }
+        }
+        CommandsName::CreateLookupTable { addresses } => {
+            let addresses = if addresses.is_empty() {
+                pool_lookup_addresses(&rpc_client, &pool_config)?
+            } else {
+                missing_addresses(None, &addresses)
+            };
+            // the address is derived from a slot which must be in the slot hashes
+            let recent_slot = rpc_client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+            let (create_instr, table) =
+                create_lookup_table_instr(&payer.pubkey(), &payer.pubkey(), recent_slot);
+            let mut extend_instrs =
+                extend_lookup_table_instrs(&table, &payer.pubkey(), &payer.pubkey(), &addresses)
+                    .into_iter();
+            let mut instructions = vec![create_instr];
+            instructions.extend(extend_instrs.next());
+            let signers = vec![&payer];
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &instructions,
+                &payer.pubkey(),
+                &signers,
+                rpc_client.get_latest_blockhash()?,
+                &[],
+            )?;
+            println!("{}", send_txn(&rpc_client, &txn, true)?);
+            for extend_instr in extend_instrs {
+                let txn = new_signed_txn(
+                    &rpc_client,
+                    &pool_config,
+                    &[extend_instr],
+                    &payer.pubkey(),
+                    &signers,
+                    rpc_client.get_latest_blockhash()?,
+                    &[],
+                )?;
+                println!("{}", send_txn(&rpc_client, &txn, true)?);
+            }
+            println!("lookup table:{}, addresses:{}", table, addresses.len());
+        }
+        CommandsName::ExtendLookupTable { table, addresses } => {
+            let lookup_tables = load_lookup_tables(&rpc_client, &[table])?;
+            let addresses = if addresses.is_empty() {
+                pool_lookup_addresses(&rpc_client, &pool_config)?
+            } else {
+                addresses
+            };
+            let addresses = missing_addresses(lookup_tables.first(), &addresses);
+            let signers = vec![&payer];
+            for extend_instr in
+                extend_lookup_table_instrs(&table, &payer.pubkey(), &payer.pubkey(), &addresses)
+            {
+                let txn = new_signed_txn(
+                    &rpc_client,
+                    &pool_config,
+                    &[extend_instr],
+                    &payer.pubkey(),
+                    &signers,
+                    rpc_client.get_latest_blockhash()?,
+                    &[],
+                )?;
+                println!("{}", send_txn(&rpc_client, &txn, true)?);
+            }
+            println!(
+                "lookup table:{}, added addresses:{}",
+                table,
+                addresses.len()
+            );
+        }
+        CommandsName::DeactivateLookupTable { table } => {
+            let signers = vec![&payer];
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &[deactivate_lookup_table_instr(&table, &payer.pubkey())],
+                &payer.pubkey(),
+                &signers,
+                rpc_client.get_latest_blockhash()?,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::CloseLookupTable { table } => {
+            let signers = vec![&payer];
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &[close_lookup_table_instr(
+                    &table,
+                    &payer.pubkey(),
+                    &payer.pubkey(),
+                )],
+                &payer.pubkey(),
+                &signers,
+                rpc_client.get_latest_blockhash()?,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+        }
+        CommandsName::CreateNonceAccount => {
+            let nonce_account = Keypair::new();
+            let lamports = rpc_client.get_minimum_balance_for_rent_exemption(
+                anchor_client::solana_sdk::nonce::State::size(),
+            )?;
+            let create_nonce_instrs = system_instruction::create_nonce_account(
+                &payer.pubkey(),
+                &nonce_account.pubkey(),
+                &payer.pubkey(),
+                lamports,
+            );
+            // a new nonce account is signed with a recent blockhash
+            let signers: Vec<&dyn Signer> = vec![&payer, &nonce_account];
+            let txn = new_signed_txn(
+                &rpc_client,
+                &ClientConfig {
+                    nonce_account: None,
+                    ..pool_config.clone()
+                },
+                &create_nonce_instrs,
+                &payer.pubkey(),
+                &signers,
+                rpc_client.get_latest_blockhash()?,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+            println!("nonce account:{}", nonce_account.pubkey());
+        }
+        CommandsName::CollectProtocolFee {
+            pool_id,
+            fund,
+            amount_0,
+            amount_1,
+            recipient_0,
+            recipient_1,
+        } => {
+            let pool_id = pool_id.or(pool_config.pool_id_account()).unwrap();
+            let pool_state: PoolState = program.account(pool_id)?;
+            let mint_accounts = account_cache.get_multiple_accounts(
+                &rpc_client,
+                &[pool_state.token_mint_0, pool_state.token_mint_1],
+            )?;
+            // the recipients are the token accounts of the admin, e.g. of the squads vault
+            let recipient_0 = recipient_0.unwrap_or(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &admin.pubkey(),
+                    &pool_state.token_mint_0,
+                    &mint_accounts[0].as_ref().unwrap().owner,
+                ),
+            );
+            let recipient_1 = recipient_1.unwrap_or(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &admin.pubkey(),
+                    &pool_state.token_mint_1,
+                    &mint_accounts[1].as_ref().unwrap().owner,
+                ),
+            );
+            let collect_instr = if fund {
+                collect_fund_fee_instr(
+                    &pool_config,
+                    pool_id,
+                    &pool_state,
+                    recipient_0,
+                    recipient_1,
+                    amount_0,
+                    amount_1,
+                )?
+            } else {
+                collect_protocol_fee_instr(
+                    &pool_config,
+                    pool_id,
+                    &pool_state,
+                    recipient_0,
+                    recipient_1,
+                    amount_0,
+                    amount_1,
+                )?
+            };
+            send_admin_instrs(&rpc_client, &pool_config, &collect_instr, &payer, &admin)?;
+        }
+        CommandsName::CollectAllFees => {
+            let position_nft_infos = get_all_nft_and_position_by_owner(
+                &rpc_client,
+                &payer.pubkey(),
+                &pool_config.raydium_v3_program,
+            );
+            let reports = load_owned_position_reports(
+                &rpc_client,
+                &pool_config,
+                &payer.pubkey(),
+                &position_nft_infos,
+            )?;
+            let reports: Vec<&PositionReport> = reports
+                .iter()
+                .filter(|report| {
+                    report.fees_0 > 0
+                        || report.fees_1 > 0
+                        || report.rewards.iter().any(|(_, amount)| *amount > 0)
+                })
+                .collect();
+            for report in reports.iter() {
+                println!(
+                    "position:{}, pool:{}, fees_0:{}, fees_1:{}",
+                    report.position, report.pool_id, report.fees_0, report.fees_1
+                );
+            }
+            send_position_collections(
+                &rpc_client,
+                &account_cache,
+                &pool_config,
+                &payer,
+                &position_nft_infos,
+                &reports,
+            )?;
+        }
+        CommandsName::HarvestRewards => {
+            let position_nft_infos = get_all_nft_and_position_by_owner(
+                &rpc_client,
+                &payer.pubkey(),
+                &pool_config.raydium_v3_program,
+            );
+            let reports = load_owned_position_reports(
+                &rpc_client,
+                &pool_config,
+                &payer.pubkey(),
+                &position_nft_infos,
+            )?;
+            let reports: Vec<&PositionReport> = reports
+                .iter()
+                .filter(|report| report.rewards.iter().any(|(_, amount)| *amount > 0))
+                .collect();
+            for report in reports.iter() {
+                for (mint, amount) in report.rewards.iter() {
+                    println!(
+                        "position:{}, pool:{}, reward mint:{}, pending:{}",
+                        report.position, report.pool_id, mint, amount
+                    );
+                }
+            }
+            send_position_collections(
+                &rpc_client,
+                &account_cache,
+                &pool_config,
+                &payer,
+                &position_nft_infos,
+                &reports,
+            )?;
+        }
+        CommandsName::ClosePosition {
+            position_nft_mint,
+            close_empty_accounts,
+        } => {
+            let (position_key, _) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+                    position_nft_mint.as_ref(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let position = deserialize_anchor_account::<
+                raydium_amm_v3::states::PersonalPositionState,
+            >(&rpc_client.get_account(&position_key)?)?;
+            let pool_state = deserialize_anchor_account::<PoolState>(
+                &rpc_client.get_account(&position.pool_id)?,
+            )?;
+            let nft_info = get_all_nft_and_position_by_owner(
+                &rpc_client,
+                &payer.pubkey(),
+                &pool_config.raydium_v3_program,
+            )
+            .into_iter()
+            .find(|nft_info| nft_info.mint == position_nft_mint)
+            .ok_or(format_err!(
+                "position nft {} is not held by {}",
+                position_nft_mint,
+                payer.pubkey()
+            ))?;
+            let exit = exit_position_instrs(
+                &pool_config.with_pool(position.pool_id, &pool_state),
+                &rpc_client,
+                &payer.pubkey(),
+                &pool_state,
+                &position,
+                (nft_info.key, nft_info.program),
+                position.liquidity,
+            )?;
+            println!(
+                "remove liquidity:{}, amount_0_min:{}, amount_1_min:{}",
+                position.liquidity, exit.amount_0_min, exit.amount_1_min
+            );
+            let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+                1_400_000u32,
+            )];
+            instructions.extend(exit.instructions);
+            // the wrapped SOL received is unwrapped by closing its account
+            for (mint, user_token_account) in [
+                (pool_state.token_mint_0, exit.user_token_account_0),
+                (pool_state.token_mint_1, exit.user_token_account_1),
+            ] {
+                if mint == spl_token::native_mint::id() {
+                    instructions.extend(close_token_account(
+                        &pool_config,
+                        &user_token_account,
+                        &payer.pubkey(),
+                        &payer,
+                    )?);
+                }
+            }
+            let signers = vec![&payer];
+            let recent_hash = rpc_client.get_latest_blockhash()?;
+            let txn = new_signed_txn(
+                &rpc_client,
+                &pool_config,
+                &instructions,
+                &payer.pubkey(),
+                &signers,
+                recent_hash,
+                &[],
+            )?;
+            let signature = send_txn(&rpc_client, &txn, true)?;
+            println!("{}", signature);
+
+            if close_empty_accounts {
+                let mut builder = TransactionBuilder::new(payer.pubkey());
+                for token_program in [spl_token::id(), spl_token_2022::id()] {
+                    for token_account in get_empty_token_accounts_by_owner(
+                        &rpc_client,
+                        &payer.pubkey(),
+                        token_program,
+                    )? {
+                        println!("close empty token account {}", token_account);
+                        builder.add_instruction(spl_token_2022::instruction::close_account(
+                            &token_program,
+                            &token_account,
+                            &payer.pubkey(),
+                            &payer.pubkey(),
+                            &[],
+                        )?);
+                    }
+                }
+                if builder.is_empty() {
+                    println!("no empty token account");
+                } else {
+                    let builder = price_transaction_builder(&rpc_client, builder)?;
+                    let signers = vec![&payer];
+                    for txn in builder.build_signed(rpc_client.get_latest_blockhash()?, &signers)? {
+                        let signature = send_versioned_txn(&rpc_client, &txn, true)?;
+                        println!("{}", signature);
+                    }
+                }
+            }
+        }
+        CommandsName::Rebalance {
+            position_nft_mint,
+            new_range_pct,
+            swap,
+            force,
+        } => {
+            if new_range_pct <= 0.0 || new_range_pct >= 100.0 {
+                return Err(format_err!("the range must be between 0 and 100 percent"));
+            }
+            let (position_key, _) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+                    position_nft_mint.as_ref(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            let position = deserialize_anchor_account::<
+                raydium_amm_v3::states::PersonalPositionState,
+            >(&rpc_client.get_account(&position_key)?)?;
+            let pool_id = position.pool_id;
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let position_config = pool_config.with_pool(pool_id, &pool_state);
+            let in_range = position.tick_lower_index <= pool_state.tick_current
+                && pool_state.tick_current < position.tick_upper_index;
+            if in_range && !force {
+                println!(
+                    "position {} is in range, nothing to rebalance",
+                    position_key
+                );
+                return Ok(());
+            }
+            let nft_info = get_all_nft_and_position_by_owner(
+                &rpc_client,
+                &payer.pubkey(),
+                &pool_config.raydium_v3_program,
+            )
+            .into_iter()
+            .find(|nft_info| nft_info.mint == position_nft_mint)
+            .ok_or(format_err!(
+                "position nft {} is not held by {}",
+                position_nft_mint,
+                payer.pubkey()
+            ))?;
+            let exit = exit_position_instrs(
+                &position_config,
+                &rpc_client,
+                &payer.pubkey(),
+                &pool_state,
+                &position,
+                (nft_info.key, nft_info.program),
+                position.liquidity,
+            )?;
+            let (user_token_account_0, user_token_account_1) =
+                (exit.user_token_account_0, exit.user_token_account_1);
+            // a missing token account holds nothing
+            let token_balances = || {
+                [user_token_account_0, user_token_account_1].map(|token_account| {
+                    rpc_client
+                        .get_token_account_balance(&token_account)
+                        .map_or(0, |balance| balance.amount.parse::<u64>().unwrap_or(0))
+                })
+            };
+            let send = |instructions: &[Instruction], signers: &[&dyn Signer]| -> Result<()> {
+                let mut priced = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+                    1_400_000u32,
+                )];
+                priced.extend_from_slice(instructions);
+                let txn = new_signed_txn(
+                    &rpc_client,
+                    &pool_config,
+                    &priced,
+                    &payer.pubkey(),
+                    signers,
+                    rpc_client.get_latest_blockhash()?,
+                    &[],
+                )?;
+                let signature = send_txn(&rpc_client, &txn, true)?;
+                println!("{}", signature);
+                Ok(())
+            };
+
+            // withdraw the liquidity and the fees, the old position is closed
+            let balances_before = token_balances();
+            send(&exit.instructions, &[&payer])?;
+            let balances = token_balances();
+            let mut amount_0 = balances[0].saturating_sub(balances_before[0]);
+            let mut amount_1 = balances[1].saturating_sub(balances_before[1]);
+            println!("withdrawn amount_0:{}, amount_1:{}", amount_0, amount_1);
+
+            // the new range around the current price
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let price = sqrt_price_x64_to_price(
+                pool_state.sqrt_price_x64,
+                pool_state.mint_decimals_0,
+                pool_state.mint_decimals_1,
+            );
+            let (tick_lower_index, tick_lower_price) = price_to_tick_with_spacing(
+                price * (1.0 - new_range_pct / 100.0),
+                pool_state.mint_decimals_0,
+                pool_state.mint_decimals_1,
+                pool_state.tick_spacing,
+                false,
+            )?;
+            let (tick_upper_index, tick_upper_price) = price_to_tick_with_spacing(
+                price * (1.0 + new_range_pct / 100.0),
+                pool_state.mint_decimals_0,
+                pool_state.mint_decimals_1,
+                pool_state.tick_spacing,
+                true,
+            )?;
+            println!(
+                "price:{}, new range:{} ({}) to {} ({})",
+                price, tick_lower_price, tick_lower_index, tick_upper_price, tick_upper_index
+            );
+
+            let pool_state = if swap {
+                match rebalance_swap_amount(
+                    amount_0,
+                    amount_1,
+                    pool_state.sqrt_price_x64,
+                    tick_lower_index,
+                    tick_upper_index,
+                )? {
+                    Some((zero_for_one, amount_in)) => {
+                        let target_mint = if zero_for_one {
+                            pool_state.token_mint_1
+                        } else {
+                            pool_state.token_mint_0
+                        };
+                        println!("swap {} into {}", amount_in, target_mint);
+                        let swap_instrs = zap_out_instrs(
+                            &position_config,
+                            &rpc_client,
+                            &payer.pubkey(),
+                            &pool_state,
+                            &target_mint,
+                            amount_in,
+                        )?;
+                        let balances_before = token_balances();
+                        send(&swap_instrs, &[&payer])?;
+                        let balances = token_balances();
+                        amount_0 = (amount_0 + balances[0]).saturating_sub(balances_before[0]);
+                        amount_1 = (amount_1 + balances[1]).saturating_sub(balances_before[1]);
+                        println!("after swap amount_0:{}, amount_1:{}", amount_0, amount_1);
+                        deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?
+                    }
+                    None => pool_state,
+                }
+            } else {
+                pool_state
+            };
+
+            let liquidity = liquidity_for_amounts(
+                &position_config,
+                &rpc_client,
+                &pool_state,
+                tick_lower_index,
+                tick_upper_index,
+                amount_0,
+                amount_1,
+            )?;
+            if liquidity == 0 {
+                return Err(format_err!("nothing to deposit in the new range"));
+            }
+            let nft_mint = Keypair::new();
+            println!(
+                "position nft mint:{}, liquidity:{}",
+                nft_mint.pubkey(),
+                liquidity
+            );
+            let open_instrs = open_position_with_token22_nft_instr(
+                &position_config,
+                pool_id,
+                pool_state.token_vault_0,
+                pool_state.token_vault_1,
+                pool_state.token_mint_0,
+                pool_state.token_mint_1,
+                nft_mint.pubkey(),
+                payer.pubkey(),
+                user_token_account_0,
+                user_token_account_1,
+                vec![AccountMeta::new(
+                    position_config.tickarray_bitmap_extension().unwrap(),
+                    false,
+                )],
+                liquidity,
+                amount_0,
+                amount_1,
+                tick_lower_index,
+                tick_upper_index,
+                raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                    tick_lower_index,
+                    pool_state.tick_spacing,
+                ),
+                raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                    tick_upper_index,
+                    pool_state.tick_spacing,
+                ),
+                false,
+            )?;
+            send(&open_instrs, &[&payer, &nft_mint])?;
+        }
+        CommandsName::OpenLadder {
+            lower_price,
+            upper_price,
+            buckets,
+            amount_0,
+            amount_1,
+            distribution,
+        } => {
+            let pool_id = pool_config.pool_id_account().unwrap();
+            let pool: raydium_amm_v3::states::PoolState = program.account(pool_id)?;
+            let (tick_lower_index, _) = price_to_tick_with_spacing(
+                lower_price,
+                pool.mint_decimals_0,
+                pool.mint_decimals_1,
+                pool.tick_spacing,
+                false,
+            )?;
+            let (tick_upper_index, _) = price_to_tick_with_spacing(
+                upper_price,
+                pool.mint_decimals_0,
+                pool.mint_decimals_1,
+                pool.tick_spacing,
+                true,
+            )?;
+            let ladder = ladder_buckets(
+                tick_lower_index,
+                tick_upper_index,
+                pool.tick_spacing,
+                buckets,
+                distribution,
+                pool.tick_current,
+                pool.sqrt_price_x64,
+                amount_0,
+                amount_1,
+            )?;
+            let transfer_fee =
+                get_pool_mints_inverse_fee(&rpc_client, pool.token_mint_0, pool.token_mint_1, 0, 0);
+            let user_token_account_0 =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &payer.pubkey(),
+                    &pool.token_mint_0,
+                    &transfer_fee.0.owner,
+                );
+            let user_token_account_1 =
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &payer.pubkey(),
+                    &pool.token_mint_1,
+                    &transfer_fee.1.owner,
+                );
+
+            let mut builder =
+                TransactionBuilder::new(payer.pubkey()).compute_unit_limit(1_400_000u32);
+            let mut nft_mints = Vec::new();
+            for bucket in ladder.iter() {
+                let amount_0_with_slippage =
+                    amount_with_slippage(bucket.amount_0, pool_config.slippage, true);
+                let amount_1_with_slippage =
+                    amount_with_slippage(bucket.amount_1, pool_config.slippage, true);
+                let transfer_fee = get_pool_mints_inverse_fee(
+                    &rpc_client,
+                    pool.token_mint_0,
+                    pool.token_mint_1,
+                    amount_0_with_slippage,
+                    amount_1_with_slippage,
+                );
+                let nft_mint = Keypair::new();
+                println!(
+                    "position nft mint:{}, ticks:{} to {}, liquidity:{}, amount_0:{}, amount_1:{}",
+                    nft_mint.pubkey(),
+                    bucket.tick_lower,
+                    bucket.tick_upper,
+                    bucket.liquidity,
+                    bucket.amount_0,
+                    bucket.amount_1
+                );
+                builder.add_group(open_position_with_token22_nft_instr(
+                    &pool_config,
+                    pool_id,
+                    pool.token_vault_0,
+                    pool.token_vault_1,
+                    pool.token_mint_0,
+                    pool.token_mint_1,
+                    nft_mint.pubkey(),
+                    payer.pubkey(),
+                    user_token_account_0,
+                    user_token_account_1,
+                    vec![AccountMeta::new(
+                        pool_config.tickarray_bitmap_extension().unwrap(),
+                        false,
+                    )],
+                    bucket.liquidity,
+                    amount_0_with_slippage + transfer_fee.0.transfer_fee,
+                    amount_1_with_slippage + transfer_fee.1.transfer_fee,
+                    bucket.tick_lower,
+                    bucket.tick_upper,
+                    raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                        bucket.tick_lower,
+                        pool.tick_spacing,
+                    ),
+                    raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                        bucket.tick_upper,
+                        pool.tick_spacing,
+                    ),
+                    false,
+                )?);
+                nft_mints.push(nft_mint);
+            }
+            let builder = price_transaction_builder(&rpc_client, builder)?
+                .lookup_tables(load_lookup_tables(&rpc_client, &pool_config.lookup_tables)?);
+            let mut signers: Vec<&dyn Signer> = vec![&payer];
+            signers.extend(nft_mints.iter().map(|nft_mint| nft_mint as &dyn Signer));
+            // each transaction is signed by the nft mints of its positions
+            for txn in builder.build_signed(rpc_client.get_latest_blockhash()?, &signers)? {
+                let signature = send_versioned_txn(&rpc_client, &txn, true)?;
+                println!("{}", signature);
+            }
+        }
+        CommandsName::Monitor {
+            webhook,
+            buffer_ticks,
+        } => {
+            let position_nft_infos = get_all_nft_and_position_by_owner(
+                &rpc_client,
+                &owner,
+                &pool_config.raydium_v3_program,
+            );
+            let position_keys: Vec<Pubkey> = position_nft_infos
+                .iter()
+                .map(|item| item.position)
+                .collect();
+            let mut pool_positions: BTreeMap<Pubkey, Vec<(Pubkey, i32, i32)>> = BTreeMap::new();
+            for (position_key, rsp) in position_keys.iter().zip(get_multiple_accounts_concurrently(
+                &rpc_client,
+                &position_keys,
+            )?) {
+                // the nft may still be held after the position is closed
+                if let Some(rsp) = rsp {
+                    let position = deserialize_anchor_account::<
+                        raydium_amm_v3::states::PersonalPositionState,
+                    >(&rsp)?;
+                    pool_positions.entry(position.pool_id).or_default().push((
+                        *position_key,
+                        position.tick_lower_index,
+                        position.tick_upper_index,
+                    ));
+                }
+            }
+            if pool_positions.is_empty() {
+                return Err(format_err!("{} holds no position", owner));
+            }
+            let pool_keys: Vec<Pubkey> = pool_positions.keys().copied().collect();
+            let mut pool_decimals = HashMap::new();
+            for (pool_id, rsp) in pool_keys
+                .iter()
+                .zip(get_multiple_accounts_concurrently(&rpc_client, &pool_keys)?)
+            {
+                let pool = deserialize_anchor_account::<PoolState>(&rsp.unwrap())?;
+                pool_decimals.insert(*pool_id, (pool.mint_decimals_0, pool.mint_decimals_1));
+            }
+            let buffer_ticks = i32::try_from(buffer_ticks)?;
+            println!(
+                "monitor {} positions in {} pools",
+                position_keys.len(),
+                pool_keys.len()
+            );
+            // one subscription per pool
+            std::thread::scope(|scope| {
+                for (pool_id, positions) in pool_positions.iter() {
+                    let (decimals_0, decimals_1) = pool_decimals[pool_id];
+                    let (webhook, health, pool_config) = (&webhook, &health, &pool_config);
+                    scope.spawn(move || {
+                        let mut statuses: HashMap<Pubkey, RangeStatus> = HashMap::new();
+                        let mut attempt = 0;
+                        loop {
+                            if let Some(health) = health {
+                                health.set_subscribed(true);
+                            }
+                            let result = subscribe_pool_ticks(
+                                pool_config.ws_endpoint(attempt),
+                                pool_id,
+                                |tick| {
+                                    if let Some(health) = health {
+                                        health.heartbeat(Some(tick.slot));
+                                    }
+                                    for (position, tick_lower, tick_upper) in positions.iter() {
+                                        let status = range_status(
+                                            tick.tick_current,
+                                            *tick_lower,
+                                            *tick_upper,
+                                            buffer_ticks,
+                                        );
+                                        // alert on the changes, and at start unless in range
+                                        let alert = match statuses.insert(*position, status) {
+                                            Some(last_status) => last_status != status,
+                                            None => status != RangeStatus::InRange,
+                                        };
+                                        if alert {
+                                            send_range_alert(
+                                                webhook.as_deref(),
+                                                &RangeAlert {
+                                                    position: *position,
+                                                    pool_id: *pool_id,
+                                                    status,
+                                                    slot: tick.slot,
+                                                    tick_current: tick.tick_current,
+                                                    tick_lower: *tick_lower,
+                                                    tick_upper: *tick_upper,
+                                                    price: sqrt_price_x64_to_price(
+                                                        tick.sqrt_price_x64,
+                                                        decimals_0,
+                                                        decimals_1,
+                                                    ),
+                                                },
+                                            );
+                                        }
+                                    }
+                                },
+                            );
+                            if let Some(health) = health {
+                                health.set_subscribed(false);
+                            }
+                            match result {
+                                Ok(()) => {
+                                    println!("subscription to {} closed, reconnecting", pool_id)
+                                }
+                                Err(err) => {
+                                    attempt += 1;
+                                    println!("{}, reconnecting", err)
+                                }
+                            }
+                            std::thread::sleep(std::time::Duration::from_secs(1));
+                        }
+                    });
+                }
+            });
+        }
+        CommandsName::Autocompound {
+            position_nft_mints,
+            min_fees_value,
+            max_compute_unit_price,
+            interval,
+        } => {
+            let estimate = PriorityFeeEstimate {
+                percentile: match pool_config.priority_fee_percentile {
+                    0 => 50,
+                    percentile => percentile.min(100),
+                },
+                max_compute_unit_price: 0,
+            };
+            loop {
+                if let Some(health) = &health {
+                    health.heartbeat(None);
+                }
+                let nft_infos: Vec<PositionNftTokenInfo> = get_all_nft_and_position_by_owner(
+                    &rpc_client,
+                    &payer.pubkey(),
+                    &pool_config.raydium_v3_program,
+                )
+                .into_iter()
+                .filter(|nft_info| {
+                    position_nft_mints.is_empty() || position_nft_mints.contains(&nft_info.mint)
+                })
+                .collect();
+                let reports = load_owned_position_reports(
+                    &rpc_client,
+                    &pool_config,
+                    &payer.pubkey(),
+                    &nft_infos,
+                )?;
+                for report in reports.iter() {
+                    let fees_value = report.fees_in_token_1();
+                    if (report.fees_0 == 0 && report.fees_1 == 0) || fees_value < min_fees_value {
+                        continue;
+                    }
+                    if max_compute_unit_price > 0 {
+                        let compute_unit_price =
+                            estimate_compute_unit_price(&rpc_client, &[report.pool_id], &estimate)?;
+                        if compute_unit_price > max_compute_unit_price {
+                            println!(
+                                "compute unit price {} above {}, position {} is compounded later",
+                                compute_unit_price, max_compute_unit_price, report.position
+                            );
+                            continue;
+                        }
+                    }
+                    let nft_info = nft_infos
+                        .iter()
+                        .find(|nft_info| nft_info.position == report.position)
+                        .unwrap();
+                    let mut journal = Journal::open(
+                        &pool_config.journal_dir,
+                        &pool_config.http_url,
+                        &format!("autocompound-{}", nft_info.mint),
+                    )?;
+                    journal.reconcile(&rpc_client)?;
+                    println!(
+                        "compound position {}, fees_in_token_1:{}",
+                        report.position, fees_value
+                    );
+                    if let Some(health) = &health {
+                        health.set_pending_txs(1);
+                    }
+                    // a failed compound is retried in the next round, its journal kept for it
+                    match compound_position(
+                        &rpc_client,
+                        &pool_config,
+                        &payer,
+                        nft_info,
+                        &mut journal,
+                    ) {
+                        Ok(()) => journal.clear()?,
+                        Err(err) => println!("compound of {} failed: {}", report.position, err),
+                    }
+                    if let Some(health) = &health {
+                        health.set_pending_txs(0);
+                    }
+                }
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+            }
+        }
+        CommandsName::PositionReport {
+            position_nft_mint,
+            concurrency,
+            requests_per_second,
+        } => {
+            let (position_key, _) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+                    position_nft_mint.as_ref(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            // the history is read offline from the Index database when there is one
+            let events = match indexed_position_events(&pool_config, &position_nft_mint)? {
+                Some(events) => events,
+                None => load_position_history(
+                    &rpc_client,
+                    &pool_config.raydium_v3_program.to_string(),
+                    &position_key,
+                    &position_nft_mint,
+                    &BackfillConfig {
+                        from_time: 0,
+                        limit: 1000,
+                        concurrency,
+                        requests_per_second,
+                    },
+                )?,
+            };
+            if events.is_empty() {
+                println!("no transactions found for position {}", position_key);
+                return Ok(());
+            }
+            let ledger = PositionLedger::from_events(&events);
+            // a closed position is valued from the pool it was opened in
+            let report = load_position_reports(
+                &rpc_client,
+                &pool_config.raydium_v3_program,
+                &[(owner, position_key)],
+            )?
+            .pop();
+            let pool_id = match &report {
+                Some(report) => report.pool_id,
+                None => position_pool_id(&events).ok_or(format_err!(
+                    "the opening of position {} is not found",
+                    position_key
+                ))?,
+            };
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let price = sqrt_price_x64_to_price(
+                pool_state.sqrt_price_x64,
+                pool_state.mint_decimals_0,
+                pool_state.mint_decimals_1,
+            );
+            let (amounts, pending_fees) = report.as_ref().map_or(((0, 0), (0, 0)), |report| {
+                (
+                    (report.amount_0, report.amount_1),
+                    (report.fees_0, report.fees_1),
+                )
+            });
+            let pnl = PositionPnl::new(
+                &ledger,
+                price,
+                (pool_state.mint_decimals_0, pool_state.mint_decimals_1),
+                amounts,
+                pending_fees,
+            );
+            println!(
+                "position:{}, pool:{}, transactions:{}, open:{}",
+                position_key,
+                pool_id,
+                events.len(),
+                report.is_some()
+            );
+            println!(
+                "deposited amount_0:{}, amount_1:{}",
+                ledger.deposited_0, ledger.deposited_1
+            );
+            println!(
+                "withdrawn amount_0:{}, amount_1:{}",
+                ledger.withdrawn_0, ledger.withdrawn_1
+            );
+            println!(
+                "fees collected amount_0:{}, amount_1:{}",
+                ledger.fees_0, ledger.fees_1
+            );
+            for (reward_info, amount) in pool_state.reward_infos.iter().zip(ledger.rewards) {
+                if amount > 0 {
+                    println!(
+                        "rewards collected mint:{}, amount:{}",
+                        reward_info.token_mint, amount
+                    );
+                }
+            }
+            println!("valued in token_1 at price {}:", price);
+            println!("deposited:{}", pnl.deposited);
+            println!("withdrawn:{}", pnl.withdrawn);
+            println!("position:{}", pnl.position);
+            println!("realized fees:{}", pnl.realized_fees);
+            println!("unrealized fees:{}", pnl.unrealized_fees);
+            println!("impermanent loss:{}", pnl.impermanent_loss);
+            println!("net p&l:{}", pnl.net);
+        }
+        CommandsName::FeeHistory {
+            position_nft_mint,
+            concurrency,
+            requests_per_second,
+            out_file,
+        } => {
+            let (position_key, _) = Pubkey::find_program_address(
+                &[
+                    raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+                    position_nft_mint.as_ref(),
+                ],
+                &pool_config.raydium_v3_program,
+            );
+            // the history is read offline from the Index database when there is one
+            let events = match indexed_position_events(&pool_config, &position_nft_mint)? {
+                Some(events) => events,
+                None => load_position_history(
+                    &rpc_client,
+                    &pool_config.raydium_v3_program.to_string(),
+                    &position_key,
+                    &position_nft_mint,
+                    &BackfillConfig {
+                        from_time: 0,
+                        limit: 1000,
+                        concurrency,
+                        requests_per_second,
+                    },
+                )?,
+            };
+            let pool_id = position_pool_id(&events).ok_or(format_err!(
+                "the opening of position {} is not found",
+                position_key
+            ))?;
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let collections = fee_collections(&events);
+            let mut totals = (0u64, 0u64);
+            let mut rows = Vec::with_capacity(collections.len());
+            for collection in collections.iter() {
+                totals = (totals.0 + collection.fees_0, totals.1 + collection.fees_1);
+                rows.push(format!(
+                    "{},{},{},{},{},{},{}",
+                    collection.block_time.map_or(String::new(), format_utc),
+                    collection.slot,
+                    collection.signature,
+                    ui_amount(collection.fees_0, pool_state.mint_decimals_0),
+                    ui_amount(collection.fees_1, pool_state.mint_decimals_1),
+                    ui_amount(totals.0, pool_state.mint_decimals_0),
+                    ui_amount(totals.1, pool_state.mint_decimals_1)
+                ));
+            }
+            let csv = std::iter::once(
+                "time,slot,signature,fees_0,fees_1,total_fees_0,total_fees_1".to_string(),
+            )
+            .chain(rows)
+            .collect::<Vec<_>>()
+            .join("\n");
+            match out_file {
+                Some(out_file) => {
+                    std::fs::write(&out_file, csv + "\n")?;
+                    println!(
+                        "{} fee collections written to {}",
+                        collections.len(),
+                        out_file
+                    );
+                }
+                None => println!("{}", csv),
+            }
+        }
+        CommandsName::EstimateApr {
+            position_nft_mint,
+            range_pct,
+            limit,
+            concurrency,
+            requests_per_second,
+        } => {
+            let report = match position_nft_mint {
+                Some(position_nft_mint) => {
+                    let (position_key, _) = Pubkey::find_program_address(
+                        &[
+                            raydium_amm_v3::states::POSITION_SEED.as_bytes(),
+                            position_nft_mint.as_ref(),
+                        ],
+                        &pool_config.raydium_v3_program,
+                    );
+                    Some(
+                        load_position_reports(
+                            &rpc_client,
+                            &pool_config.raydium_v3_program,
+                            &[(owner, position_key)],
+                        )?
+                        .pop()
+                        .ok_or(format_err!("position {} not found", position_key))?,
+                    )
+                }
+                None => None,
+            };
+            let pool_id = report
+                .as_ref()
+                .map_or(pool_config.pool_id_account().unwrap(), |report| {
+                    report.pool_id
+                });
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let amm_config = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
+                &rpc_client.get_account(&pool_state.amm_config)?,
+            )?;
+            let price = sqrt_price_x64_to_price(
+                pool_state.sqrt_price_x64,
+                pool_state.mint_decimals_0,
+                pool_state.mint_decimals_1,
+            );
+            let sample = sample_pool_swaps(
+                &rpc_client,
+                &pool_config.raydium_v3_program.to_string(),
+                &pool_id,
+                limit,
+                concurrency,
+                requests_per_second,
+            )?;
+            if sample.swaps < 2 || sample.seconds == 0 {
+                println!(
+                    "not enough swaps in the latest {} transactions of pool {}",
+                    limit, pool_id
+                );
+                return Ok(());
+            }
+            let yearly_fees = yearly_lp_fees(
+                &sample,
+                &amm_config,
+                price,
+                pool_state.mint_decimals_0,
+                pool_state.mint_decimals_1,
+            );
+            println!(
+                "pool:{}, swaps:{} over {}s, volume_0:{}, volume_1:{}",
+                pool_id, sample.swaps, sample.seconds, sample.volume_0, sample.volume_1
+            );
+            println!("yearly lp fees in token_1:{}", yearly_fees);
+
+            // the reserves of the vaults, without the protocol and fund fees they hold
+            let vault_balance = |vault: &Pubkey| -> Result<u64> {
+                Ok(rpc_client
+                    .get_token_account_balance(vault)?
+                    .amount
+                    .parse::<u64>()?)
+            };
+            let reserve_0 = vault_balance(&pool_state.token_vault_0)?
+                .saturating_sub(pool_state.protocol_fees_token_0 + pool_state.fund_fees_token_0);
+            let reserve_1 = vault_balance(&pool_state.token_vault_1)?
+                .saturating_sub(pool_state.protocol_fees_token_1 + pool_state.fund_fees_token_1);
+            let tvl = ui_amount(reserve_0, pool_state.mint_decimals_0) * price
+                + ui_amount(reserve_1, pool_state.mint_decimals_1);
+            let pool_apr = if tvl > 0.0 {
+                yearly_fees / tvl * 100.0
+            } else {
+                0.0
+            };
+            println!("pool tvl in token_1:{}, apr:{}%", tvl, pool_apr);
+
+            if let Some(report) = &report {
+                if report.in_range() {
+                    let value = report.value_in_token_1() - report.fees_in_token_1();
+                    println!(
+                        "position:{}, share of the active liquidity:{}%, apr:{}%",
+                        report.position,
+                        report.liquidity as f64 / pool_state.liquidity as f64 * 100.0,
+                        liquidity_apr(yearly_fees, report.liquidity, pool_state.liquidity, value)
+                    );
+                } else {
+                    println!(
+                        "position:{} is out of range, it earns no fees, apr:0%",
+                        report.position
+                    );
+                }
+            }
+            if let Some(range_pct) = range_pct {
+                if range_pct <= 0.0 || range_pct >= 100.0 {
+                    return Err(format_err!("the range must be between 0 and 100 percent"));
+                }
+                let (tick_lower_index, tick_lower_price) = price_to_tick_with_spacing(
+                    price * (1.0 - range_pct / 100.0),
+                    pool_state.mint_decimals_0,
+                    pool_state.mint_decimals_1,
+                    pool_state.tick_spacing,
+                    false,
+                )?;
+                let (tick_upper_index, tick_upper_price) = price_to_tick_with_spacing(
+                    price * (1.0 + range_pct / 100.0),
+                    pool_state.mint_decimals_0,
+                    pool_state.mint_decimals_1,
+                    pool_state.tick_spacing,
+                    true,
+                )?;
+                println!(
+                    "range:{} ({}) to {} ({}), apr:{}%",
+                    tick_lower_price,
+                    tick_lower_index,
+                    tick_upper_price,
+                    tick_upper_index,
+                    range_apr(
+                        yearly_fees,
+                        &pool_state,
+                        price,
+                        tick_lower_index,
+                        tick_upper_index
+                    )?
+                );
+            }
+        }
+        CommandsName::ListPools { mint, config_index } => {
+            let amm_config = config_index.map(|config_index| {
+                Pubkey::find_program_address(
+                    &[
+                        raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
+                        &config_index.to_be_bytes(),
+                    ],
+                    &pool_config.raydium_v3_program,
+                )
+                .0
+            });
+            let pools = list_pools(
+                &rpc_client,
+                &pool_config.raydium_v3_program,
+                mint.as_ref(),
+                amm_config.as_ref(),
+            )?;
+            let mut amm_config_keys: Vec<Pubkey> =
+                pools.iter().map(|(_, pool)| pool.amm_config).collect();
+            amm_config_keys.sort();
+            amm_config_keys.dedup();
+            let mut trade_fee_rates = HashMap::new();
+            for (key, rsp) in amm_config_keys
+                .iter()
+                .zip(account_cache.get_multiple_accounts(&rpc_client, &amm_config_keys)?)
+            {
+                if let Some(account) = rsp {
+                    let amm_config =
+                        deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(&account)?;
+                    trade_fee_rates.insert(*key, amm_config.trade_fee_rate);
+                }
+            }
+            let mut printer = AccountPrinter::new(opts.output);
+            for (pool_id, pool) in pools.iter() {
+                let price = sqrt_price_x64_to_price(
+                    pool.sqrt_price_x64,
+                    pool.mint_decimals_0,
+                    pool.mint_decimals_1,
+                );
+                let trade_fee_percent = trade_fee_rates.get(&pool.amm_config).map(|rate| {
+                    f64::from(*rate) * 100.0
+                        / f64::from(raydium_amm_v3::states::FEE_RATE_DENOMINATOR_VALUE)
+                });
+                printer.text(format!(
+                    "pool:{}, mint_0:{}, mint_1:{}, price:{}, liquidity:{}, tick_spacing:{}, trade_fee_percent:{}",
+                    pool_id,
+                    pool.token_mint_0,
+                    pool.token_mint_1,
+                    price,
+                    pool.liquidity,
+                    pool.tick_spacing,
+                    trade_fee_percent.map_or("unknown".to_string(), |percent| percent.to_string())
+                ));
+                printer.json(serde_json::json!({
+                    "pool_id": pool_id.to_string(),
+                    "amm_config": pool.amm_config.to_string(),
+                    "token_mint_0": pool.token_mint_0.to_string(),
+                    "token_mint_1": pool.token_mint_1.to_string(),
+                    "price": price,
+                    "liquidity": pool.liquidity.to_string(),
+                    "tick_spacing": pool.tick_spacing,
+                    "trade_fee_percent": trade_fee_percent,
+                }));
+            }
+            printer.text(format!("{} pools", pools.len()));
+            printer.finish(false);
+        }
+        CommandsName::ListConfigs => {
+            let amm_configs = list_amm_configs(&rpc_client, &pool_config.raydium_v3_program)?;
+            let mut printer = AccountPrinter::new(opts.output);
+            for (key, amm_config) in amm_configs.iter() {
+                printer.text(key);
+                printer.account(Some(("amm_config", *key)), &AmmConfigDisplay(amm_config));
+            }
+            printer.text(format!("{} amm configs", amm_configs.len()));
+            printer.finish(false);
+        }
+        CommandsName::ExportDepth {
+            pool_id,
+            range_ticks,
+            out_file,
+        } => {
+            let pool_id = if let Some(pool_id) = pool_id {
+                pool_id
+            } else {
+                pool_config.pool_id_account().unwrap()
+            };
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let position_config = pool_config.with_pool(pool_id, &pool_state);
+            let tickarray_bitmap_extension = deserialize_anchor_account::<
+                raydium_amm_v3::states::TickArrayBitmapExtension,
+            >(
+                &rpc_client.get_account(&position_config.tickarray_bitmap_extension().unwrap())?,
+            )?;
+            let tick_current = pool_state.tick_current;
+            let ticks = load_initialized_ticks_in_range(
+                &rpc_client,
+                &pool_config.raydium_v3_program,
+                &pool_id,
+                &pool_state,
+                &tickarray_bitmap_extension,
+                tick_current.saturating_sub_unsigned(range_ticks),
+                tick_current.saturating_add_unsigned(range_ticks),
+            )?;
+            let levels =
+                liquidity_profile_in_range(&ticks, tick_current, pool_state.liquidity, range_ticks);
+            let rows: Vec<String> = levels
+                .iter()
+                .map(|level| {
+                    let price = tick_to_price(level.tick) * multipler(pool_state.mint_decimals_0)
+                        / multipler(pool_state.mint_decimals_1);
+                    format!(
+                        "{},{},{},{}",
+                        level.tick, price, level.liquidity_net, level.active_liquidity
+                    )
+                })
+                .collect();
+            let csv = std::iter::once("tick,price,liquidity_net,active_liquidity".to_string())
+                .chain(rows.iter().cloned())
+                .collect::<Vec<String>>()
+                .join("\n");
+            match out_file {
+                Some(out_file) => {
+                    std::fs::write(&out_file, csv + "\n")?;
+                    println!("{} ticks written to {}", rows.len(), out_file);
+                }
+                None => println!("{}", csv),
+            }
+        }
+        CommandsName::TickArrayMap { pool_id, arrays } => {
+            let pool_id = if let Some(pool_id) = pool_id {
+                pool_id
+            } else {
+                pool_config.pool_id_account().unwrap()
+            };
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let position_config = pool_config.with_pool(pool_id, &pool_state);
+            let tickarray_bitmap_extension = deserialize_anchor_account::<
+                raydium_amm_v3::states::TickArrayBitmapExtension,
+            >(
+                &rpc_client.get_account(&position_config.tickarray_bitmap_extension().unwrap())?,
+            )?;
+            let slots = tick_array_map(
+                &rpc_client,
+                &pool_config.raydium_v3_program,
+                &pool_id,
+                &pool_state,
+                &tickarray_bitmap_extension,
+                arrays,
+            )?;
+            let tick_count =
+                raydium_amm_v3::states::TickArrayState::tick_count(pool_state.tick_spacing);
+            let current_start = raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                pool_state.tick_current,
+                pool_state.tick_spacing,
+            );
+            println!(
+                "pool_id:{}, tick_current:{}, tick_spacing:{}, ticks per array:{}",
+                pool_id, pool_state.tick_current, pool_state.tick_spacing, tick_count
+            );
+            println!("# initialized, . not initialized, ! flagged without account, o account without flag, [ ] current array");
+            let map: String = slots
+                .iter()
+                .map(|slot| {
+                    let symbol = match (slot.flagged, slot.exists) {
+                        (true, true) => '#',
+                        (true, false) => '!',
+                        (false, true) => 'o',
+                        (false, false) => '.',
+                    };
+                    if slot.start_index == current_start {
+                        format!("[{}]", symbol)
+                    } else {
+                        symbol.to_string()
+                    }
+                })
+                .collect();
+            if let (Some(first), Some(last)) = (slots.first(), slots.last()) {
+                println!(
+                    "{} {} {}",
+                    first.start_index,
+                    map,
+                    last.start_index + tick_count
+                );
+            }
+            for slot in slots.iter().filter(|slot| slot.flagged) {
+                let price = tick_to_price(slot.start_index.max(tick_math::MIN_TICK))
+                    * multipler(pool_state.mint_decimals_0)
+                    / multipler(pool_state.mint_decimals_1);
+                println!(
+                    "initialized tick array:{}, price from:{}, account:{}",
+                    slot.start_index, price, slot.exists
+                );
+            }
+            if !slots
+                .iter()
+                .any(|slot| slot.start_index == current_start && slot.flagged)
+            {
+                println!(
+                    "the current tick array {} is not initialized, swaps start from the next initialized one",
+                    current_start
+                );
+            }
+            for slot in slots.iter().filter(|slot| slot.is_missing()) {
+                println!(
+                    "tick array {} is flagged initialized but its account is missing, swaps crossing it fail",
+                    slot.start_index
+                );
+            }
+            for (first, last) in tick_array_gaps(&slots) {
+                println!(
+                    "gap without liquidity from tick {} to {}",
+                    first,
+                    last + tick_count
+                );
+            }
+        }
+        CommandsName::Twap { pool_id, seconds } => {
+            let pool_id = if let Some(pool_id) = pool_id {
+                pool_id
+            } else {
+                pool_config.pool_id_account().unwrap()
+            };
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let observation_state =
+                deserialize_anchor_account::<raydium_amm_v3::states::ObservationState>(
+                    &rpc_client.get_account(&pool_state.observation_key)?,
+                )?;
+            // the observations are stamped with the clock of the chain, truncated to 32 bits
+            let now = rpc_client.get_block_time(rpc_client.get_slot()?)? as u32;
+            let tick = twap_tick(&observation_state, pool_state.tick_current, now, seconds)?;
+            let twap_price = 1.0001f64.powf(tick) * multipler(pool_state.mint_decimals_0)
+                / multipler(pool_state.mint_decimals_1);
+            let price = sqrt_price_x64_to_price(
+                pool_state.sqrt_price_x64,
+                pool_state.mint_decimals_0,
+                pool_state.mint_decimals_1,
+            );
+            let observations = ordered_observations(&observation_state);
+            println!(
+                "pool:{}, window:{}s, twap tick:{:.2}, twap price:{}, price:{}",
+                pool_id, seconds, tick, twap_price, price
+            );
+            if let Some(oldest) = observations.first() {
+                println!(
+                    "observations:{}, oldest window:{}s",
+                    observations.len(),
+                    now.saturating_sub(oldest.block_timestamp)
+                );
+            }
+        }
+        CommandsName::Volume {
+            pool_id,
+            since_slot,
+            limit,
+            concurrency,
+            requests_per_second,
+        } => {
+            let pool_id = if let Some(pool_id) = pool_id {
+                pool_id
+            } else {
+                pool_config.pool_id_account().unwrap()
+            };
+            let pool_state =
+                deserialize_anchor_account::<PoolState>(&rpc_client.get_account(&pool_id)?)?;
+            let amm_config = deserialize_anchor_account::<raydium_amm_v3::states::AmmConfig>(
+                &rpc_client.get_account(&pool_state.amm_config)?,
+            )?;
+            let swaps = match indexed_pool_swaps(&pool_config, &pool_id, since_slot)? {
+                Some(swaps) => swaps,
+                None => load_pool_swaps(
+                    &rpc_client,
+                    &pool_config.raydium_v3_program.to_string(),
+                    &pool_id,
+                    since_slot,
+                    limit,
+                    concurrency,
+                    requests_per_second,
+                )?,
+            };
+            let (decimals_0, decimals_1) = (pool_state.mint_decimals_0, pool_state.mint_decimals_1);
+            let ui = |amount: u128, decimals: u8| amount as f64 / 10f64.powi(i32::from(decimals));
+            let mut printer = AccountPrinter::new(opts.output);
+            for daily in daily_volumes(&swaps, amm_config.trade_fee_rate) {
+                let date = format_utc(daily.day * 86400)[..10].to_string();
+                printer.text(format!(
+                    "{} swaps:{}, volume_0:{}, volume_1:{}, fees_0:{}, fees_1:{}",
+                    date,
+                    daily.swaps,
+                    ui(daily.volume_0, decimals_0),
+                    ui(daily.volume_1, decimals_1),
+                    ui(daily.fees_0, decimals_0),
+                    ui(daily.fees_1, decimals_1)
+                ));
+                printer.json(serde_json::json!({
+                    "date": date,
+                    "swaps": daily.swaps,
+                    "volume_0": daily.volume_0.to_string(),
+                    "volume_1": daily.volume_1.to_string(),
+                    "fees_0": daily.fees_0.to_string(),
+                    "fees_1": daily.fees_1.to_string(),
+                }));
+            }
+            printer.finish(false);
+        }
+        CommandsName::MetricsExporter { addr, interval } => {
+            let exporter = MetricsExporter::serve(&addr)?;
+            loop {
+                let refresh = || -> Result<(usize, usize)> {
+                    let position_nft_infos = get_all_nft_and_position_by_owner(
+                        &rpc_client,
+                        &owner,
+                        &pool_config.raydium_v3_program,
+                    );
+                    let reports = load_owned_position_reports(
+                        &rpc_client,
+                        &pool_config,
+                        &owner,
+                        &position_nft_infos,
+                    )?;
+                    let mut pool_keys: Vec<Pubkey> = reports
+                        .iter()
+                        .map(|report| report.pool_id)
+                        .chain(pool_config.pool_id_account())
+                        .collect();
+                    pool_keys.sort();
+                    pool_keys.dedup();
+                    let mut pools = Vec::with_capacity(pool_keys.len());
+                    for (pool_id, rsp) in pool_keys
+                        .iter()
+                        .zip(get_multiple_accounts_concurrently(&rpc_client, &pool_keys)?)
+                    {
+                        if let Some(rsp) = rsp {
+                            pools.push((*pool_id, deserialize_anchor_account::<PoolState>(&rsp)?));
+                        }
+                    }
+                    exporter.update(render_metrics(&pools, &reports));
+                    Ok((pools.len(), reports.len()))
+                };
+                // a failed refresh keeps serving the previous metrics
+                match refresh() {
+                    Ok((pools, positions)) => {
+                        if let Some(health) = &health {
+                            health.heartbeat(None);
+                        }
+                        println!("refreshed {} pools and {} positions", pools, positions);
+                    }
+                    Err(err) => println!("refresh failed: {}", err),
+                }
+                std::thread::sleep(std::time::Duration::from_secs(interval));
+            }
+        }
+        CommandsName::Serve {
+            pool_ids,
+            addr,
+            refresh_ms,
+        } => {
+            let pool_ids = if pool_ids.is_empty() {
+                vec![pool_config.pool_id_account().unwrap()]
+            } else {
+                pool_ids
+            };
+            let slot_cache = SlotCache::new(pool_config.slot_cache_max_age, !opts.no_cache);
+            let mut pools = Vec::with_capacity(pool_ids.len());
+            for pool_id in pool_ids.iter() {
+                pools.push(WarmPool::load(
+                    &rpc_client,
+                    &account_cache,
+                    &slot_cache,
+                    &pool_config,
+                    *pool_id,
+                )?);
+            }
+            let pools = std::sync::RwLock::new(pools);
+            std::thread::scope(|scope| {
+                scope.spawn(|| loop {
+                    std::thread::sleep(std::time::Duration::from_millis(refresh_ms));
+                    for (i, pool_id) in pool_ids.iter().enumerate() {
+                        // a pool which fails to reload is quoted from its previous accounts
+                        match WarmPool::load(
+                            &rpc_client,
+                            &account_cache,
+                            &slot_cache,
+                            &pool_config,
+                            *pool_id,
+                        ) {
+                            Ok(pool) => pools.write().unwrap()[i] = pool,
+                            Err(err) => println!("reload of pool {} failed: {}", pool_id, err),
+                        }
+                    }
+                    if let Some(health) = &health {
+                        health.heartbeat(None);
+                        health.set_cache_stats(slot_cache.stats());
+                    }
+                });
+                serve_quotes(&addr, |request| {
+                    let pools = pools.read().unwrap();
+                    let (pool, quote) = best_warm_quote(&pools, request)?;
+                    Ok(serde_json::json!({
+                        "pool_id": pool.pool_id.to_string(),
+                        "slot": pool.slot,
+                        "amount_in": quote.amount_in,
+                        "amount_out": quote.amount_out,
+                        "trade_fee": quote.trade_fee,
+                        "transfer_fee_in": quote.transfer_fee_in,
+                        "transfer_fee_out": quote.transfer_fee_out,
+                        "price_before": quote.price_before,
+                        "price_after": quote.price_after,
+                        "price_impact": quote.price_impact(),
+                        "ticks_crossed": quote.ticks_crossed(),
+                    }))
+                })
+            })?;
+        }
+        #[cfg(feature = "grpc")]
+        CommandsName::GrpcServe {
+            pool_ids,
+            addr,
+            refresh_ms,
+        } => {
+            let pool_ids = if pool_ids.is_empty() {
+                vec![pool_config.pool_id_account().unwrap()]
+            } else {
+                pool_ids
+            };
+            let slot_cache = SlotCache::new(pool_config.slot_cache_max_age, !opts.no_cache);
+            let mut pools = Vec::with_capacity(pool_ids.len());
+            for pool_id in pool_ids.iter() {
+                pools.push(WarmPool::load(
+                    &rpc_client,
+                    &account_cache,
+                    &slot_cache,
+                    &pool_config,
+                    *pool_id,
+                )?);
+            }
+            let backend = Arc::new(GrpcBackend {
+                rpc_client: new_rpc_client(&pool_config, opts.rpc_trace)?,
+                account_cache,
+                slot_cache,
+                pool_config,
+                payer,
+                pools: std::sync::RwLock::new(pools),
+            });
+            let refreshed = backend.clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(std::time::Duration::from_millis(refresh_ms));
+                for (i, pool_id) in pool_ids.iter().enumerate() {
+                    // a pool which fails to reload is quoted from its previous accounts
+                    match WarmPool::load(
+                        &refreshed.rpc_client,
+                        &refreshed.account_cache,
+                        &refreshed.slot_cache,
+                        &refreshed.pool_config,
+                        *pool_id,
+                    ) {
+                        Ok(pool) => refreshed.pools.write().unwrap()[i] = pool,
+                        Err(err) => println!("reload of pool {} failed: {}", pool_id, err),
+                    }
+                }
+                if let Some(health) = &health {
+                    health.heartbeat(None);
+                    health.set_cache_stats(refreshed.slot_cache.stats());
+                }
+            });
+            instructions::grpc::serve_grpc(&addr, backend)?;
+        }
+        #[cfg(not(feature = "grpc"))]
+        CommandsName::GrpcServe { .. } => {
+            return Err(format_err!("the client is built without the grpc feature"));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod opts_test {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn opts_debug_assert_test() {
+        Opts::command().debug_assert();
+    }
+
+    // the file of these commands must not clash with the global --output format
+    #[test]
+    fn out_file_parse_test() {
+        let pool_id = Pubkey::new_unique().to_string();
+        let position_nft_mint = Pubkey::new_unique().to_string();
+        let parse = |args: &[&str]| {
+            Opts::try_parse_from(["client", "--output", "json"].iter().chain(args)).unwrap()
+        };
+
+        let opts = parse(&["tax-export", &pool_id, "--out-file", "tax.csv"]);
+        assert_eq!(opts.output, OutputFormat::Json);
+        match opts.command {
+            CommandsName::TaxExport { out_file, .. } => {
+                assert_eq!(out_file.as_deref(), Some("tax.csv"))
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+        match parse(&["tax-export", &pool_id]).command {
+            CommandsName::TaxExport { out_file, .. } => assert_eq!(out_file, None),
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        match parse(&["fee-history", &position_nft_mint, "-o", "fees.csv"]).command {
+            CommandsName::FeeHistory { out_file, .. } => {
+                assert_eq!(out_file.as_deref(), Some("fees.csv"))
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+        match parse(&["fee-history", &position_nft_mint]).command {
+            CommandsName::FeeHistory { out_file, .. } => assert_eq!(out_file, None),
+            command => panic!("unexpected command {:?}", command),
+        }
+
+        match parse(&["export-depth", &pool_id, "--out-file", "depth.csv"]).command {
+            CommandsName::ExportDepth { out_file, .. } => {
+                assert_eq!(out_file.as_deref(), Some("depth.csv"))
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+        match parse(&["export-depth"]).command {
+            CommandsName::ExportDepth { out_file, .. } => assert_eq!(out_file, None),
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
 }
@@ -0,0 +1,611 @@
+//! Display wrappers of the program accounts, with labeled and decimal adjusted fields.
+//! Each wrapper prints one `label: value` line per field and serializes to a json object, a label
+//! repeated for each reward is serialized once with the array of the values.
+
+use anchor_client::solana_sdk::{program_option::COption, pubkey::Pubkey};
+use clap::ValueEnum;
+use raydium_amm_v3::states::{
+    AmmConfig, ObservationState, OperationState, PersonalPositionState, PoolCreationMode,
+    PoolState, ProtocolPositionState, RewardState, TickArrayBitmapExtension, TickState,
+    FEE_RATE_DENOMINATOR_VALUE,
+};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::{Map, Value};
+use spl_token_2022::{
+    extension::{BaseStateWithExtensions, StateWithExtensions},
+    state::{Account, Mint},
+};
+use std::fmt;
+
+use crate::instructions::portfolio::ui_amount;
+use crate::instructions::utils::{
+    decode_pool_status, multipler, sqrt_price_x64_to_price, tick_to_price,
+};
+
+/// An account shown as labeled fields
+pub trait LabeledFields {
+    fn fields(&self) -> Vec<(&'static str, Value)>;
+
+    /// The fields as a json object, in their order
+    fn to_json(&self) -> Map<String, Value> {
+        let mut object = Map::new();
+        let fields = self.fields();
+        for (label, value) in fields.iter() {
+            if object.contains_key(*label) {
+                continue;
+            }
+            let values: Vec<Value> = fields
+                .iter()
+                .filter(|(other, _)| other == label)
+                .map(|(_, value)| value.clone())
+                .collect();
+            let value = if values.len() == 1 {
+                value.clone()
+            } else {
+                Value::Array(values)
+            };
+            object.insert(label.to_string(), value);
+        }
+        object
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Labeled fields, one per line
+    #[default]
+    Text,
+    /// One json document, to be piped into jq or an indexer
+    Json,
+}
+
+/// Prints the accounts of a command, as text while they are loaded or as one json document once
+/// the command is done: an object for a single account and an array otherwise
+pub struct AccountPrinter {
+    format: OutputFormat,
+    items: Vec<Value>,
+}
+
+impl AccountPrinter {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            items: Vec::new(),
+        }
+    }
+
+    /// A line of the text output only, e.g. the address of the account or a total
+    pub fn text(&self, line: impl fmt::Display) {
+        if self.format == OutputFormat::Text {
+            println!("{}", line);
+        }
+    }
+
+    /// The account, with its address as the first field of the json object
+    pub fn account<T: LabeledFields + fmt::Display>(
+        &mut self,
+        key: Option<(&str, Pubkey)>,
+        account: &T,
+    ) {
+        match self.format {
+            OutputFormat::Text => println!("{}", account),
+            OutputFormat::Json => {
+                let mut object = Map::new();
+                if let Some((label, key)) = key {
+                    object.insert(label.to_string(), text(key));
+                }
+                object.extend(account.to_json());
+                self.items.push(Value::Object(object));
+            }
+        }
+    }
+
+    /// An item of the json output only, for the outputs not made of labeled accounts
+    pub fn json(&mut self, value: Value) {
+        if self.format == OutputFormat::Json {
+            self.items.push(value);
+        }
+    }
+
+    pub fn finish(self, single: bool) {
+        if self.format != OutputFormat::Json {
+            return;
+        }
+        let document = match (single, self.items.len()) {
+            (true, 1) => self.items.into_iter().next().unwrap(),
+            _ => Value::Array(self.items),
+        };
+        println!("{}", serde_json::to_string_pretty(&document).unwrap());
+    }
+}
+
+// the fields are taken by value, references to the fields of packed accounts are not allowed
+fn text(value: impl ToString) -> Value {
+    Value::String(value.to_string())
+}
+
+fn number(value: impl Into<serde_json::Number>) -> Value {
+    Value::Number(value.into())
+}
+
+fn float(value: f64) -> Value {
+    serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+// a fee rate in hundredths of a bip as a percentage
+fn rate_percent(rate: u32) -> Value {
+    float(f64::from(rate) * 100.0 / f64::from(FEE_RATE_DENOMINATOR_VALUE))
+}
+
+fn tick_price(tick: i32, decimals_0: u8, decimals_1: u8) -> Value {
+    float(tick_to_price(tick) * multipler(decimals_0) / multipler(decimals_1))
+}
+
+fn keys(keys: &[Pubkey]) -> Value {
+    Value::Array(
+        keys.iter()
+            .filter(|key| **key != Pubkey::default())
+            .map(text)
+            .collect(),
+    )
+}
+
+macro_rules! labeled_display {
+    ($($wrapper:ident),*) => {
+        $(
+            impl fmt::Display for $wrapper<'_> {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    for (label, value) in self.fields() {
+                        match value {
+                            Value::String(value) => writeln!(f, "{:<30}{}", label, value)?,
+                            value => writeln!(f, "{:<30}{}", label, value)?,
+                        }
+                    }
+                    Ok(())
+                }
+            }
+
+            impl Serialize for $wrapper<'_> {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let object = self.to_json();
+                    let mut map = serializer.serialize_map(Some(object.len()))?;
+                    for (label, value) in object {
+                        map.serialize_entry(&label, &value)?;
+                    }
+                    map.end()
+                }
+            }
+        )*
+    };
+}
+
+labeled_display!(
+    PoolDisplay,
+    AmmConfigDisplay,
+    PersonalPositionDisplay,
+    ProtocolPositionDisplay,
+    TickDisplay,
+    ObservationDisplay,
+    OperationDisplay,
+    BitmapExtensionDisplay,
+    MintDisplay,
+    TokenAccountDisplay
+);
+
+pub struct PoolDisplay<'a>(pub &'a PoolState);
+
+impl LabeledFields for PoolDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let pool = self.0;
+        let (decimals_0, decimals_1) = (pool.mint_decimals_0, pool.mint_decimals_1);
+        let mut fields = vec![
+            ("amm_config", text(pool.amm_config)),
+            ("owner", text(pool.owner)),
+            ("token_mint_0", text(pool.token_mint_0)),
+            ("token_mint_1", text(pool.token_mint_1)),
+            ("token_vault_0", text(pool.token_vault_0)),
+            ("token_vault_1", text(pool.token_vault_1)),
+            ("observation", text(pool.observation_key)),
+            ("decimals_0", number(decimals_0)),
+            ("decimals_1", number(decimals_1)),
+            ("tick_spacing", number(pool.tick_spacing)),
+            ("liquidity", text(pool.liquidity)),
+            ("sqrt_price_x64", text(pool.sqrt_price_x64)),
+            (
+                "price",
+                float(sqrt_price_x64_to_price(
+                    pool.sqrt_price_x64,
+                    decimals_0,
+                    decimals_1,
+                )),
+            ),
+            ("tick_current", number(pool.tick_current)),
+            (
+                "fee_growth_global_0_x64",
+                text(pool.fee_growth_global_0_x64),
+            ),
+            (
+                "fee_growth_global_1_x64",
+                text(pool.fee_growth_global_1_x64),
+            ),
+            (
+                "protocol_fees_0",
+                float(ui_amount(pool.protocol_fees_token_0, decimals_0)),
+            ),
+            (
+                "protocol_fees_1",
+                float(ui_amount(pool.protocol_fees_token_1, decimals_1)),
+            ),
+            (
+                "fund_fees_0",
+                float(ui_amount(pool.fund_fees_token_0, decimals_0)),
+            ),
+            (
+                "fund_fees_1",
+                float(ui_amount(pool.fund_fees_token_1, decimals_1)),
+            ),
+            (
+                "total_fees_0",
+                float(ui_amount(pool.total_fees_token_0, decimals_0)),
+            ),
+            (
+                "total_fees_claimed_0",
+                float(ui_amount(pool.total_fees_claimed_token_0, decimals_0)),
+            ),
+            (
+                "total_fees_1",
+                float(ui_amount(pool.total_fees_token_1, decimals_1)),
+            ),
+            (
+                "total_fees_claimed_1",
+                float(ui_amount(pool.total_fees_claimed_token_1, decimals_1)),
+            ),
+            ("swap_in_amount_0", text(pool.swap_in_amount_token_0)),
+            ("swap_out_amount_0", text(pool.swap_out_amount_token_0)),
+            ("swap_in_amount_1", text(pool.swap_in_amount_token_1)),
+            ("swap_out_amount_1", text(pool.swap_out_amount_token_1)),
+            ("status", text(format!("{:#010b}", pool.status))),
+            (
+                "disabled",
+                Value::Array(
+                    decode_pool_status(pool.status)
+                        .into_iter()
+                        .filter(|(_, enabled)| !enabled)
+                        .map(|(capability, _)| text(capability))
+                        .collect(),
+                ),
+            ),
+            ("open_time", number(pool.open_time)),
+            ("recent_epoch", number(pool.recent_epoch)),
+        ];
+        let reward_infos = pool.reward_infos;
+        for reward_info in reward_infos.iter() {
+            let reward_state = reward_info.reward_state;
+            if reward_state == RewardState::Uninitialized as u8 {
+                continue;
+            }
+            fields.push(("reward_mint", text(reward_info.token_mint)));
+            fields.push(("reward_open_time", number(reward_info.open_time)));
+            fields.push(("reward_end_time", number(reward_info.end_time)));
+            fields.push((
+                "reward_emissions_per_second",
+                float(reward_info.emissions_per_second_x64 as f64 / 2f64.powi(64)),
+            ));
+            fields.push((
+                "reward_total_emissioned",
+                number(reward_info.reward_total_emissioned),
+            ));
+            fields.push(("reward_claimed", number(reward_info.reward_claimed)));
+        }
+        fields
+    }
+}
+
+pub struct AmmConfigDisplay<'a>(pub &'a AmmConfig);
+
+impl LabeledFields for AmmConfigDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let config = self.0;
+        vec![
+            ("index", number(config.index)),
+            ("owner", text(config.owner)),
+            ("trade_fee_percent", rate_percent(config.trade_fee_rate)),
+            (
+                "protocol_fee_percent_of_trade_fee",
+                rate_percent(config.protocol_fee_rate),
+            ),
+            (
+                "fund_fee_percent_of_trade_fee",
+                rate_percent(config.fund_fee_rate),
+            ),
+            ("tick_spacing", number(config.tick_spacing)),
+            ("fund_owner", text(config.fund_owner)),
+            (
+                "pool_creation_mode",
+                match PoolCreationMode::try_from(config.pool_creation_mode) {
+                    Ok(mode) => text(format!("{:?}", mode)),
+                    Err(_) => text(format!("unknown {}", config.pool_creation_mode)),
+                },
+            ),
+            (
+                "create_pool_fee_sol",
+                float(ui_amount(config.create_pool_fee, 9)),
+            ),
+        ]
+    }
+}
+
+/// A personal position, its amounts in ui units when the decimals of the pool are known
+pub struct PersonalPositionDisplay<'a> {
+    pub position: &'a PersonalPositionState,
+    pub key: Option<Pubkey>,
+    pub decimals: Option<(u8, u8)>,
+}
+
+impl<'a> PersonalPositionDisplay<'a> {
+    pub fn new(position: &'a PersonalPositionState) -> Self {
+        Self {
+            position,
+            key: None,
+            decimals: None,
+        }
+    }
+
+    pub fn with_key(self, key: Pubkey) -> Self {
+        Self {
+            key: Some(key),
+            ..self
+        }
+    }
+
+    pub fn with_decimals(self, decimals_0: u8, decimals_1: u8) -> Self {
+        Self {
+            decimals: Some((decimals_0, decimals_1)),
+            ..self
+        }
+    }
+}
+
+impl LabeledFields for PersonalPositionDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let position = self.position;
+        let mut fields = Vec::new();
+        if let Some(key) = self.key {
+            fields.push(("position", text(key)));
+        }
+        fields.extend([
+            ("nft_mint", text(position.nft_mint)),
+            ("pool_id", text(position.pool_id)),
+            ("tick_lower", number(position.tick_lower_index)),
+            ("tick_upper", number(position.tick_upper_index)),
+            ("liquidity", text(position.liquidity)),
+            (
+                "fee_growth_inside_0_last_x64",
+                text(position.fee_growth_inside_0_last_x64),
+            ),
+            (
+                "fee_growth_inside_1_last_x64",
+                text(position.fee_growth_inside_1_last_x64),
+            ),
+        ]);
+        match self.decimals {
+            Some((decimals_0, decimals_1)) => fields.extend([
+                (
+                    "price_lower",
+                    tick_price(position.tick_lower_index, decimals_0, decimals_1),
+                ),
+                (
+                    "price_upper",
+                    tick_price(position.tick_upper_index, decimals_0, decimals_1),
+                ),
+                (
+                    "fees_owed_0",
+                    float(ui_amount(position.token_fees_owed_0, decimals_0)),
+                ),
+                (
+                    "fees_owed_1",
+                    float(ui_amount(position.token_fees_owed_1, decimals_1)),
+                ),
+            ]),
+            None => fields.extend([
+                ("fees_owed_0", number(position.token_fees_owed_0)),
+                ("fees_owed_1", number(position.token_fees_owed_1)),
+            ]),
+        }
+        for reward_info in position.reward_infos.iter() {
+            fields.push(("reward_owed", number(reward_info.reward_amount_owed)));
+            fields.push((
+                "reward_growth_inside_last_x64",
+                text(reward_info.growth_inside_last_x64),
+            ));
+        }
+        fields
+    }
+}
+
+pub struct ProtocolPositionDisplay<'a>(pub &'a ProtocolPositionState);
+
+impl LabeledFields for ProtocolPositionDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let position = self.0;
+        let mut fields = vec![
+            ("pool_id", text(position.pool_id)),
+            ("tick_lower", number(position.tick_lower_index)),
+            ("tick_upper", number(position.tick_upper_index)),
+            ("liquidity", text(position.liquidity)),
+            (
+                "fee_growth_inside_0_last_x64",
+                text(position.fee_growth_inside_0_last_x64),
+            ),
+            (
+                "fee_growth_inside_1_last_x64",
+                text(position.fee_growth_inside_1_last_x64),
+            ),
+            ("fees_owed_0", number(position.token_fees_owed_0)),
+            ("fees_owed_1", number(position.token_fees_owed_1)),
+        ];
+        for reward_growth_inside in position.reward_growth_inside {
+            fields.push(("reward_growth_inside_x64", text(reward_growth_inside)));
+        }
+        fields
+    }
+}
+
+pub struct TickDisplay<'a>(pub &'a TickState);
+
+impl LabeledFields for TickDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let tick = self.0;
+        let mut fields = vec![
+            ("tick", number(tick.tick)),
+            ("liquidity_net", text(tick.liquidity_net)),
+            ("liquidity_gross", text(tick.liquidity_gross)),
+            (
+                "fee_growth_outside_0_x64",
+                text(tick.fee_growth_outside_0_x64),
+            ),
+            (
+                "fee_growth_outside_1_x64",
+                text(tick.fee_growth_outside_1_x64),
+            ),
+        ];
+        let reward_growths_outside_x64 = tick.reward_growths_outside_x64;
+        for reward_growth_outside in reward_growths_outside_x64 {
+            fields.push(("reward_growth_outside_x64", text(reward_growth_outside)));
+        }
+        fields
+    }
+}
+
+pub struct ObservationDisplay<'a>(pub &'a ObservationState);
+
+impl LabeledFields for ObservationDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let observation_state = self.0;
+        let observations = observation_state.observations;
+        let index = usize::from(observation_state.observation_index);
+        let latest = &observations[index];
+        vec![
+            ("pool_id", text(observation_state.pool_id)),
+            ("initialized", Value::Bool(observation_state.initialized)),
+            (
+                "observation_index",
+                number(observation_state.observation_index),
+            ),
+            (
+                "recorded",
+                number(
+                    observations
+                        .iter()
+                        .filter(|observation| observation.block_timestamp != 0)
+                        .count() as u64,
+                ),
+            ),
+            ("latest_block_timestamp", number(latest.block_timestamp)),
+            ("latest_tick_cumulative", number(latest.tick_cumulative)),
+            ("recent_epoch", number(observation_state.recent_epoch)),
+        ]
+    }
+}
+
+pub struct OperationDisplay<'a>(pub &'a OperationState);
+
+impl LabeledFields for OperationDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let operation_owners = self.0.operation_owners;
+        let whitelist_mints = self.0.whitelist_mints;
+        vec![
+            ("operation_owners", keys(&operation_owners)),
+            ("whitelist_mints", keys(&whitelist_mints)),
+        ]
+    }
+}
+
+pub struct BitmapExtensionDisplay<'a>(pub &'a TickArrayBitmapExtension);
+
+impl LabeledFields for BitmapExtensionDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let extension = self.0;
+        let count = |bitmap: &[[u64; 8]]| -> u64 {
+            bitmap
+                .iter()
+                .flatten()
+                .map(|word| u64::from(word.count_ones()))
+                .sum()
+        };
+        let positive_tick_array_bitmap = extension.positive_tick_array_bitmap;
+        let negative_tick_array_bitmap = extension.negative_tick_array_bitmap;
+        vec![
+            ("pool_id", text(extension.pool_id)),
+            (
+                "positive_tick_arrays",
+                number(count(&positive_tick_array_bitmap)),
+            ),
+            (
+                "negative_tick_arrays",
+                number(count(&negative_tick_array_bitmap)),
+            ),
+        ]
+    }
+}
+
+fn optional_key(key: COption<Pubkey>) -> Value {
+    match key {
+        COption::Some(key) => text(key),
+        COption::None => Value::Null,
+    }
+}
+
+pub struct MintDisplay<'a>(pub &'a StateWithExtensions<'a, Mint>);
+
+impl LabeledFields for MintDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let mint = self.0.base;
+        vec![
+            ("mint_authority", optional_key(mint.mint_authority)),
+            ("supply", number(mint.supply)),
+            ("decimals", number(mint.decimals)),
+            ("is_initialized", Value::Bool(mint.is_initialized)),
+            ("freeze_authority", optional_key(mint.freeze_authority)),
+            (
+                "extensions",
+                Value::Array(
+                    self.0
+                        .get_extension_types()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|extension| text(format!("{:?}", extension)))
+                        .collect(),
+                ),
+            ),
+        ]
+    }
+}
+
+pub struct TokenAccountDisplay<'a>(pub &'a StateWithExtensions<'a, Account>);
+
+impl LabeledFields for TokenAccountDisplay<'_> {
+    fn fields(&self) -> Vec<(&'static str, Value)> {
+        let account = self.0.base;
+        vec![
+            ("mint", text(account.mint)),
+            ("owner", text(account.owner)),
+            ("amount", number(account.amount)),
+            ("delegate", optional_key(account.delegate)),
+            ("state", text(format!("{:?}", account.state))),
+            ("delegated_amount", number(account.delegated_amount)),
+            ("close_authority", optional_key(account.close_authority)),
+            (
+                "extensions",
+                Value::Array(
+                    self.0
+                        .get_extension_types()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|extension| text(format!("{:?}", extension)))
+                        .collect(),
+                ),
+            ),
+        ]
+    }
+}
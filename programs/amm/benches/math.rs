@@ -0,0 +1,130 @@
+//! Benchmarks of the math libraries on the paths a swap quote runs for every step.
+//!
+//! Run with `cargo bench -p raydium-amm-v3`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use raydium_amm_v3::libraries::{
+    big_num::{U128, U256},
+    full_math::MulDiv,
+    liquidity_math, sqrt_price_math, swap_math, tick_math,
+};
+
+// the price of tick 1000 and a liquidity of a medium size pool
+const SQRT_PRICE_X64: u128 = 19_392_480_388_906_836_277;
+const LIQUIDITY: u128 = 4_000_000_000_000;
+
+fn bench_tick_math(c: &mut Criterion) {
+    c.bench_function("get_sqrt_price_at_tick", |b| {
+        b.iter(|| tick_math::get_sqrt_price_at_tick(black_box(-52_345)).unwrap())
+    });
+    c.bench_function("get_tick_at_sqrt_price", |b| {
+        b.iter(|| tick_math::get_tick_at_sqrt_price(black_box(SQRT_PRICE_X64)).unwrap())
+    });
+}
+
+fn bench_sqrt_price_math(c: &mut Criterion) {
+    c.bench_function("next_sqrt_price_from_input_0", |b| {
+        b.iter(|| {
+            sqrt_price_math::get_next_sqrt_price_from_input(
+                black_box(SQRT_PRICE_X64),
+                black_box(LIQUIDITY),
+                black_box(1_000_000),
+                true,
+            )
+        })
+    });
+    c.bench_function("next_sqrt_price_from_input_1", |b| {
+        b.iter(|| {
+            sqrt_price_math::get_next_sqrt_price_from_input(
+                black_box(SQRT_PRICE_X64),
+                black_box(LIQUIDITY),
+                black_box(1_000_000),
+                false,
+            )
+        })
+    });
+    c.bench_function("get_delta_amount_0_unsigned", |b| {
+        b.iter(|| {
+            liquidity_math::get_delta_amount_0_unsigned(
+                black_box(SQRT_PRICE_X64),
+                black_box(SQRT_PRICE_X64 + (1 << 60)),
+                black_box(LIQUIDITY),
+                true,
+            )
+            .unwrap()
+        })
+    });
+}
+
+fn bench_swap_math(c: &mut Criterion) {
+    let sqrt_price_target_x64 = tick_math::get_sqrt_price_at_tick(1_060).unwrap();
+    c.bench_function("compute_swap_step_base_input", |b| {
+        b.iter(|| {
+            swap_math::compute_swap_step(
+                black_box(SQRT_PRICE_X64),
+                black_box(sqrt_price_target_x64),
+                black_box(LIQUIDITY),
+                black_box(1_000_000_000),
+                2500,
+                true,
+                false,
+                1,
+            )
+            .unwrap()
+        })
+    });
+    c.bench_function("compute_swap_step_base_output", |b| {
+        b.iter(|| {
+            swap_math::compute_swap_step(
+                black_box(SQRT_PRICE_X64),
+                black_box(sqrt_price_target_x64),
+                black_box(LIQUIDITY),
+                black_box(1_000_000_000),
+                2500,
+                false,
+                false,
+                1,
+            )
+            .unwrap()
+        })
+    });
+}
+
+fn bench_mul_div(c: &mut Criterion) {
+    c.bench_function("mul_div_floor_u64", |b| {
+        b.iter(|| black_box(u64::MAX / 3).mul_div_floor(black_box(997_500), black_box(1_000_000)))
+    });
+    c.bench_function("mul_div_ceil_u128_narrow", |b| {
+        b.iter(|| {
+            U128::from(black_box(LIQUIDITY)).mul_div_ceil(
+                U128::from(black_box(1u128 << 60)),
+                U128::from(black_box(SQRT_PRICE_X64)),
+            )
+        })
+    });
+    c.bench_function("mul_div_ceil_u128_wide", |b| {
+        b.iter(|| {
+            U128::from(black_box(SQRT_PRICE_X64)).mul_div_ceil(
+                U128::from(black_box(SQRT_PRICE_X64)),
+                U128::from(black_box(LIQUIDITY)),
+            )
+        })
+    });
+    c.bench_function("mul_div_floor_u256", |b| {
+        b.iter(|| {
+            U256::from(black_box(LIQUIDITY) << 64).mul_div_floor(
+                U256::from(black_box(SQRT_PRICE_X64)),
+                U256::from(black_box(SQRT_PRICE_X64 + 1)),
+            )
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_tick_math,
+    bench_sqrt_price_math,
+    bench_swap_math,
+    bench_mul_div
+);
+criterion_main!(benches);
@@ -107,4 +107,16 @@ pub enum ErrorCode {
     CalculateOverflow,
     #[msg("TransferFee calculate not match")]
     TransferFeeCalculateNotMatch,
+    #[msg("Invalid pool creation mode")]
+    InvalidPoolCreationMode,
+    #[msg("Missing the receiver account of the create pool fee")]
+    MissingCreatePoolFeeReceiver,
+    #[msg("Invalid fee discount tier")]
+    InvalidFeeDiscountTier,
+    #[msg("Missing the emergency pause account")]
+    MissingEmergencyPauseAccount,
+    #[msg("Swaps and deposits are paused")]
+    EmergencyPaused,
+    #[msg("Invalid emergency pause duration")]
+    InvalidEmergencyPauseDuration,
 }
@@ -0,0 +1,33 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ClosePoolCreatorPermission<'info> {
+    /// The amm config owner or admin, receives the rent of the permission account
+    #[account(
+        mut,
+        constraint = (owner.key() == amm_config.owner || owner.key() == crate::admin::ID) @ ErrorCode::NotApproved
+    )]
+    pub owner: Signer<'info>,
+
+    /// The amm config the permission was granted for
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// The permission account to revoke
+    #[account(
+        mut,
+        seeds = [
+            POOL_CREATOR_PERMISSION_SEED.as_bytes(),
+            amm_config.key().as_ref(),
+            pool_creator_permission.creator.as_ref(),
+        ],
+        bump = pool_creator_permission.bump,
+        close = owner
+    )]
+    pub pool_creator_permission: Account<'info, PoolCreatorPermission>,
+}
+
+pub fn close_pool_creator_permission(_ctx: Context<ClosePoolCreatorPermission>) -> Result<()> {
+    Ok(())
+}
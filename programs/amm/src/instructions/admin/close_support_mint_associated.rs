@@ -0,0 +1,28 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CloseSupportMintAssociated<'info> {
+    /// The admin or support mint owner, receives the rent of the closed account
+    #[account(
+        mut,
+        constraint = (owner.key() == crate::admin::ID || owner.key() == crate::create_support_mint_associated_owner::ID) @ ErrorCode::NotApproved
+    )]
+    pub owner: Signer<'info>,
+    /// The support mint state account to close, the mint is no longer supported afterwards
+    #[account(
+        mut,
+        seeds = [
+            SUPPORT_MINT_SEED.as_bytes(),
+            support_mint_associated.mint.as_ref(),
+        ],
+        bump = support_mint_associated.bump,
+        close = owner
+    )]
+    pub support_mint_associated: Account<'info, SupportMintAssociated>,
+}
+
+pub fn close_support_mint_associated(_ctx: Context<CloseSupportMintAssociated>) -> Result<()> {
+    Ok(())
+}
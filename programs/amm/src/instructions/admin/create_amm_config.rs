@@ -55,6 +55,8 @@ pub fn create_amm_config(
         tick_spacing: amm_config.tick_spacing,
         fund_fee_rate: amm_config.fund_fee_rate,
         fund_owner: amm_config.fund_owner,
+        pool_creation_mode: amm_config.pool_creation_mode,
+        create_pool_fee: amm_config.create_pool_fee,
     });
 
     Ok(())
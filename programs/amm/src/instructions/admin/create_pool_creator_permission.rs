@@ -0,0 +1,44 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+use std::ops::DerefMut;
+
+#[derive(Accounts)]
+pub struct CreatePoolCreatorPermission<'info> {
+    /// The amm config owner or admin
+    #[account(
+        mut,
+        constraint = (owner.key() == amm_config.owner || owner.key() == crate::admin::ID) @ ErrorCode::NotApproved
+    )]
+    pub owner: Signer<'info>,
+
+    /// The amm config the permission is granted for
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// CHECK: Address allowed to create pools, only its key is stored
+    pub creator: UncheckedAccount<'info>,
+
+    /// Initialize the permission account of the creator
+    #[account(
+        init,
+        seeds = [
+            POOL_CREATOR_PERMISSION_SEED.as_bytes(),
+            amm_config.key().as_ref(),
+            creator.key().as_ref(),
+        ],
+        bump,
+        payer = owner,
+        space = PoolCreatorPermission::LEN
+    )]
+    pub pool_creator_permission: Account<'info, PoolCreatorPermission>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_pool_creator_permission(ctx: Context<CreatePoolCreatorPermission>) -> Result<()> {
+    let permission = ctx.accounts.pool_creator_permission.deref_mut();
+    permission.bump = ctx.bumps.pool_creator_permission;
+    permission.amm_config = ctx.accounts.amm_config.key();
+    permission.creator = ctx.accounts.creator.key();
+    Ok(())
+}
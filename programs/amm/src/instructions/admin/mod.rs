@@ -24,3 +24,18 @@ pub use update_pool_status::*;
 
 pub mod create_support_mint_associated;
 pub use create_support_mint_associated::*;
+
+pub mod close_support_mint_associated;
+pub use close_support_mint_associated::*;
+
+pub mod create_pool_creator_permission;
+pub use create_pool_creator_permission::*;
+
+pub mod close_pool_creator_permission;
+pub use close_pool_creator_permission::*;
+
+pub mod set_fee_discount;
+pub use set_fee_discount::*;
+
+pub mod set_emergency_pause;
+pub use set_emergency_pause::*;
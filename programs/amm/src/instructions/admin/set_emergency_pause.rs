@@ -0,0 +1,46 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetEmergencyPause<'info> {
+    /// Only the admin can pause the program
+    #[account(
+        mut,
+        address = crate::admin::ID @ ErrorCode::NotApproved
+    )]
+    pub authority: Signer<'info>,
+
+    /// Initialize the emergency pause account on the first pause
+    #[account(
+        init_if_needed,
+        seeds = [
+            EMERGENCY_PAUSE_SEED.as_bytes(),
+        ],
+        bump,
+        payer = authority,
+        space = EmergencyPause::LEN
+    )]
+    pub emergency_pause: Account<'info, EmergencyPause>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_emergency_pause(ctx: Context<SetEmergencyPause>, pause_secs: u64) -> Result<()> {
+    require_gte!(
+        MAX_EMERGENCY_PAUSE_SECS,
+        pause_secs,
+        ErrorCode::InvalidEmergencyPauseDuration
+    );
+    let emergency_pause = &mut ctx.accounts.emergency_pause;
+    emergency_pause.bump = ctx.bumps.emergency_pause;
+    emergency_pause.paused_until = if pause_secs == 0 {
+        0
+    } else {
+        Clock::get()?.unix_timestamp as u64 + pause_secs
+    };
+    emit!(EmergencyPauseEvent {
+        paused_until: emergency_pause.paused_until
+    });
+    Ok(())
+}
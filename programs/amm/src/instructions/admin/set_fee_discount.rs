@@ -0,0 +1,48 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct SetFeeDiscount<'info> {
+    /// The amm config owner or admin
+    #[account(
+        mut,
+        constraint = (owner.key() == amm_config.owner || owner.key() == crate::admin::ID) @ ErrorCode::NotApproved
+    )]
+    pub owner: Signer<'info>,
+
+    /// The amm config the discounts apply to
+    pub amm_config: Account<'info, AmmConfig>,
+
+    /// The token whose holding grants the discounts
+    pub discount_mint: InterfaceAccount<'info, Mint>,
+
+    /// Initialize the fee discount config on the first call
+    #[account(
+        init_if_needed,
+        seeds = [
+            FEE_DISCOUNT_SEED.as_bytes(),
+            amm_config.key().as_ref(),
+        ],
+        bump,
+        payer = owner,
+        space = FeeDiscountConfig::LEN
+    )]
+    pub fee_discount_config: Account<'info, FeeDiscountConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn set_fee_discount(
+    ctx: Context<SetFeeDiscount>,
+    tiers: [FeeDiscountTier; FEE_DISCOUNT_TIER_NUM],
+) -> Result<()> {
+    FeeDiscountConfig::validate_tiers(&tiers)?;
+    let fee_discount_config = &mut ctx.accounts.fee_discount_config;
+    fee_discount_config.bump = ctx.bumps.fee_discount_config;
+    fee_discount_config.amm_config = ctx.accounts.amm_config.key();
+    fee_discount_config.mint = ctx.accounts.discount_mint.key();
+    fee_discount_config.tiers = tiers;
+    Ok(())
+}
@@ -28,9 +28,26 @@ pub fn update_amm_config(ctx: Context<UpdateAmmConfig>, param: u8, value: u32) -
             let new_fund_owner = *ctx.remaining_accounts.iter().next().unwrap().key;
             set_new_fund_owner(amm_config, new_fund_owner);
         }
+        Some(5) => update_pool_creation_mode(amm_config, value)?,
         _ => return err!(ErrorCode::InvalidUpdateConfigFlag),
     }
 
+    emit_config_change(amm_config);
+
+    Ok(())
+}
+
+/// The fee is a u64 of lamports, it doesn't fit the u32 value of `update_amm_config`
+pub fn update_create_pool_fee(ctx: Context<UpdateAmmConfig>, create_pool_fee: u64) -> Result<()> {
+    let amm_config = &mut ctx.accounts.amm_config;
+    amm_config.create_pool_fee = create_pool_fee;
+
+    emit_config_change(amm_config);
+
+    Ok(())
+}
+
+fn emit_config_change(amm_config: &AmmConfig) {
     emit!(ConfigChangeEvent {
         index: amm_config.index,
         owner: amm_config.owner,
@@ -39,9 +56,9 @@ pub fn update_amm_config(ctx: Context<UpdateAmmConfig>, param: u8, value: u32) -
         tick_spacing: amm_config.tick_spacing,
         fund_fee_rate: amm_config.fund_fee_rate,
         fund_owner: amm_config.fund_owner,
+        pool_creation_mode: amm_config.pool_creation_mode,
+        create_pool_fee: amm_config.create_pool_fee,
     });
-
-    Ok(())
 }
 
 fn update_protocol_fee_rate(amm_config: &mut Account<AmmConfig>, protocol_fee_rate: u32) {
@@ -80,3 +97,10 @@ fn set_new_fund_owner(amm_config: &mut Account<AmmConfig>, new_fund_owner: Pubke
     );
     amm_config.fund_owner = new_fund_owner;
 }
+
+fn update_pool_creation_mode(amm_config: &mut Account<AmmConfig>, mode: u32) -> Result<()> {
+    let mode = u8::try_from(mode).map_err(|_| ErrorCode::InvalidPoolCreationMode)?;
+    PoolCreationMode::try_from(mode)?;
+    amm_config.pool_creation_mode = mode;
+    Ok(())
+}
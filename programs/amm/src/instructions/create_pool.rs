@@ -2,7 +2,7 @@ use crate::error::ErrorCode;
 use crate::states::*;
 use crate::util::create_token_vault_account;
 use crate::{libraries::tick_math, util};
-use anchor_lang::{prelude::*, solana_program};
+use anchor_lang::{prelude::*, solana_program, system_program};
 use anchor_spl::token_interface::{Mint, TokenInterface};
 // use solana_program::{program::invoke_signed, system_instruction};
 #[derive(Accounts)]
@@ -121,6 +121,41 @@ pub struct CreatePool<'info> {
 }
 
 pub fn create_pool(ctx: Context<CreatePool>, sqrt_price_x64: u128, open_time: u64) -> Result<()> {
+    let amm_config = ctx.accounts.amm_config.as_ref();
+    let pool_creator = ctx.accounts.pool_creator.key();
+    // the permission is only looked up when the config requires it
+    let is_whitelisted = amm_config.pool_creation_mode()? == PoolCreationMode::Whitelist
+        && pool_creator_is_whitelisted(
+            &ctx.remaining_accounts,
+            &ctx.accounts.amm_config.key(),
+            &pool_creator,
+        )?;
+    require!(
+        amm_config.can_create_pool(pool_creator, is_whitelisted)?,
+        ErrorCode::NotApproved
+    );
+    // the owners don't pay the protocol, its treasury is the fund owner passed in the remaining
+    // accounts
+    if amm_config.create_pool_fee > 0 && !amm_config.is_owner(pool_creator) {
+        let fee_receiver = ctx
+            .remaining_accounts
+            .iter()
+            .find(|account_info| {
+                account_info.key() == amm_config.fund_owner && account_info.is_writable
+            })
+            .ok_or(ErrorCode::MissingCreatePoolFeeReceiver)?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.pool_creator.to_account_info(),
+                    to: fee_receiver.clone(),
+                },
+            ),
+            amm_config.create_pool_fee,
+        )?;
+    }
+
     let mint0_associated_is_initialized = util::support_mint_associated_is_initialized(
         &ctx.remaining_accounts,
         &ctx.accounts.token_mint_0,
@@ -218,3 +253,30 @@ pub fn create_pool(ctx: Context<CreatePool>, sqrt_price_x64: u128, open_time: u6
     });
     Ok(())
 }
+
+// whether a remaining account is the pool creator permission of the creator under the config
+fn pool_creator_is_whitelisted(
+    remaining_accounts: &[AccountInfo],
+    amm_config: &Pubkey,
+    pool_creator: &Pubkey,
+) -> Result<bool> {
+    let (expect_permission, _) = Pubkey::find_program_address(
+        &[
+            POOL_CREATOR_PERMISSION_SEED.as_bytes(),
+            amm_config.as_ref(),
+            pool_creator.as_ref(),
+        ],
+        &crate::id(),
+    );
+    for permission_info in remaining_accounts.iter() {
+        if *permission_info.owner != crate::id() || permission_info.key() != expect_permission {
+            continue;
+        }
+        let permission =
+            PoolCreatorPermission::try_deserialize(&mut permission_info.data.borrow().as_ref())?;
+        if permission.amm_config == *amm_config && permission.creator == *pool_creator {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
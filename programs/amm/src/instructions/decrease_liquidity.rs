@@ -165,7 +165,7 @@ pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
         pool_sqrt_price_x64 = pool_state.sqrt_price_x64;
         pool_tick_current = pool_state.tick_current;
 
-        let use_tickarray_bitmap_extension = pool_state.is_overflow_default_tickarray_bitmap(vec![
+        let use_tickarray_bitmap_extension = pool_state.is_overflow_default_tickarray_bitmap(&[
             tick_array_lower_loader.load()?.start_tick_index,
             tick_array_upper_loader.load()?.start_tick_index,
         ]);
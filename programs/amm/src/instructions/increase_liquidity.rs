@@ -146,11 +146,12 @@ pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
     if !pool_state.get_status_by_bit(PoolStatusBitIndex::OpenPositionOrIncreaseLiquidity) {
         return err!(ErrorCode::NotApproved);
     }
+    EmergencyPause::require_not_paused(remaining_accounts, Clock::get()?.unix_timestamp as u64)?;
     let tick_lower = personal_position.tick_lower_index;
     let tick_upper = personal_position.tick_upper_index;
 
     let use_tickarray_bitmap_extension =
-        pool_state.is_overflow_default_tickarray_bitmap(vec![tick_lower, tick_upper]);
+        pool_state.is_overflow_default_tickarray_bitmap(&[tick_lower, tick_upper]);
 
     let (amount_0, amount_1, amount_0_transfer_fee, amount_1_transfer_fee) = add_liquidity(
         &nft_owner,
@@ -0,0 +1,53 @@
+use crate::error::ErrorCode;
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct InitializeTickArrays<'info> {
+    /// Pays to create the tick array accounts
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The pool which the tick arrays belong to
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Program to create the tick array accounts
+    pub system_program: Program<'info, System>,
+    // remaining accounts
+    // the tick array accounts, one for each start index and in the same order
+}
+
+pub fn initialize_tick_arrays<'a, 'b, 'c: 'info, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, InitializeTickArrays<'info>>,
+    tick_array_start_indexs: Vec<i32>,
+) -> Result<()> {
+    require!(
+        !tick_array_start_indexs.is_empty()
+            && tick_array_start_indexs.len() == ctx.remaining_accounts.len(),
+        ErrorCode::NotEnoughTickArrayAccount
+    );
+    let tick_spacing = ctx.accounts.pool_state.load()?.tick_spacing;
+
+    for (tick_array_start_index, tick_array_info) in tick_array_start_indexs
+        .into_iter()
+        .zip(ctx.remaining_accounts.iter())
+    {
+        // an already initialized tick array is only loaded to check that it belongs to the pool
+        let tick_array_loader = TickArrayState::get_or_create_tick_array(
+            ctx.accounts.payer.to_account_info(),
+            tick_array_info.clone(),
+            ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.pool_state,
+            tick_array_start_index,
+            tick_spacing,
+        )?;
+        let tick_array = tick_array_loader.load()?;
+        require_keys_eq!(tick_array.pool_id, ctx.accounts.pool_state.key());
+        require_eq!(
+            tick_array.start_tick_index,
+            tick_array_start_index,
+            ErrorCode::InvalidTickArray
+        );
+    }
+    Ok(())
+}
@@ -1,6 +1,9 @@
 pub mod create_pool;
 pub use create_pool::*;
 
+pub mod initialize_tick_arrays;
+pub use initialize_tick_arrays::*;
+
 pub mod open_position;
 pub use open_position::*;
 
@@ -19,6 +22,9 @@ pub use increase_liquidity::*;
 pub mod increase_liquidity_v2;
 pub use increase_liquidity_v2::*;
 
+pub mod donate_liquidity;
+pub use donate_liquidity::*;
+
 pub mod decrease_liquidity;
 pub use decrease_liquidity::*;
 
@@ -46,5 +52,8 @@ pub use set_reward_params::*;
 pub mod collect_remaining_rewards;
 pub use collect_remaining_rewards::*;
 
+pub mod settle_position_fee_growth;
+pub use settle_position_fee_growth::*;
+
 pub mod admin;
 pub use admin::*;
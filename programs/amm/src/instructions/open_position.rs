@@ -261,6 +261,10 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
         if !pool_state.get_status_by_bit(PoolStatusBitIndex::OpenPositionOrIncreaseLiquidity) {
             return err!(ErrorCode::NotApproved);
         }
+        EmergencyPause::require_not_paused(
+            remaining_accounts,
+            Clock::get()?.unix_timestamp as u64,
+        )?;
         check_ticks_order(tick_lower_index, tick_upper_index)?;
         check_tick_array_start_index(
             tick_array_lower_start_index,
@@ -316,7 +320,7 @@ pub fn open_position<'a, 'b, 'c: 'info, 'info>(
                 .tick = tick_upper_index;
         }
 
-        let use_tickarray_bitmap_extension = pool_state.is_overflow_default_tickarray_bitmap(vec![
+        let use_tickarray_bitmap_extension = pool_state.is_overflow_default_tickarray_bitmap(&[
             tick_array_lower_start_index,
             tick_array_upper_start_index,
         ]);
@@ -0,0 +1,146 @@
+use crate::error::ErrorCode;
+use crate::libraries::{big_num::U256, fixed_point_64, full_math::MulDiv};
+use crate::states::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SettlePositionFeeGrowth<'info> {
+    /// Anyone can settle a position, only the admin can repair it
+    pub authority: Signer<'info>,
+
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Settle the fee growth checkpoints of this position
+    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    /// Stores the state of the lower tick
+    #[account(constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    /// Stores the state of the upper tick
+    #[account(constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+}
+
+pub fn settle_position_fee_growth(ctx: Context<SettlePositionFeeGrowth>) -> Result<()> {
+    let pool_state = ctx.accounts.pool_state.load()?;
+    let personal_position = &mut ctx.accounts.personal_position;
+
+    let tick_lower_state = *ctx
+        .accounts
+        .tick_array_lower
+        .load()?
+        .get_tick_state(personal_position.tick_lower_index, pool_state.tick_spacing)?;
+    let tick_upper_state = *ctx
+        .accounts
+        .tick_array_upper
+        .load()?
+        .get_tick_state(personal_position.tick_upper_index, pool_state.tick_spacing)?;
+    let (fee_growth_inside_0_x64, fee_growth_inside_1_x64) = get_fee_growth_inside(
+        &tick_lower_state,
+        &tick_upper_state,
+        pool_state.tick_current,
+        pool_state.fee_growth_global_0_x64,
+        pool_state.fee_growth_global_1_x64,
+    );
+
+    // the fees of all the positions can't exceed the fees not yet claimed from the pool
+    let fee_delta_0 = fee_growth_delta_amount(
+        personal_position.fee_growth_inside_0_last_x64,
+        fee_growth_inside_0_x64,
+        personal_position.liquidity,
+    )
+    .filter(|amount| {
+        *amount <= pool_state.total_fees_token_0 - pool_state.total_fees_claimed_token_0
+    });
+    let fee_delta_1 = fee_growth_delta_amount(
+        personal_position.fee_growth_inside_1_last_x64,
+        fee_growth_inside_1_x64,
+        personal_position.liquidity,
+    )
+    .filter(|amount| {
+        *amount <= pool_state.total_fees_token_1 - pool_state.total_fees_claimed_token_1
+    });
+
+    // an inconsistent checkpoint is reset without crediting its fees
+    let repaired = fee_delta_0.is_none() || fee_delta_1.is_none();
+    if repaired {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            crate::admin::ID,
+            ErrorCode::NotApproved
+        );
+    }
+    let fee_amount_0 = fee_delta_0.unwrap_or(0);
+    let fee_amount_1 = fee_delta_1.unwrap_or(0);
+
+    let event = SettlePositionFeeGrowthEvent {
+        position_nft_mint: personal_position.nft_mint,
+        fee_growth_inside_0_last_x64: personal_position.fee_growth_inside_0_last_x64,
+        fee_growth_inside_1_last_x64: personal_position.fee_growth_inside_1_last_x64,
+        fee_growth_inside_0_x64,
+        fee_growth_inside_1_x64,
+        fee_amount_0,
+        fee_amount_1,
+        repaired,
+    };
+
+    personal_position.token_fees_owed_0 = personal_position
+        .token_fees_owed_0
+        .checked_add(fee_amount_0)
+        .unwrap();
+    personal_position.token_fees_owed_1 = personal_position
+        .token_fees_owed_1
+        .checked_add(fee_amount_1)
+        .unwrap();
+    personal_position.fee_growth_inside_0_last_x64 = fee_growth_inside_0_x64;
+    personal_position.fee_growth_inside_1_last_x64 = fee_growth_inside_1_x64;
+
+    emit!(event);
+
+    Ok(())
+}
+
+/// The fees earned by the liquidity since the last fee growth checkpoint, none when the wrapped
+/// growth delta gives more than a u64 of fees
+pub fn fee_growth_delta_amount(
+    fee_growth_inside_last_x64: u128,
+    fee_growth_inside_latest_x64: u128,
+    liquidity: u128,
+) -> Option<u64> {
+    let amount = U256::from(fee_growth_inside_latest_x64.wrapping_sub(fee_growth_inside_last_x64))
+        .mul_div_floor(U256::from(liquidity), U256::from(fixed_point_64::Q64))?;
+    if amount > U256::from(u64::MAX) {
+        return None;
+    }
+    Some(amount.as_u64())
+}
+
+#[cfg(test)]
+mod settle_position_fee_growth_test {
+    use super::*;
+
+    #[test]
+    fn fee_growth_delta_amount_test() {
+        let liquidity = 1_000_000u128;
+        // one token of fee per unit of liquidity
+        assert_eq!(
+            fee_growth_delta_amount(0, fixed_point_64::Q64, liquidity),
+            Some(1_000_000)
+        );
+        // the growth wraps around u128
+        assert_eq!(
+            fee_growth_delta_amount(u128::MAX, fixed_point_64::Q64 - 1, liquidity),
+            Some(1_000_000)
+        );
+        assert_eq!(fee_growth_delta_amount(5, 5, liquidity), Some(0));
+        // a checkpoint ahead of the current growth wraps to a huge delta
+        assert_eq!(
+            fee_growth_delta_amount(fixed_point_64::Q64, 0, liquidity),
+            None
+        );
+        // without liquidity there are no fees whatever the checkpoint
+        assert_eq!(fee_growth_delta_amount(fixed_point_64::Q64, 0, 0), Some(0));
+    }
+}
@@ -84,6 +84,37 @@ pub struct SwapAccounts<'b, 'info> {
     pub observation_state: &'b mut AccountLoader<'info, ObservationState>,
 }
 
+/// A tick array account passed to the swap.
+/// Accounts in remaining_accounts stay unloaded until the swap actually reaches them,
+/// so tick arrays which are skipped or never crossed are not deserialized.
+pub enum SwapTickArray<'a, 'info> {
+    Loaded(RefMut<'a, TickArrayState>),
+    Unloaded(&'a AccountInfo<'info>),
+}
+
+impl<'a, 'info> SwapTickArray<'a, 'info> {
+    /// Read the start tick index without loading the whole account
+    pub fn start_tick_index(&self) -> Result<i32> {
+        match self {
+            SwapTickArray::Loaded(tick_array) => Ok(tick_array.start_tick_index),
+            SwapTickArray::Unloaded(account_info) => {
+                let data = account_info.try_borrow_data()?;
+                require_gte!(data.len(), TickArrayState::LEN, ErrorCode::InvalidTickArray);
+                // skip the discriminator and pool_id
+                Ok(i32::from_le_bytes(data[40..44].try_into().unwrap()))
+            }
+        }
+    }
+
+    /// Check the owner and discriminator, then return the tick array for writing
+    pub fn load_mut(self) -> Result<RefMut<'a, TickArrayState>> {
+        match self {
+            SwapTickArray::Loaded(tick_array) => Ok(tick_array),
+            SwapTickArray::Unloaded(account_info) => AccountLoad::load_data_mut(account_info),
+        }
+    }
+}
+
 // the top level state of the swap, the results of which are recorded in storage at the end
 #[derive(Debug)]
 pub struct SwapState {
@@ -128,7 +159,7 @@ struct StepComputations {
 pub fn swap_internal<'b, 'info>(
     amm_config: &AmmConfig,
     pool_state: &mut RefMut<PoolState>,
-    tick_array_states: &mut VecDeque<RefMut<TickArrayState>>,
+    tick_array_states: &mut VecDeque<SwapTickArray<'b, 'info>>,
     observation_state: &mut RefMut<ObservationState>,
     tickarray_bitmap_extension: &Option<TickArrayBitmapExtension>,
     amount_specified: u64,
@@ -179,16 +210,17 @@ pub fn swap_internal<'b, 'info>(
         pool_state.get_first_initialized_tick_array(&tickarray_bitmap_extension, zero_for_one)?;
     let mut current_valid_tick_array_start_index = first_valid_tick_array_start_index;
 
-    let mut tick_array_current = tick_array_states.pop_front().unwrap();
-    // find the first active tick array account
-    for _ in 0..tick_array_states.len() {
-        if tick_array_current.start_tick_index == current_valid_tick_array_start_index {
-            break;
-        }
-        tick_array_current = tick_array_states
+    // find the first active tick array account, the skipped accounts are never loaded
+    let mut tick_array_current = loop {
+        let tick_array = tick_array_states
             .pop_front()
             .ok_or(ErrorCode::NotEnoughTickArrayAccount)?;
-    }
+        if tick_array.start_tick_index()? == current_valid_tick_array_start_index
+            || tick_array_states.is_empty()
+        {
+            break tick_array.load_mut()?;
+        }
+    };
     // check the first tick_array account is owned by the pool
     require_keys_eq!(tick_array_current.pool_id, pool_state.key());
     // check first tick array account is correct
@@ -223,13 +255,13 @@ pub fn swap_internal<'b, 'info>(
         let mut next_initialized_tick = if let Some(tick_state) = tick_array_current
             .next_initialized_tick(state.tick, pool_state.tick_spacing, zero_for_one)?
         {
-            Box::new(*tick_state)
+            *tick_state
         } else {
             if !is_match_pool_current_tick_array {
                 is_match_pool_current_tick_array = true;
-                Box::new(*tick_array_current.first_initialized_tick(zero_for_one)?)
+                *tick_array_current.first_initialized_tick(zero_for_one)?
             } else {
-                Box::new(TickState::default())
+                TickState::default()
             }
         };
         #[cfg(feature = "enable-log")]
@@ -250,17 +282,22 @@ pub fn swap_internal<'b, 'info>(
                 return err!(ErrorCode::LiquidityInsufficient);
             }
 
-            while tick_array_current.start_tick_index != next_initialized_tickarray_index.unwrap() {
-                tick_array_current = tick_array_states
-                    .pop_front()
-                    .ok_or(ErrorCode::NotEnoughTickArrayAccount)?;
+            if tick_array_current.start_tick_index != next_initialized_tickarray_index.unwrap() {
+                tick_array_current = loop {
+                    let tick_array = tick_array_states
+                        .pop_front()
+                        .ok_or(ErrorCode::NotEnoughTickArrayAccount)?;
+                    if tick_array.start_tick_index()? == next_initialized_tickarray_index.unwrap() {
+                        break tick_array.load_mut()?;
+                    }
+                };
                 // check the tick_array account is owned by the pool
                 require_keys_eq!(tick_array_current.pool_id, pool_state.key());
             }
             current_valid_tick_array_start_index = next_initialized_tickarray_index.unwrap();
 
             let first_initialized_tick = tick_array_current.first_initialized_tick(zero_for_one)?;
-            next_initialized_tick = Box::new(*first_initialized_tick);
+            next_initialized_tick = *first_initialized_tick;
         }
         step.tick_next = next_initialized_tick.tick;
         step.initialized = next_initialized_tick.is_initialized();
@@ -411,7 +448,7 @@ pub fn swap_internal<'b, 'info>(
                 tick_array_current.update_tick_state(
                     next_initialized_tick.tick,
                     pool_state.tick_spacing.into(),
-                    *next_initialized_tick,
+                    next_initialized_tick,
                 )?;
 
                 if zero_for_one {
@@ -586,8 +623,12 @@ pub fn exact_internal<'b, 'c: 'info, 'info>(
 
         let mut tickarray_bitmap_extension = None;
         let tick_array_states = &mut VecDeque::new();
-        tick_array_states.push_back(ctx.tick_array_state.load_mut()?);
+        tick_array_states.push_back(SwapTickArray::Loaded(ctx.tick_array_state.load_mut()?));
 
+        let fee_discount =
+            FeeDiscount::load(&ctx.amm_config.key(), &ctx.signer.key(), remaining_accounts)?;
+        EmergencyPause::require_not_paused(remaining_accounts, block_timestamp)?;
+        let emergency_pause_key = EmergencyPause::key();
         let tick_array_bitmap_extension_key = TickArrayBitmapExtension::key(pool_state.key());
         for account_info in remaining_accounts.into_iter() {
             if account_info.key().eq(&tick_array_bitmap_extension_key) {
@@ -598,11 +639,21 @@ pub fn exact_internal<'b, 'c: 'info, 'info>(
                 );
                 continue;
             }
-            tick_array_states.push_back(AccountLoad::load_data_mut(account_info)?);
+            if let Some(fee_discount) = &fee_discount {
+                if fee_discount.is_discount_account(account_info.key) {
+                    continue;
+                }
+            }
+            if account_info.key() == emergency_pause_key {
+                continue;
+            }
+            tick_array_states.push_back(SwapTickArray::Unloaded(account_info));
         }
 
+        let discounted_amm_config =
+            fee_discount.map(|fee_discount| fee_discount.apply(&ctx.amm_config));
         (amount_0, amount_1) = swap_internal(
-            &ctx.amm_config,
+            discounted_amm_config.as_ref().unwrap_or(&ctx.amm_config),
             pool_state,
             tick_array_states,
             &mut ctx.observation_state.load_mut()?,
@@ -808,13 +859,13 @@ mod swap_test {
     use std::collections::HashMap;
     use std::vec;
 
-    pub fn get_tick_array_states_mut(
-        deque_tick_array_states: &VecDeque<RefCell<TickArrayState>>,
-    ) -> RefCell<VecDeque<RefMut<TickArrayState>>> {
+    pub fn get_tick_array_states_mut<'a>(
+        deque_tick_array_states: &'a VecDeque<RefCell<TickArrayState>>,
+    ) -> RefCell<VecDeque<SwapTickArray<'a, 'a>>> {
         let mut tick_array_states = VecDeque::new();
 
         for tick_array_state in deque_tick_array_states {
-            tick_array_states.push_back(tick_array_state.borrow_mut());
+            tick_array_states.push_back(SwapTickArray::Loaded(tick_array_state.borrow_mut()));
         }
         RefCell::new(tick_array_states)
     }
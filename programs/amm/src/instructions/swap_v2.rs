@@ -3,7 +3,7 @@ use std::ops::Deref;
 
 use crate::error::ErrorCode;
 use crate::libraries::tick_math;
-use crate::swap::swap_internal;
+use crate::swap::{swap_internal, SwapTickArray};
 use crate::util::*;
 use crate::{states::*, util};
 use anchor_lang::{prelude::*, solana_program};
@@ -127,6 +127,10 @@ pub fn exact_internal_v2<'c: 'info, 'info>(
         let mut tickarray_bitmap_extension = None;
         let tick_array_states = &mut VecDeque::new();
 
+        let fee_discount =
+            FeeDiscount::load(&ctx.amm_config.key(), &ctx.payer.key(), remaining_accounts)?;
+        EmergencyPause::require_not_paused(remaining_accounts, block_timestamp)?;
+        let emergency_pause_key = EmergencyPause::key();
         let tick_array_bitmap_extension_key = TickArrayBitmapExtension::key(pool_state.key());
         for account_info in remaining_accounts.into_iter() {
             if account_info.key().eq(&tick_array_bitmap_extension_key) {
@@ -137,14 +141,24 @@ pub fn exact_internal_v2<'c: 'info, 'info>(
                 );
                 continue;
             }
+            if let Some(fee_discount) = &fee_discount {
+                if fee_discount.is_discount_account(account_info.key) {
+                    continue;
+                }
+            }
+            if account_info.key() == emergency_pause_key {
+                continue;
+            }
             if account_info.data_len() != TickArrayState::LEN {
                 break;
             }
-            tick_array_states.push_back(AccountLoad::load_data_mut(account_info)?);
+            tick_array_states.push_back(SwapTickArray::Unloaded(account_info));
         }
 
+        let discounted_amm_config =
+            fee_discount.map(|fee_discount| fee_discount.apply(&ctx.amm_config));
         (amount_0, amount_1) = swap_internal(
-            &ctx.amm_config,
+            discounted_amm_config.as_ref().unwrap_or(&ctx.amm_config),
             pool_state,
             tick_array_states,
             &mut ctx.observation_state.load_mut()?,
@@ -323,10 +337,12 @@ pub fn exact_internal_v2<'c: 'info, 'info>(
                 require_eq!(amount_specified, transfer_amount_1);
             }
         } else {
+            // the pool sends the output grossed up by its transfer fee, so the user receives
+            // exactly amount_specified
             if zero_for_one {
-                require_eq!(amount_specified, transfer_amount_1);
+                require_eq!(amount_calculate_specified, transfer_amount_1);
             } else {
-                require_eq!(amount_specified, transfer_amount_0);
+                require_eq!(amount_calculate_specified, transfer_amount_0);
             }
         }
     }
@@ -75,6 +75,11 @@ pub mod amm_v3 {
         instructions::create_support_mint_associated(ctx)
     }
 
+    /// Close support token22 mint account, the mint is then checked against the supported extensions again.
+    pub fn close_support_mint_associated(ctx: Context<CloseSupportMintAssociated>) -> Result<()> {
+        instructions::close_support_mint_associated(ctx)
+    }
+
     /// Updates the owner of the amm config
     /// Must be called by the current owner or admin
     ///
@@ -86,12 +91,77 @@ pub mod amm_v3 {
     /// * `fund_fee_rate`- The new fund fee rate of amm config, be set when `param` is 2
     /// * `new_owner`- The config's new owner, be set when `param` is 3
     /// * `new_fund_owner`- The config's new fund owner, be set when `param` is 4
-    /// * `param`- The value can be 0 | 1 | 2 | 3 | 4, otherwise will report a error
+    /// * `pool_creation_mode`- Who may create pools, 0 open | 1 whitelist | 2 owner only, be set when `param` is 5
+    /// * `param`- The value can be 0 | 1 | 2 | 3 | 4 | 5, otherwise will report a error
     ///
     pub fn update_amm_config(ctx: Context<UpdateAmmConfig>, param: u8, value: u32) -> Result<()> {
         instructions::update_amm_config(ctx, param, value)
     }
 
+    /// Update the lamports a pool creator pays to the fund owner of the config to create a pool
+    /// Must be called by the admin
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `create_pool_fee`- The create pool fee in lamports, zero for none
+    ///
+    pub fn update_create_pool_fee(
+        ctx: Context<UpdateAmmConfig>,
+        create_pool_fee: u64,
+    ) -> Result<()> {
+        instructions::update_create_pool_fee(ctx, create_pool_fee)
+    }
+
+    /// Allow a creator to create pools under an amm config in whitelist pool creation mode
+    /// Must be called by the config owner or admin
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    ///
+    pub fn create_pool_creator_permission(ctx: Context<CreatePoolCreatorPermission>) -> Result<()> {
+        instructions::create_pool_creator_permission(ctx)
+    }
+
+    /// Revoke the permission of a creator, the rent is returned to the config owner or admin
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    ///
+    pub fn close_pool_creator_permission(ctx: Context<ClosePoolCreatorPermission>) -> Result<()> {
+        instructions::close_pool_creator_permission(ctx)
+    }
+
+    /// Set the trade fee discounts of an amm config for the holders of a token, the discount of a
+    /// swap is read from the fee discount config and the signer token account passed in the
+    /// remaining accounts. Must be called by the config owner or admin
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `tiers` - The min held amount and the discount of each tier, an unused tier has no discount
+    ///
+    pub fn set_fee_discount(
+        ctx: Context<SetFeeDiscount>,
+        tiers: [FeeDiscountTier; FEE_DISCOUNT_TIER_NUM],
+    ) -> Result<()> {
+        instructions::set_fee_discount(ctx, tiers)
+    }
+
+    /// Pause the swaps and deposits of all the pools for `pause_secs`, the pause expires on its own
+    /// and withdrawals are unaffected. Must be called by the admin
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts
+    /// * `pause_secs` - Duration of the pause at most `MAX_EMERGENCY_PAUSE_SECS`, zero lifts it
+    ///
+    pub fn set_emergency_pause(ctx: Context<SetEmergencyPause>, pause_secs: u64) -> Result<()> {
+        instructions::set_emergency_pause(ctx, pause_secs)
+    }
+
     /// Creates a pool for the given token pair and the initial price
     ///
     /// # Arguments
@@ -99,6 +169,9 @@ pub mod amm_v3 {
     /// * `ctx`- The context of accounts
     /// * `sqrt_price_x64` - the initial sqrt price (amount_token_1 / amount_token_0) of the pool as a Q64.64
     /// Note: The open_time must be smaller than the current block_timestamp on chain.
+    /// The pool creator permission of a whitelisted creator is passed by remaining accounts when
+    /// the config requires it, and the writable fund owner receiving the create pool fee when the
+    /// config charges one to creators other than its owner and the admin.
     pub fn create_pool(
         ctx: Context<CreatePool>,
         sqrt_price_x64: u128,
@@ -107,6 +180,21 @@ pub mod amm_v3 {
         instructions::create_pool(ctx, sqrt_price_x64, open_time)
     }
 
+    /// Initialize several tick arrays of the pool in one instruction, already initialized
+    /// tick arrays are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx`- The context of accounts, the tick array accounts are passed by remaining accounts
+    /// * `tick_array_start_indexs` - the start index of each tick array, in the same order as remaining accounts
+    ///
+    pub fn initialize_tick_arrays<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, InitializeTickArrays<'info>>,
+        tick_array_start_indexs: Vec<i32>,
+    ) -> Result<()> {
+        instructions::initialize_tick_arrays(ctx, tick_array_start_indexs)
+    }
+
     /// Update pool status for given value
     ///
     /// # Arguments
@@ -439,6 +527,30 @@ pub mod amm_v3 {
         instructions::increase_liquidity_v2(ctx, liquidity, amount_0_max, amount_1_max, base_flag)
     }
 
+    /// Add liquidity to an existing position of any owner, with amount paid by `donor`, support
+    /// Token2022. The position owner and nft are unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context of accounts
+    /// * `liquidity` - The desired liquidity to be added, if zero, calculate liquidity base amount_0 or amount_1 according base_flag
+    /// * `amount_0_max` - The max amount of token_0 to spend, which serves as a slippage check
+    /// * `amount_1_max` - The max amount of token_1 to spend, which serves as a slippage check
+    /// * `base_flag` - must be specified if liquidity is zero, true: calculate liquidity base amount_0_max otherwise base amount_1_max
+    ///
+    pub fn donate_liquidity<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, DonateLiquidity<'info>>,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        base_flag: Option<bool>,
+    ) -> Result<()> {
+        if liquidity == 0 {
+            assert!(base_flag.is_some());
+        }
+        instructions::donate_liquidity(ctx, liquidity, amount_0_max, amount_1_max, base_flag)
+    }
+
     /// #[deprecated(note = "Use `decrease_liquidity_v2` instead.")]
     /// Decreases liquidity for an existing position
     ///
@@ -476,6 +588,18 @@ pub mod amm_v3 {
         instructions::decrease_liquidity_v2(ctx, liquidity, amount_0_min, amount_1_min)
     }
 
+    /// Settle the fees earned by a position since its fee growth checkpoints, anyone can settle.
+    /// A checkpoint giving more fees than the pool holds unclaimed is reset without crediting
+    /// them, only by the admin.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` -  The context of accounts
+    ///
+    pub fn settle_position_fee_growth(ctx: Context<SettlePositionFeeGrowth>) -> Result<()> {
+        instructions::settle_position_fee_growth(ctx)
+    }
+
     /// #[deprecated(note = "Use `swap_v2` instead.")]
     /// Swaps one token for as much as possible of another token across a single pool
     ///
@@ -529,7 +653,8 @@ pub mod amm_v3 {
         )
     }
 
-    /// Swap token for as much as possible of another token across the path provided, base input
+    /// Swap token for as much as possible of another token across the path provided, base input.
+    /// The emergency pause account is passed after the accounts of the last pool
     ///
     /// # Arguments
     ///
@@ -83,6 +83,37 @@ pub trait MulDiv<RHS = Self> {
     fn to_underflow_u64(self) -> u64;
 }
 
+/// Rounding direction of [`mul_div`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Towards positive infinity, as the program does for the amounts a user pays
+    Up,
+    /// Towards zero, as the program does for the amounts a user receives
+    Down,
+}
+
+/// Calculates `a * b / denom` rounded in the given direction, with the same arithmetic as the
+/// program so off-chain results match on-chain ones. The product may overflow the type, `None` is
+/// returned only when the result does.
+///
+/// Panics when `denom` is zero.
+///
+/// ## Example
+///
+/// ```rust
+/// use raydium_amm_v3::libraries::full_math::{mul_div, Rounding};
+///
+/// assert_eq!(mul_div(5u64, 2, 3, Rounding::Down), Some(3));
+/// assert_eq!(mul_div(5u64, 2, 3, Rounding::Up), Some(4));
+/// assert_eq!(mul_div(u64::MAX, 2, 1, Rounding::Down), None);
+/// ```
+pub fn mul_div<T: MulDiv<Output = T>>(a: T, b: T, denom: T, rounding: Rounding) -> Option<T> {
+    match rounding {
+        Rounding::Up => a.mul_div_ceil(b, denom),
+        Rounding::Down => a.mul_div_floor(b, denom),
+    }
+}
+
 pub trait Upcast256 {
     fn as_u256(self) -> U256;
 }
@@ -126,24 +157,17 @@ impl Downcast512 for U512 {
 impl MulDiv for u64 {
     type Output = u64;
 
+    // the product of two u64 always fits in the native u128, which is much cheaper than U128
     fn mul_div_floor(self, num: Self, denom: Self) -> Option<Self::Output> {
         assert_ne!(denom, 0);
-        let r = (U128::from(self) * U128::from(num)) / U128::from(denom);
-        if r > U128::from(u64::MAX) {
-            None
-        } else {
-            Some(r.as_u64())
-        }
+        let r = (u128::from(self) * u128::from(num)) / u128::from(denom);
+        u64::try_from(r).ok()
     }
 
     fn mul_div_ceil(self, num: Self, denom: Self) -> Option<Self::Output> {
         assert_ne!(denom, 0);
-        let r = (U128::from(self) * U128::from(num) + U128::from(denom - 1)) / U128::from(denom);
-        if r > U128::from(u64::MAX) {
-            None
-        } else {
-            Some(r.as_u64())
-        }
+        let r = (u128::from(self) * u128::from(num) + u128::from(denom - 1)) / u128::from(denom);
+        u64::try_from(r).ok()
     }
 
     fn to_underflow_u64(self) -> u64 {
@@ -156,6 +180,10 @@ impl MulDiv for U128 {
 
     fn mul_div_floor(self, num: Self, denom: Self) -> Option<Self::Output> {
         assert_ne!(denom, U128::default());
+        // a product fitting in 128 bits doesn't need the 256 bits division
+        if let Some(product) = self.as_u128().checked_mul(num.as_u128()) {
+            return Some(U128::from(product / denom.as_u128()));
+        }
         let r = ((self.as_u256()) * (num.as_u256())) / (denom.as_u256());
         if r > U128::MAX.as_u256() {
             None
@@ -166,6 +194,12 @@ impl MulDiv for U128 {
 
     fn mul_div_ceil(self, num: Self, denom: Self) -> Option<Self::Output> {
         assert_ne!(denom, U128::default());
+        if let Some(product) = self.as_u128().checked_mul(num.as_u128()) {
+            let denom = denom.as_u128();
+            return Some(U128::from(
+                product / denom + u128::from(product % denom != 0),
+            ));
+        }
         let r = (self.as_u256() * num.as_u256() + (denom - 1).as_u256()) / denom.as_u256();
         if r > U128::MAX.as_u256() {
             None
@@ -269,6 +303,23 @@ mod muldiv_u64_tests {
     }
 }
 
+#[cfg(test)]
+mod mul_div_rounding_tests {
+    use super::*;
+
+    use quickcheck::quickcheck;
+
+    quickcheck! {
+        fn matches_trait(val: u64, num: u64, den: u64) -> bool {
+            let den = den.max(1);
+            mul_div(val, num, den, Rounding::Down) == val.mul_div_floor(num, den)
+                && mul_div(val, num, den, Rounding::Up) == val.mul_div_ceil(num, den)
+                && mul_div(U128::from(val), U128::from(num), U128::from(den), Rounding::Up)
+                    == U128::from(val).mul_div_ceil(U128::from(num), U128::from(den))
+        }
+    }
+}
+
 #[cfg(test)]
 mod muldiv_u128_tests {
     use super::*;
@@ -332,4 +383,40 @@ mod muldiv_u128_tests {
             }
         }
     }
+
+    // the arbitrary values are small, shift them so the product overflows 128 bits
+    quickcheck! {
+        fn scale_floor_wide(val: U128, num: U128, den: NonZero) -> bool {
+            let (val, num) = (val << 64, num << 32);
+            let res = val.mul_div_floor(num, den.0);
+
+            let expected = ((val.as_u256()) * (num.as_u256())) / (den.0.as_u256());
+
+            if expected > U128::MAX.as_u256() {
+                res.is_none()
+            } else {
+                res == Some(expected.as_u128())
+            }
+        }
+    }
+
+    quickcheck! {
+        fn scale_ceil_wide(val: U128, num: U128, den: NonZero) -> bool {
+            let (val, num) = (val << 64, num << 32);
+            let res = val.mul_div_ceil(num, den.0);
+
+            let mut expected = ((val.as_u256()) * (num.as_u256())) / (den.0.as_u256());
+            let expected_rem = ((val.as_u256()) * (num.as_u256())) % (den.0.as_u256());
+
+            if expected_rem != U256::default() {
+                expected += U256::from(1)
+            }
+
+            if expected > U128::MAX.as_u256() {
+                res.is_none()
+            } else {
+                res == Some(expected.as_u128())
+            }
+        }
+    }
 }
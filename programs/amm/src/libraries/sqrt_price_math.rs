@@ -90,15 +90,14 @@ pub fn get_next_sqrt_price_from_amount_1_rounding_down(
     amount: u64,
     add: bool,
 ) -> u128 {
+    // a u64 amount shifted by 64 bits fits in 128 bits, so does the quotient
+    let numerator = u128::from(amount) << fixed_point_64::RESOLUTION;
     if add {
-        let quotient = U256::from(u128::from(amount) << fixed_point_64::RESOLUTION) / liquidity;
-        sqrt_price_x64.checked_add(quotient.as_u128()).unwrap()
+        let quotient = numerator / liquidity;
+        sqrt_price_x64.checked_add(quotient).unwrap()
     } else {
-        let quotient = U256::div_rounding_up(
-            U256::from(u128::from(amount) << fixed_point_64::RESOLUTION),
-            U256::from(liquidity),
-        );
-        sqrt_price_x64.checked_sub(quotient.as_u128()).unwrap()
+        let quotient = numerator / liquidity + u128::from(numerator % liquidity != 0);
+        sqrt_price_x64.checked_sub(quotient).unwrap()
     }
 }
 
@@ -145,3 +144,33 @@ pub fn get_next_sqrt_price_from_output(
         get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_out, false)
     }
 }
+
+#[cfg(test)]
+mod sqrt_price_math_test {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    // the native u128 computation gives the same prices as the 256 bits one
+    quickcheck! {
+        fn amount_1_rounding_down_matches_u256(sqrt_price_x64: u128, liquidity: u128, amount: u64) -> bool {
+            let liquidity = liquidity.max(1);
+            let numerator = U256::from(amount) << fixed_point_64::RESOLUTION;
+            let added = U256::from(sqrt_price_x64) + numerator / U256::from(liquidity);
+            let removed = U256::from(sqrt_price_x64)
+                .checked_sub(U256::div_rounding_up(numerator, U256::from(liquidity)));
+
+            let next_added =
+                get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x64, liquidity, amount, true);
+            next_added == added.as_u128()
+                && removed.map_or(true, |removed| {
+                    let next_removed = get_next_sqrt_price_from_amount_1_rounding_down(
+                        sqrt_price_x64,
+                        liquidity,
+                        amount,
+                        false,
+                    );
+                    next_removed == removed.as_u128()
+                })
+        }
+    }
+}
@@ -22,15 +22,63 @@ pub struct AmmConfig {
     pub tick_spacing: u16,
     /// The fund fee, denominated in hundredths of a bip (10^-6)
     pub fund_fee_rate: u32,
+    /// Who may create pools under the config, see `PoolCreationMode`
+    pub pool_creation_mode: u8,
     // padding space for upgrade
-    pub padding_u32: u32,
+    pub padding_u8: [u8; 3],
     pub fund_owner: Pubkey,
-    pub padding: [u64; 3],
+    /// Lamports paid by the pool creator to the fund owner, the protocol treasury, when a pool is
+    /// created
+    pub create_pool_fee: u64,
+    pub padding: [u64; 2],
+}
+
+/// Who may create pools under an amm config
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolCreationMode {
+    /// Anyone
+    Open = 0,
+    /// The config owner, the admin and the creators holding a `PoolCreatorPermission`
+    Whitelist = 1,
+    /// The config owner and the admin only
+    OwnerOnly = 2,
+}
+
+impl TryFrom<u8> for PoolCreationMode {
+    type Error = anchor_lang::error::Error;
+
+    fn try_from(mode: u8) -> Result<Self> {
+        match mode {
+            0 => Ok(PoolCreationMode::Open),
+            1 => Ok(PoolCreationMode::Whitelist),
+            2 => Ok(PoolCreationMode::OwnerOnly),
+            _ => err!(ErrorCode::InvalidPoolCreationMode),
+        }
+    }
 }
 
 impl AmmConfig {
     pub const LEN: usize = 8 + 1 + 2 + 32 + 4 + 4 + 2 + 64;
 
+    pub fn pool_creation_mode(&self) -> Result<PoolCreationMode> {
+        PoolCreationMode::try_from(self.pool_creation_mode)
+    }
+
+    /// Whether `creator` may create a pool under the config, `is_whitelisted` tells if it holds
+    /// a `PoolCreatorPermission` of the config
+    /// The config owner and the admin, they create pools in any mode without fee
+    pub fn is_owner(&self, creator: Pubkey) -> bool {
+        creator == self.owner || creator == crate::admin::ID
+    }
+
+    pub fn can_create_pool(&self, creator: Pubkey, is_whitelisted: bool) -> Result<bool> {
+        Ok(match self.pool_creation_mode()? {
+            PoolCreationMode::Open => true,
+            PoolCreationMode::Whitelist => self.is_owner(creator) || is_whitelisted,
+            PoolCreationMode::OwnerOnly => self.is_owner(creator),
+        })
+    }
+
     pub fn is_authorized<'info>(
         &self,
         signer: &Signer<'info>,
@@ -55,4 +103,35 @@ pub struct ConfigChangeEvent {
     pub tick_spacing: u16,
     pub fund_fee_rate: u32,
     pub fund_owner: Pubkey,
+    pub pool_creation_mode: u8,
+    pub create_pool_fee: u64,
+}
+
+#[cfg(test)]
+mod config_test {
+    use super::*;
+
+    #[test]
+    fn pool_creation_mode_test() {
+        let owner = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let mut amm_config = AmmConfig {
+            owner,
+            ..Default::default()
+        };
+        assert!(amm_config.can_create_pool(creator, false).unwrap());
+
+        amm_config.pool_creation_mode = PoolCreationMode::Whitelist as u8;
+        assert!(!amm_config.can_create_pool(creator, false).unwrap());
+        assert!(amm_config.can_create_pool(creator, true).unwrap());
+        assert!(amm_config.can_create_pool(owner, false).unwrap());
+
+        amm_config.pool_creation_mode = PoolCreationMode::OwnerOnly as u8;
+        assert!(!amm_config.can_create_pool(creator, true).unwrap());
+        assert!(amm_config.can_create_pool(owner, false).unwrap());
+        assert!(amm_config.can_create_pool(crate::admin::ID, false).unwrap());
+
+        amm_config.pool_creation_mode = 3;
+        assert!(amm_config.can_create_pool(owner, false).is_err());
+    }
 }
@@ -0,0 +1,94 @@
+use crate::error::ErrorCode;
+use anchor_lang::{prelude::*, system_program};
+
+pub const EMERGENCY_PAUSE_SEED: &str = "emergency_pause";
+/// The PDA of `[EMERGENCY_PAUSE_SEED]`, not derived on every swap
+#[cfg(feature = "devnet")]
+pub const EMERGENCY_PAUSE_ID: Pubkey = pubkey!("Bza6xmR9hZzXyNmmXU3modqaS428Cufr4BVMm95n3kDJ");
+#[cfg(not(feature = "devnet"))]
+pub const EMERGENCY_PAUSE_ID: Pubkey = pubkey!("DcGcJYL6dbSPSrwfvNkbA4WKc1yZJPK77an2KMWM1QQU");
+/// The longest an emergency pause lasts before it expires on its own
+pub const MAX_EMERGENCY_PAUSE_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Program wide pause of the swaps and deposits of all the pools, withdrawals are unaffected
+///
+/// PDA of `[EMERGENCY_PAUSE_SEED]`
+///
+#[account]
+#[derive(Default, Debug)]
+pub struct EmergencyPause {
+    /// Bump to identify PDA
+    pub bump: u8,
+    /// Swaps and deposits are rejected until this unix timestamp
+    pub paused_until: u64,
+    pub padding: [u64; 4],
+}
+
+impl EmergencyPause {
+    pub const LEN: usize = 8 + 1 + 8 + 8 * 4;
+
+    pub fn key() -> Pubkey {
+        EMERGENCY_PAUSE_ID
+    }
+
+    pub fn is_paused(&self, block_timestamp: u64) -> bool {
+        block_timestamp < self.paused_until
+    }
+
+    /// Reject swaps and deposits while the program is paused. The emergency pause account must be
+    /// passed in the remaining accounts, it is an uninitialized system account until the program
+    /// is paused once.
+    pub fn require_not_paused(
+        remaining_accounts: &[AccountInfo],
+        block_timestamp: u64,
+    ) -> Result<()> {
+        let emergency_pause_info = remaining_accounts
+            .iter()
+            .find(|account_info| *account_info.key == EMERGENCY_PAUSE_ID)
+            .ok_or(ErrorCode::MissingEmergencyPauseAccount)?;
+        if *emergency_pause_info.owner == system_program::ID && emergency_pause_info.data_is_empty()
+        {
+            return Ok(());
+        }
+        // checks the owner and the discriminator
+        let emergency_pause = Account::<EmergencyPause>::try_from(emergency_pause_info)?;
+        require!(
+            !emergency_pause.is_paused(block_timestamp),
+            ErrorCode::EmergencyPaused
+        );
+        Ok(())
+    }
+}
+
+/// Emitted when the emergency pause is set or lifted
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct EmergencyPauseEvent {
+    /// Swaps and deposits are rejected until this unix timestamp, zero when lifted
+    pub paused_until: u64,
+}
+
+#[cfg(test)]
+mod emergency_pause_test {
+    use super::*;
+
+    #[test]
+    fn is_paused_test() {
+        let emergency_pause = EmergencyPause {
+            paused_until: 1000,
+            ..Default::default()
+        };
+        assert!(emergency_pause.is_paused(999));
+        // expires on its own
+        assert!(!emergency_pause.is_paused(1000));
+        assert!(!EmergencyPause::default().is_paused(0));
+    }
+
+    #[test]
+    fn emergency_pause_id_test() {
+        assert_eq!(
+            EMERGENCY_PAUSE_ID,
+            Pubkey::find_program_address(&[EMERGENCY_PAUSE_SEED.as_bytes()], &crate::id()).0
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::error::ErrorCode;
+use crate::states::{AmmConfig, FEE_RATE_DENOMINATOR_VALUE};
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::token_interface::TokenAccount;
+
+pub const FEE_DISCOUNT_SEED: &str = "fee_discount";
+pub const FEE_DISCOUNT_TIER_NUM: usize = 4;
+/// The max discount of the trade fee, denominated in hundredths of a bip (10^-6)
+pub const MAX_FEE_DISCOUNT_RATE: u32 = 500_000;
+
+/// A trader holding at least `min_amount` of the discount mint pays
+/// `trade_fee_rate * (1 - discount_rate / 10^6)`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct FeeDiscountTier {
+    pub min_amount: u64,
+    /// The discount of the trade fee, denominated in hundredths of a bip (10^-6)
+    pub discount_rate: u32,
+}
+
+/// Trade fee discounts of an amm config for the holders of a token
+///
+/// PDA of `[FEE_DISCOUNT_SEED, amm_config]`
+///
+#[account]
+#[derive(Default, Debug)]
+pub struct FeeDiscountConfig {
+    /// Bump to identify PDA
+    pub bump: u8,
+    /// The amm config the discounts apply to
+    pub amm_config: Pubkey,
+    /// The token whose holding grants the discounts
+    pub mint: Pubkey,
+    /// Tiers sorted by min_amount, a tier without discount is unused
+    pub tiers: [FeeDiscountTier; FEE_DISCOUNT_TIER_NUM],
+    pub padding: [u64; 8],
+}
+
+impl FeeDiscountConfig {
+    pub const LEN: usize = 8 + 1 + 32 + 32 + (8 + 4) * FEE_DISCOUNT_TIER_NUM + 64;
+
+    /// Check the discounts are bounded and the tiers sorted
+    pub fn validate_tiers(tiers: &[FeeDiscountTier; FEE_DISCOUNT_TIER_NUM]) -> Result<()> {
+        let mut last_min_amount = 0;
+        for tier in tiers.iter().filter(|tier| tier.discount_rate != 0) {
+            require_gte!(
+                MAX_FEE_DISCOUNT_RATE,
+                tier.discount_rate,
+                ErrorCode::InvalidFeeDiscountTier
+            );
+            require_gt!(
+                tier.min_amount,
+                last_min_amount,
+                ErrorCode::InvalidFeeDiscountTier
+            );
+            last_min_amount = tier.min_amount;
+        }
+        Ok(())
+    }
+
+    /// The discount of the highest tier reached by the held amount
+    pub fn discount_rate(&self, amount: u64) -> u32 {
+        self.tiers
+            .iter()
+            .filter(|tier| tier.discount_rate != 0 && tier.min_amount <= amount)
+            .map(|tier| tier.discount_rate)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// The trade fee discount of a swap signer
+#[derive(Debug, Clone, Copy)]
+pub struct FeeDiscount {
+    pub discount_rate: u32,
+    /// The fee discount config and the signer token account, they are not tick arrays
+    pub config: Pubkey,
+    pub token_account: Option<Pubkey>,
+}
+
+impl FeeDiscount {
+    /// Read the discount from the fee discount config of the amm config and the signer token
+    /// account of the discount mint in the remaining accounts, none when the config is not passed.
+    pub fn load(
+        amm_config: &Pubkey,
+        signer: &Pubkey,
+        remaining_accounts: &[AccountInfo],
+    ) -> Result<Option<Self>> {
+        let config_info = remaining_accounts.iter().find(|account_info| {
+            *account_info.owner == crate::id()
+                && account_info
+                    .try_borrow_data()
+                    .map(|data| data.starts_with(&FeeDiscountConfig::DISCRIMINATOR))
+                    .unwrap_or(false)
+        });
+        let config_info = match config_info {
+            Some(config_info) => config_info,
+            None => return Ok(None),
+        };
+        let config = FeeDiscountConfig::try_deserialize(&mut config_info.data.borrow().as_ref())?;
+        require_keys_eq!(config.amm_config, *amm_config);
+
+        let mut fee_discount = FeeDiscount {
+            discount_rate: 0,
+            config: config_info.key(),
+            token_account: None,
+        };
+        for account_info in remaining_accounts.iter() {
+            if *account_info.owner != anchor_spl::token::ID
+                && *account_info.owner != anchor_spl::token_2022::ID
+            {
+                continue;
+            }
+            // the mints of the next pools of a route are owned by the token programs too
+            let token_account =
+                match TokenAccount::try_deserialize(&mut account_info.data.borrow().as_ref()) {
+                    Ok(token_account) => token_account,
+                    Err(_) => continue,
+                };
+            if token_account.owner == *signer && token_account.mint == config.mint {
+                fee_discount.discount_rate = config.discount_rate(token_account.amount);
+                fee_discount.token_account = Some(account_info.key());
+                break;
+            }
+        }
+        Ok(Some(fee_discount))
+    }
+
+    /// The amm config with the discounted trade fee rate
+    pub fn apply(&self, amm_config: &AmmConfig) -> AmmConfig {
+        let discount = u64::from(amm_config.trade_fee_rate) * u64::from(self.discount_rate)
+            / u64::from(FEE_RATE_DENOMINATOR_VALUE);
+        AmmConfig {
+            trade_fee_rate: amm_config.trade_fee_rate - discount as u32,
+            ..amm_config.clone()
+        }
+    }
+
+    pub fn is_discount_account(&self, key: &Pubkey) -> bool {
+        *key == self.config || Some(*key) == self.token_account
+    }
+}
+
+#[cfg(test)]
+mod fee_discount_test {
+    use super::*;
+
+    fn tiers(tiers: &[(u64, u32)]) -> [FeeDiscountTier; FEE_DISCOUNT_TIER_NUM] {
+        let mut result = [FeeDiscountTier::default(); FEE_DISCOUNT_TIER_NUM];
+        for (i, (min_amount, discount_rate)) in tiers.iter().enumerate() {
+            result[i] = FeeDiscountTier {
+                min_amount: *min_amount,
+                discount_rate: *discount_rate,
+            };
+        }
+        result
+    }
+
+    #[test]
+    fn discount_rate_test() {
+        let config = FeeDiscountConfig {
+            tiers: tiers(&[(100, 100_000), (1_000, 200_000), (10_000, 500_000)]),
+            ..Default::default()
+        };
+        assert_eq!(config.discount_rate(0), 0);
+        assert_eq!(config.discount_rate(99), 0);
+        assert_eq!(config.discount_rate(100), 100_000);
+        assert_eq!(config.discount_rate(9_999), 200_000);
+        assert_eq!(config.discount_rate(u64::MAX), 500_000);
+    }
+
+    #[test]
+    fn validate_tiers_test() {
+        assert!(FeeDiscountConfig::validate_tiers(&tiers(&[])).is_ok());
+        assert!(
+            FeeDiscountConfig::validate_tiers(&tiers(&[(1, 1), (2, MAX_FEE_DISCOUNT_RATE)]))
+                .is_ok()
+        );
+        // over the max discount
+        assert!(
+            FeeDiscountConfig::validate_tiers(&tiers(&[(1, MAX_FEE_DISCOUNT_RATE + 1)])).is_err()
+        );
+        // not sorted
+        assert!(FeeDiscountConfig::validate_tiers(&tiers(&[(2, 1), (1, 2)])).is_err());
+        // a discount without holding
+        assert!(FeeDiscountConfig::validate_tiers(&tiers(&[(0, 1)])).is_err());
+    }
+
+    #[test]
+    fn apply_test() {
+        let amm_config = AmmConfig {
+            trade_fee_rate: 2500,
+            ..Default::default()
+        };
+        let fee_discount = FeeDiscount {
+            discount_rate: 200_000,
+            config: Pubkey::default(),
+            token_account: None,
+        };
+        assert_eq!(fee_discount.apply(&amm_config).trade_fee_rate, 2000);
+    }
+}
@@ -1,18 +1,24 @@
 pub mod config;
+pub mod emergency_pause;
+pub mod fee_discount;
 pub mod operation_account;
 pub mod oracle;
 pub mod personal_position;
 pub mod pool;
+pub mod pool_creator_permission;
 pub mod protocol_position;
 pub mod support_mint_associated;
 pub mod tick_array;
 pub mod tickarray_bitmap_extension;
 
 pub use config::*;
+pub use emergency_pause::*;
+pub use fee_discount::*;
 pub use operation_account::*;
 pub use oracle::*;
 pub use personal_position::*;
 pub use pool::*;
+pub use pool_creator_permission::*;
 pub use protocol_position::*;
 pub use support_mint_associated::*;
 pub use tick_array::*;
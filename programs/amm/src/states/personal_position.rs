@@ -164,6 +164,20 @@ pub struct IncreaseLiquidityEvent {
     pub amount_1_transfer_fee: u64,
 }
 
+/// Emitted when liquidity is donated to a position.
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct DonateLiquidityEvent {
+    /// The ID of the token for which liquidity was donated
+    pub position_nft_mint: Pubkey,
+
+    /// The payer of the liquidity
+    pub donor: Pubkey,
+
+    /// The amount by which liquidity for the NFT position was increased
+    pub liquidity: u128,
+}
+
 /// Emitted when liquidity is decreased.
 #[event]
 #[cfg_attr(feature = "client", derive(Debug))]
@@ -188,6 +202,28 @@ pub struct DecreaseLiquidityEvent {
     pub transfer_fee_1: u64,
 }
 
+/// Emitted when the fee growth checkpoints of a position are settled
+#[event]
+#[cfg_attr(feature = "client", derive(Debug))]
+pub struct SettlePositionFeeGrowthEvent {
+    /// The ID of the token of the settled position
+    pub position_nft_mint: Pubkey,
+    /// The token_0 fee growth checkpoint before the settlement
+    pub fee_growth_inside_0_last_x64: u128,
+    /// The token_1 fee growth checkpoint before the settlement
+    pub fee_growth_inside_1_last_x64: u128,
+    /// The token_0 fee growth inside the position range, the new checkpoint
+    pub fee_growth_inside_0_x64: u128,
+    /// The token_1 fee growth inside the position range, the new checkpoint
+    pub fee_growth_inside_1_x64: u128,
+    /// The amount of token_0 fee credited to the position
+    pub fee_amount_0: u64,
+    /// The amount of token_1 fee credited to the position
+    pub fee_amount_1: u64,
+    /// Whether an inconsistent checkpoint was reset without crediting its fees
+    pub repaired: bool,
+}
+
 /// Emitted when liquidity decreased or increase.
 #[event]
 #[cfg_attr(feature = "client", derive(Debug))]
@@ -458,7 +458,7 @@ impl PoolState {
         tickarray_bitmap_extension: Option<&'c AccountInfo<'info>>,
         tick_array_start_index: i32,
     ) -> Result<()> {
-        if self.is_overflow_default_tickarray_bitmap(vec![tick_array_start_index]) {
+        if self.is_overflow_default_tickarray_bitmap(&[tick_array_start_index]) {
             require_keys_eq!(
                 tickarray_bitmap_extension.unwrap().key(),
                 TickArrayBitmapExtension::key(self.key())
@@ -479,8 +479,9 @@ impl PoolState {
         zero_for_one: bool,
     ) -> Result<(bool, i32)> {
         let (is_initialized, start_index) =
-            if self.is_overflow_default_tickarray_bitmap(vec![self.tick_current]) {
+            if self.is_overflow_default_tickarray_bitmap(&[self.tick_current]) {
                 tickarray_bitmap_extension
+                    .as_ref()
                     .unwrap()
                     .check_tick_array_is_initialized(
                         TickArrayState::get_array_start_index(self.tick_current, self.tick_spacing),
@@ -535,6 +536,7 @@ impl PoolState {
             }
 
             let (is_found, start_index) = tickarray_bitmap_extension
+                .as_ref()
                 .unwrap()
                 .next_initialized_tick_array_from_one_bitmap(
                     last_tick_array_start_index,
@@ -574,10 +576,10 @@ impl PoolState {
         self.status.bitand(status) == 0
     }
 
-    pub fn is_overflow_default_tickarray_bitmap(&self, tick_indexs: Vec<i32>) -> bool {
+    pub fn is_overflow_default_tickarray_bitmap(&self, tick_indexs: &[i32]) -> bool {
         let (min_tick_array_start_index_boundary, max_tick_array_index_boundary) =
             self.tick_array_start_index_range();
-        for tick_index in tick_indexs {
+        for &tick_index in tick_indexs {
             let tick_array_start_index =
                 TickArrayState::get_array_start_index(tick_index, self.tick_spacing);
             if tick_array_start_index >= max_tick_array_index_boundary
@@ -943,21 +945,21 @@ pub mod pool_test {
             let mut pool_state = PoolState::default();
             pool_state.tick_spacing = 60;
             // -443580 is the min tick can use to open a position when tick_spacing is 60 due to MIN_TICK is -443636
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![-443580]) == false);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[-443580]) == false);
             // 443580 is the min tick can use to open a position when tick_spacing is 60 due to MAX_TICK is 443636
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![443580]) == false);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[443580]) == false);
 
             pool_state.tick_spacing = 10;
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![-307200]) == false);
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![-307201]) == true);
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![307200]) == true);
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![307199]) == false);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[-307200]) == false);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[-307201]) == true);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[307200]) == true);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[307199]) == false);
 
             pool_state.tick_spacing = 1;
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![-30720]) == false);
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![-30721]) == true);
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![30720]) == true);
-            assert!(pool_state.is_overflow_default_tickarray_bitmap(vec![30719]) == false);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[-30720]) == false);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[-30721]) == true);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[30720]) == true);
+            assert!(pool_state.is_overflow_default_tickarray_bitmap(&[30719]) == false);
         }
     }
 
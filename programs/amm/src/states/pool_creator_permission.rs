@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+pub const POOL_CREATOR_PERMISSION_SEED: &str = "pool_creator_permission";
+
+/// Allows the creator to create pools under an amm config whose pool creation mode is whitelist
+#[account]
+#[derive(Default, Debug)]
+pub struct PoolCreatorPermission {
+    /// Bump to identify PDA
+    pub bump: u8,
+    /// The amm config the permission is granted for
+    pub amm_config: Pubkey,
+    /// Address allowed to create pools
+    pub creator: Pubkey,
+    pub padding: [u64; 4],
+}
+
+impl PoolCreatorPermission {
+    pub const LEN: usize = 8 + 1 + 32 + 32 + 32;
+}
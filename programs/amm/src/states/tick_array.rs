@@ -121,6 +121,11 @@ impl TickArrayState {
         Ok(())
     }
 
+    pub fn get_tick_state(&self, tick_index: i32, tick_spacing: u16) -> Result<&TickState> {
+        let offset_in_array = self.get_tick_offset_in_array(tick_index, tick_spacing)?;
+        Ok(&self.ticks[offset_in_array])
+    }
+
     pub fn get_tick_state_mut(
         &mut self,
         tick_index: i32,